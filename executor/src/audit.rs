@@ -0,0 +1,252 @@
+//! Replay determinism audit.
+//!
+//! Executes the same transaction several times in perturbed host
+//! environments and compares the results. Any difference means that
+//! the execution depends on the host state (time, thread, hash map
+//! iteration order, cell identity or cached data).
+//!
+//! NOTE: Hash maps are seeded randomly on each instantiation, so
+//! every run also gets a different iteration order.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use everscale_types::models::{ShardAccount, StdAddr};
+use everscale_types::num::Tokens;
+use everscale_types::prelude::*;
+
+use crate::{Executor, ExecutorOutput, ExecutorParams, ParsedConfig, TxCache, TxError};
+
+/// Host environment perturbation of an audit run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Perturbation {
+    /// Execute as is.
+    None,
+    /// Execute on a separate thread.
+    OtherThread,
+    /// Execute after a delay to shift the host time.
+    Delayed,
+    /// Execute with inputs decoded from BOC (no shared cells).
+    FreshCells,
+    /// Execute through a [`TxCache`] (twice, to also check a cache hit).
+    Cached,
+}
+
+impl Perturbation {
+    /// All perturbations in the order of execution.
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::OtherThread,
+        Self::Delayed,
+        Self::FreshCells,
+        Self::Cached,
+    ];
+}
+
+/// Determinism-relevant parts of the execution result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionFingerprint {
+    /// `None` if the transaction was skipped.
+    pub transaction: Option<HashBytes>,
+    pub new_state: Option<HashBytes>,
+    pub out_msgs: Vec<HashBytes>,
+    pub total_fees: Tokens,
+    pub burned: Tokens,
+    pub gas_used: u64,
+    pub next_lt: u64,
+}
+
+impl ExecutionFingerprint {
+    const SKIPPED: Self = Self {
+        transaction: None,
+        new_state: None,
+        out_msgs: Vec::new(),
+        total_fees: Tokens::ZERO,
+        burned: Tokens::ZERO,
+        gas_used: 0,
+        next_lt: 0,
+    };
+
+    fn from_output(output: &ExecutorOutput) -> Result<Self> {
+        let meta = &output.transaction_meta;
+        Ok(Self {
+            transaction: Some(*output.transaction.repr_hash()),
+            new_state: Some(*CellBuilder::build_from(&output.new_state)?.repr_hash()),
+            out_msgs: meta.out_msgs.iter().map(|msg| *msg.repr_hash()).collect(),
+            total_fees: meta.total_fees,
+            burned: output.burned,
+            gas_used: meta.gas_used,
+            next_lt: meta.next_lt,
+        })
+    }
+}
+
+/// Result of a perturbed run which differs from the reference run.
+#[derive(Debug, Clone)]
+pub struct AuditMismatch {
+    pub perturbation: Perturbation,
+    pub fingerprint: ExecutionFingerprint,
+}
+
+/// Determinism audit report.
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    /// Result of the first (unperturbed) run.
+    pub reference: ExecutionFingerprint,
+    /// Perturbed runs with different results.
+    pub mismatches: Vec<AuditMismatch>,
+}
+
+impl AuditReport {
+    /// Returns `true` if all runs produced the same result.
+    pub fn is_deterministic(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Executes an ordinary transaction with each of [`Perturbation::ALL`]
+/// and compares the results.
+pub fn audit_ordinary(
+    params: &ExecutorParams,
+    config: &ParsedConfig,
+    address: &StdAddr,
+    is_external: bool,
+    msg: &Cell,
+    state: &ShardAccount,
+) -> Result<AuditReport> {
+    let mut reference = None;
+    let mut mismatches = Vec::new();
+
+    for perturbation in Perturbation::ALL {
+        let fingerprint = run_perturbed(
+            perturbation,
+            params,
+            config,
+            address,
+            is_external,
+            msg,
+            state,
+        )?;
+
+        match &reference {
+            None => reference = Some(fingerprint),
+            Some(reference) if *reference == fingerprint => {}
+            Some(_) => mismatches.push(AuditMismatch {
+                perturbation,
+                fingerprint,
+            }),
+        }
+    }
+
+    Ok(AuditReport {
+        reference: reference.expect("there is always at least one run"),
+        mismatches,
+    })
+}
+
+fn run_perturbed(
+    perturbation: Perturbation,
+    params: &ExecutorParams,
+    config: &ParsedConfig,
+    address: &StdAddr,
+    is_external: bool,
+    msg: &Cell,
+    state: &ShardAccount,
+) -> Result<ExecutionFingerprint> {
+    let run = |msg: &Cell, state: &ShardAccount| {
+        let res = Executor::new(params, config).begin_ordinary(address, is_external, msg, state);
+        match res {
+            Ok(uncommited) => ExecutionFingerprint::from_output(&uncommited.commit()?),
+            Err(TxError::Skipped) => Ok(ExecutionFingerprint::SKIPPED),
+            Err(TxError::Fatal(e)) => Err(e.into()),
+            Err(TxError::Interrupted(reason)) => Err(reason.into()),
+        }
+    };
+
+    match perturbation {
+        Perturbation::None => run(msg, state),
+        Perturbation::OtherThread => std::thread::scope(|scope| {
+            scope
+                .spawn(|| run(msg, state))
+                .join()
+                .map_err(|_| anyhow::anyhow!("audit thread panicked"))?
+        }),
+        Perturbation::Delayed => {
+            std::thread::sleep(Duration::from_millis(10));
+            run(msg, state)
+        }
+        Perturbation::FreshCells => {
+            let msg = Boc::decode(Boc::encode(msg))?;
+            let state = Boc::decode(Boc::encode(CellBuilder::build_from(state)?))?;
+            run(&msg, &state.parse::<ShardAccount>()?)
+        }
+        Perturbation::Cached => {
+            let executor = Executor::new(params, config);
+            let mut cache = TxCache::new(1);
+
+            let mut fingerprints = Vec::with_capacity(2);
+            for _ in 0..2 {
+                let res =
+                    executor.execute_ordinary_cached(&mut cache, address, is_external, msg, state);
+                fingerprints.push(match res {
+                    Ok(output) => ExecutionFingerprint::from_output(&output)?,
+                    Err(TxError::Skipped) => ExecutionFingerprint::SKIPPED,
+                    Err(TxError::Fatal(e)) => return Err(e.into()),
+                    Err(TxError::Interrupted(reason)) => return Err(reason.into()),
+                });
+            }
+
+            // Return the mismatched one if the cache hit differs.
+            let hit = fingerprints.pop().unwrap();
+            let miss = fingerprints.pop().unwrap();
+            Ok(if hit == miss { miss } else { hit })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, IntMsgInfo, OptionalAccount, StorageInfo,
+    };
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+
+    #[test]
+    fn ordinary_tx_is_deterministic() -> Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Uninit,
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let report = audit_ordinary(&params, &config, &address, false, &msg, &state)?;
+        assert!(report.is_deterministic(), "{:?}", report.mismatches);
+        assert!(report.reference.transaction.is_some());
+
+        Ok(())
+    }
+}
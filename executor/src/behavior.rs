@@ -0,0 +1,126 @@
+use everscale_types::models::{GlobalCapability, GlobalVersion};
+
+use crate::{AnycastPolicy, ExecutorParams, ParsedConfig};
+
+/// Condition under which a versioned behaviour is enabled.
+#[derive(Debug, Clone, Copy)]
+enum Since {
+    /// Enabled starting from the specified global version.
+    Version(u32),
+    /// Enabled when the capability is set.
+    Capability(GlobalCapability),
+}
+
+impl Since {
+    fn is_enabled(&self, global: &GlobalVersion) -> bool {
+        match self {
+            Self::Version(version) => global.version >= *version,
+            Self::Capability(cap) => global.capabilities.contains(*cap),
+        }
+    }
+}
+
+/// A rule which changed at some global version.
+struct VersionedBehavior {
+    since: Since,
+    apply: fn(&mut ExecutorParams, bool, &ParsedConfig),
+}
+
+/// All version-dependent executor behaviours.
+const VERSIONED_BEHAVIORS: &[VersionedBehavior] = &[
+    // Action fines for failed action phase.
+    VersionedBehavior {
+        since: Since::Version(4),
+        apply: |params, enabled, _| params.charge_action_fees_on_fail = enabled,
+    },
+    // Original message body as a reference in bounced messages.
+    VersionedBehavior {
+        since: Since::Capability(GlobalCapability::CapFullBodyInBounced),
+        apply: |params, enabled, _| params.full_body_in_bounced = enabled,
+    },
+    // Extra currencies are no longer bounced or reserved implicitly.
+    VersionedBehavior {
+        since: Since::Version(10),
+        apply: |params, enabled, _| params.strict_extra_currency = enabled,
+    },
+    // Anycast addresses are forbidden.
+    VersionedBehavior {
+        since: Since::Version(10),
+        apply: |params, enabled, _| {
+            params.anycast_policy = if enabled {
+                AnycastPolicy::Reject
+            } else {
+                AnycastPolicy::Rewrite
+            };
+        },
+    },
+    // Signature checks include the global id.
+    VersionedBehavior {
+        since: Since::Capability(GlobalCapability::CapSignatureWithId),
+        apply: |params, enabled, config| {
+            params.vm_modifiers.signature_with_id = enabled.then_some(config.global_id);
+        },
+    },
+];
+
+/// Updates all version-dependent fields of `params`
+/// according to the global version from the config.
+pub(crate) fn apply_versioned_behaviors(params: &mut ExecutorParams, config: &ParsedConfig) {
+    for behavior in VERSIONED_BEHAVIORS {
+        let enabled = behavior.since.is_enabled(&config.global);
+        (behavior.apply)(params, enabled, config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::models::GlobalCapabilities;
+
+    use super::*;
+    use crate::testkit::{make_custom_config, make_default_params};
+
+    fn make_params(version: u32, capabilities: &[GlobalCapability]) -> ExecutorParams {
+        let mut caps = 0;
+        for cap in capabilities {
+            caps |= *cap as u64;
+        }
+
+        let config = make_custom_config(|config| {
+            config.params.set_global_version(&GlobalVersion {
+                version,
+                capabilities: GlobalCapabilities::new(caps),
+            })?;
+            Ok(())
+        });
+
+        let mut params = make_default_params();
+        params.apply_global_version(&config);
+        params
+    }
+
+    #[test]
+    fn old_version_behaviors() {
+        let params = make_params(3, &[]);
+        assert!(!params.charge_action_fees_on_fail);
+        assert!(!params.full_body_in_bounced);
+        assert!(!params.strict_extra_currency);
+        assert_eq!(params.anycast_policy, AnycastPolicy::Rewrite);
+        // Storage phase always goes first for bounceable messages.
+        assert!(!params.credit_first_for_bounceable);
+        assert_eq!(params.vm_modifiers.signature_with_id, None);
+    }
+
+    #[test]
+    fn new_version_behaviors() {
+        let params = make_params(10, &[
+            GlobalCapability::CapFullBodyInBounced,
+            GlobalCapability::CapSignatureWithId,
+        ]);
+        assert!(params.charge_action_fees_on_fail);
+        assert!(params.full_body_in_bounced);
+        assert!(params.strict_extra_currency);
+        assert_eq!(params.anycast_policy, AnycastPolicy::Reject);
+        assert!(!params.credit_first_for_bounceable);
+        assert_eq!(params.vm_modifiers.signature_with_id, Some(100));
+    }
+}
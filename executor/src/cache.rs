@@ -0,0 +1,334 @@
+use std::collections::VecDeque;
+
+use ahash::HashMap;
+use everscale_types::models::{ShardAccount, StdAddr};
+use everscale_types::prelude::*;
+
+use crate::util::{AnycastPolicy, FeeRounding, Quirks, RandSeedDerivation};
+use crate::{Executor, ExecutorOutput, ExecutorParams, LoadMessage, TxResult};
+
+/// Memoized outputs of ordinary transactions.
+///
+/// Entries are keyed by the account state, inbound message,
+/// all [`ExecutorParams`] which affect the output and config root hash,
+/// so a changed input never results in a stale hit.
+///
+/// NOTE: [`ExecutionLimits`] are not part of the key since interrupted
+/// executions are never cached.
+///
+/// [`ExecutionLimits`]: tycho_vm::ExecutionLimits
+pub struct TxCache {
+    capacity: usize,
+    entries: HashMap<TxCacheKey, ExecutorOutput>,
+    order: VecDeque<TxCacheKey>,
+    hits: u64,
+    misses: u64,
+}
+
+impl TxCache {
+    /// Creates an empty cache which holds at most `capacity` outputs.
+    ///
+    /// The oldest entries are evicted first.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::default(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the number of cached outputs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the cache has no outputs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of lookups which were served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups which required an execution.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Removes all cached outputs.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn get(&mut self, key: &TxCacheKey) -> Option<ExecutorOutput> {
+        match self.entries.get(key) {
+            Some(output) => {
+                self.hits += 1;
+                Some(output.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: TxCacheKey, output: ExecutorOutput) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        while self.entries.len() >= self.capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        if self.entries.insert(key.clone(), output).is_none() {
+            self.order.push_back(key);
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TxCacheKey {
+    address: StdAddr,
+    account: HashBytes,
+    last_trans_hash: HashBytes,
+    last_trans_lt: u64,
+    in_msg: HashBytes,
+    is_external: bool,
+    min_lt: u64,
+    override_special: Option<bool>,
+    recovered_state: Option<HashBytes>,
+    in_msg_lt: Option<u64>,
+    params: ParamsKey,
+    config: Option<HashBytes>,
+    config_address: HashBytes,
+}
+
+/// All [`ExecutorParams`] which affect the execution output.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ParamsKey {
+    libraries: Option<HashBytes>,
+    rand_seed: HashBytes,
+    rand_seed_derivation: RandSeedDerivation,
+    block_unixtime: u32,
+    block_lt: u64,
+    prev_blocks_info: Option<tycho_vm::PrevBlocksInfo>,
+    vm_modifiers: tycho_vm::BehaviourModifiers,
+    disable_delete_frozen_accounts: bool,
+    charge_action_fees_on_fail: bool,
+    full_body_in_bounced: bool,
+    strict_extra_currency: bool,
+    fee_rounding: FeeRounding,
+    compute_vm_state_hashes: bool,
+    ext_msg_gas_credit: Option<u64>,
+    gas_limit_override: Option<u64>,
+    anycast_policy: AnycastPolicy,
+    extended_send_msg_flags: u8,
+    credit_first_for_bounceable: bool,
+    precompiled_contracts: Vec<(HashBytes, usize)>,
+    pruned_state: bool,
+    quirks: Quirks,
+}
+
+impl ParamsKey {
+    fn new(params: &ExecutorParams) -> Self {
+        // NOTE: Destructure all fields to not miss new ones.
+        let ExecutorParams {
+            libraries,
+            rand_seed,
+            rand_seed_derivation,
+            block_unixtime,
+            block_lt,
+            prev_blocks_info,
+            vm_modifiers,
+            disable_delete_frozen_accounts,
+            charge_action_fees_on_fail,
+            full_body_in_bounced,
+            strict_extra_currency,
+            fee_rounding,
+            compute_vm_state_hashes,
+            ext_msg_gas_credit,
+            gas_limit_override,
+            anycast_policy,
+            extended_send_msg_flags,
+            credit_first_for_bounceable,
+            precompiled_contracts,
+            pruned_state,
+            execution_limits: _,
+            quirks,
+        } = params;
+
+        Self {
+            libraries: libraries.root().as_ref().map(|c| *c.repr_hash()),
+            rand_seed: *rand_seed,
+            rand_seed_derivation: *rand_seed_derivation,
+            block_unixtime: *block_unixtime,
+            block_lt: *block_lt,
+            prev_blocks_info: prev_blocks_info.clone(),
+            vm_modifiers: *vm_modifiers,
+            disable_delete_frozen_accounts: *disable_delete_frozen_accounts,
+            charge_action_fees_on_fail: *charge_action_fees_on_fail,
+            full_body_in_bounced: *full_body_in_bounced,
+            strict_extra_currency: *strict_extra_currency,
+            fee_rounding: *fee_rounding,
+            compute_vm_state_hashes: *compute_vm_state_hashes,
+            ext_msg_gas_credit: *ext_msg_gas_credit,
+            gas_limit_override: *gas_limit_override,
+            anycast_policy: *anycast_policy,
+            extended_send_msg_flags: *extended_send_msg_flags,
+            credit_first_for_bounceable: *credit_first_for_bounceable,
+            precompiled_contracts: precompiled_contracts.fingerprint(),
+            pruned_state: *pruned_state,
+            quirks: *quirks,
+        }
+    }
+}
+
+impl Executor<'_> {
+    /// Executes and commits an ordinary transaction,
+    /// reusing the output of the same execution from `cache`.
+    ///
+    /// Skipped transactions are not cached.
+    pub fn execute_ordinary_cached<M>(
+        &self,
+        cache: &mut TxCache,
+        address: &StdAddr,
+        is_external: bool,
+        msg: M,
+        state: &ShardAccount,
+    ) -> TxResult<ExecutorOutput>
+    where
+        M: LoadMessage,
+    {
+        let msg_root = msg.load_message_root()?;
+
+        let key = TxCacheKey {
+            address: address.clone(),
+            account: *state.account.repr_hash(),
+            last_trans_hash: state.last_trans_hash,
+            last_trans_lt: state.last_trans_lt,
+            in_msg: *msg_root.repr_hash(),
+            is_external,
+            min_lt: self.min_lt,
+            override_special: self.override_special,
+            recovered_state: self
+                .recovered_state
+                .map(|state| CellBuilder::build_from(state).map(|cell| *cell.repr_hash()))
+                .transpose()?,
+            in_msg_lt: self.in_msg_lt,
+            params: ParamsKey::new(self.params),
+            config: self
+                .config
+                .raw
+                .params
+                .as_dict()
+                .root()
+                .as_ref()
+                .map(|c| *c.repr_hash()),
+            config_address: self.config.raw.address,
+        };
+
+        if let Some(output) = cache.get(&key) {
+            return Ok(output);
+        }
+
+        let output = self
+            .begin_ordinary(address, is_external, msg_root, state)?
+            .commit()?;
+
+        cache.insert(key, output.clone());
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, IntMsgInfo, OptionalAccount, StorageInfo,
+    };
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+
+    #[test]
+    fn cached_ordinary_tx() -> anyhow::Result<()> {
+        let config = make_default_config();
+        let mut params = make_default_params();
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Uninit,
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let mut cache = TxCache::new(1);
+
+        let first = Executor::new(&params, config.as_ref())
+            .execute_ordinary_cached(&mut cache, &address, false, &msg, &state)?;
+        assert_eq!(cache.misses(), 1);
+        assert_eq!(cache.len(), 1);
+
+        let second = Executor::new(&params, config.as_ref())
+            .execute_ordinary_cached(&mut cache, &address, false, &msg, &state)?;
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(
+            first.transaction.repr_hash(),
+            second.transaction.repr_hash()
+        );
+
+        // Different block time must not reuse the cached output.
+        params.block_unixtime += 100;
+        let third = Executor::new(&params, config.as_ref())
+            .execute_ordinary_cached(&mut cache, &address, false, &msg, &state)?;
+        assert_eq!(cache.misses(), 2);
+        assert_ne!(first.transaction.repr_hash(), third.transaction.repr_hash());
+
+        // Only the latest output is kept.
+        assert_eq!(cache.len(), 1);
+        Executor::new(&params, config.as_ref())
+            .execute_ordinary_cached(&mut cache, &address, false, &msg, &state)?;
+        assert_eq!(cache.hits(), 2);
+
+        // Changed behaviour switch must not reuse the cached output.
+        params.compute_vm_state_hashes = !params.compute_vm_state_hashes;
+        Executor::new(&params, config.as_ref())
+            .execute_ordinary_cached(&mut cache, &address, false, &msg, &state)?;
+        assert_eq!(cache.hits(), 2);
+        assert_eq!(cache.misses(), 3);
+
+        params.vm_modifiers.chksig_always_succeed = !params.vm_modifiers.chksig_always_succeed;
+        Executor::new(&params, config.as_ref())
+            .execute_ordinary_cached(&mut cache, &address, false, &msg, &state)?;
+        assert_eq!(cache.misses(), 4);
+
+        Ok(())
+    }
+}
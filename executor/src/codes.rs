@@ -0,0 +1,201 @@
+//! Exit and result codes produced by the VM and the executor.
+//!
+//! - Compute phase exit codes are either thrown by the contract
+//!   (VM exceptions use `0..=14`), or are negative codes of unhandled
+//!   exceptions (see [`ComputeExitCode`]);
+//! - Action phase result codes are [`ActionResultCode`] (`32..=50`).
+
+pub use tycho_vm::VmException;
+
+/// Exit code of the successful compute phase.
+pub const COMPUTE_SUCCESS: i32 = VmException::Ok.code();
+/// Exit code of the successful compute phase (alternative termination).
+pub const COMPUTE_ALT_SUCCESS: i32 = VmException::Alternative.code();
+/// Exit code of the compute phase which ran out of gas.
+pub const COMPUTE_OUT_OF_GAS: i32 = VmException::OutOfGas.as_exit_code();
+/// Exit code of the compute phase which accessed a missing code.
+pub const COMPUTE_FATAL: i32 = VmException::Fatal.as_exit_code();
+
+/// Known compute phase exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeExitCode {
+    /// VM exception (or a contract exception with the same code).
+    Exception(VmException),
+    /// Unhandled exception which terminated the VM.
+    Unhandled(VmException),
+}
+
+impl ComputeExitCode {
+    /// Parses the compute phase exit code.
+    ///
+    /// Returns `None` for custom contract exit codes.
+    pub const fn from_exit_code(exit_code: i32) -> Option<Self> {
+        if exit_code >= 0 {
+            match VmException::from_code(exit_code) {
+                Some(exception) => Some(Self::Exception(exception)),
+                None => None,
+            }
+        } else {
+            match VmException::from_code(!exit_code) {
+                Some(exception) => Some(Self::Unhandled(exception)),
+                None => None,
+            }
+        }
+    }
+
+    /// Returns the compute phase exit code.
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::Exception(exception) => exception.code(),
+            Self::Unhandled(exception) => exception.as_exit_code(),
+        }
+    }
+
+    /// Returns `true` if the code is a successful exit code.
+    pub const fn is_success(&self) -> bool {
+        matches!(
+            self,
+            Self::Exception(VmException::Ok | VmException::Alternative)
+        )
+    }
+}
+
+impl std::fmt::Display for ComputeExitCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exception(exception) => std::fmt::Display::fmt(exception, f),
+            Self::Unhandled(exception) => write!(f, "unhandled {exception}"),
+        }
+    }
+}
+
+/// Action phase result code.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ActionResultCode {
+    #[error("invalid action list")]
+    ActionListInvalid = 32,
+    #[error("too many actions")]
+    TooManyActions = 33,
+    #[error("invalid or unsupported action")]
+    ActionInvalid = 34,
+    #[error("invalid source address")]
+    InvalidSrcAddr = 35,
+    #[error("invalid destination address")]
+    InvalidDstAddr = 36,
+    #[error("not enough balance (base currency)")]
+    NotEnoughBalance = 37,
+    #[error("not enough balance (extra currency)")]
+    NotEnoughExtraBalance = 38,
+    #[error("failed to fit message into cell")]
+    FailedToFitMessage = 39,
+    #[error("message exceeds limits")]
+    MessageOutOfLimits = 40,
+    #[error("library code not found")]
+    NoLibCode = 41,
+    #[error("failed to change libraries dict")]
+    InvalidLibrariesDict = 42,
+    #[error("too many library cells")]
+    LibOutOfLimits = 43,
+    #[error("too many extra currencies")]
+    TooManyExtraCurrencies = 44,
+    #[error("state exceeds limits")]
+    StateOutOfLimits = 50,
+}
+
+impl ActionResultCode {
+    /// All result codes in ascending order.
+    pub const ALL: [Self; 14] = [
+        Self::ActionListInvalid,
+        Self::TooManyActions,
+        Self::ActionInvalid,
+        Self::InvalidSrcAddr,
+        Self::InvalidDstAddr,
+        Self::NotEnoughBalance,
+        Self::NotEnoughExtraBalance,
+        Self::FailedToFitMessage,
+        Self::MessageOutOfLimits,
+        Self::NoLibCode,
+        Self::InvalidLibrariesDict,
+        Self::LibOutOfLimits,
+        Self::TooManyExtraCurrencies,
+        Self::StateOutOfLimits,
+    ];
+
+    /// Returns a result code for the specified action phase `result_code`.
+    pub fn from_code(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|item| item.code() == code)
+    }
+
+    /// Returns the action phase `result_code`.
+    pub const fn code(&self) -> i32 {
+        *self as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn vm_exception_codes() {
+        for (i, exception) in VmException::ALL.into_iter().enumerate() {
+            assert_eq!(exception.code(), i as i32);
+            assert_eq!(VmException::from_code(i as i32), Some(exception));
+        }
+        assert_eq!(VmException::from_code(-1), None);
+        assert_eq!(VmException::from_code(VmException::ALL.len() as i32), None);
+    }
+
+    #[test]
+    fn compute_exit_codes() {
+        assert_eq!(COMPUTE_OUT_OF_GAS, -14);
+        assert_eq!(COMPUTE_FATAL, -13);
+
+        for exception in VmException::ALL {
+            for code in [
+                ComputeExitCode::Exception(exception),
+                ComputeExitCode::Unhandled(exception),
+            ] {
+                let exit_code = code.exit_code();
+                assert_eq!(ComputeExitCode::from_exit_code(exit_code), Some(code));
+            }
+        }
+
+        let success = [COMPUTE_SUCCESS, COMPUTE_ALT_SUCCESS];
+        for exit_code in success {
+            assert!(ComputeExitCode::from_exit_code(exit_code)
+                .unwrap()
+                .is_success());
+        }
+        assert!(!ComputeExitCode::from_exit_code(COMPUTE_OUT_OF_GAS)
+            .unwrap()
+            .is_success());
+
+        // Custom contract exit codes.
+        assert_eq!(ComputeExitCode::from_exit_code(100), None);
+        assert_eq!(ComputeExitCode::from_exit_code(-100), None);
+    }
+
+    #[test]
+    fn action_result_codes() {
+        let mut unique = HashSet::default();
+        for code in ActionResultCode::ALL {
+            assert!(unique.insert(code.code()), "duplicate code {code:?}");
+            assert_eq!(ActionResultCode::from_code(code.code()), Some(code));
+
+            // Must not overlap with VM exceptions.
+            assert_eq!(VmException::from_code(code.code()), None);
+        }
+
+        // Codes must be sorted.
+        for pair in ActionResultCode::ALL.windows(2) {
+            assert!(pair[0].code() < pair[1].code());
+        }
+
+        assert_eq!(ActionResultCode::from_code(0), None);
+        assert_eq!(ActionResultCode::from_code(45), None);
+    }
+}
@@ -1,3 +1,5 @@
+use std::num::NonZeroU32;
+
 use ahash::{HashMap, HashSet};
 use anyhow::Result;
 use everscale_types::error::Error;
@@ -14,6 +16,10 @@ use crate::util::shift_ceil_price;
 /// Parsed [`BlockchainConfigParams`].
 pub struct ParsedConfig {
     pub blackhole_addr: Option<HashBytes>,
+    /// Fraction of collected fees which is burned (numerator).
+    pub fee_burn_num: u32,
+    /// Fraction of collected fees which is burned (denominator).
+    pub fee_burn_denom: NonZeroU32,
     pub mc_gas_prices: GasLimitsPrices,
     pub gas_prices: GasLimitsPrices,
     pub mc_fwd_prices: MsgForwardPrices,
@@ -24,6 +30,8 @@ pub struct ParsedConfig {
     pub global: GlobalVersion,
     pub workchains: HashMap<i32, WorkchainDescription>,
     pub special_accounts: HashSet<HashBytes>,
+    /// Fixed gas usage of precompiled contracts (`ConfigParam45`) by code hash.
+    pub precompiled_gas_usage: HashMap<HashBytes, u64>,
     pub raw: BlockchainConfig,
     pub unpacked: UnpackedConfig,
 }
@@ -31,7 +39,6 @@ pub struct ParsedConfig {
 impl ParsedConfig {
     // TODO: Pass `global_id` here as well? For now we assume that
     //       `params` will contain a global id entry (`ConfigParam19`).
-    // TODO: Return error if storage prices `utime_since` is not properly sorted.
     pub fn parse(config: BlockchainConfig, now: u32) -> Result<Self, Error> {
         let dict = config.params.as_dict();
 
@@ -79,8 +86,12 @@ impl ParsedConfig {
             special_accounts.insert(addr?);
         }
 
+        let precompiled_gas_usage = parse_precompiled_contracts(&config.params)?;
+
         Ok(Self {
             blackhole_addr: burning.blackhole_addr,
+            fee_burn_num: burning.fee_burn_num,
+            fee_burn_denom: burning.fee_burn_denom,
             mc_gas_prices: mc_gas_prices_raw.parse::<GasLimitsPrices>()?,
             gas_prices: gas_prices_raw.parse::<GasLimitsPrices>()?,
             mc_fwd_prices: mc_fwd_prices_raw.parse::<MsgForwardPrices>()?,
@@ -94,6 +105,7 @@ impl ParsedConfig {
             global,
             workchains,
             special_accounts,
+            precompiled_gas_usage,
             raw: config,
             unpacked: UnpackedConfig {
                 latest_storage_prices,
@@ -138,6 +150,11 @@ impl ParsedConfig {
         }
     }
 
+    /// Returns a fixed gas usage of the precompiled contract with the specified code.
+    pub fn precompiled_gas_usage(&self, code_hash: &HashBytes) -> Option<u64> {
+        self.precompiled_gas_usage.get(code_hash).copied()
+    }
+
     pub fn gas_prices(&self, is_masterchain: bool) -> &GasLimitsPrices {
         if is_masterchain {
             &self.mc_gas_prices
@@ -157,58 +174,17 @@ impl ParsedConfig {
         is_special: bool,
         is_masterchain: bool,
     ) -> Tokens {
-        // No fees in following cases:
-        // - Time has not moved forward since the last transaction;
-        // - Account was just created (last_paid: 0);
-        // - Special accounts;
-        // - No storage prices.
-        if now <= storage_stat.last_paid || storage_stat.last_paid == 0 || is_special {
+        if is_special {
             return Tokens::ZERO;
         }
 
-        let Some(oldest_prices) = self.storage_prices.first() else {
-            // No storage prices.
-            return Tokens::ZERO;
-        };
-        if now <= oldest_prices.utime_since {
-            // No storage prices (being active for long enought time).
-            return Tokens::ZERO;
-        }
-
-        let get_prices = if is_masterchain {
-            |prices: &StoragePrices| (prices.mc_bit_price_ps, prices.mc_cell_price_ps)
-        } else {
-            |prices: &StoragePrices| (prices.bit_price_ps, prices.cell_price_ps)
-        };
-
-        let mut total = 0u128;
-
-        // Sum fees for all segments (starting from the most recent).
-        let mut upto = now;
-        for prices in self.storage_prices.iter().rev() {
-            if prices.utime_since > upto {
-                continue;
-            }
-
-            // Compute for how long the segment was active
-            let delta = upto - std::cmp::max(prices.utime_since, storage_stat.last_paid);
-
-            // Sum fees
-            let (bit_price, cell_price) = get_prices(prices);
-            let fee = (bit_price as u128 * storage_stat.used.bits.into_inner() as u128)
-                .saturating_add(cell_price as u128 * storage_stat.used.cells.into_inner() as u128)
-                .saturating_mul(delta as u128);
-            total = total.saturating_add(fee);
-
-            // Stop on the first outdated segment.
-            upto = prices.utime_since;
-            if upto <= storage_stat.last_paid {
-                break;
-            }
-        }
-
-        // Convert from fixed point int.
-        Tokens::new(shift_ceil_price(total))
+        crate::fees::compute_storage_fee(
+            self,
+            is_masterchain,
+            &storage_stat.used,
+            storage_stat.last_paid,
+            now,
+        )
     }
 
     /// Computes gas credit and limits bought for the provided balances.
@@ -221,7 +197,42 @@ impl ParsedConfig {
         is_tx_ordinary: bool,
         is_in_msg_external: bool,
     ) -> GasParams {
-        let prices = self.gas_prices(is_masterchain);
+        self.compute_gas_params_ext(
+            account_balance,
+            msg_balance_remaining,
+            is_special,
+            is_masterchain,
+            is_tx_ordinary,
+            is_in_msg_external,
+            None,
+        )
+    }
+
+    /// Computes gas credit and limits bought for the provided balances.
+    ///
+    /// `gas_limit_override` replaces the gas limit (and the special gas limit)
+    /// from config. It can be both lower and higher than the config value.
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_gas_params_ext(
+        &self,
+        account_balance: &Tokens,
+        msg_balance_remaining: &Tokens,
+        is_special: bool,
+        is_masterchain: bool,
+        is_tx_ordinary: bool,
+        is_in_msg_external: bool,
+        gas_limit_override: Option<u64>,
+    ) -> GasParams {
+        let mut prices = self.gas_prices(is_masterchain);
+        let overridden;
+        if let Some(gas_limit) = gas_limit_override {
+            overridden = GasLimitsPrices {
+                gas_limit,
+                special_gas_limit: gas_limit,
+                ..prices.clone()
+            };
+            prices = &overridden;
+        }
 
         let gas_max = if is_special {
             prices.special_gas_limit
@@ -261,11 +272,15 @@ fn parse_storage_prices(
     now: u32,
 ) -> Result<ParsedStoragePrices, Error> {
     let storage_prices_dict = RawDict::<32>::from(config.as_dict().get(18)?);
-    let mut storage_prices = Vec::new();
+    let mut storage_prices = Vec::<StoragePrices>::new();
     let mut latest_storage_prices = None;
     for value in storage_prices_dict.values_owned() {
         let value = value?;
         let prices = StoragePrices::load_from(&mut value.0.apply_allow_exotic(&value.1))?;
+        if matches!(storage_prices.last(), Some(prev) if prev.utime_since >= prices.utime_since) {
+            // Storage prices schedule must be strictly increasing.
+            return Err(Error::InvalidData);
+        }
         if prices.utime_since <= now {
             latest_storage_prices = Some(value);
         }
@@ -279,6 +294,50 @@ fn parse_storage_prices(
     })
 }
 
+/// Parses `ConfigParam45`.
+///
+/// ```text
+/// precompiled_smc#b0 gas_usage:uint64 = PrecompiledSmc;
+/// precompiled_contracts_config#c0 list:(HashmapE 256 PrecompiledSmc)
+///     = PrecompiledContractsConfig;
+/// ```
+fn parse_precompiled_contracts(
+    params: &BlockchainConfigParams,
+) -> Result<HashMap<HashBytes, u64>, Error> {
+    struct PrecompiledSmc {
+        gas_usage: u64,
+    }
+
+    impl<'a> Load<'a> for PrecompiledSmc {
+        fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+            if slice.load_u8()? != 0xb0 {
+                return Err(Error::InvalidTag);
+            }
+            Ok(Self {
+                gas_usage: slice.load_u64()?,
+            })
+        }
+    }
+
+    let mut result = HashMap::default();
+
+    let Some(param) = params.as_dict().get(45)? else {
+        return Ok(result);
+    };
+    let mut cs = param.as_slice()?;
+    if cs.load_u8()? != 0xc0 {
+        return Err(Error::InvalidTag);
+    }
+
+    let list = Dict::<HashBytes, PrecompiledSmc>::load_from(&mut cs)?;
+    for entry in list.iter() {
+        let (code_hash, smc) = entry?;
+        result.insert(code_hash, smc.gas_usage);
+    }
+
+    Ok(result)
+}
+
 struct ParsedStoragePrices {
     latest_storage_prices: Option<CellSliceParts>,
     storage_prices: Vec<StoragePrices>,
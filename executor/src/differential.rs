@@ -0,0 +1,334 @@
+//! Differential testing against the reference transaction executor.
+//!
+//! Executes the same transaction with this executor and with a reference
+//! implementation (e.g. a wrapper around the C++ `emulator` library)
+//! and reports field-level divergences of the resulting transactions.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use everscale_types::models::{ShardAccount, StdAddr};
+use everscale_types::prelude::*;
+use serde_json::{json, Value};
+
+use crate::trace::transaction_to_json;
+use crate::{Executor, ExecutorParams, ParsedConfig, TxError};
+
+/// Ordinary transaction input.
+#[derive(Debug, Clone, Copy)]
+pub struct DiffInput<'a> {
+    pub address: &'a StdAddr,
+    pub is_external: bool,
+    pub msg: &'a Cell,
+    pub state: &'a ShardAccount,
+}
+
+/// Reference executor result.
+#[derive(Debug, Clone)]
+pub enum ReferenceOutput {
+    /// Transaction was executed.
+    Executed {
+        transaction: Cell,
+        shard_account: ShardAccount,
+    },
+    /// External message was not accepted.
+    Skipped,
+}
+
+/// Reference transaction executor.
+pub trait ReferenceExecutor {
+    fn execute_ordinary(
+        &mut self,
+        params: &ExecutorParams,
+        config: &ParsedConfig,
+        input: &DiffInput<'_>,
+    ) -> Result<ReferenceOutput>;
+}
+
+/// Reference executor which runs a separate process for each transaction.
+///
+/// The process receives a JSON request to its stdin:
+///
+/// ```json
+/// {
+///   "config": "<base64 BOC of the config params dict>",
+///   "libs": "<base64 BOC of the libraries dict or null>",
+///   "shard_account": "<base64 BOC>",
+///   "message": "<base64 BOC>",
+///   "is_external": false,
+///   "utime": 1738799198,
+///   "lt": "1000",
+///   "rand_seed": "<hex>",
+///   "ignore_chksig": false
+/// }
+/// ```
+///
+/// and must print the output of the C++ `emulate_transaction`
+/// (`{"success": true, "transaction": ..., "shard_account": ...}`) to its stdout.
+#[derive(Debug, Clone)]
+pub struct SubprocessExecutor {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl SubprocessExecutor {
+    pub fn new<P: Into<PathBuf>>(program: P) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+}
+
+impl ReferenceExecutor for SubprocessExecutor {
+    fn execute_ordinary(
+        &mut self,
+        params: &ExecutorParams,
+        config: &ParsedConfig,
+        input: &DiffInput<'_>,
+    ) -> Result<ReferenceOutput> {
+        let request = json!({
+            "config": config.raw.params.as_dict().root().as_ref().map(Boc::encode_base64),
+            "libs": params.libraries.root().as_ref().map(Boc::encode_base64),
+            "shard_account": Boc::encode_base64(CellBuilder::build_from(input.state)?),
+            "message": Boc::encode_base64(input.msg),
+            "is_external": input.is_external,
+            "utime": params.block_unixtime,
+            "lt": params.block_lt.to_string(),
+            "rand_seed": params.rand_seed.to_string(),
+            "ignore_chksig": params.vm_modifiers.chksig_always_succeed,
+        });
+
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("failed to spawn the reference executor")?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(serde_json::to_string(&request)?.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+        anyhow::ensure!(
+            output.status.success(),
+            "reference executor failed: {}",
+            output.status
+        );
+
+        let response = serde_json::from_slice::<Value>(&output.stdout)
+            .context("invalid reference executor output")?;
+
+        if response["success"] != true {
+            if response["external_not_accepted"] == true {
+                return Ok(ReferenceOutput::Skipped);
+            }
+            anyhow::bail!("reference executor error: {}", response["error"]);
+        }
+
+        let decode = |field: &str| -> Result<Cell> {
+            let boc = response[field]
+                .as_str()
+                .with_context(|| format!("reference executor output has no `{field}`"))?;
+            Ok(Boc::decode_base64(boc)?)
+        };
+
+        Ok(ReferenceOutput::Executed {
+            transaction: decode("transaction")?,
+            shard_account: decode("shard_account")?.parse::<ShardAccount>()?,
+        })
+    }
+}
+
+/// Field-level difference between two executions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Dot-separated path of the field (e.g. `transaction.description.compute_ph.gas_used`).
+    pub path: String,
+    /// Value produced by this executor.
+    pub ours: Value,
+    /// Value produced by the reference executor.
+    pub reference: Value,
+}
+
+/// Executes an ordinary transaction with both executors
+/// and returns the differences of the results.
+pub fn diff_ordinary<R: ReferenceExecutor + ?Sized>(
+    reference: &mut R,
+    params: &ExecutorParams,
+    config: &ParsedConfig,
+    input: &DiffInput<'_>,
+) -> Result<Vec<Divergence>> {
+    let res = Executor::new(params, config).begin_ordinary(
+        input.address,
+        input.is_external,
+        input.msg,
+        input.state,
+    );
+    let ours = match res {
+        Ok(uncommitted) => {
+            let output = uncommitted.commit()?;
+            output_to_json(output.transaction.inner(), &output.new_state)?
+        }
+        Err(TxError::Skipped) => Value::String("skipped".to_owned()),
+        Err(TxError::Fatal(e)) => return Err(e.into()),
+        Err(TxError::Interrupted(reason)) => return Err(reason.into()),
+    };
+
+    let reference = match reference.execute_ordinary(params, config, input)? {
+        ReferenceOutput::Executed {
+            transaction,
+            shard_account,
+        } => output_to_json(&transaction, &shard_account)?,
+        ReferenceOutput::Skipped => Value::String("skipped".to_owned()),
+    };
+
+    let mut divergences = Vec::new();
+    diff_values(String::new(), &ours, &reference, &mut divergences);
+    Ok(divergences)
+}
+
+fn output_to_json(transaction: &Cell, shard_account: &ShardAccount) -> Result<Value> {
+    let mut transaction = transaction_to_json(transaction)?;
+    // BOC differs whenever any other field differs.
+    if let Value::Object(fields) = &mut transaction {
+        fields.remove("boc");
+    }
+
+    Ok(json!({
+        "transaction": transaction,
+        "shard_account_hash": CellBuilder::build_from(shard_account)?.repr_hash().to_string(),
+    }))
+}
+
+fn diff_values(path: String, ours: &Value, reference: &Value, res: &mut Vec<Divergence>) {
+    let join = |key: &dyn std::fmt::Display| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match (ours, reference) {
+        (Value::Object(ours), Value::Object(reference)) => {
+            let mut keys = ours.keys().chain(reference.keys()).collect::<Vec<_>>();
+            keys.sort_unstable();
+            keys.dedup();
+
+            for key in keys {
+                let ours = ours.get(key).unwrap_or(&Value::Null);
+                let reference = reference.get(key).unwrap_or(&Value::Null);
+                diff_values(join(key), ours, reference, res);
+            }
+        }
+        (Value::Array(ours), Value::Array(reference)) => {
+            for i in 0..std::cmp::max(ours.len(), reference.len()) {
+                let ours = ours.get(i).unwrap_or(&Value::Null);
+                let reference = reference.get(i).unwrap_or(&Value::Null);
+                diff_values(join(&i), ours, reference, res);
+            }
+        }
+        _ if ours != reference => res.push(Divergence {
+            path,
+            ours: ours.clone(),
+            reference: reference.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, IntMsgInfo, OptionalAccount, StorageInfo,
+    };
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+
+    /// Uses this executor with different params as a reference.
+    struct SelfReference {
+        params: ExecutorParams,
+    }
+
+    impl ReferenceExecutor for SelfReference {
+        fn execute_ordinary(
+            &mut self,
+            _: &ExecutorParams,
+            config: &ParsedConfig,
+            input: &DiffInput<'_>,
+        ) -> Result<ReferenceOutput> {
+            let output = Executor::new(&self.params, config)
+                .begin_ordinary(input.address, input.is_external, input.msg, input.state)?
+                .commit()?;
+            Ok(ReferenceOutput::Executed {
+                transaction: output.transaction.inner().clone(),
+                shard_account: output.new_state,
+            })
+        }
+    }
+
+    #[test]
+    fn diff_with_reference() -> Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Uninit,
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        let input = DiffInput {
+            address: &address,
+            is_external: false,
+            msg: &msg,
+            state: &state,
+        };
+
+        // Same params.
+        let mut reference = SelfReference {
+            params: params.clone(),
+        };
+        let divergences = diff_ordinary(&mut reference, &params, &config, &input)?;
+        assert!(divergences.is_empty(), "{divergences:?}");
+
+        // Different block time.
+        let mut reference = SelfReference {
+            params: ExecutorParams {
+                block_unixtime: params.block_unixtime + 1,
+                ..params.clone()
+            },
+        };
+        let divergences = diff_ordinary(&mut reference, &params, &config, &input)?;
+        let now = divergences
+            .iter()
+            .find(|item| item.path == "transaction.now")
+            .expect("block time must diverge");
+        assert_eq!(now.ours, json!(params.block_unixtime));
+        assert_eq!(now.reference, json!(params.block_unixtime + 1));
+
+        Ok(())
+    }
+}
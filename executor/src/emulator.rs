@@ -0,0 +1,534 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::path::Path;
+
+use ahash::HashMap;
+use anyhow::Result;
+use everscale_types::boc::Boc;
+use everscale_types::cell::Lazy;
+use everscale_types::models::{
+    IntAddr, MsgInfo, OptionalAccount, ShardAccount, ShardAccounts, ShardIdent, ShardStateUnsplit,
+    StdAddr, Transaction,
+};
+use everscale_types::num::Tokens;
+use everscale_types::prelude::*;
+
+use crate::{Executor, ExecutorParams, ParsedConfig, TxError};
+
+/// Local block emulator.
+///
+/// Executes inbound messages for all accounts of a single shard
+/// in LT order, including internal messages produced along the way.
+pub struct BlockEmulator<'a> {
+    params: &'a ExecutorParams,
+    config: &'a ParsedConfig,
+    shard: ShardIdent,
+    accounts: ShardAccounts,
+    updated_accounts: HashMap<HashBytes, ShardAccount>,
+    pending: Vec<Cell>,
+    last_lt: u64,
+    max_transactions: usize,
+}
+
+impl<'a> BlockEmulator<'a> {
+    pub const DEFAULT_MAX_TRANSACTIONS: usize = 10000;
+
+    pub fn new(
+        params: &'a ExecutorParams,
+        config: &'a ParsedConfig,
+        shard: ShardIdent,
+        accounts: ShardAccounts,
+    ) -> Self {
+        Self {
+            params,
+            config,
+            shard,
+            accounts,
+            updated_accounts: HashMap::default(),
+            pending: Vec::new(),
+            last_lt: 0,
+            max_transactions: Self::DEFAULT_MAX_TRANSACTIONS,
+        }
+    }
+
+    /// Restores the emulator from the snapshot.
+    ///
+    /// NOTE: `block_lt` and `block_unixtime` of the snapshot must be
+    /// applied to the `params` by the caller.
+    pub fn from_snapshot(
+        params: &'a ExecutorParams,
+        config: &'a ParsedConfig,
+        snapshot: EmulatorSnapshot,
+    ) -> Self {
+        let mut emulator = Self::new(params, config, snapshot.shard, snapshot.accounts);
+        emulator.updated_accounts = snapshot.updated_accounts;
+        emulator.pending = snapshot.pending;
+        emulator.last_lt = snapshot.block_lt;
+        emulator
+    }
+
+    pub fn from_shard_state(
+        params: &'a ExecutorParams,
+        config: &'a ParsedConfig,
+        state: &ShardStateUnsplit,
+    ) -> Result<Self> {
+        let accounts = state.accounts.load()?;
+        Ok(Self::new(params, config, state.shard_ident, accounts))
+    }
+
+    /// Limits the number of transactions per block.
+    ///
+    /// All messages left in the queue after the limit is reached are
+    /// returned as [`EmulatedBlock::unprocessed`].
+    pub fn with_max_transactions(mut self, max_transactions: usize) -> Self {
+        self.max_transactions = max_transactions;
+        self
+    }
+
+    /// Returns all accounts modified by the emulated blocks.
+    pub fn updated_accounts(&self) -> &HashMap<HashBytes, ShardAccount> {
+        &self.updated_accounts
+    }
+
+    /// Returns messages which will be executed first on the next run.
+    pub fn pending_messages(&self) -> &[Cell] {
+        &self.pending
+    }
+
+    /// Captures the current emulator state.
+    pub fn snapshot(&self) -> EmulatorSnapshot {
+        EmulatorSnapshot {
+            block_lt: self.start_lt(),
+            block_unixtime: self.params.block_unixtime,
+            shard: self.shard,
+            accounts: self.accounts.clone(),
+            updated_accounts: self.updated_accounts.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+
+    /// Returns the latest known state of the account.
+    pub fn get_account(&self, address: &HashBytes) -> Result<ShardAccount> {
+        if let Some(state) = self.updated_accounts.get(address) {
+            return Ok(state.clone());
+        }
+
+        Ok(match self.accounts.get(address)? {
+            Some((_, state)) => state,
+            None => ShardAccount {
+                account: Lazy::new(&OptionalAccount::EMPTY)?,
+                last_trans_hash: HashBytes::ZERO,
+                last_trans_lt: 0,
+            },
+        })
+    }
+
+    /// Executes all messages and produces a block.
+    pub fn run<I>(&mut self, messages: I) -> Result<EmulatedBlock>
+    where
+        I: IntoIterator<Item = Cell>,
+    {
+        let mut block = EmulatedBlock {
+            transactions: Vec::new(),
+            out_msg_queue: Vec::new(),
+            unprocessed: Vec::new(),
+            stats: BlockStats {
+                start_lt: self.start_lt(),
+                end_lt: self.start_lt(),
+                ..Default::default()
+            },
+        };
+
+        let mut queue = BinaryHeap::new();
+        let mut seqno = 0;
+        for msg in std::mem::take(&mut self.pending)
+            .into_iter()
+            .chain(messages)
+        {
+            self.enqueue(msg, &mut seqno, &mut queue, &mut block)?;
+        }
+
+        while let Some(msg) = queue.pop() {
+            if block.transactions.len() >= self.max_transactions {
+                block.unprocessed.push(msg.root);
+                block.unprocessed.extend(
+                    queue
+                        .into_sorted_vec()
+                        .into_iter()
+                        .rev()
+                        .map(|msg| msg.root),
+                );
+                self.pending.clone_from(&block.unprocessed);
+                break;
+            }
+
+            let state = self.get_account(&msg.dst.address)?;
+
+            let min_lt = std::cmp::max(block.stats.start_lt, msg.lt + 1);
+            let executor = Executor::new(self.params, self.config).with_min_lt(min_lt);
+            let output = match executor.begin_ordinary(&msg.dst, msg.is_external, msg.root, &state)
+            {
+                Ok(tx) => tx.commit()?,
+                Err(TxError::Skipped) => {
+                    block.stats.skipped_ext_msgs += 1;
+                    continue;
+                }
+                Err(TxError::Fatal(e)) => return Err(e.into()),
+                Err(TxError::Interrupted(reason)) => return Err(reason.into()),
+            };
+
+            let meta = &output.transaction_meta;
+            block.stats.tx_count += 1;
+            block.stats.gas_used = block.stats.gas_used.saturating_add(meta.gas_used);
+            block.stats.total_fees.try_add_assign(meta.total_fees)?;
+            block.stats.burned.try_add_assign(output.burned)?;
+            block.stats.end_lt = std::cmp::max(block.stats.end_lt, meta.next_lt);
+
+            for out_msg in &meta.out_msgs {
+                self.enqueue(out_msg.inner().clone(), &mut seqno, &mut queue, &mut block)?;
+            }
+
+            self.updated_accounts
+                .insert(msg.dst.address, output.new_state);
+            block.transactions.push(output.transaction);
+        }
+
+        self.last_lt = block.stats.end_lt;
+        Ok(block)
+    }
+
+    /// Creates an executor for the next message of the current block.
+    pub(crate) fn executor(&self) -> Executor<'a> {
+        Executor::new(self.params, self.config).with_min_lt(self.start_lt())
+    }
+
+    fn start_lt(&self) -> u64 {
+        std::cmp::max(self.params.block_lt, self.last_lt)
+    }
+
+    fn enqueue(
+        &self,
+        root: Cell,
+        seqno: &mut u64,
+        queue: &mut BinaryHeap<QueuedMessage>,
+        block: &mut EmulatedBlock,
+    ) -> Result<()> {
+        let (dst, lt, is_external) = match MsgInfo::load_from(&mut root.as_slice()?)? {
+            MsgInfo::Int(info) => (info.dst, info.created_lt, false),
+            // External messages are always processed first.
+            MsgInfo::ExtIn(info) => (info.dst, 0, true),
+            MsgInfo::ExtOut(_) => {
+                block.out_msg_queue.push(root);
+                return Ok(());
+            }
+        };
+
+        let dst = match dst {
+            IntAddr::Std(dst) if self.contains(&dst) => dst,
+            // External messages to other shards are ignored.
+            _ if is_external => {
+                block.stats.skipped_ext_msgs += 1;
+                return Ok(());
+            }
+            _ => {
+                block.out_msg_queue.push(root);
+                return Ok(());
+            }
+        };
+
+        *seqno += 1;
+        queue.push(QueuedMessage {
+            lt,
+            seqno: *seqno,
+            is_external,
+            dst,
+            root,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn contains(&self, addr: &StdAddr) -> bool {
+        addr.workchain as i32 == self.shard.workchain()
+            && self.shard.contains_account(&addr.address)
+    }
+}
+
+/// Emulated block contents.
+#[derive(Debug, Clone)]
+pub struct EmulatedBlock {
+    /// Executed transactions in execution order.
+    pub transactions: Vec<Lazy<Transaction>>,
+    /// Outbound messages which left the shard.
+    pub out_msg_queue: Vec<Cell>,
+    /// Messages left in the queue after the transactions limit was reached.
+    pub unprocessed: Vec<Cell>,
+    /// Aggregate block stats.
+    pub stats: BlockStats,
+}
+
+/// Serializable emulator state.
+#[derive(Debug, Clone)]
+pub struct EmulatorSnapshot {
+    /// Logical time to continue from.
+    pub block_lt: u64,
+    /// Unix timestamp of the last emulated block.
+    pub block_unixtime: u32,
+    /// Emulated shard.
+    pub shard: ShardIdent,
+    /// Initial shard accounts.
+    pub accounts: ShardAccounts,
+    /// Accounts modified by the emulator.
+    pub updated_accounts: HashMap<HashBytes, ShardAccount>,
+    /// Messages left in the queue.
+    pub pending: Vec<Cell>,
+}
+
+impl EmulatorSnapshot {
+    const TAG: u32 = 0x656d7531;
+
+    /// Serializes the snapshot into a BOC.
+    pub fn to_boc(&self) -> Result<Vec<u8>> {
+        let mut updated_accounts = Dict::<HashBytes, ShardAccount>::new();
+        for (address, state) in &self.updated_accounts {
+            updated_accounts.set(address, state)?;
+        }
+
+        let mut pending = Dict::<u32, Cell>::new();
+        for (i, msg) in self.pending.iter().enumerate() {
+            pending.set(i as u32, msg)?;
+        }
+
+        let cx = Cell::empty_context();
+        let mut b = CellBuilder::new();
+        b.store_u32(Self::TAG)?;
+        b.store_u64(self.block_lt)?;
+        b.store_u32(self.block_unixtime)?;
+        b.store_u32(self.shard.workchain() as u32)?;
+        b.store_u64(self.shard.prefix())?;
+        self.accounts.store_into(&mut b, cx)?;
+        updated_accounts.store_into(&mut b, cx)?;
+        pending.store_into(&mut b, cx)?;
+
+        Ok(Boc::encode(b.build_ext(cx)?))
+    }
+
+    /// Deserializes the snapshot from a BOC.
+    pub fn from_boc(data: &[u8]) -> Result<Self> {
+        let root = Boc::decode(data)?;
+        let mut cs = root.as_slice()?;
+        anyhow::ensure!(cs.load_u32()? == Self::TAG, "invalid emulator snapshot tag");
+
+        let block_lt = cs.load_u64()?;
+        let block_unixtime = cs.load_u32()?;
+        let workchain = cs.load_u32()? as i32;
+        let prefix = cs.load_u64()?;
+        let Some(shard) = ShardIdent::new(workchain, prefix) else {
+            anyhow::bail!("invalid shard in emulator snapshot");
+        };
+        let accounts = ShardAccounts::load_from(&mut cs)?;
+        let updated_accounts = Dict::<HashBytes, ShardAccount>::load_from(&mut cs)?;
+        let pending = Dict::<u32, Cell>::load_from(&mut cs)?;
+
+        Ok(Self {
+            block_lt,
+            block_unixtime,
+            shard,
+            accounts,
+            updated_accounts: updated_accounts.iter().collect::<Result<_, _>>()?,
+            pending: pending.values().collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Writes the snapshot into the file.
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_boc()?)?;
+        Ok(())
+    }
+
+    /// Reads the snapshot from the file.
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_boc(&std::fs::read(path)?)
+    }
+}
+
+/// Aggregate block stats.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockStats {
+    /// Number of executed transactions.
+    pub tx_count: usize,
+    /// Number of skipped external messages.
+    pub skipped_ext_msgs: usize,
+    /// Total gas used by all transactions.
+    pub gas_used: u64,
+    /// Total fees collected by all transactions.
+    pub total_fees: Tokens,
+    /// Total burned tokens.
+    pub burned: Tokens,
+    /// Logical time of the block start.
+    pub start_lt: u64,
+    /// Logical time after the last transaction.
+    pub end_lt: u64,
+}
+
+struct QueuedMessage {
+    lt: u64,
+    seqno: u64,
+    is_external: bool,
+    dst: StdAddr,
+    root: Cell,
+}
+
+impl QueuedMessage {
+    fn key(&self) -> (u64, u64) {
+        (self.lt, self.seqno)
+    }
+}
+
+impl Eq for QueuedMessage {}
+
+impl PartialEq for QueuedMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Ord for QueuedMessage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // NOTE: Reversed to make a min-heap.
+        other.key().cmp(&self.key())
+    }
+}
+
+impl PartialOrd for QueuedMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::models::{CurrencyCollection, ExtInMsgInfo, IntMsgInfo};
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+
+    #[test]
+    fn emulate_simple_block() -> Result<()> {
+        let config = make_default_config();
+        let mut params = make_default_params();
+        params.block_lt = 1000;
+
+        let local = StdAddr::new(0, HashBytes([0x11; 32]));
+        let remote = StdAddr::new(-1, HashBytes([0x22; 32]));
+
+        let mut emulator = BlockEmulator::new(
+            &params,
+            &config,
+            ShardIdent::BASECHAIN,
+            ShardAccounts::new(),
+        );
+
+        let block = emulator.run([
+            // Transfer to a non-existing local account.
+            make_message(
+                IntMsgInfo {
+                    src: remote.clone().into(),
+                    dst: local.clone().into(),
+                    value: CurrencyCollection::new(1_000_000_000),
+                    bounce: false,
+                    created_lt: 500,
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            // External message to an uninit account is skipped.
+            make_message(
+                ExtInMsgInfo {
+                    src: None,
+                    dst: local.clone().into(),
+                    import_fee: Tokens::ZERO,
+                },
+                None,
+                None,
+            ),
+            // Message to other shard.
+            make_message(
+                IntMsgInfo {
+                    src: local.clone().into(),
+                    dst: remote.clone().into(),
+                    value: CurrencyCollection::new(1_000_000_000),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+        ])?;
+
+        assert_eq!(block.stats.tx_count, 1);
+        assert_eq!(block.stats.skipped_ext_msgs, 1);
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(block.out_msg_queue.len(), 1);
+        assert!(block.unprocessed.is_empty());
+        assert!(block.stats.end_lt > block.stats.start_lt);
+
+        let account = emulator.get_account(&local.address)?.load_account()?;
+        let account = account.expect("account must be created");
+        assert!(!account.balance.tokens.is_zero());
+
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_roundtrip() -> Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let local = StdAddr::new(0, HashBytes([0x11; 32]));
+        let remote = StdAddr::new(-1, HashBytes([0x22; 32]));
+
+        let transfer = |created_lt: u64| {
+            make_message(
+                IntMsgInfo {
+                    src: remote.clone().into(),
+                    dst: local.clone().into(),
+                    value: CurrencyCollection::new(1_000_000_000),
+                    bounce: false,
+                    created_lt,
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+        };
+
+        let mut emulator = BlockEmulator::new(
+            &params,
+            &config,
+            ShardIdent::BASECHAIN,
+            ShardAccounts::new(),
+        )
+        .with_max_transactions(1);
+
+        let block = emulator.run([transfer(10), transfer(20)])?;
+        assert_eq!(block.stats.tx_count, 1);
+        assert_eq!(block.unprocessed.len(), 1);
+
+        let snapshot = EmulatorSnapshot::from_boc(&emulator.snapshot().to_boc()?)?;
+        assert_eq!(snapshot.pending, block.unprocessed);
+        assert_eq!(snapshot.block_lt, block.stats.end_lt);
+
+        let mut restored = BlockEmulator::from_snapshot(&params, &config, snapshot);
+        assert_eq!(
+            restored.get_account(&local.address)?,
+            emulator.get_account(&local.address)?
+        );
+
+        // Pending messages are executed first.
+        let block = restored.run([])?;
+        assert_eq!(block.stats.tx_count, 1);
+        assert!(restored.pending_messages().is_empty());
+
+        Ok(())
+    }
+}
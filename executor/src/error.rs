@@ -7,12 +7,108 @@ pub enum TxError {
     #[error("transaction skipped")]
     Skipped,
     #[error("fatal error")]
-    Fatal(#[from] anyhow::Error),
+    Fatal(#[from] ExecutorError),
+    #[error("execution interrupted")]
+    Interrupted(#[from] tycho_vm::VmInterrupt),
+}
+
+impl From<anyhow::Error> for TxError {
+    fn from(value: anyhow::Error) -> Self {
+        // NOTE: Interruptions are reported by the compute phase
+        // through the `anyhow` error chain.
+        match value.downcast_ref::<tycho_vm::VmInterrupt>() {
+            Some(reason) => Self::Interrupted(*reason),
+            None => Self::Fatal(ExecutorError::from(value)),
+        }
+    }
 }
 
 impl From<everscale_types::error::Error> for TxError {
     #[inline]
     fn from(value: everscale_types::error::Error) -> Self {
-        Self::Fatal(anyhow::Error::from(value))
+        Self::Fatal(ExecutorError::Cell(value))
+    }
+}
+
+/// Fatal executor error.
+///
+/// Transactions are never produced in case of this error.
+#[derive(Debug, thiserror::Error)]
+pub enum ExecutorError {
+    /// Config doesn't allow to execute the transaction.
+    #[error("invalid config: {0}")]
+    Config(&'static str),
+    /// Failed to load or build cells.
+    #[error("cell error")]
+    Cell(#[from] everscale_types::error::Error),
+    /// Message or state exceeds the size limits.
+    #[error("limit exceeded: {0}")]
+    LimitExceeded(&'static str),
+    /// Execution reached a pruned branch of the state
+    /// reconstructed from a Merkle proof.
+    ///
+    /// Contains the hash of the missing cell. The transaction
+    /// can be executed again with a proof which includes this cell.
+    ///
+    /// Reported only when [`ExecutorParams::pruned_state`] is set.
+    ///
+    /// [`ExecutorParams::pruned_state`]: crate::ExecutorParams::pruned_state
+    #[error("pruned branch accessed: {0}")]
+    PrunedBranchAccess(everscale_types::cell::HashBytes),
+    /// Inconsistent executor input or state.
+    #[error("invariant violated")]
+    Invariant(#[source] anyhow::Error),
+}
+
+impl From<anyhow::Error> for ExecutorError {
+    fn from(value: anyhow::Error) -> Self {
+        // NOTE: Phases report typed errors through the `anyhow` error chain.
+        let value = match value.downcast::<Self>() {
+            Ok(error) => return error,
+            Err(value) => value,
+        };
+        match value.downcast::<everscale_types::error::Error>() {
+            Ok(error) => Self::Cell(error),
+            Err(value) => Self::Invariant(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn classify_phase_errors() {
+        let err = anyhow::Error::from(everscale_types::error::Error::CellUnderflow)
+            .context("compute phase failed");
+        assert!(matches!(
+            TxError::from(err),
+            TxError::Fatal(ExecutorError::Cell(
+                everscale_types::error::Error::CellUnderflow
+            ))
+        ));
+
+        let err = Err::<(), _>(ExecutorError::LimitExceeded("inbound message"))
+            .context("storage phase failed")
+            .unwrap_err();
+        assert!(matches!(
+            TxError::from(err),
+            TxError::Fatal(ExecutorError::LimitExceeded("inbound message"))
+        ));
+
+        let err = anyhow::anyhow!("something went wrong").context("action phase failed");
+        assert!(matches!(
+            TxError::from(err),
+            TxError::Fatal(ExecutorError::Invariant(_))
+        ));
+
+        let err = anyhow::Error::from(tycho_vm::VmInterrupt::Cancelled);
+        assert!(matches!(
+            TxError::from(err),
+            TxError::Interrupted(tycho_vm::VmInterrupt::Cancelled)
+        ));
     }
 }
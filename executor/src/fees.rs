@@ -0,0 +1,341 @@
+//! Fee and price formulas.
+//!
+//! Pure functions used by the executor phases. Unless a rounding policy
+//! is passed explicitly, all of them use the reference rounding
+//! (see [`FeeRounding::Reference`]). None of them apply any exemptions
+//! for special accounts.
+
+use everscale_types::cell::CellTreeStats;
+use everscale_types::models::StorageUsed;
+use everscale_types::num::Tokens;
+use everscale_types::prelude::*;
+
+use crate::util::{shift_ceil_price, ExtStorageStat, FeeRounding, StorageStatLimits};
+use crate::ParsedConfig;
+
+/// Computes a forwarding fee for the message of the specified size.
+///
+/// NOTE: `stats` must not include the root cell.
+pub fn compute_fwd_fee(
+    config: &ParsedConfig,
+    rounding: FeeRounding,
+    is_masterchain: bool,
+    stats: CellTreeStats,
+) -> Tokens {
+    rounding.compute_fwd_fee(config.fwd_prices(is_masterchain), stats)
+}
+
+/// Computes a forwarding fee for the serialized message.
+///
+/// Returns `None` if the message exceeds the size limits.
+pub fn compute_msg_fwd_fee(
+    config: &ParsedConfig,
+    is_masterchain: bool,
+    msg: &DynCell,
+) -> Option<Tokens> {
+    let cs = msg.as_slice_allow_exotic();
+    let mut stats = ExtStorageStat::compute_for_slice(
+        &cs,
+        StorageStatLimits::for_message(&config.size_limits),
+    )?;
+
+    stats.cell_count -= 1; // root cell is ignored.
+    stats.bit_count -= cs.size_bits() as u64; // bits in the root cells are free.
+
+    Some(compute_fwd_fee(
+        config,
+        FeeRounding::Reference,
+        is_masterchain,
+        stats,
+    ))
+}
+
+/// Forwarding fee split between the sender and the next hops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FwdFeeSplit {
+    /// Part collected when the message is sent.
+    pub first_frac: Tokens,
+    /// Part which remains in the message (`fwd_fee` field).
+    pub remaining: Tokens,
+}
+
+/// Splits the forwarding fee into the collected part and the part
+/// which remains in the message.
+pub fn split_fwd_fee(config: &ParsedConfig, is_masterchain: bool, fwd_fee: Tokens) -> FwdFeeSplit {
+    let first_frac = config.fwd_prices(is_masterchain).get_first_part(fwd_fee);
+    FwdFeeSplit {
+        first_frac,
+        remaining: fwd_fee - first_frac,
+    }
+}
+
+/// Computes a fee for the used gas (including the flat gas price).
+pub fn compute_gas_fee(config: &ParsedConfig, is_masterchain: bool, gas_used: u64) -> Tokens {
+    config.gas_prices(is_masterchain).compute_gas_fee(gas_used)
+}
+
+/// Computes a part of the collected fees which is burned
+/// (according to the burning config, `ConfigParam5`).
+///
+/// The result is rounded down.
+pub fn compute_burned_fees(config: &ParsedConfig, fees: Tokens) -> Tokens {
+    if config.fee_burn_num == 0 {
+        return Tokens::ZERO;
+    }
+
+    let burned = fees
+        .into_inner()
+        .saturating_mul(config.fee_burn_num as u128)
+        / config.fee_burn_denom.get() as u128;
+    Tokens::new(std::cmp::min(burned, fees.into_inner()))
+}
+
+/// Storage prices active during a time interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoragePricesSegment {
+    /// Start of the interval (inclusive).
+    pub since: u32,
+    /// End of the interval (exclusive).
+    pub until: u32,
+    /// Price of storing one bit for one second (fixed point, 16 bits).
+    pub bit_price_ps: u64,
+    /// Price of storing one cell for one second (fixed point, 16 bits).
+    pub cell_price_ps: u64,
+}
+
+impl StoragePricesSegment {
+    /// Computes a fee for storing `used` bits and cells during this interval.
+    ///
+    /// Returns a fixed point value (16 bits).
+    pub fn compute_fee_ps(&self, used: &StorageUsed) -> u128 {
+        (self.bit_price_ps as u128 * used.bits.into_inner() as u128)
+            .saturating_add(self.cell_price_ps as u128 * used.cells.into_inner() as u128)
+            .saturating_mul((self.until - self.since) as u128)
+    }
+}
+
+/// Splits the time since `last_paid` and up until `now` into intervals
+/// with different storage prices (in chronological order).
+///
+/// All storage prices entries which were active during this time are used.
+/// Returns an empty list in the same cases when [`compute_storage_fee`]
+/// returns zero.
+pub fn storage_prices_schedule(
+    config: &ParsedConfig,
+    is_masterchain: bool,
+    last_paid: u32,
+    now: u32,
+) -> Vec<StoragePricesSegment> {
+    let mut res = Vec::new();
+
+    // No fees in following cases:
+    // - Time has not moved forward since the last transaction;
+    // - Account was just created (last_paid: 0);
+    if now <= last_paid || last_paid == 0 {
+        return res;
+    }
+
+    // NOTE: Storage prices are sorted by `utime_since`.
+    let prices = &config.storage_prices;
+    for (i, item) in prices.iter().enumerate() {
+        // Each entry is active until the next one (or until now).
+        let since = std::cmp::max(item.utime_since, last_paid);
+        let until = match prices.get(i + 1) {
+            Some(next) => std::cmp::min(next.utime_since, now),
+            None => now,
+        };
+        if since >= until {
+            continue;
+        }
+
+        let (bit_price_ps, cell_price_ps) = if is_masterchain {
+            (item.mc_bit_price_ps, item.mc_cell_price_ps)
+        } else {
+            (item.bit_price_ps, item.cell_price_ps)
+        };
+        res.push(StoragePricesSegment {
+            since,
+            until,
+            bit_price_ps,
+            cell_price_ps,
+        });
+    }
+
+    res
+}
+
+/// Computes a fee for storing `used` bits and cells
+/// since `last_paid` and up until `now`.
+///
+/// Fees are computed piecewise for all storage prices entries which
+/// were active during this time (see [`storage_prices_schedule`]).
+/// Zero `last_paid` means that the account was just created.
+///
+/// NOTE: This fee doesn't include `due_payment`.
+pub fn compute_storage_fee(
+    config: &ParsedConfig,
+    is_masterchain: bool,
+    used: &StorageUsed,
+    last_paid: u32,
+    now: u32,
+) -> Tokens {
+    let mut total = 0u128;
+    for segment in storage_prices_schedule(config, is_masterchain, last_paid, now) {
+        total = total.saturating_add(segment.compute_fee_ps(used));
+    }
+
+    // Convert from fixed point int (rounded once for all segments).
+    Tokens::new(shift_ceil_price(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use std::rc::Rc;
+
+    use everscale_types::models::{BurningConfig, IntMsgInfo, StdAddr, StoragePrices};
+    use everscale_types::num::VarUint56;
+
+    use super::*;
+    use crate::testkit::{make_custom_config, make_default_config, make_message};
+
+    #[test]
+    fn fee_formulas() {
+        let config = make_default_config();
+
+        // Forwarding fees.
+        let body = {
+            let mut b = CellBuilder::new();
+            b.store_reference(CellBuilder::build_from(123u32).unwrap())
+                .unwrap();
+            b
+        };
+        let msg = make_message(
+            IntMsgInfo {
+                src: StdAddr::new(0, HashBytes::ZERO).into(),
+                dst: StdAddr::new(0, HashBytes::ZERO).into(),
+                ..Default::default()
+            },
+            None,
+            Some(body),
+        );
+        let fwd_fee = compute_msg_fwd_fee(&config, false, msg.as_ref()).unwrap();
+        assert_eq!(
+            fwd_fee,
+            config.fwd_prices.compute_fwd_fee(CellTreeStats {
+                bit_count: 32,
+                cell_count: 1,
+            })
+        );
+
+        let split = split_fwd_fee(&config, false, fwd_fee);
+        assert_eq!(split.first_frac, config.fwd_prices.get_first_part(fwd_fee));
+        assert_eq!(split.first_frac + split.remaining, fwd_fee);
+
+        // Gas fees.
+        assert_eq!(
+            compute_gas_fee(&config, true, 1000),
+            config.mc_gas_prices.compute_gas_fee(1000)
+        );
+
+        // Storage fees.
+        let used = StorageUsed {
+            cells: VarUint56::new(10),
+            bits: VarUint56::new(1000),
+            public_cells: VarUint56::ZERO,
+        };
+        let prices = config.storage_prices.last().unwrap();
+        let since = prices.utime_since + 1;
+        let fee = (prices.bit_price_ps as u128 * 1000 + prices.cell_price_ps as u128 * 10) * 100;
+        assert_eq!(
+            compute_storage_fee(&config, false, &used, since, since + 100),
+            Tokens::new(shift_ceil_price(fee))
+        );
+        assert_eq!(
+            compute_storage_fee(&config, false, &used, 0, since + 100),
+            Tokens::ZERO
+        );
+    }
+
+    #[test]
+    fn fee_burning() {
+        let config = make_default_config();
+        assert_eq!(
+            compute_burned_fees(&config, Tokens::new(1000)),
+            Tokens::new(1000 * config.fee_burn_num as u128 / config.fee_burn_denom.get() as u128)
+        );
+
+        let config = make_custom_config(|config| {
+            config.set_burning_config(&BurningConfig {
+                fee_burn_num: 1,
+                fee_burn_denom: NonZeroU32::new(3).unwrap(),
+                ..Default::default()
+            })?;
+            Ok(())
+        });
+        assert_eq!(
+            compute_burned_fees(&config, Tokens::new(1000)),
+            Tokens::new(333)
+        );
+        assert_eq!(compute_burned_fees(&config, Tokens::ZERO), Tokens::ZERO);
+    }
+
+    #[test]
+    fn multi_epoch_storage_fees() {
+        let mut config = make_custom_config(|_| Ok(()));
+        let prices = |utime_since: u32, price: u64| StoragePrices {
+            utime_since,
+            bit_price_ps: price,
+            cell_price_ps: price * 100,
+            mc_bit_price_ps: price * 1000,
+            mc_cell_price_ps: price * 100000,
+        };
+        Rc::get_mut(&mut config).unwrap().storage_prices =
+            vec![prices(1000, 1), prices(2000, 2), prices(3000, 3)];
+
+        let used = StorageUsed {
+            cells: VarUint56::new(10),
+            bits: VarUint56::new(1000),
+            public_cells: VarUint56::ZERO,
+        };
+        let fee_ps = |price: u128, delta: u128| (1000 * price + 10 * 100 * price) * delta;
+
+        // All epochs.
+        let schedule = storage_prices_schedule(&config, false, 1500, 3500);
+        assert_eq!(
+            schedule
+                .iter()
+                .map(|s| (s.since, s.until, s.bit_price_ps))
+                .collect::<Vec<_>>(),
+            [(1500, 2000, 1), (2000, 3000, 2), (3000, 3500, 3)]
+        );
+        assert_eq!(
+            compute_storage_fee(&config, false, &used, 1500, 3500),
+            Tokens::new(shift_ceil_price(
+                fee_ps(1, 500) + fee_ps(2, 1000) + fee_ps(3, 500)
+            ))
+        );
+
+        // Masterchain prices.
+        let schedule = storage_prices_schedule(&config, true, 1500, 2500);
+        assert_eq!(schedule[0].cell_price_ps, 100000);
+        assert_eq!(schedule[1].cell_price_ps, 200000);
+
+        // Before the first epoch.
+        assert_eq!(storage_prices_schedule(&config, false, 500, 1500), [
+            StoragePricesSegment {
+                since: 1000,
+                until: 1500,
+                bit_price_ps: 1,
+                cell_price_ps: 100,
+            }
+        ]);
+        assert!(storage_prices_schedule(&config, false, 100, 1000).is_empty());
+
+        // Inside a single epoch.
+        assert_eq!(
+            compute_storage_fee(&config, false, &used, 2100, 2200),
+            Tokens::new(shift_ceil_price(fee_ps(2, 100)))
+        );
+    }
+}
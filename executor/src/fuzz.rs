@@ -0,0 +1,408 @@
+//! [`Arbitrary`] generators for fuzzing the action phase.
+//!
+//! Generated values are structurally valid (they can be stored into
+//! cells) but are biased towards edge cases: exotic cells, cells at
+//! the depth limit, invalid mode bits and extreme prices.
+
+use std::path::Path;
+
+use arbitrary::{Arbitrary, Unstructured};
+use everscale_types::arbitrary::OrdinaryCell;
+use everscale_types::error::Error;
+use everscale_types::models::{
+    ChangeLibraryMode, CurrencyCollection, IntAddr, LibRef, MsgForwardPrices, OutAction,
+    OwnedRelaxedMessage, RelaxedExtOutMsgInfo, RelaxedIntMsgInfo, RelaxedMessage, RelaxedMsgInfo,
+    ReserveCurrencyFlags, SendMsgFlags, SizeLimitsConfig, StateInit, StdAddr,
+};
+use everscale_types::num::Tokens;
+use everscale_types::prelude::*;
+
+use crate::ParsedConfig;
+
+/// Max depth of a valid cell tree.
+const MAX_DEPTH: u16 = 1024;
+/// Max number of actions in a valid action list.
+const MAX_ACTIONS: usize = 255;
+/// Large enough to overflow any realistic balance.
+const BIG_VALUE: u128 = 100_000_000_000_000_000_000;
+
+/// Cell which is either an arbitrary ordinary cell or an adversarial one.
+#[derive(Debug, Clone)]
+pub struct AdversarialCell(pub Cell);
+
+impl<'a> Arbitrary<'a> for AdversarialCell {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let cell = match u.int_in_range(0..=5u8)? {
+            0 => CorpusBuilder::deep_chain(u.int_in_range(0..=MAX_DEPTH)?),
+            1 => CorpusBuilder::library_ref(&HashBytes(u.arbitrary()?)),
+            2 => CorpusBuilder::pruned_branch(&u.arbitrary::<OrdinaryCell>()?.0),
+            3 => CorpusBuilder::merkle_proof(u.arbitrary::<OrdinaryCell>()?.0),
+            4 => CorpusBuilder::wide_tree(u.int_in_range(0..=6)?),
+            _ => return Ok(Self(u.arbitrary::<OrdinaryCell>()?.0)),
+        };
+        cell.map(Self)
+            .map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+
+    #[inline]
+    fn size_hint(_: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+/// Send message mode.
+///
+/// Mostly a valid base mode with some flags, sometimes any 8 bits.
+#[derive(Debug, Clone, Copy)]
+pub struct AnySendMode(pub SendMsgFlags);
+
+impl<'a> Arbitrary<'a> for AnySendMode {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        const BASE: [u8; 3] = [0, 64, 128];
+        const FLAGS: [u8; 4] = [1, 2, 16, 32];
+
+        let bits = if u.ratio(1, 16)? {
+            u.arbitrary::<u8>()?
+        } else {
+            let mut bits = *u.choose(&BASE)?;
+            for flag in FLAGS {
+                if u.arbitrary()? {
+                    bits |= flag;
+                }
+            }
+            bits
+        };
+        Ok(Self(SendMsgFlags::from_bits_retain(bits)))
+    }
+
+    #[inline]
+    fn size_hint(_: usize) -> (usize, Option<usize>) {
+        (1, Some(6))
+    }
+}
+
+/// Outbound message from the `SENDMSG` action.
+#[derive(Debug, Clone)]
+pub struct AnyRelaxedMessage(pub Lazy<OwnedRelaxedMessage>);
+
+impl<'a> Arbitrary<'a> for AnyRelaxedMessage {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let info = if u.ratio(1, 8)? {
+            RelaxedMsgInfo::ExtOut(RelaxedExtOutMsgInfo {
+                src: None,
+                dst: None,
+                created_lt: u.arbitrary()?,
+                created_at: u.arbitrary()?,
+            })
+        } else {
+            RelaxedMsgInfo::Int(RelaxedIntMsgInfo {
+                ihr_disabled: u.arbitrary()?,
+                bounce: u.arbitrary()?,
+                bounced: u.arbitrary()?,
+                src: if u.arbitrary()? {
+                    Some(any_std_addr(u)?)
+                } else {
+                    None
+                },
+                dst: any_std_addr(u)?,
+                value: CurrencyCollection::new(u.int_in_range(0..=BIG_VALUE)?),
+                ihr_fee: Tokens::new(u.int_in_range(0..=BIG_VALUE)?),
+                fwd_fee: Tokens::new(u.int_in_range(0..=BIG_VALUE)?),
+                created_lt: u.arbitrary()?,
+                created_at: u.arbitrary()?,
+            })
+        };
+
+        let init = if u.ratio(1, 4)? {
+            Some(StateInit {
+                code: Some(u.arbitrary::<AdversarialCell>()?.0),
+                data: Some(u.arbitrary::<AdversarialCell>()?.0),
+                ..Default::default()
+            })
+        } else {
+            None
+        };
+
+        let mut body = CellBuilder::new();
+        if u.arbitrary()? {
+            body.store_reference(u.arbitrary::<AdversarialCell>()?.0)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        }
+
+        let msg = Lazy::new(&RelaxedMessage {
+            info,
+            init,
+            body: body.as_full_slice(),
+            layout: None,
+        })
+        .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+
+        Ok(Self(msg.cast_into()))
+    }
+
+    #[inline]
+    fn size_hint(_: usize) -> (usize, Option<usize>) {
+        (8, None)
+    }
+}
+
+/// Single out action.
+#[derive(Debug, Clone)]
+pub struct AnyOutAction(pub OutAction);
+
+impl<'a> Arbitrary<'a> for AnyOutAction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self(match u.int_in_range(0..=3u8)? {
+            0 => OutAction::SendMsg {
+                mode: u.arbitrary::<AnySendMode>()?.0,
+                out_msg: u.arbitrary::<AnyRelaxedMessage>()?.0,
+            },
+            1 => OutAction::SetCode {
+                new_code: u.arbitrary::<AdversarialCell>()?.0,
+            },
+            2 => OutAction::ReserveCurrency {
+                mode: ReserveCurrencyFlags::from_bits_retain(u.arbitrary()?),
+                value: CurrencyCollection::new(u.int_in_range(0..=BIG_VALUE)?),
+            },
+            _ => OutAction::ChangeLibrary {
+                mode: ChangeLibraryMode::from_bits_retain(u.arbitrary()?),
+                lib: if u.arbitrary()? {
+                    LibRef::Hash(HashBytes(u.arbitrary()?))
+                } else {
+                    LibRef::Cell(u.arbitrary::<AdversarialCell>()?.0)
+                },
+            },
+        }))
+    }
+
+    #[inline]
+    fn size_hint(_: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+/// Action list cell (`c5`).
+///
+/// Can contain slightly more actions than allowed.
+#[derive(Debug, Clone)]
+pub struct AnyOutActions(pub Cell);
+
+impl<'a> Arbitrary<'a> for AnyOutActions {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=MAX_ACTIONS + 1)?;
+
+        let mut root = Cell::empty_cell();
+        for _ in 0..len {
+            if u.is_empty() {
+                break;
+            }
+            let AnyOutAction(action) = u.arbitrary()?;
+            root = CellBuilder::build_from((root, action))
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        }
+        Ok(Self(root))
+    }
+
+    #[inline]
+    fn size_hint(_: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+/// Config params used by the action phase.
+#[derive(Debug, Clone)]
+pub struct AnyConfigSubset {
+    pub fwd_prices: MsgForwardPrices,
+    pub mc_fwd_prices: MsgForwardPrices,
+    pub size_limits: SizeLimitsConfig,
+}
+
+impl AnyConfigSubset {
+    /// Replaces the parsed params.
+    ///
+    /// NOTE: Raw and unpacked (VM) configs are left untouched.
+    pub fn apply(&self, config: &mut ParsedConfig) {
+        config.fwd_prices = self.fwd_prices.clone();
+        config.mc_fwd_prices = self.mc_fwd_prices.clone();
+        config.size_limits = self.size_limits.clone();
+    }
+}
+
+impl<'a> Arbitrary<'a> for AnyConfigSubset {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        fn fwd_prices(u: &mut Unstructured<'_>) -> arbitrary::Result<MsgForwardPrices> {
+            Ok(MsgForwardPrices {
+                lump_price: u.int_in_range(0..=1_000_000_000)?,
+                bit_price: u.int_in_range(0..=1 << 32)?,
+                cell_price: u.int_in_range(0..=1 << 40)?,
+                ihr_price_factor: u.arbitrary()?,
+                first_frac: u.arbitrary()?,
+                next_frac: u.arbitrary()?,
+            })
+        }
+
+        Ok(Self {
+            fwd_prices: fwd_prices(u)?,
+            mc_fwd_prices: fwd_prices(u)?,
+            size_limits: SizeLimitsConfig {
+                max_msg_bits: u.int_in_range(0..=1 << 21)?,
+                max_msg_cells: u.int_in_range(0..=1 << 13)?,
+                max_library_cells: u.int_in_range(0..=1000)?,
+                max_vm_data_depth: u.int_in_range(0..=512)?,
+                max_ext_msg_size: u.int_in_range(0..=65535)?,
+                max_ext_msg_depth: u.int_in_range(0..=512)?,
+                max_acc_state_cells: u.int_in_range(0..=1 << 16)?,
+                max_acc_state_bits: u.int_in_range(0..=(1 << 16) * 1023)?,
+                max_acc_public_libraries: u.int_in_range(0..=256)?,
+                defer_out_queue_size_limit: u.int_in_range(0..=256)?,
+            },
+        })
+    }
+
+    #[inline]
+    fn size_hint(_: usize) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+/// Builder of a seed corpus with adversarial cells.
+#[derive(Default)]
+pub struct CorpusBuilder {
+    cells: Vec<Cell>,
+}
+
+impl CorpusBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the default set of adversarial cells.
+    pub fn with_defaults(mut self) -> Result<Self, Error> {
+        let leaf = CellBuilder::build_from(0xdeafbeafu32)?;
+
+        self.cells.extend([
+            Self::deep_chain(MAX_DEPTH - 1)?,
+            Self::deep_chain(MAX_DEPTH)?,
+            Self::wide_tree(7)?,
+            Self::library_ref(leaf.repr_hash())?,
+            Self::pruned_branch(&leaf)?,
+            Self::merkle_proof(leaf.clone())?,
+            Self::merkle_proof(Self::pruned_branch(&leaf)?)?,
+        ]);
+        Ok(self)
+    }
+
+    /// Adds a cell to the corpus.
+    pub fn push(&mut self, cell: Cell) {
+        self.cells.push(cell);
+    }
+
+    /// Returns the corpus cells.
+    pub fn build(self) -> Vec<Cell> {
+        self.cells
+    }
+
+    /// Writes each cell as a BOC file named by its hash.
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for cell in &self.cells {
+            std::fs::write(dir.join(cell.repr_hash().to_string()), Boc::encode(cell))?;
+        }
+        Ok(())
+    }
+
+    /// Chain of cells with the specified depth.
+    pub fn deep_chain(depth: u16) -> Result<Cell, Error> {
+        let mut cell = Cell::empty_cell();
+        for _ in 0..depth {
+            let mut b = CellBuilder::new();
+            b.store_reference(cell)?;
+            cell = b.build()?;
+        }
+        Ok(cell)
+    }
+
+    /// Full tree with 4 refs per cell.
+    ///
+    /// Different cells on each level to avoid deduplication.
+    pub fn wide_tree(depth: u8) -> Result<Cell, Error> {
+        fn make(depth: u8, index: &mut u32) -> Result<Cell, Error> {
+            let mut b = CellBuilder::new();
+            b.store_u32(*index)?;
+            *index += 1;
+            if depth > 0 {
+                for _ in 0..4 {
+                    b.store_reference(make(depth - 1, index)?)?;
+                }
+            }
+            b.build()
+        }
+
+        make(depth, &mut 0)
+    }
+
+    /// Library reference exotic cell.
+    pub fn library_ref(hash: &HashBytes) -> Result<Cell, Error> {
+        let mut b = CellBuilder::new();
+        b.set_exotic(true);
+        b.store_u8(2)?;
+        b.store_u256(hash)?;
+        b.build()
+    }
+
+    /// Pruned branch exotic cell in place of the specified cell.
+    pub fn pruned_branch(cell: &DynCell) -> Result<Cell, Error> {
+        let mut b = CellBuilder::new();
+        b.set_exotic(true);
+        b.store_u8(1)?;
+        b.store_u8(1)?; // level mask
+        b.store_u256(cell.repr_hash())?;
+        b.store_u16(cell.repr_depth())?;
+        b.build()
+    }
+
+    /// Merkle proof exotic cell with the specified cell as a root.
+    pub fn merkle_proof(cell: Cell) -> Result<Cell, Error> {
+        let mut b = CellBuilder::new();
+        b.set_exotic(true);
+        b.store_u8(3)?;
+        b.store_u256(cell.hash(0))?;
+        b.store_u16(cell.depth(0))?;
+        b.store_reference(cell)?;
+        b.build()
+    }
+}
+
+fn any_std_addr(u: &mut Unstructured<'_>) -> arbitrary::Result<IntAddr> {
+    let workchain = match u.int_in_range(0..=3u8)? {
+        0 | 1 => 0,
+        2 => -1,
+        _ => u.arbitrary()?,
+    };
+    Ok(StdAddr::new(workchain, HashBytes(u.arbitrary()?)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_corpus() -> anyhow::Result<()> {
+        let cells = CorpusBuilder::new().with_defaults()?.build();
+        assert_eq!(cells.len(), 7);
+
+        assert_eq!(cells[0].repr_depth(), MAX_DEPTH - 1);
+        assert_eq!(cells[1].repr_depth(), MAX_DEPTH);
+        assert!(!cells[2].is_exotic());
+        for cell in &cells[3..] {
+            assert!(cell.is_exotic());
+        }
+
+        // Merkle proof hides the level of the pruned branch.
+        assert_eq!(cells[5].level(), 0);
+        assert_eq!(cells[6].level(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use everscale_types::models::{AccountState, CurrencyCollection};
+use everscale_types::num::Tokens;
+use everscale_types::prelude::*;
+use num_bigint::BigInt;
+use tycho_vm::{GasParams, RcStackValue, SafeRc, SmcInfoBase, Stack, VmInterrupt, VmState};
+
+use crate::{ExecutorError, ExecutorState};
+
+/// Get-method execution result.
+#[derive(Debug, Clone)]
+pub struct GetterOutput {
+    /// VM exit code (`0` or `1` on success).
+    pub exit_code: i32,
+    /// VM stack after the execution.
+    pub stack: SafeRc<Stack>,
+    /// Gas used by the execution.
+    pub gas_used: u64,
+    /// Hash of the first library which could not be resolved.
+    pub missing_library: Option<HashBytes>,
+}
+
+impl GetterOutput {
+    /// Returns `true` if the get-method finished without errors.
+    pub const fn is_ok(&self) -> bool {
+        self.exit_code == 0 || self.exit_code == 1
+    }
+}
+
+/// Get-method execution error.
+#[derive(Debug, thiserror::Error)]
+pub enum GetterError {
+    #[error("account is not active")]
+    AccountNotActive,
+    #[error("account has no code")]
+    NoCode,
+    #[error("fatal error")]
+    Fatal(#[from] ExecutorError),
+    #[error("execution interrupted")]
+    Interrupted(#[from] VmInterrupt),
+}
+
+impl ExecutorState<'_> {
+    /// Runs a get-method of the active account.
+    ///
+    /// Builds `c7` from the account state, executor params and config
+    /// (the same way as the compute phase does, but without an inbound
+    /// message) and runs the VM with getter gas limits.
+    ///
+    /// `args` are passed in the stack order (the last one is on top),
+    /// `method_id` is pushed on top of them.
+    ///
+    /// NOTE: Account state is not changed.
+    pub fn run_getter(
+        &self,
+        method_id: i32,
+        args: Vec<RcStackValue>,
+    ) -> Result<GetterOutput, GetterError> {
+        let AccountState::Active(state) = &self.state else {
+            return Err(GetterError::AccountNotActive);
+        };
+        let Some(code) = state.code.clone() else {
+            return Err(GetterError::NoCode);
+        };
+
+        let mut smc_info = SmcInfoBase::new()
+            .with_now(self.params.block_unixtime)
+            .with_block_lt(self.params.block_lt)
+            .with_tx_lt(self.start_lt)
+            .with_raw_rand_seed(self.params.rand_seed_derivation.derive(
+                &self.params.rand_seed,
+                &self.address.address,
+                self.start_lt,
+            ))
+            .with_account_balance(self.balance.clone())
+            .with_account_addr(self.address.clone().into())
+            .with_config(self.config.raw.params.clone())
+            .require_ton_v4()
+            .with_code(code.clone())
+            .with_message_balance(CurrencyCollection::ZERO)
+            .with_storage_fees(Tokens::ZERO);
+        if let Some(info) = &self.params.prev_blocks_info {
+            smc_info = smc_info.with_prev_blocks_info(info.as_tuple());
+        }
+        let smc_info = smc_info
+            .require_ton_v6()
+            .with_unpacked_config(self.config.unpacked.as_tuple())
+            .with_due_payment(self.storage_stat.due_payment.unwrap_or_default())
+            .with_precompiled_gas_usage(self.config.precompiled_gas_usage(code.repr_hash()))
+            .require_ton_v11();
+
+        let mut stack = args;
+        stack.push(SafeRc::new_dyn_value(BigInt::from(method_id)));
+
+        let libraries = (&state.libraries, &self.params.libraries);
+        let mut vm = VmState::builder()
+            .with_smc_info(smc_info)
+            .with_code(code)
+            .with_data(state.data.clone().unwrap_or_default())
+            .with_libraries(&libraries)
+            .with_init_selector(false)
+            .with_raw_stack(SafeRc::new(Stack::with_items(stack)))
+            .with_gas(GasParams::getter())
+            .with_modifiers(self.params.vm_modifiers)
+            .with_limits(self.params.execution_limits.clone())
+            .build();
+
+        let exit_code = !vm.run();
+        if let Some(reason) = vm.interrupted {
+            return Err(reason.into());
+        }
+        if let Some(hash) = vm.gas.pruned_branch().filter(|_| self.params.pruned_state) {
+            return Err(ExecutorError::PrunedBranchAccess(hash).into());
+        }
+
+        Ok(GetterOutput {
+            exit_code,
+            stack: vm.stack.clone(),
+            gas_used: vm.gas.consumed(),
+            missing_library: vm.gas.missing_library(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_asm_macros::tvmasm;
+    use everscale_types::cell::CellType;
+    use everscale_types::models::{BlockId, ShardIdent, StdAddr};
+    use tycho_vm::{tuple, PrevBlocksInfo};
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params};
+    use crate::ExecutorParams;
+
+    #[test]
+    fn run_getters() -> anyhow::Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let addr = StdAddr::new(0, HashBytes([0x11; 32]));
+
+        let state = ExecutorState::new_active(
+            &params,
+            &config,
+            &addr,
+            Tokens::new(1_000_000_000),
+            CellBuilder::build_from(123u32)?,
+            tvmasm!(
+                r#"
+                // get_value
+                DUP PUSHINT 1 EQUAL
+                PUSHCONT { DROP PUSHCTR c4 CTOS LDU 32 ENDS ADD }
+                IFJMP
+                // get_balance
+                PUSHINT 2 EQUAL
+                PUSHCONT { BALANCE FIRST }
+                IFJMP
+                THROW 11
+                "#
+            ),
+        );
+
+        let output = state.run_getter(1, tuple![int 10])?;
+        assert!(output.is_ok());
+        assert_eq!(output.stack.items.len(), 1);
+        assert_eq!(output.stack.get_exit_arg(), Some(133));
+        assert!(output.gas_used > 0);
+
+        let output = state.run_getter(2, Vec::new())?;
+        assert!(output.is_ok());
+        assert_eq!(output.stack.get_exit_arg(), Some(1_000_000_000));
+
+        let output = state.run_getter(3, Vec::new())?;
+        assert_eq!(output.exit_code, 11);
+
+        let state = ExecutorState::new_uninit(&params, &config, &addr, Tokens::ZERO);
+        assert!(matches!(
+            state.run_getter(1, Vec::new()),
+            Err(GetterError::AccountNotActive)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn getter_prev_blocks_info() -> anyhow::Result<()> {
+        let block_id = |seqno: u32| BlockId {
+            shard: ShardIdent::MASTERCHAIN,
+            seqno,
+            root_hash: HashBytes::ZERO,
+            file_hash: HashBytes::ZERO,
+        };
+
+        let mut params = make_default_params();
+        let config = make_default_config();
+        let addr = StdAddr::new(0, HashBytes([0x11; 32]));
+
+        let code = tvmasm!("DROP PREVBLOCKSINFOTUPLE ISNULL");
+        let state = ExecutorState::new_active(
+            &params,
+            &config,
+            &addr,
+            Tokens::new(1_000_000_000),
+            Cell::empty_cell(),
+            code,
+        );
+        let output = state.run_getter(0, Vec::new())?;
+        assert!(output.is_ok());
+        assert_eq!(output.stack.get_exit_arg(), Some(-1));
+
+        params.prev_blocks_info = Some(PrevBlocksInfo {
+            last_mc_blocks: vec![block_id(100), block_id(99)],
+            prev_key_block: block_id(90),
+            last_mc_blocks_100: None,
+        });
+
+        let code = tvmasm!("DROP PREVKEYBLOCK INDEX 2");
+        let state = ExecutorState::new_active(
+            &params,
+            &config,
+            &addr,
+            Tokens::new(1_000_000_000),
+            Cell::empty_cell(),
+            code,
+        );
+        let output = state.run_getter(0, Vec::new())?;
+        assert!(output.is_ok());
+        assert_eq!(output.stack.get_exit_arg(), Some(90));
+
+        Ok(())
+    }
+
+    #[test]
+    fn getter_missing_library() -> anyhow::Result<()> {
+        let mut params = make_default_params();
+        let config = make_default_config();
+        let addr = StdAddr::new(0, HashBytes([0x11; 32]));
+
+        let library_hash = HashBytes([0x22; 32]);
+        let library = {
+            let mut b = CellBuilder::new();
+            b.set_exotic(true);
+            b.store_u8(CellType::LibraryReference.to_byte())?;
+            b.store_u256(&library_hash)?;
+            b.build()?
+        };
+
+        let code = tvmasm!("DROP CTOS SBITS");
+        let run = |params: &ExecutorParams| {
+            let state = ExecutorState::new_active(
+                params,
+                &config,
+                &addr,
+                Tokens::new(1_000_000_000),
+                Cell::empty_cell(),
+                code,
+            );
+            state.run_getter(0, vec![SafeRc::new_dyn_value(library.clone())])
+        };
+
+        let output = run(&params)?;
+        assert_eq!(output.exit_code, 9);
+        assert_eq!(output.missing_library, Some(library_hash));
+
+        // Missing library is resolved as an empty cell.
+        params.vm_modifiers.missing_libraries_as_empty = true;
+        let output = run(&params)?;
+        assert!(output.is_ok());
+        assert_eq!(output.stack.get_exit_arg(), Some(0));
+        assert_eq!(output.missing_library, Some(library_hash));
+
+        Ok(())
+    }
+}
@@ -4,23 +4,61 @@ use everscale_types::dict;
 use everscale_types::error::Error;
 use everscale_types::models::{
     Account, AccountState, AccountStatus, CurrencyCollection, HashUpdate, IntAddr, LibDescr,
-    Message, OwnedMessage, ShardAccount, SimpleLib, StdAddr, StorageInfo, StorageUsed, TickTock,
-    Transaction, TxInfo,
+    Message, MessageLayout, OwnedMessage, ShardAccount, SimpleLib, StateInit, StdAddr, StorageInfo,
+    StorageUsed, TickTock, Transaction, TxInfo,
 };
 use everscale_types::num::{Tokens, Uint15, VarUint56};
 use everscale_types::prelude::*;
+use tycho_vm::{SafeRc, SendMsgEstimate, Stack};
 
+pub use self::cache::TxCache;
 pub use self::config::ParsedConfig;
-pub use self::error::{TxError, TxResult};
-use self::util::new_varuint56_truncate;
-pub use self::util::{ExtStorageStat, OwnedExtStorageStat, StorageStatLimits};
+pub use self::emulator::{BlockEmulator, BlockStats, EmulatedBlock, EmulatorSnapshot};
+pub use self::error::{ExecutorError, TxError, TxResult};
+pub use self::getter::{GetterError, GetterOutput};
+pub use self::mempool::{ExtMsgPool, MempoolOrdering, SubmitStatus};
+pub use self::params::{ExecutorParamsBuilder, ExecutorParamsError, ExecutorPreset};
+pub use self::precompiled::{
+    PrecompiledContext, PrecompiledContract, PrecompiledContracts, PrecompiledOutput,
+};
+pub use self::recovery::{check_frozen_state, FrozenStateBuilder, StateRecoveryError};
+pub use self::tx::ExtMsgCheck;
+pub use self::util::{
+    check_state_init_limits, check_state_init_limits_diff, compute_state_stats, normalize_balance,
+    AnycastPolicy, ExtStorageStat, FeeRounding, OwnedExtStorageStat, Quirks, RandSeedDerivation,
+    RelaxedMsgPreview, StateLimitsResult, StateStorageStat, StorageStatLimits, VmLogBuffer,
+};
+use self::util::{find_pruned_branch, new_varuint56_truncate};
 
+#[cfg(feature = "determinism-audit")]
+pub mod audit;
+mod behavior;
+mod cache;
+pub mod codes;
 mod config;
+#[cfg(feature = "differential")]
+pub mod differential;
+mod emulator;
 mod error;
-mod util;
+pub mod fees;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+mod getter;
+mod mempool;
+mod metrics;
+mod params;
+mod precompiled;
+mod recovery;
+#[cfg(feature = "serde")]
+mod serde_helpers;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+#[cfg(feature = "serde")]
+pub mod trace;
+pub mod util;
 
 pub mod phase {
-    pub use self::action::{ActionPhaseContext, ActionPhaseFull};
+    pub use self::action::{ActionPhaseContext, ActionPhaseFull, OutMsgFees};
     pub use self::bounce::BouncePhaseContext;
     pub use self::compute::{ComputePhaseContext, ComputePhaseFull, TransactionInput};
     pub use self::receive::{MsgStateInit, ReceivedMessage};
@@ -35,6 +73,9 @@ pub mod phase {
 }
 
 mod tx {
+    pub use self::ext_check::ExtMsgCheck;
+
+    mod ext_check;
     mod ordinary;
     mod ticktock;
 }
@@ -45,6 +86,8 @@ pub struct Executor<'a> {
     config: &'a ParsedConfig,
     min_lt: u64,
     override_special: Option<bool>,
+    recovered_state: Option<&'a StateInit>,
+    in_msg_lt: Option<u64>,
 }
 
 impl<'a> Executor<'a> {
@@ -54,6 +97,8 @@ impl<'a> Executor<'a> {
             config,
             min_lt: 0,
             override_special: None,
+            recovered_state: None,
+            in_msg_lt: None,
         }
     }
 
@@ -71,6 +116,25 @@ impl<'a> Executor<'a> {
         self
     }
 
+    /// Executes frozen accounts with the specified original state.
+    ///
+    /// The state hash must match the frozen state hash of the account.
+    /// See [`FrozenStateBuilder`].
+    ///
+    /// The account remains frozen (with a hash of the resulting state)
+    /// unless the inbound message unfreezes it with its state init.
+    pub fn with_recovered_state(mut self, state: &'a StateInit) -> Self {
+        self.recovered_state = Some(state);
+        self
+    }
+
+    /// Uses the specified LT instead of the `created_lt` of inbound
+    /// internal messages (e.g. for messages from a dispatch queue).
+    pub fn with_in_msg_lt(mut self, lt: u64) -> Self {
+        self.in_msg_lt = Some(lt);
+        self
+    }
+
     #[inline]
     pub fn begin_ordinary<'s, M>(
         &self,
@@ -85,6 +149,15 @@ impl<'a> Executor<'a> {
         self.begin_ordinary_ext(address, is_external, msg, state, None)
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ordinary_transaction",
+            skip_all,
+            fields(address = %address, is_external = is_external),
+        )
+    )]
     pub fn begin_ordinary_ext<'s, M>(
         &self,
         address: &StdAddr,
@@ -102,7 +175,7 @@ impl<'a> Executor<'a> {
         let mut exec = self.begin(address, account)?;
         let info = exec.run_ordinary_transaction(is_external, msg_root.clone(), inspector)?;
 
-        UncommittedTransaction::with_info(exec, state, Some(msg_root), info).map_err(TxError::Fatal)
+        UncommittedTransaction::with_info(exec, state, Some(msg_root), info).map_err(TxError::from)
     }
 
     #[inline]
@@ -115,6 +188,15 @@ impl<'a> Executor<'a> {
         self.begin_tick_tock_ext(address, kind, state, None)
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "tick_tock_transaction",
+            skip_all,
+            fields(address = %address, kind = ?kind),
+        )
+    )]
     pub fn begin_tick_tock_ext<'s>(
         &self,
         address: &StdAddr,
@@ -126,10 +208,14 @@ impl<'a> Executor<'a> {
         let mut exec = self.begin(address, account)?;
         let info = exec.run_tick_tock_transaction(kind, inspector)?;
 
-        UncommittedTransaction::with_info(exec, state, None, info).map_err(TxError::Fatal)
+        UncommittedTransaction::with_info(exec, state, None, info).map_err(TxError::from)
     }
 
-    pub fn begin(&self, address: &StdAddr, account: Option<Account>) -> Result<ExecutorState<'a>> {
+    pub fn begin(
+        &self,
+        address: &StdAddr,
+        account: Option<Account>,
+    ) -> Result<ExecutorState<'a>, ExecutorError> {
         let is_special = self
             .override_special
             .unwrap_or_else(|| self.config.is_special(address));
@@ -141,6 +227,7 @@ impl<'a> Executor<'a> {
         let orig_status;
         let end_status;
         let start_lt;
+        let mut recovered_state = None;
         match account {
             Some(acc) => {
                 acc_address = 'addr: {
@@ -149,10 +236,19 @@ impl<'a> Executor<'a> {
                             break 'addr acc_addr;
                         }
                     }
-                    anyhow::bail!("account address mismatch");
+                    return Err(ExecutorError::Invariant(anyhow::anyhow!(
+                        "account address mismatch"
+                    )));
                 };
                 acc_storage_stat = acc.storage_stat;
                 acc_balance = acc.balance;
+                if let (AccountState::Frozen(frozen_hash), Some(recovered)) =
+                    (&acc.state, self.recovered_state)
+                {
+                    check_frozen_state(frozen_hash, recovered)
+                        .map_err(|e| ExecutorError::Invariant(e.into()))?;
+                    recovered_state = Some(recovered);
+                }
                 acc_state = acc.state;
                 orig_status = acc_state.status();
                 end_status = orig_status;
@@ -181,10 +277,13 @@ impl<'a> Executor<'a> {
             storage_stat: acc_storage_stat,
             balance: acc_balance,
             state: acc_state,
+            recovered_state,
             orig_status,
             end_status,
+            status_change_reason: None,
             start_lt,
             end_lt: start_lt + 1,
+            in_msg_lt: self.in_msg_lt,
             out_msgs: Vec::new(),
             total_fees: Tokens::ZERO,
             burned: Tokens::ZERO,
@@ -205,8 +304,86 @@ pub struct ExecutorInspector<'e> {
     pub public_libs_diff: Vec<PublicLibraryChange>,
     /// Compute phase exit code.
     pub exit_code: Option<i32>,
+    /// VM stack after the compute phase.
+    pub final_stack: Option<SafeRc<Stack>>,
     /// Debug output target.
     pub debug: Option<&'e mut dyn std::fmt::Write>,
+    /// Bounded buffer for the VM debug output.
+    ///
+    /// Filled during the compute phase (in addition to [`debug`])
+    /// and exported into the transaction trace. With the `tracing`
+    /// feature it also captures VM logs enabled by the `log_mask`
+    /// of [`ExecutorParams::vm_modifiers`].
+    ///
+    /// NOTE: VM log subscriber replaces the default one while the VM is running.
+    ///
+    /// [`debug`]: Self::debug
+    pub vm_log: Option<VmLogBuffer>,
+    /// Outbound messages filter.
+    ///
+    /// Called for each finalized outbound message before it is added
+    /// to the transaction. [`OutMsgVerdict::Veto`] fails the action phase
+    /// with an invalid action (even if the `IGNORE_ERROR` flag is set).
+    pub out_msg_filter: Option<&'e mut OutMsgFilter<'e>>,
+    /// Collect `SENDMSG` estimates during the compute phase
+    /// and compare them with the sent messages.
+    pub check_send_msg_estimates: bool,
+    /// `SENDMSG` estimates from the compute phase.
+    pub send_msg_estimates: Vec<SendMsgEstimate>,
+    /// Sent messages which differ from their `SENDMSG` estimates.
+    pub send_msg_divergences: Vec<SendMsgDivergence>,
+}
+
+impl ExecutorInspector<'_> {
+    /// Folds [`public_libs_diff`] into a list of updates
+    /// of the global public libraries dict, sorted by library hash.
+    ///
+    /// `publisher` is an address of the executed masterchain account.
+    ///
+    /// [`public_libs_diff`]: Self::public_libs_diff
+    pub fn public_lib_updates(&self, publisher: &HashBytes) -> Vec<PublicLibraryUpdate> {
+        let mut updates = std::collections::BTreeMap::new();
+        for change in &self.public_libs_diff {
+            let (count, last) = updates.entry(*change.lib_hash()).or_insert((0, change));
+            *count += 1;
+            *last = change;
+        }
+
+        updates
+            .into_iter()
+            // NOTE: Only actual changes are tracked, so operations for the same
+            // library always alternate and an even number of them is a no-op.
+            .filter(|(_, (count, _))| count % 2 == 1)
+            .map(|(lib_hash, (_, change))| PublicLibraryUpdate {
+                lib_hash,
+                publisher: *publisher,
+                change: change.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Mismatch between a `SENDMSG` estimate and the actually sent message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendMsgDivergence {
+    /// Estimate computed by the VM.
+    pub estimate: SendMsgEstimate,
+    /// Forwarding fee charged by the action phase.
+    pub actual_fee: Tokens,
+    /// Layout of the sent message.
+    pub actual_layout: MessageLayout,
+}
+
+/// Outbound messages filter callback.
+pub type OutMsgFilter<'e> = dyn FnMut(&Lazy<OwnedMessage>) -> OutMsgVerdict + 'e;
+
+/// Decision of the [`OutMsgFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutMsgVerdict {
+    /// Add message to the transaction.
+    Accept,
+    /// Fail the action which produced the message.
+    Veto,
 }
 
 /// Public library diff operation.
@@ -226,6 +403,17 @@ impl PublicLibraryChange {
     }
 }
 
+/// Update of the global public libraries dict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicLibraryUpdate {
+    /// Hash of the changed library.
+    pub lib_hash: HashBytes,
+    /// Address of the masterchain account which changed the library.
+    pub publisher: HashBytes,
+    /// Library change.
+    pub change: PublicLibraryChange,
+}
+
 /// Shared state for executor phases.
 pub struct ExecutorState<'a> {
     pub params: &'a ExecutorParams,
@@ -237,11 +425,23 @@ pub struct ExecutorState<'a> {
     pub storage_stat: StorageInfo,
     pub balance: CurrencyCollection,
     pub state: AccountState,
+    /// Original state of the frozen account (see [`Executor::with_recovered_state`]).
+    ///
+    /// Only used to execute the account code, the account stays frozen
+    /// unless an inbound message unfreezes it.
+    pub recovered_state: Option<&'a StateInit>,
 
     pub orig_status: AccountStatus,
     pub end_status: AccountStatus,
+    /// Why the phase which changed `end_status` last has changed it.
+    pub status_change_reason: Option<AccountStatusChangeReason>,
     pub start_lt: u64,
     pub end_lt: u64,
+    /// Effective LT of the inbound internal message.
+    ///
+    /// Overrides the `created_lt` of the message for ordering and
+    /// `start_lt` derivation (e.g. for messages from a dispatch queue).
+    pub in_msg_lt: Option<u64>,
 
     pub out_msgs: Vec<Lazy<OwnedMessage>>,
     pub total_fees: Tokens,
@@ -266,10 +466,13 @@ impl<'a> ExecutorState<'a> {
             storage_stat: Default::default(),
             balance: CurrencyCollection::ZERO,
             state: AccountState::Uninit,
+            recovered_state: None,
             orig_status: AccountStatus::NotExists,
             end_status: AccountStatus::Uninit,
+            status_change_reason: None,
             start_lt: 0,
             end_lt: 1,
+            in_msg_lt: None,
             out_msgs: Vec::new(),
             total_fees: Tokens::ZERO,
             burned: Tokens::ZERO,
@@ -336,10 +539,18 @@ pub struct ExecutorParams {
     pub libraries: Dict<HashBytes, LibDescr>,
     /// Rand seed of the block.
     pub rand_seed: HashBytes,
+    /// How to derive the VM rand seed from [`rand_seed`].
+    ///
+    /// [`rand_seed`]: Self::rand_seed
+    pub rand_seed_derivation: RandSeedDerivation,
     /// Unix timestamp in seconds of the block.
     pub block_unixtime: u32,
     /// Logical time of the block.
     pub block_lt: u64,
+    /// Previous masterchain blocks info for the `PREVBLOCKSINFOTUPLE` c7 entry.
+    ///
+    /// The entry is `null` when not set.
+    pub prev_blocks_info: Option<tycho_vm::PrevBlocksInfo>,
     /// VM behaviour modifiers.
     pub vm_modifiers: tycho_vm::BehaviourModifiers,
     /// Prevent [`Frozen`] accounts from being deleted
@@ -355,6 +566,75 @@ pub struct ExecutorParams {
     pub full_body_in_bounced: bool,
     /// More gas-predictable extra currency behaviour.
     pub strict_extra_currency: bool,
+    /// Rounding policy for forwarding fees and fines.
+    pub fee_rounding: FeeRounding,
+    /// Fill `vm_init_state_hash` and `vm_final_state_hash`
+    /// of the compute phase instead of leaving them zero.
+    pub compute_vm_state_hashes: bool,
+    /// Overrides the gas credit for external messages
+    /// instead of using the one from config.
+    pub ext_msg_gas_credit: Option<u64>,
+    /// Overrides the gas limit from config (both lower and higher).
+    ///
+    /// Also caps the gas limit of forcibly accepted executions.
+    pub gas_limit_override: Option<u64>,
+    /// How to handle anycast destination addresses.
+    ///
+    /// Anycasts are rejected by default, other policies
+    /// are required to replay historical transactions.
+    pub anycast_policy: AnycastPolicy,
+    /// Send message mode bits beyond [`SendMsgFlags`] which
+    /// are accepted by the action phase.
+    ///
+    /// Must be set only when the network capability which defines
+    /// these bits is enabled. Otherwise any unknown bit makes
+    /// the action invalid.
+    ///
+    /// [`SendMsgFlags`]: everscale_types::models::SendMsgFlags
+    pub extended_send_msg_flags: u8,
+    /// Run the credit phase before the storage phase for bounceable
+    /// messages too (instead of only for non-bounceable ones).
+    ///
+    /// The reference implementation always runs the storage phase first
+    /// for bounceable messages (independent of the global version), so this
+    /// is not changed by [`apply_global_version`]. Must be set only to
+    /// emulate networks which used this ordering.
+    ///
+    /// [`apply_global_version`]: Self::apply_global_version
+    pub credit_first_for_bounceable: bool,
+    /// Native implementations of well-known contracts keyed by code hash.
+    ///
+    /// Accounts with a matching code are executed without the VM
+    /// for a fixed amount of gas (like contracts from `ConfigParam45`).
+    pub precompiled_contracts: PrecompiledContracts,
+    /// Fail with [`ExecutorError::PrunedBranchAccess`] when a pruned
+    /// branch is accessed (instead of a cell underflow exception).
+    ///
+    /// Must be set only for account states reconstructed from Merkle proofs.
+    /// Contracts can legitimately load pruned branches of valid states
+    /// (e.g. from stored proofs), so this changes the execution result.
+    pub pruned_state: bool,
+    /// Wall-clock limits of the VM execution (deadline and cancellation).
+    ///
+    /// Interrupted executions fail with [`TxError::Interrupted`].
+    /// These limits are not deterministic, so they must only be used
+    /// to bound the execution time (e.g. for RPC services).
+    pub execution_limits: tycho_vm::ExecutionLimits,
+    /// Deviations from the reference implementation to restore.
+    ///
+    /// Empty by default. Must be set only to replay transactions
+    /// produced by the reference implementation bit-for-bit.
+    pub quirks: Quirks,
+}
+
+impl ExecutorParams {
+    /// Sets all version-dependent behaviour switches according to
+    /// the global version and capabilities from the config (`ConfigParam8`).
+    ///
+    /// Fields which don't depend on the global version are left untouched.
+    pub fn apply_global_version(&mut self, config: &ParsedConfig) {
+        behavior::apply_versioned_behaviors(self, config);
+    }
 }
 
 /// Executed transaction.
@@ -377,7 +657,7 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
         original: &'s ShardAccount,
         in_msg: Option<Cell>,
         info: impl Into<TxInfo>,
-    ) -> Result<Self> {
+    ) -> Result<Self, Error> {
         use everscale_types::models::ComputePhase;
 
         let info = info.into();
@@ -419,7 +699,7 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
     }
 
     /// Creates a final transaction and a new contract state.
-    pub fn commit(mut self) -> Result<ExecutorOutput> {
+    pub fn commit(mut self) -> Result<ExecutorOutput, ExecutorError> {
         // Collect brief account state info and build new account state.
         let account_state;
         let new_state_meta;
@@ -432,6 +712,8 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
                 new_state_meta = AccountMeta {
                     balance: CurrencyCollection::ZERO,
                     libraries: Dict::new(),
+                    storage_stat: None,
+                    state_stat: None,
                     exists: false,
                 };
 
@@ -466,6 +748,23 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
                 // state:AccountState
                 state.store_into(&mut account_storage, Cell::empty_context())?;
 
+                // NOTE: Cached stats are reused (and extended) below,
+                // so we must take a snapshot of the state part first.
+                let state_stat = self
+                    .exec
+                    .cached_storage_stat
+                    .as_ref()
+                    .map(|stats| StateStorageStat::from(stats.stats()));
+
+                // Storage stats can't be computed for partial states.
+                if self.exec.params.pruned_state {
+                    for cell in account_storage.as_full_slice().references() {
+                        if let Some(hash) = find_pruned_branch(cell) {
+                            return Err(ExecutorError::PrunedBranchAccess(hash).into());
+                        }
+                    }
+                }
+
                 // Update storage info.
                 self.exec.storage_stat.used = compute_storage_used(
                     prev_account_storage,
@@ -490,6 +789,8 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
                 new_state_meta = AccountMeta {
                     balance: self.exec.balance.clone(),
                     libraries,
+                    storage_stat: Some(self.exec.storage_stat.clone()),
+                    state_stat,
                     exists: true,
                 };
 
@@ -508,11 +809,22 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
             .and_then(|tx| Lazy::new(&tx))?;
 
         // Collect brief transaction info.
+        let fees_burned = fees::compute_burned_fees(self.exec.config, self.exec.total_fees);
+        let status_change_reason = if self.exec.end_status == AccountStatus::Uninit {
+            // Uninit account with zero balance is deleted when building the account state.
+            Some(AccountStatusChangeReason::EmptyBalance)
+        } else {
+            self.exec.status_change_reason
+        };
         let transaction_meta = TransactionMeta {
             total_fees: self.exec.total_fees,
+            fees_burned,
             next_lt: self.exec.end_lt,
             out_msgs: self.exec.out_msgs,
             gas_used: self.brief_info.gas_used,
+            status_change: AccountStatusEventKind::from_statuses(self.exec.orig_status, end_status)
+                .zip(status_change_reason)
+                .map(|(kind, reason)| AccountStatusEvent { kind, reason }),
         };
 
         // New shard account state.
@@ -533,7 +845,7 @@ impl<'a, 's> UncommittedTransaction<'a, 's> {
         })
     }
 
-    fn build_account_state(&self) -> Result<Option<AccountState>> {
+    fn build_account_state(&self) -> Result<Option<AccountState>, Error> {
         Ok(match self.exec.end_status {
             // Account was deleted.
             AccountStatus::NotExists => None,
@@ -677,9 +989,12 @@ fn compute_storage_used(
 
 /// Committed transaction output.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExecutorOutput {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::repr"))]
     pub new_state: ShardAccount,
     pub new_state_meta: AccountMeta,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::lazy"))]
     pub transaction: Lazy<Transaction>,
     pub transaction_meta: TransactionMeta,
     pub burned: Tokens,
@@ -687,19 +1002,105 @@ pub struct ExecutorOutput {
 
 /// Short account description.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccountMeta {
     pub balance: CurrencyCollection,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::dict"))]
     pub libraries: Dict<HashBytes, SimpleLib>,
+    /// Updated storage info (with recomputed `used` cells and bits).
+    ///
+    /// `None` if the account does not exist.
+    pub storage_stat: Option<StorageInfo>,
+    /// Stats of the new account state computed during the state limits check.
+    ///
+    /// `None` if the account does not exist or its state
+    /// was not changed by the transaction.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub state_stat: Option<StateStorageStat>,
     pub exists: bool,
 }
 
 /// Short transaction description.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransactionMeta {
     pub total_fees: Tokens,
+    /// Part of `total_fees` which is burned (according to `ConfigParam5`).
+    ///
+    /// Computed for this transaction alone and rounded down, so the sum over
+    /// a block may differ from the amount burned from the block's total fees.
+    /// Block-level rounding is up to the caller. Not included in
+    /// [`ExecutorOutput::burned`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fees_burned: Tokens,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::lazy_vec"))]
     pub out_msgs: Vec<Lazy<OwnedMessage>>,
     pub gas_used: u64,
     pub next_lt: u64,
+    /// Account status change caused by the transaction.
+    pub status_change: Option<AccountStatusEvent>,
+}
+
+/// Account status change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountStatusEvent {
+    /// Transition between the original and the final account status.
+    pub kind: AccountStatusEventKind,
+    /// What caused the transition.
+    pub reason: AccountStatusChangeReason,
+}
+
+/// Account status transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountStatusEventKind {
+    /// Uninit or non-existing account became active.
+    Activated,
+    /// Account was frozen due to the storage debt.
+    Frozen,
+    /// Frozen account became active.
+    Unfrozen,
+    /// Account was deleted.
+    Deleted,
+}
+
+impl AccountStatusEventKind {
+    /// Returns a kind of the transition between
+    /// the original and the final account status.
+    pub fn from_statuses(orig: AccountStatus, end: AccountStatus) -> Option<Self> {
+        match (orig, end) {
+            (AccountStatus::Uninit | AccountStatus::NotExists, AccountStatus::Active) => {
+                Some(Self::Activated)
+            }
+            (AccountStatus::Frozen, AccountStatus::Active) => Some(Self::Unfrozen),
+            (AccountStatus::Uninit | AccountStatus::Active, AccountStatus::Frozen) => {
+                Some(Self::Frozen)
+            }
+            (
+                AccountStatus::Uninit | AccountStatus::Active | AccountStatus::Frozen,
+                AccountStatus::NotExists,
+            ) => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
+
+/// Cause of the account status change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccountStatusChangeReason {
+    /// Account was deployed or unfrozen with a state init from
+    /// the inbound message (compute phase).
+    StateInit,
+    /// Account was frozen or deleted because of the storage
+    /// debt (storage phase).
+    StorageDebt,
+    /// Account was deleted by an outbound message with
+    /// the destroy flag (action phase).
+    DestroyFlag,
+    /// Uninit account was deleted because its balance became empty.
+    EmptyBalance,
 }
 
 /// Message cell source.
@@ -752,100 +1153,3 @@ fn build_out_msgs(out_msgs: &[Lazy<OwnedMessage>]) -> Result<Dict<Uint15, Cell>,
     )
     .map(Dict::from_raw)
 }
-
-#[cfg(test)]
-mod tests {
-    use std::rc::Rc;
-
-    use everscale_types::boc::BocRepr;
-    use everscale_types::models::{BlockchainConfig, MsgInfo, SizeLimitsConfig, StateInit};
-
-    use super::*;
-
-    pub fn make_default_config() -> Rc<ParsedConfig> {
-        thread_local! {
-            pub static PARSED_CONFIG: Rc<ParsedConfig> = make_custom_config(|_| Ok(()));
-        }
-
-        PARSED_CONFIG.with(Clone::clone)
-    }
-
-    pub fn make_custom_config<F>(f: F) -> Rc<ParsedConfig>
-    where
-        F: FnOnce(&mut BlockchainConfig) -> anyhow::Result<()>,
-    {
-        let mut config: BlockchainConfig =
-            BocRepr::decode(include_bytes!("../res/config.boc")).unwrap();
-
-        config.params.set_global_id(100).unwrap();
-
-        // TODO: Update config BOC
-        config
-            .params
-            .set_size_limits(&SizeLimitsConfig {
-                max_msg_bits: 1 << 21,
-                max_msg_cells: 1 << 13,
-                max_library_cells: 1000,
-                max_vm_data_depth: 512,
-                max_ext_msg_size: 65535,
-                max_ext_msg_depth: 512,
-                max_acc_state_cells: 1 << 16,
-                max_acc_state_bits: (1 << 16) * 1023,
-                max_acc_public_libraries: 256,
-                defer_out_queue_size_limit: 256,
-            })
-            .unwrap();
-
-        f(&mut config).unwrap();
-
-        Rc::new(ParsedConfig::parse(config, u32::MAX).unwrap())
-    }
-
-    pub fn make_default_params() -> ExecutorParams {
-        ExecutorParams {
-            block_unixtime: 1738799198,
-            full_body_in_bounced: false,
-            strict_extra_currency: true,
-            vm_modifiers: tycho_vm::BehaviourModifiers {
-                chksig_always_succeed: true,
-                ..Default::default()
-            },
-            ..Default::default()
-        }
-    }
-
-    pub fn make_message(
-        info: impl Into<MsgInfo>,
-        init: Option<StateInit>,
-        body: Option<CellBuilder>,
-    ) -> Cell {
-        let body = match &body {
-            None => Cell::empty_cell_ref().as_slice_allow_exotic(),
-            Some(cell) => cell.as_full_slice(),
-        };
-        CellBuilder::build_from(Message {
-            info: info.into(),
-            init,
-            body,
-            layout: None,
-        })
-        .unwrap()
-    }
-
-    pub fn make_big_tree(depth: u8, count: &mut u16, target: u16) -> Cell {
-        *count += 1;
-
-        if depth == 0 {
-            CellBuilder::build_from(*count).unwrap()
-        } else {
-            let mut b = CellBuilder::new();
-            for _ in 0..4 {
-                if *count < target {
-                    b.store_reference(make_big_tree(depth - 1, count, target))
-                        .unwrap();
-                }
-            }
-            b.build().unwrap()
-        }
-    }
-}
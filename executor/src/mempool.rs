@@ -0,0 +1,379 @@
+use anyhow::Result;
+use everscale_types::models::{IntAddr, MsgInfo, StdAddr};
+use everscale_types::num::Tokens;
+use everscale_types::prelude::*;
+
+use crate::BlockEmulator;
+
+/// Ordering of external messages taken from [`ExtMsgPool`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MempoolOrdering {
+    /// In submission order.
+    #[default]
+    Fifo,
+    /// By the highest `import_fee` first, then in submission order.
+    ImportFee,
+}
+
+/// External message submission result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitStatus {
+    /// Message was added to the pool.
+    Added,
+    /// Message replaced a pending message with the same seqno
+    /// (it has a higher `import_fee`).
+    Replaced,
+    /// Message has the same seqno as a pending message
+    /// with the same or higher `import_fee`.
+    Conflict,
+    /// Message is not accepted by the account
+    /// (or the destination is not in the emulated shard).
+    Rejected,
+    /// Message is already in the pool.
+    Duplicate,
+}
+
+/// Pool of external messages for the [`BlockEmulator`].
+///
+/// Models a validator mempool: each message must be accepted by
+/// the destination account in its latest state, and at most one message
+/// per account seqno is kept (bumping the `import_fee` replaces it).
+#[derive(Default)]
+pub struct ExtMsgPool {
+    ordering: MempoolOrdering,
+    entries: Vec<PoolEntry>,
+    next_id: u64,
+}
+
+impl ExtMsgPool {
+    /// Creates an empty pool with the specified ordering.
+    pub fn new(ordering: MempoolOrdering) -> Self {
+        Self {
+            ordering,
+            entries: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Returns the number of pending messages.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no pending messages.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks and adds an external message to the pool.
+    ///
+    /// `seqno` is a replay protection counter of the destination account
+    /// (e.g. a wallet seqno). Messages without it never conflict.
+    pub fn submit(
+        &mut self,
+        emulator: &BlockEmulator<'_>,
+        msg: Cell,
+        seqno: Option<u32>,
+    ) -> Result<SubmitStatus> {
+        let (dst, import_fee) = match MsgInfo::load_from(&mut msg.as_slice()?)? {
+            MsgInfo::ExtIn(info) => (info.dst, info.import_fee),
+            MsgInfo::Int(_) | MsgInfo::ExtOut(_) => {
+                anyhow::bail!("expected an external inbound message")
+            }
+        };
+
+        let dst = match dst {
+            IntAddr::Std(dst) if emulator.contains(&dst) => dst,
+            _ => return Ok(SubmitStatus::Rejected),
+        };
+
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.root.repr_hash() == msg.repr_hash())
+        {
+            return Ok(SubmitStatus::Duplicate);
+        }
+
+        let conflict = match seqno {
+            Some(seqno) => self
+                .entries
+                .iter()
+                .position(|entry| entry.dst == dst && entry.seqno == Some(seqno)),
+            None => None,
+        };
+        if let Some(i) = conflict {
+            if self.entries[i].import_fee >= import_fee {
+                return Ok(SubmitStatus::Conflict);
+            }
+        }
+
+        if !is_accepted(emulator, &dst, &msg)? {
+            return Ok(SubmitStatus::Rejected);
+        }
+
+        let entry = PoolEntry {
+            id: self.next_id,
+            dst,
+            seqno,
+            import_fee,
+            root: msg,
+        };
+        self.next_id += 1;
+
+        Ok(match conflict {
+            Some(i) => {
+                self.entries[i] = entry;
+                SubmitStatus::Replaced
+            }
+            None => {
+                self.entries.push(entry);
+                SubmitStatus::Added
+            }
+        })
+    }
+
+    /// Removes at most `limit` messages from the pool in the pool order.
+    ///
+    /// The result can be passed directly to [`BlockEmulator::run`].
+    pub fn take(&mut self, limit: usize) -> Vec<Cell> {
+        match self.ordering {
+            MempoolOrdering::Fifo => self.entries.sort_by_key(|entry| entry.id),
+            MempoolOrdering::ImportFee => self
+                .entries
+                .sort_by(|a, b| b.import_fee.cmp(&a.import_fee).then(a.id.cmp(&b.id))),
+        }
+
+        let limit = std::cmp::min(limit, self.entries.len());
+        self.entries
+            .drain(..limit)
+            .map(|entry| entry.root)
+            .collect()
+    }
+
+    /// Removes messages which are no longer accepted by their accounts
+    /// (e.g. after a new block was emulated).
+    ///
+    /// Returns the number of removed messages.
+    pub fn revalidate(&mut self, emulator: &BlockEmulator<'_>) -> Result<usize> {
+        let mut removed = 0;
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for entry in std::mem::take(&mut self.entries) {
+            if is_accepted(emulator, &entry.dst, &entry.root)? {
+                entries.push(entry);
+            } else {
+                removed += 1;
+            }
+        }
+        self.entries = entries;
+        Ok(removed)
+    }
+
+    /// Removes all pending messages.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn is_accepted(emulator: &BlockEmulator<'_>, dst: &StdAddr, msg: &Cell) -> Result<bool> {
+    let state = emulator.get_account(&dst.address)?;
+    let res = emulator
+        .executor()
+        .check_external_message(dst, msg, &state)?;
+    Ok(res.accepted)
+}
+
+struct PoolEntry {
+    id: u64,
+    dst: StdAddr,
+    seqno: Option<u32>,
+    import_fee: Tokens,
+    root: Cell,
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::HashMap;
+    use everscale_asm_macros::tvmasm;
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, ExtInMsgInfo, OptionalAccount, ShardAccount,
+        ShardAccounts, ShardIdent, StateInit, StorageInfo,
+    };
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+    use crate::EmulatorSnapshot;
+
+    fn make_wallet(address: &StdAddr, seqno: u32) -> Result<ShardAccount> {
+        // Accepts external messages with the current seqno and increments it.
+        let code = Boc::decode(tvmasm!(
+            r#"
+            DROP
+            LDU 32 DROP
+            PUSH c4 CTOS PLDU 32
+            SWAP OVER
+            EQUAL THROWIFNOT 33
+            ACCEPT
+            INC NEWC STU 32 ENDC
+            POP c4
+            "#
+        ))?;
+
+        Ok(ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Active(StateInit {
+                    split_depth: None,
+                    special: None,
+                    code: Some(code),
+                    data: Some(CellBuilder::build_from(seqno)?),
+                    libraries: Dict::new(),
+                }),
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        })
+    }
+
+    fn make_ext_msg(address: &StdAddr, seqno: u32, import_fee: u128) -> Result<Cell> {
+        let mut body = CellBuilder::new();
+        body.store_u32(seqno)?;
+        Ok(make_message(
+            ExtInMsgInfo {
+                src: None,
+                dst: address.clone().into(),
+                import_fee: Tokens::new(import_fee),
+            },
+            None,
+            Some(body),
+        ))
+    }
+
+    #[test]
+    fn mempool_seqno_conflicts() -> Result<()> {
+        let config = make_default_config();
+        let mut params = make_default_params();
+        params.block_lt = 2000;
+
+        let wallet = StdAddr::new(0, HashBytes([0x11; 32]));
+
+        let mut updated_accounts = HashMap::default();
+        updated_accounts.insert(wallet.address, make_wallet(&wallet, 5)?);
+        let mut emulator = BlockEmulator::from_snapshot(&params, &config, EmulatorSnapshot {
+            block_lt: params.block_lt,
+            block_unixtime: params.block_unixtime,
+            shard: ShardIdent::BASECHAIN,
+            accounts: ShardAccounts::new(),
+            updated_accounts,
+            pending: Vec::new(),
+        });
+
+        let mut pool = ExtMsgPool::new(MempoolOrdering::ImportFee);
+
+        // Wrong seqno is not accepted.
+        let msg = make_ext_msg(&wallet, 4, 0)?;
+        assert_eq!(
+            pool.submit(&emulator, msg, Some(4))?,
+            SubmitStatus::Rejected
+        );
+
+        let msg = make_ext_msg(&wallet, 5, 10)?;
+        assert_eq!(
+            pool.submit(&emulator, msg.clone(), Some(5))?,
+            SubmitStatus::Added
+        );
+        assert_eq!(
+            pool.submit(&emulator, msg, Some(5))?,
+            SubmitStatus::Duplicate
+        );
+
+        // Same seqno requires a fee bump.
+        let msg = make_ext_msg(&wallet, 5, 5)?;
+        assert_eq!(
+            pool.submit(&emulator, msg, Some(5))?,
+            SubmitStatus::Conflict
+        );
+
+        let bumped = make_ext_msg(&wallet, 5, 20)?;
+        assert_eq!(
+            pool.submit(&emulator, bumped.clone(), Some(5))?,
+            SubmitStatus::Replaced
+        );
+        assert_eq!(pool.len(), 1);
+
+        let block = emulator.run(pool.take(usize::MAX))?;
+        assert_eq!(block.stats.tx_count, 1);
+        assert!(pool.is_empty());
+
+        // Next seqno is accepted only after the block.
+        let next = make_ext_msg(&wallet, 6, 0)?;
+        assert_eq!(pool.submit(&emulator, next, Some(6))?, SubmitStatus::Added);
+
+        assert_eq!(pool.revalidate(&emulator)?, 0);
+        assert_eq!(pool.len(), 1);
+
+        // Message with the same seqno was included bypassing the pool.
+        let other = make_ext_msg(&wallet, 6, 1)?;
+        let block = emulator.run([other])?;
+        assert_eq!(block.stats.tx_count, 1);
+
+        // Stale messages are removed on revalidation.
+        assert_eq!(pool.revalidate(&emulator)?, 1);
+        assert!(pool.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mempool_ordering() -> Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let wallets = [
+            StdAddr::new(0, HashBytes([0x11; 32])),
+            StdAddr::new(0, HashBytes([0x22; 32])),
+        ];
+
+        let mut updated_accounts = HashMap::default();
+        for wallet in &wallets {
+            updated_accounts.insert(wallet.address, make_wallet(wallet, 0)?);
+        }
+        let emulator = BlockEmulator::from_snapshot(&params, &config, EmulatorSnapshot {
+            block_lt: 2000,
+            block_unixtime: params.block_unixtime,
+            shard: ShardIdent::BASECHAIN,
+            accounts: ShardAccounts::new(),
+            updated_accounts,
+            pending: Vec::new(),
+        });
+
+        let low = make_ext_msg(&wallets[0], 0, 1)?;
+        let high = make_ext_msg(&wallets[1], 0, 100)?;
+
+        for (ordering, expected) in [
+            (MempoolOrdering::Fifo, [&low, &high]),
+            (MempoolOrdering::ImportFee, [&high, &low]),
+        ] {
+            let mut pool = ExtMsgPool::new(ordering);
+            assert_eq!(
+                pool.submit(&emulator, low.clone(), None)?,
+                SubmitStatus::Added
+            );
+            assert_eq!(
+                pool.submit(&emulator, high.clone(), None)?,
+                SubmitStatus::Added
+            );
+
+            let taken = pool.take(1);
+            assert_eq!(taken, [expected[0].clone()]);
+            let taken = pool.take(1);
+            assert_eq!(taken, [expected[1].clone()]);
+            assert!(pool.take(1).is_empty());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Execution counters and histograms.
+//!
+//! Metrics are reported through the `metrics` facade when the `metrics`
+//! feature is enabled. Otherwise all functions are no-op.
+
+use everscale_types::models::OutAction;
+use everscale_types::num::Tokens;
+
+/// Records the duration of a transaction phase when dropped.
+#[must_use]
+pub(crate) struct PhaseTimer {
+    #[cfg(feature = "metrics")]
+    phase: &'static str,
+    #[cfg(feature = "metrics")]
+    started_at: std::time::Instant,
+}
+
+impl PhaseTimer {
+    #[inline]
+    pub(crate) fn start(phase: &'static str) -> Self {
+        #[cfg(not(feature = "metrics"))]
+        _ = phase;
+
+        Self {
+            #[cfg(feature = "metrics")]
+            phase,
+            #[cfg(feature = "metrics")]
+            started_at: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        metrics::histogram!("tycho_executor_phase_duration_seconds", "phase" => self.phase)
+            .record(self.started_at.elapsed());
+    }
+}
+
+/// Records gas and steps of the executed compute phase.
+#[inline]
+pub(crate) fn record_compute_phase(gas_used: u64, vm_steps: u64) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::histogram!("tycho_executor_compute_gas_used").record(gas_used as f64);
+        metrics::histogram!("tycho_executor_vm_steps").record(vm_steps as f64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    _ = (gas_used, vm_steps);
+}
+
+/// Records the number of actions in the action list.
+#[inline]
+pub(crate) fn record_action_count(count: u16) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("tycho_executor_actions_per_tx").record(count as f64);
+    #[cfg(not(feature = "metrics"))]
+    _ = count;
+}
+
+/// Records the size of a sent outbound message.
+#[inline]
+pub(crate) fn record_out_msg_size(cells: u64, bits: u64) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::histogram!("tycho_executor_out_msg_cells").record(cells as f64);
+        metrics::histogram!("tycho_executor_out_msg_bits").record(bits as f64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    _ = (cells, bits);
+}
+
+/// Counts a processed action of the specified type.
+#[inline]
+pub(crate) fn record_action(action: &OutAction) {
+    let kind = match action {
+        OutAction::SendMsg { .. } => "send_msg",
+        OutAction::SetCode { .. } => "set_code",
+        OutAction::ReserveCurrency { .. } => "reserve_currency",
+        OutAction::ChangeLibrary { .. } => "change_library",
+    };
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!("tycho_executor_actions_total", "type" => kind).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    _ = kind;
+}
+
+/// Counts an action skipped due to the `IGNORE_ERROR` flag.
+#[inline]
+pub(crate) fn record_skipped_action() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("tycho_executor_skipped_actions_total").increment(1);
+}
+
+/// Counts an outbound message layout rewrite.
+#[inline]
+pub(crate) fn record_message_rewrite(kind: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("tycho_executor_msg_rewrites_total", "type" => kind).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    _ = kind;
+}
+
+/// Counts a collected action fine.
+#[inline]
+pub(crate) fn record_action_fine(fine: Tokens) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("tycho_executor_action_fines_total").increment(1);
+        metrics::counter!("tycho_executor_action_fines_amount")
+            .increment(fine.into_inner().try_into().unwrap_or(u64::MAX));
+    }
+    #[cfg(not(feature = "metrics"))]
+    _ = fine;
+}
+
+/// Counts a sent message which differs from its `SENDMSG` estimate.
+#[inline]
+pub(crate) fn record_send_msg_divergence() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("tycho_executor_send_msg_divergences_total").increment(1);
+}
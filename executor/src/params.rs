@@ -0,0 +1,283 @@
+use std::time::Instant;
+
+use everscale_types::models::LibDescr;
+use everscale_types::prelude::*;
+
+use crate::{
+    AnycastPolicy, ExecutorParams, FeeRounding, ParsedConfig, PrecompiledContracts, Quirks,
+};
+
+/// Intended use of the executor params.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutorPreset {
+    /// Execution of new transactions for a new block.
+    ///
+    /// Requires a complete block info and real signature checks.
+    Collation,
+    /// Re-execution of already committed transactions.
+    ///
+    /// Same as [`Collation`], but anycast destinations are handled
+    /// the way the network did when the transactions were produced.
+    ///
+    /// [`Collation`]: ExecutorPreset::Collation
+    Replay {
+        /// How the network handled anycast destination addresses.
+        anycast_policy: AnycastPolicy,
+    },
+    /// Execution to estimate fees or results (e.g. for emulation).
+    ///
+    /// Signature checks always succeed and block info can be partial.
+    Estimation,
+}
+
+impl ExecutorPreset {
+    const fn requires_block_info(self) -> bool {
+        matches!(self, Self::Collation | Self::Replay { .. })
+    }
+}
+
+/// [`ExecutorParams`] builder.
+pub struct ExecutorParamsBuilder {
+    preset: ExecutorPreset,
+    params: ExecutorParams,
+}
+
+impl ExecutorParams {
+    /// Creates a builder with defaults for the specified preset.
+    pub fn builder(preset: ExecutorPreset) -> ExecutorParamsBuilder {
+        ExecutorParamsBuilder::new(preset)
+    }
+}
+
+impl ExecutorParamsBuilder {
+    /// Creates a builder with defaults for the specified preset.
+    pub fn new(preset: ExecutorPreset) -> Self {
+        let mut params = ExecutorParams::default();
+        match preset {
+            ExecutorPreset::Collation => {}
+            ExecutorPreset::Replay { anycast_policy } => {
+                params.anycast_policy = anycast_policy;
+            }
+            ExecutorPreset::Estimation => {
+                params.vm_modifiers.chksig_always_succeed = true;
+            }
+        }
+        Self { preset, params }
+    }
+
+    /// Returns the selected preset.
+    pub fn preset(&self) -> ExecutorPreset {
+        self.preset
+    }
+
+    /// Sets public libraries from the referenced masterchain state.
+    pub fn with_libraries(mut self, libraries: Dict<HashBytes, LibDescr>) -> Self {
+        self.params.libraries = libraries;
+        self
+    }
+
+    /// Sets rand seed of the block.
+    pub fn with_rand_seed(mut self, rand_seed: HashBytes) -> Self {
+        self.params.rand_seed = rand_seed;
+        self
+    }
+
+    /// Sets unix timestamp in seconds and logical time of the block.
+    ///
+    /// Logical time can be left zero for [`Estimation`], but it must not
+    /// be set without the unix timestamp.
+    ///
+    /// [`Estimation`]: ExecutorPreset::Estimation
+    pub fn with_block_info(mut self, block_unixtime: u32, block_lt: u64) -> Self {
+        self.params.block_unixtime = block_unixtime;
+        self.params.block_lt = block_lt;
+        self
+    }
+
+    /// Sets previous masterchain blocks info.
+    pub fn with_prev_blocks_info(mut self, prev_blocks_info: tycho_vm::PrevBlocksInfo) -> Self {
+        self.params.prev_blocks_info = Some(prev_blocks_info);
+        self
+    }
+
+    /// Sets VM behaviour modifiers.
+    pub fn with_vm_modifiers(mut self, vm_modifiers: tycho_vm::BehaviourModifiers) -> Self {
+        self.params.vm_modifiers = vm_modifiers;
+        self
+    }
+
+    /// Sets all version-dependent behaviours from the config.
+    ///
+    /// See [`ExecutorParams::apply_global_version`].
+    pub fn with_global_version(mut self, config: &ParsedConfig) -> Self {
+        self.params.apply_global_version(config);
+        self
+    }
+
+    /// Sets rounding policy for forwarding fees and fines.
+    pub fn with_fee_rounding(mut self, fee_rounding: FeeRounding) -> Self {
+        self.params.fee_rounding = fee_rounding;
+        self
+    }
+
+    /// Overrides the gas credit for external messages.
+    pub fn with_ext_msg_gas_credit(mut self, credit: u64) -> Self {
+        self.params.ext_msg_gas_credit = Some(credit);
+        self
+    }
+
+    /// Overrides the gas limit from config.
+    pub fn with_gas_limit_override(mut self, gas_limit: u64) -> Self {
+        self.params.gas_limit_override = Some(gas_limit);
+        self
+    }
+
+    /// Sets native implementations of well-known contracts.
+    pub fn with_precompiled_contracts(mut self, contracts: PrecompiledContracts) -> Self {
+        self.params.precompiled_contracts = contracts;
+        self
+    }
+
+    /// Aborts the VM execution after the specified instant.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.params.execution_limits.deadline = Some(deadline);
+        self
+    }
+
+    /// Aborts the VM execution after the flag is set.
+    pub fn with_cancellation(mut self, cancellation: tycho_vm::CancellationFlag) -> Self {
+        self.params.execution_limits.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Restores the specified deviations from the reference implementation.
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.params.quirks = quirks;
+        self
+    }
+
+    /// Modifies any other params.
+    pub fn with_custom<F: FnOnce(&mut ExecutorParams)>(mut self, f: F) -> Self {
+        f(&mut self.params);
+        self
+    }
+
+    /// Validates and returns the params.
+    pub fn build(self) -> Result<ExecutorParams, ExecutorParamsError> {
+        let params = self.params;
+
+        if params.block_unixtime == 0 && params.block_lt != 0 {
+            return Err(ExecutorParamsError::BlockLtWithoutUnixtime);
+        }
+        if params.block_unixtime == 0 {
+            return Err(ExecutorParamsError::ZeroBlockUnixtime);
+        }
+
+        if self.preset.requires_block_info() {
+            if params.block_lt == 0 {
+                return Err(ExecutorParamsError::ZeroBlockLt);
+            }
+            if params.rand_seed == HashBytes::ZERO {
+                return Err(ExecutorParamsError::ZeroRandSeed);
+            }
+            if params.vm_modifiers.chksig_always_succeed {
+                return Err(ExecutorParamsError::FakeSignatureChecks);
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+/// Invalid executor params.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ExecutorParamsError {
+    #[error("block unixtime is not set")]
+    ZeroBlockUnixtime,
+    #[error("block lt is not set")]
+    ZeroBlockLt,
+    #[error("block lt is set without block unixtime")]
+    BlockLtWithoutUnixtime,
+    #[error("rand seed is not set")]
+    ZeroRandSeed,
+    #[error("signature checks must not be disabled")]
+    FakeSignatureChecks,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAND_SEED: HashBytes = HashBytes([0x55; 32]);
+
+    #[test]
+    fn presets() {
+        let params = ExecutorParams::builder(ExecutorPreset::Collation)
+            .with_block_info(1738799198, 1000)
+            .with_rand_seed(RAND_SEED)
+            .build()
+            .unwrap();
+        assert_eq!(params.anycast_policy, AnycastPolicy::Reject);
+        assert!(!params.vm_modifiers.chksig_always_succeed);
+
+        for anycast_policy in [
+            AnycastPolicy::Reject,
+            AnycastPolicy::Rewrite,
+            AnycastPolicy::Allow,
+        ] {
+            let params = ExecutorParams::builder(ExecutorPreset::Replay { anycast_policy })
+                .with_block_info(1738799198, 1000)
+                .with_rand_seed(RAND_SEED)
+                .build()
+                .unwrap();
+            assert_eq!(params.anycast_policy, anycast_policy);
+        }
+
+        // Estimation does not require a complete block info.
+        let params = ExecutorParams::builder(ExecutorPreset::Estimation)
+            .with_block_info(1738799198, 0)
+            .build()
+            .unwrap();
+        assert!(params.vm_modifiers.chksig_always_succeed);
+    }
+
+    #[test]
+    fn validation() {
+        let res = ExecutorParams::builder(ExecutorPreset::Estimation).build();
+        assert_eq!(res.err(), Some(ExecutorParamsError::ZeroBlockUnixtime));
+
+        // Block lt without block unixtime is contradictory for any preset.
+        for preset in [
+            ExecutorPreset::Collation,
+            ExecutorPreset::Replay {
+                anycast_policy: AnycastPolicy::Rewrite,
+            },
+            ExecutorPreset::Estimation,
+        ] {
+            let res = ExecutorParams::builder(preset)
+                .with_block_info(0, 1000)
+                .with_rand_seed(RAND_SEED)
+                .build();
+            assert_eq!(res.err(), Some(ExecutorParamsError::BlockLtWithoutUnixtime));
+        }
+
+        let builder = || {
+            ExecutorParams::builder(ExecutorPreset::Collation)
+                .with_block_info(1738799198, 1000)
+                .with_rand_seed(RAND_SEED)
+        };
+
+        let res = builder().with_block_info(1738799198, 0).build();
+        assert_eq!(res.err(), Some(ExecutorParamsError::ZeroBlockLt));
+
+        let res = builder().with_rand_seed(HashBytes::ZERO).build();
+        assert_eq!(res.err(), Some(ExecutorParamsError::ZeroRandSeed));
+
+        let res = builder()
+            .with_custom(|params| params.vm_modifiers.chksig_always_succeed = true)
+            .build();
+        assert_eq!(res.err(), Some(ExecutorParamsError::FakeSignatureChecks));
+
+        assert!(builder().build().is_ok());
+    }
+}
@@ -3,19 +3,26 @@ use everscale_types::cell::{CellTreeStats, Lazy};
 use everscale_types::error::Error;
 use everscale_types::models::{
     AccountState, AccountStatus, AccountStatusChange, ActionPhase, ChangeLibraryMode,
-    CurrencyCollection, ExecutedComputePhase, ExtraCurrencyCollection, LibRef, OutAction,
-    OwnedMessage, OwnedRelaxedMessage, RelaxedMsgInfo, ReserveCurrencyFlags, SendMsgFlags,
-    SimpleLib, StateInit, StorageUsedShort,
+    CurrencyCollection, ExecutedComputePhase, ExtraCurrencyCollection, LibRef, MessageLayout,
+    OutAction, OwnedMessage, OwnedRelaxedMessage, RelaxedMsgInfo, ReserveCurrencyFlags,
+    SendMsgFlags, SimpleLib, StateInit, StorageUsedShort,
 };
 use everscale_types::num::{Tokens, VarUint56};
 use everscale_types::prelude::*;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+use tycho_vm::SendMsgEstimate;
 
+use crate::codes::ActionResultCode as ResultCode;
 use crate::phase::receive::ReceivedMessage;
 use crate::util::{
     check_rewrite_dst_addr, check_rewrite_src_addr, check_state_limits, check_state_limits_diff,
-    ExtStorageStat, StateLimitsResult, StorageStatLimits,
+    normalize_balance, ExtStorageStat, Quirks, StateLimitsResult, StorageStatLimits,
+};
+use crate::{
+    fees, metrics, AccountStatusChangeReason, ExecutorInspector, ExecutorState, OutMsgFilter,
+    OutMsgVerdict, PublicLibraryChange, SendMsgDivergence,
 };
-use crate::{ExecutorInspector, ExecutorState, PublicLibraryChange};
 
 /// Action phase input context.
 pub struct ActionPhaseContext<'a, 'e> {
@@ -35,6 +42,7 @@ pub struct ActionPhaseContext<'a, 'e> {
 
 /// Executed action phase with additional info.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ActionPhaseFull {
     /// Resulting action phase.
     pub action_phase: ActionPhase,
@@ -44,12 +52,63 @@ pub struct ActionPhaseFull {
     pub state_exceeds_limits: bool,
     /// Whether bounce phase is required.
     pub bounce: bool,
+    /// Forwarding fees of each created message (in the same order).
+    ///
+    /// Empty if the action phase failed.
+    pub out_msg_fees: Vec<OutMsgFees>,
+}
+
+/// Forwarding fees of a created outbound message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutMsgFees {
+    /// Total forwarding fee.
+    pub fwd_fee_total: Tokens,
+    /// Part of the forwarding fee collected in this transaction.
+    pub first_frac_collected: Tokens,
+    /// Part of the forwarding fee stored in the message (`fwd_fee`).
+    pub fee_remaining_in_msg: Tokens,
+    /// IHR fee stored in the message.
+    pub ihr_fee: Tokens,
+    /// Number of cells in the message (including the root).
+    pub cells: u64,
+    /// Number of bits in the message (including the root).
+    pub bits: u64,
 }
 
 impl ExecutorState<'_> {
-    pub fn action_phase(&mut self, mut ctx: ActionPhaseContext<'_, '_>) -> Result<ActionPhaseFull> {
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            level = "debug",
+            name = "action_phase",
+            skip_all,
+            fields(
+                address = %self.address,
+                lt = self.start_lt,
+                result_code = tracing::field::Empty,
+            ),
+        )
+    )]
+    pub fn action_phase(&mut self, ctx: ActionPhaseContext<'_, '_>) -> Result<ActionPhaseFull> {
+        let res = self.action_phase_impl(ctx);
+
+        #[cfg(feature = "tracing")]
+        if let Ok(res) = &res {
+            tracing::Span::current().record("result_code", res.action_phase.result_code);
+        }
+
+        res
+    }
+
+    fn action_phase_impl(
+        &mut self,
+        mut ctx: ActionPhaseContext<'_, '_>,
+    ) -> Result<ActionPhaseFull> {
         const MAX_ACTIONS: u16 = 255;
 
+        let _timer = metrics::PhaseTimer::start("action");
+
         let mut res = ActionPhaseFull {
             action_phase: ActionPhase {
                 success: false,
@@ -70,6 +129,7 @@ impl ExecutorState<'_> {
             action_fine: Tokens::ZERO,
             state_exceeds_limits: false,
             bounce: false,
+            out_msg_fees: Vec::new(),
         };
 
         // Unpack actions list.
@@ -117,6 +177,7 @@ impl ExecutorState<'_> {
         }
 
         res.action_phase.total_actions = action_idx;
+        metrics::record_action_count(action_idx);
 
         // Parse actions.
         let mut parsed_list = Vec::with_capacity(list.len());
@@ -140,6 +201,7 @@ impl ExecutorState<'_> {
                 if mode.contains(SendMsgFlags::IGNORE_ERROR) {
                     // "IGNORE_ERROR" flag means that we can just skip this action.
                     res.action_phase.skipped_actions += 1;
+                    metrics::record_skipped_action();
                     parsed_list.push(None);
                     continue;
                 } else if mode.contains(SendMsgFlags::BOUNCE_ON_ERROR) {
@@ -162,6 +224,7 @@ impl ExecutorState<'_> {
         let mut action_ctx = ActionContext {
             need_bounce_on_fail: false,
             strict_extra_currency: self.params.strict_extra_currency,
+            quirks: self.params.quirks,
             received_message: ctx.received_message,
             original_balance: &ctx.original_balance,
             remaining_balance: self.balance.clone(),
@@ -170,8 +233,21 @@ impl ExecutorState<'_> {
             new_state: &mut ctx.new_state,
             end_lt: self.end_lt,
             out_msgs: Vec::new(),
+            out_msg_fees: Vec::new(),
             delete_account: false,
             public_libs_diff: ctx.inspector.is_some().then(Vec::new),
+            send_msg_estimates: match &ctx.inspector {
+                Some(inspector) if inspector.check_send_msg_estimates => {
+                    inspector.send_msg_estimates.clone()
+                }
+                _ => Vec::new(),
+            },
+            send_msg_divergences: Vec::new(),
+            out_msg_filter: ctx
+                .inspector
+                .as_deref_mut()
+                .and_then(|inspector| inspector.out_msg_filter.as_deref_mut())
+                .map(|filter| filter as &mut OutMsgFilter<'_>),
             compute_phase: ctx.compute_phase,
             action_phase: &mut res.action_phase,
         };
@@ -185,13 +261,18 @@ impl ExecutorState<'_> {
             action_ctx.action_phase.result_code = -1;
             action_ctx.action_phase.result_arg = Some(action_idx as _);
 
+            metrics::record_action(&action);
+
             let action = match action {
                 OutAction::SendMsg { mode, out_msg } => {
                     let mut rewrite = None;
                     loop {
                         match self.do_send_message(mode, &out_msg, &mut action_ctx, rewrite) {
                             Ok(SendMsgResult::Sent) => break Ok(()),
-                            Ok(SendMsgResult::Rewrite(r)) => rewrite = Some(r),
+                            Ok(SendMsgResult::Rewrite(r)) => {
+                                metrics::record_message_rewrite(r.as_str());
+                                rewrite = Some(r);
+                            }
                             Err(e) => break Err(e),
                         }
                     }
@@ -219,7 +300,7 @@ impl ExecutorState<'_> {
                 // TODO: Enforce state limits here if we want to persist
                 // library changes even if action phase fails. This is
                 // not the case for now, but this is how the reference
-                // implementation works.
+                // implementation works (not covered by `Quirks` yet).
 
                 // Apply action fine to the balance.
                 action_ctx.apply_fine_on_error(
@@ -312,6 +393,7 @@ impl ExecutorState<'_> {
                 // Leave account as uninit if it still has some extra currencies.
                 AccountStatus::Uninit
             };
+            self.status_change_reason = Some(AccountStatusChangeReason::DestroyFlag);
             self.cached_storage_stat = None;
         }
 
@@ -321,12 +403,16 @@ impl ExecutorState<'_> {
         }
         self.balance = action_ctx.remaining_balance;
 
-        if let Some(inspector) = ctx.inspector {
-            inspector.public_libs_diff = action_ctx.public_libs_diff.unwrap_or_default();
-        }
-
         self.out_msgs = action_ctx.out_msgs;
         self.end_lt = action_ctx.end_lt;
+        res.out_msg_fees = action_ctx.out_msg_fees;
+
+        let public_libs_diff = action_ctx.public_libs_diff;
+        let send_msg_divergences = action_ctx.send_msg_divergences;
+        if let Some(inspector) = ctx.inspector {
+            inspector.public_libs_diff = public_libs_diff.unwrap_or_default();
+            inspector.send_msg_divergences = send_msg_divergences;
+        }
         self.state = AccountState::Active(ctx.new_state);
 
         Ok(res)
@@ -355,8 +441,9 @@ impl ExecutorState<'_> {
             ctx.need_bounce_on_fail = true;
         }
 
-        if mode.bits() & !MASK != 0 || mode.contains(INVALID_MASK) {
-            // - Mode has some unknown bits;
+        let mask = MASK | self.params.extended_send_msg_flags;
+        if mode.bits() & !mask != 0 || mode.contains(INVALID_MASK) {
+            // - Mode has some unknown (or not enabled) bits;
             // - Or "ALL_BALANCE" flag was used with "WITH_REMAINING_BALANCE".
             return Err(ActionFailed);
         }
@@ -366,6 +453,7 @@ impl ExecutorState<'_> {
         let check_skip_invalid = |e: ResultCode, ctx: &mut ActionContext<'_>| {
             if skip_invalid {
                 ctx.action_phase.skipped_actions += 1;
+                metrics::record_skipped_action();
                 Ok(SendMsgResult::Sent)
             } else {
                 ctx.action_phase.result_code = e as i32;
@@ -431,7 +519,11 @@ impl ExecutorState<'_> {
                 };
 
                 // Rewrite destination address.
-                if !check_rewrite_dst_addr(&self.config.workchains, &mut info.dst) {
+                if !check_rewrite_dst_addr(
+                    &self.config.workchains,
+                    self.params.anycast_policy,
+                    &mut info.dst,
+                ) {
                     return check_skip_invalid(ResultCode::InvalidDstAddr, ctx);
                 }
                 use_mc_prices |= info.dst.is_masterchain();
@@ -490,7 +582,7 @@ impl ExecutorState<'_> {
         if self.is_special {
             fine_per_cell = 0;
         } else {
-            fine_per_cell = (prices.cell_price >> 16) / 4;
+            fine_per_cell = self.params.fee_rounding.fine_per_cell(prices);
 
             let mut funds = ctx.remaining_balance.tokens;
             if let RelaxedMsgInfo::Int(info) = &relaxed_info {
@@ -534,14 +626,15 @@ impl ExecutorState<'_> {
             );
             fine = std::cmp::min(fine, ctx.remaining_balance.tokens);
             ctx.action_fine.try_add_assign(fine)?;
+            metrics::record_action_fine(fine);
             ctx.remaining_balance.try_sub_assign_tokens(fine)
         };
 
         // Compute size of the message.
         let stats = 'stats: {
             let mut stats = ExtStorageStat::with_limits(StorageStatLimits {
-                bit_count: self.config.size_limits.max_msg_bits,
                 cell_count: max_cell_count,
+                ..StorageStatLimits::for_message(&self.config.size_limits)
             });
 
             'valid: {
@@ -585,7 +678,7 @@ impl ExecutorState<'_> {
         let fwd_fee = if self.is_special {
             Tokens::ZERO
         } else {
-            prices.compute_fwd_fee(stats)
+            fees::compute_fwd_fee(self.config, self.params.fee_rounding, use_mc_prices, stats)
         };
 
         // Finalize message.
@@ -618,8 +711,9 @@ impl ExecutorState<'_> {
                 };
 
                 // Split forwarding fee.
-                fees_collected = prices.get_first_part(fwd_fee);
-                info.fwd_fee = fwd_fee - fees_collected;
+                let split = fees::split_fwd_fee(self.config, use_mc_prices, fwd_fee);
+                fees_collected = split.first_frac;
+                info.fwd_fee = split.remaining;
 
                 // Finalize message.
                 msg = match build_message(&relaxed_info, &state_init_cs, &body_cs) {
@@ -648,6 +742,7 @@ impl ExecutorState<'_> {
                 // Update the remaining balance.
                 ctx.remaining_balance.tokens -= value_to_pay;
                 ctx.remaining_balance.other = other;
+                normalize_balance(&mut ctx.remaining_balance)?;
             }
             RelaxedMsgInfo::ExtOut(_) => {
                 // Check if the remaining balance is enough to pay forwarding fees.
@@ -670,16 +765,42 @@ impl ExecutorState<'_> {
             }
         }
 
+        let msg_cells = stats.cell_count.saturating_add(1);
+        let msg_bits = stats.bit_count.saturating_add(msg.bit_len() as _);
         update_total_msg_stat(
             &mut ctx.action_phase.total_message_size,
             stats,
             msg.bit_len(),
         );
 
+        // Compare with the `SENDMSG` estimate from the compute phase.
+        if !ctx.send_msg_estimates.is_empty() {
+            ctx.check_send_msg_estimate(out_msg.repr_hash(), &msg, fwd_fee)?;
+        }
+
+        if let Some(filter) = ctx.out_msg_filter.as_deref_mut() {
+            if filter(&msg) == OutMsgVerdict::Veto {
+                return Err(ActionFailed);
+            }
+        }
+
+        metrics::record_out_msg_size(msg_cells, msg_bits);
+
         ctx.action_phase.messages_created += 1;
         ctx.end_lt += 1;
 
         ctx.out_msgs.push(msg);
+        ctx.out_msg_fees.push(OutMsgFees {
+            fwd_fee_total: fwd_fee,
+            first_frac_collected: fees_collected,
+            fee_remaining_in_msg: fwd_fee - fees_collected,
+            ihr_fee: match &relaxed_info {
+                RelaxedMsgInfo::Int(info) => info.ihr_fee,
+                RelaxedMsgInfo::ExtOut(_) => Tokens::ZERO,
+            },
+            cells: msg_cells,
+            bits: msg_bits,
+        });
 
         *ctx.action_phase.total_action_fees.get_or_insert_default() += fees_collected;
         *ctx.action_phase.total_fwd_fees.get_or_insert_default() += fwd_fee;
@@ -779,6 +900,7 @@ impl ExecutorState<'_> {
 
         // Always normalize reserved balance.
         reserve.other.normalize()?;
+        normalize_balance(&mut new_balance)?;
 
         // Apply "ALL_BUT" flag. Leave only "new_balance", reserve everything else.
         if mode.contains(ReserveCurrencyFlags::ALL_BUT) {
@@ -847,10 +969,9 @@ impl ExecutorState<'_> {
                 return Err(ActionFailed);
             };
 
-            let mut stats = ExtStorageStat::with_limits(StorageStatLimits {
-                bit_count: u32::MAX,
-                cell_count: self.config.size_limits.max_library_cells,
-            });
+            let mut stats = ExtStorageStat::with_limits(StorageStatLimits::for_library(
+                &self.config.size_limits,
+            ));
             if !stats.add_cell(root.as_ref()) {
                 ctx.action_phase.result_code = ResultCode::LibOutOfLimits as i32;
                 return Err(ActionFailed);
@@ -913,6 +1034,7 @@ impl ExecutorState<'_> {
 struct ActionContext<'a> {
     need_bounce_on_fail: bool,
     strict_extra_currency: bool,
+    quirks: Quirks,
     received_message: Option<&'a mut ReceivedMessage>,
     original_balance: &'a CurrencyCollection,
     remaining_balance: CurrencyCollection,
@@ -921,14 +1043,54 @@ struct ActionContext<'a> {
     new_state: &'a mut StateInit,
     end_lt: u64,
     out_msgs: Vec<Lazy<OwnedMessage>>,
+    out_msg_fees: Vec<OutMsgFees>,
     delete_account: bool,
     public_libs_diff: Option<Vec<PublicLibraryChange>>,
+    send_msg_estimates: Vec<SendMsgEstimate>,
+    send_msg_divergences: Vec<SendMsgDivergence>,
+    out_msg_filter: Option<&'a mut OutMsgFilter<'a>>,
 
     compute_phase: &'a ExecutedComputePhase,
     action_phase: &'a mut ActionPhase,
 }
 
 impl ActionContext<'_> {
+    fn check_send_msg_estimate(
+        &mut self,
+        out_msg_hash: &HashBytes,
+        msg: &Lazy<OwnedMessage>,
+        actual_fee: Tokens,
+    ) -> Result<(), Error> {
+        let Some(idx) = self
+            .send_msg_estimates
+            .iter()
+            .position(|estimate| estimate.msg_hash == *out_msg_hash)
+        else {
+            // Message was sent by `SENDRAWMSG`.
+            return Ok(());
+        };
+        let estimate = self.send_msg_estimates.swap_remove(idx);
+
+        let actual_layout = match msg.load()?.layout {
+            Some(layout) => layout,
+            None => MessageLayout {
+                init_to_cell: false,
+                body_to_cell: false,
+            },
+        };
+
+        if estimate.total_fee != actual_fee || estimate.layout != actual_layout {
+            metrics::record_send_msg_divergence();
+            self.send_msg_divergences.push(SendMsgDivergence {
+                estimate,
+                actual_fee,
+                actual_layout,
+            });
+        }
+
+        Ok(())
+    }
+
     fn apply_fine_on_error(
         &mut self,
         balance: &mut CurrencyCollection,
@@ -944,7 +1106,9 @@ impl ActionContext<'_> {
         // Reset forwarding fee since no messages were actually sent.
         // NOTE: This behaviour is not present in the reference implementation
         //       but it seems to be more correct.
-        self.action_phase.total_fwd_fees = None;
+        if !self.quirks.contains(Quirks::KEEP_FWD_FEES_ON_FAIL) {
+            self.action_phase.total_fwd_fees = None;
+        }
 
         // Charge the account balance for the action fine.
         self.action_phase.total_action_fees = Some(*self.action_fine).filter(|t| !t.is_zero());
@@ -1033,6 +1197,13 @@ enum MessageRewrite {
 }
 
 impl MessageRewrite {
+    const fn as_str(&self) -> &'static str {
+        match self {
+            Self::StateInitToCell => "state_init_to_cell",
+            Self::BodyToCell => "body_to_cell",
+        }
+    }
+
     pub fn next(rewrite: Option<Self>) -> Option<Self> {
         match rewrite {
             None => Some(Self::StateInitToCell),
@@ -1180,39 +1351,6 @@ enum BalanceExtraError {
     InvalidDict(#[allow(unused)] Error),
 }
 
-#[repr(i32)]
-#[derive(Debug, thiserror::Error)]
-enum ResultCode {
-    #[error("invalid action list")]
-    ActionListInvalid = 32,
-    #[error("too many actions")]
-    TooManyActions = 33,
-    #[error("invalid or unsupported action")]
-    ActionInvalid = 34,
-    #[error("invalid source address")]
-    InvalidSrcAddr = 35,
-    #[error("invalid destination address")]
-    InvalidDstAddr = 36,
-    #[error("not enough balance (base currency)")]
-    NotEnoughBalance = 37,
-    #[error("not enough balance (extra currency)")]
-    NotEnoughExtraBalance = 38,
-    #[error("failed to fit message into cell")]
-    FailedToFitMessage = 39,
-    #[error("message exceeds limits")]
-    MessageOutOfLimits = 40,
-    #[error("library code not found")]
-    NoLibCode = 41,
-    #[error("failed to change libraries dict")]
-    InvalidLibrariesDict = 42,
-    #[error("too many library cells")]
-    LibOutOfLimits = 43,
-    #[error("too many extra currencies")]
-    TooManyExtraCurrencies = 44,
-    #[error("state exceeds limits")]
-    StateOutOfLimits = 50,
-}
-
 // TODO: Move into config parm 43.
 const MAX_MSG_EXTRA_CURRENCIES: usize = 2;
 
@@ -1223,14 +1361,15 @@ mod tests {
     use everscale_asm_macros::tvmasm;
     use everscale_types::merkle::MerkleProof;
     use everscale_types::models::{
-        Anycast, IntAddr, MessageLayout, MsgInfo, RelaxedIntMsgInfo, RelaxedMessage, StdAddr,
-        VarAddr,
+        Anycast, IntAddr, MessageLayout, MsgInfo, RelaxedIntMsgInfo, StdAddr, VarAddr,
     };
     use everscale_types::num::{Uint9, VarUint248};
 
     use super::*;
-    use crate::tests::{make_default_config, make_default_params};
-    use crate::ExecutorParams;
+    use crate::testkit::{
+        make_action_list, make_default_config, make_default_params, make_relaxed_message,
+    };
+    use crate::{ExecutorParams, PublicLibraryUpdate};
 
     const STUB_ADDR: StdAddr = StdAddr::new(0, HashBytes::ZERO);
     const OK_BALANCE: Tokens = Tokens::new(1_000_000_000);
@@ -1273,33 +1412,6 @@ mod tests {
         }
     }
 
-    fn make_action_list<I: IntoIterator<Item: Store>>(actions: I) -> Cell {
-        let mut root = Cell::default();
-        for action in actions {
-            root = CellBuilder::build_from((root, action)).unwrap();
-        }
-        root
-    }
-
-    fn make_relaxed_message(
-        info: impl Into<RelaxedMsgInfo>,
-        init: Option<StateInit>,
-        body: Option<CellBuilder>,
-    ) -> Lazy<OwnedRelaxedMessage> {
-        let body = match &body {
-            None => Cell::empty_cell_ref().as_slice_allow_exotic(),
-            Some(cell) => cell.as_full_slice(),
-        };
-        Lazy::new(&RelaxedMessage {
-            info: info.into(),
-            init,
-            body,
-            layout: None,
-        })
-        .unwrap()
-        .cast_into()
-    }
-
     fn compute_full_stats(msg: &Lazy<OwnedMessage>, params: &ExecutorParams) -> StorageUsedShort {
         let msg = 'cell: {
             if params.strict_extra_currency {
@@ -1352,6 +1464,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1394,6 +1507,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1443,6 +1557,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1507,6 +1622,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1559,6 +1675,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1617,6 +1734,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            out_msg_fees,
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1668,6 +1786,16 @@ mod tests {
             ..empty_action_phase()
         });
 
+        let total_message_size = compute_full_stats(last_msg, &params);
+        assert_eq!(out_msg_fees, [OutMsgFees {
+            fwd_fee_total: expected_fwd_fees,
+            first_frac_collected: expected_first_frac,
+            fee_remaining_in_msg: expected_fwd_fees - expected_first_frac,
+            ihr_fee: Tokens::ZERO,
+            cells: total_message_size.cells.into_inner(),
+            bits: total_message_size.bits.into_inner(),
+        }]);
+
         assert_eq!(state.total_fees, prev_total_fees + expected_first_frac);
         assert_eq!(state.balance.other, prev_balance.other);
         assert_eq!(state.balance.tokens, prev_balance.tokens - msg_value);
@@ -1675,6 +1803,207 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn extended_send_msg_flags() -> Result<()> {
+        const EXT_FLAG: u8 = 0b100;
+
+        let config = make_default_config();
+        let compute_phase = stub_compute_phase(OK_GAS);
+
+        let actions = make_action_list([OutAction::SendMsg {
+            mode: SendMsgFlags::from_bits_retain(EXT_FLAG),
+            out_msg: make_relaxed_message(
+                RelaxedIntMsgInfo {
+                    dst: STUB_ADDR.into(),
+                    value: Tokens::new(500_000_000).into(),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+        }]);
+
+        for enabled in [false, true] {
+            let mut params = make_default_params();
+            if enabled {
+                params.extended_send_msg_flags = EXT_FLAG;
+            }
+            let mut state = ExecutorState::new_uninit(&params, &config, &STUB_ADDR, OK_BALANCE);
+
+            let ActionPhaseFull { action_phase, .. } = state.action_phase(ActionPhaseContext {
+                received_message: None,
+                original_balance: original_balance(&state, &compute_phase),
+                new_state: StateInit::default(),
+                actions: actions.clone(),
+                compute_phase: &compute_phase,
+                inspector: None,
+            })?;
+
+            if enabled {
+                assert!(action_phase.success);
+                assert_eq!(state.out_msgs.len(), 1);
+            } else {
+                assert!(!action_phase.success);
+                assert_eq!(action_phase.result_code, ResultCode::ActionInvalid as i32);
+                assert_eq!(action_phase.result_arg, Some(0));
+                assert!(state.out_msgs.is_empty());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn out_msg_filter() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let compute_phase = stub_compute_phase(OK_GAS);
+
+        let vetoed_addr = StdAddr::new(0, HashBytes([0x55; 32]));
+        let make_send_msg = |dst: &StdAddr| OutAction::SendMsg {
+            mode: SendMsgFlags::IGNORE_ERROR,
+            out_msg: make_relaxed_message(
+                RelaxedIntMsgInfo {
+                    dst: dst.clone().into(),
+                    value: Tokens::new(100_000_000).into(),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+        };
+
+        for actions in [vec![make_send_msg(&STUB_ADDR)], vec![
+            make_send_msg(&STUB_ADDR),
+            make_send_msg(&vetoed_addr),
+        ]] {
+            let expect_veto = actions.len() > 1;
+            let actions = make_action_list(actions);
+
+            let mut state = ExecutorState::new_uninit(&params, &config, &STUB_ADDR, OK_BALANCE);
+
+            let mut seen = Vec::new();
+            let mut filter = |msg: &Lazy<OwnedMessage>| {
+                let info = match msg.load().unwrap().info {
+                    MsgInfo::Int(info) => info,
+                    e => panic!("unexpected msg info {e:?}"),
+                };
+                let vetoed = info.dst == IntAddr::from(vetoed_addr.clone());
+                seen.push(info.dst);
+                if vetoed {
+                    OutMsgVerdict::Veto
+                } else {
+                    OutMsgVerdict::Accept
+                }
+            };
+            let mut inspector = ExecutorInspector {
+                out_msg_filter: Some(&mut filter),
+                ..Default::default()
+            };
+
+            let ActionPhaseFull { action_phase, .. } = state.action_phase(ActionPhaseContext {
+                received_message: None,
+                original_balance: original_balance(&state, &compute_phase),
+                new_state: StateInit::default(),
+                actions: actions.clone(),
+                compute_phase: &compute_phase,
+                inspector: Some(&mut inspector),
+            })?;
+
+            if expect_veto {
+                assert!(!action_phase.success);
+                assert_eq!(action_phase.result_code, ResultCode::ActionInvalid as i32);
+                assert_eq!(action_phase.result_arg, Some(1));
+                assert_eq!(action_phase.skipped_actions, 0);
+                assert!(state.out_msgs.is_empty());
+                assert_eq!(seen, [
+                    IntAddr::from(STUB_ADDR),
+                    IntAddr::from(vetoed_addr.clone())
+                ]);
+            } else {
+                assert!(action_phase.success);
+                assert_eq!(state.out_msgs.len(), 1);
+                assert_eq!(seen, [IntAddr::from(STUB_ADDR)]);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn send_msg_estimate_divergence() -> Result<()> {
+        const PLAIN_LAYOUT: MessageLayout = MessageLayout {
+            init_to_cell: false,
+            body_to_cell: false,
+        };
+
+        let params = make_default_params();
+        let config = make_default_config();
+        let compute_phase = stub_compute_phase(OK_GAS);
+
+        let make_out_msg = |value: u128| {
+            make_relaxed_message(
+                RelaxedIntMsgInfo {
+                    dst: STUB_ADDR.into(),
+                    value: Tokens::new(value).into(),
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+        };
+        let exact_msg = make_out_msg(100_000_000);
+        let wrong_msg = make_out_msg(200_000_000);
+        let raw_msg = make_out_msg(300_000_000);
+
+        let expected_fee = Tokens::new(config.fwd_prices.lump_price as _);
+        let wrong_estimate = SendMsgEstimate {
+            msg_hash: *wrong_msg.repr_hash(),
+            total_fee: expected_fee + Tokens::new(1),
+            layout: PLAIN_LAYOUT,
+        };
+
+        let actions = make_action_list([exact_msg.clone(), wrong_msg, raw_msg].map(|out_msg| {
+            OutAction::SendMsg {
+                mode: SendMsgFlags::empty(),
+                out_msg,
+            }
+        }));
+
+        let mut state = ExecutorState::new_uninit(&params, &config, &STUB_ADDR, OK_BALANCE);
+        let mut inspector = ExecutorInspector {
+            check_send_msg_estimates: true,
+            send_msg_estimates: vec![
+                SendMsgEstimate {
+                    msg_hash: *exact_msg.repr_hash(),
+                    total_fee: expected_fee,
+                    layout: PLAIN_LAYOUT,
+                },
+                wrong_estimate.clone(),
+            ],
+            ..Default::default()
+        };
+
+        let ActionPhaseFull { action_phase, .. } = state.action_phase(ActionPhaseContext {
+            received_message: None,
+            original_balance: original_balance(&state, &compute_phase),
+            new_state: StateInit::default(),
+            actions,
+            compute_phase: &compute_phase,
+            inspector: Some(&mut inspector),
+        })?;
+
+        assert!(action_phase.success);
+        assert_eq!(state.out_msgs.len(), 3);
+        assert_eq!(inspector.send_msg_divergences, [SendMsgDivergence {
+            estimate: wrong_estimate,
+            actual_fee: expected_fee,
+            actual_layout: PLAIN_LAYOUT,
+        }]);
+
+        Ok(())
+    }
+
     #[test]
     fn send_all_balance() -> Result<()> {
         let params = make_default_params();
@@ -1704,6 +2033,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1804,6 +2134,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -1959,6 +2290,7 @@ mod tests {
                 action_fine,
                 state_exceeds_limits,
                 bounce,
+                ..
             } = state.action_phase(ActionPhaseContext {
                 received_message: None,
                 original_balance: original_balance(&state, &compute_phase),
@@ -1973,6 +2305,10 @@ mod tests {
             assert!(!bounce);
 
             assert_eq!(state.end_status, expected_end_status);
+            assert_eq!(
+                state.status_change_reason,
+                Some(AccountStatusChangeReason::DestroyFlag)
+            );
             assert_eq!(state.out_msgs.len(), 1);
             assert_eq!(state.end_lt, prev_end_lt + 1);
             let last_msg = state.out_msgs.last().unwrap();
@@ -2061,6 +2397,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -2163,6 +2500,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -2245,6 +2583,7 @@ mod tests {
                 action_fine,
                 state_exceeds_limits,
                 bounce,
+                ..
             } = state.action_phase(ActionPhaseContext {
                 received_message: None,
                 original_balance: original_balance(&state, &compute_phase),
@@ -2310,6 +2649,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -2343,6 +2683,79 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn keep_fwd_fees_on_fail_quirk() -> Result<()> {
+        let config = make_default_config();
+
+        let compute_phase = stub_compute_phase(OK_GAS);
+        let actions = make_action_list([
+            OutAction::SendMsg {
+                mode: SendMsgFlags::PAY_FEE_SEPARATELY,
+                out_msg: make_relaxed_message(
+                    RelaxedIntMsgInfo {
+                        value: CurrencyCollection::new(1000),
+                        dst: STUB_ADDR.into(),
+                        ..Default::default()
+                    },
+                    None,
+                    None,
+                ),
+            },
+            OutAction::SendMsg {
+                mode: SendMsgFlags::empty(),
+                out_msg: make_relaxed_message(
+                    RelaxedIntMsgInfo {
+                        value: CurrencyCollection::new(OK_BALANCE.into_inner() * 10),
+                        dst: STUB_ADDR.into(),
+                        ..Default::default()
+                    },
+                    None,
+                    None,
+                ),
+            },
+        ]);
+
+        let run = |quirks: Quirks| -> Result<ActionPhase> {
+            let params = ExecutorParams {
+                quirks,
+                ..make_default_params()
+            };
+            let mut state = ExecutorState::new_uninit(&params, &config, &STUB_ADDR, OK_BALANCE);
+            let res = state.action_phase(ActionPhaseContext {
+                received_message: None,
+                original_balance: original_balance(&state, &compute_phase),
+                new_state: StateInit::default(),
+                actions: actions.clone(),
+                compute_phase: &compute_phase,
+                inspector: None,
+            })?;
+            assert!(state.out_msgs.is_empty());
+            Ok(res.action_phase)
+        };
+
+        // Forwarding fees are reset by default.
+        let action_phase = run(Quirks::empty())?;
+        assert!(!action_phase.success);
+        assert!(action_phase.no_funds);
+        assert_eq!(
+            action_phase.result_code,
+            ResultCode::NotEnoughBalance as i32
+        );
+        assert_eq!(action_phase.total_fwd_fees, None);
+
+        // Forwarding fees of the first message are kept like in the reference implementation.
+        let action_phase = run(Quirks::KEEP_FWD_FEES_ON_FAIL)?;
+        assert!(!action_phase.success);
+        assert!(action_phase.no_funds);
+        assert_eq!(
+            action_phase.result_code,
+            ResultCode::NotEnoughBalance as i32
+        );
+        assert!(matches!(action_phase.total_fwd_fees, Some(fees) if !fees.is_zero()));
+
+        Ok(())
+    }
+
     #[test]
     fn rewrite_message() -> Result<()> {
         let params = make_default_params();
@@ -2379,6 +2792,7 @@ mod tests {
             action_fine,
             state_exceeds_limits,
             bounce,
+            ..
         } = state.action_phase(ActionPhaseContext {
             received_message: None,
             original_balance: original_balance(&state, &compute_phase),
@@ -2655,6 +3069,7 @@ mod tests {
                 action_fine,
                 state_exceeds_limits,
                 bounce,
+                ..
             } = state.action_phase(ActionPhaseContext {
                 received_message: None,
                 original_balance: original_balance(&state, &compute_phase),
@@ -2684,4 +3099,43 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn fold_public_lib_updates() {
+        let lib = |id: u32| CellBuilder::build_from(id).unwrap();
+        let publisher = HashBytes([0x11; 32]);
+
+        let inspector = ExecutorInspector {
+            public_libs_diff: vec![
+                PublicLibraryChange::Add(lib(123)),
+                PublicLibraryChange::Remove(*lib(234).repr_hash()),
+                PublicLibraryChange::Remove(*lib(123).repr_hash()),
+                PublicLibraryChange::Add(lib(345)),
+                PublicLibraryChange::Add(lib(234)),
+                PublicLibraryChange::Remove(*lib(345).repr_hash()),
+                PublicLibraryChange::Add(lib(345)),
+                PublicLibraryChange::Remove(*lib(456).repr_hash()),
+            ],
+            ..Default::default()
+        };
+
+        let mut expected = vec![
+            PublicLibraryUpdate {
+                lib_hash: *lib(345).repr_hash(),
+                publisher,
+                change: PublicLibraryChange::Add(lib(345)),
+            },
+            PublicLibraryUpdate {
+                lib_hash: *lib(456).repr_hash(),
+                publisher,
+                change: PublicLibraryChange::Remove(*lib(456).repr_hash()),
+            },
+        ];
+        expected.sort_by(|a, b| a.lib_hash.cmp(&b.lib_hash));
+        assert_eq!(inspector.public_lib_updates(&publisher), expected);
+
+        assert!(ExecutorInspector::default()
+            .public_lib_updates(&publisher)
+            .is_empty());
+    }
 }
@@ -4,12 +4,15 @@ use everscale_types::models::{
     BouncePhase, ExecutedBouncePhase, MsgInfo, NoFundsBouncePhase, StorageUsedShort,
 };
 use everscale_types::num::Tokens;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
 
 use crate::phase::receive::ReceivedMessage;
 use crate::util::{
-    check_rewrite_dst_addr, new_varuint56_truncate, ExtStorageStat, StorageStatLimits,
+    check_rewrite_dst_addr, new_varuint56_truncate, normalize_balance, ExtStorageStat,
+    StorageStatLimits,
 };
-use crate::ExecutorState;
+use crate::{fees, metrics, ExecutorError, ExecutorState};
 
 /// Bounce phase input context.
 pub struct BouncePhaseContext<'a> {
@@ -36,7 +39,33 @@ impl ExecutorState<'_> {
     /// not happen on networks with valid value flow.
     ///
     /// [`total_fees`]: Self::total_fees
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            level = "debug",
+            name = "bounce_phase",
+            skip_all,
+            fields(
+                address = %self.address,
+                lt = self.start_lt,
+                no_funds = tracing::field::Empty,
+            ),
+        )
+    )]
     pub fn bounce_phase(&mut self, ctx: BouncePhaseContext<'_>) -> Result<BouncePhase> {
+        let res = self.bounce_phase_impl(ctx);
+
+        #[cfg(feature = "tracing")]
+        if let Ok(res) = &res {
+            tracing::Span::current().record("no_funds", matches!(res, BouncePhase::NoFunds(_)));
+        }
+
+        res
+    }
+
+    fn bounce_phase_impl(&mut self, ctx: BouncePhaseContext<'_>) -> Result<BouncePhase> {
+        let _timer = metrics::PhaseTimer::start("bounce");
+
         let mut info = ctx.received_message.root.parse::<MsgInfo>()?;
         let MsgInfo::Int(int_msg_info) = &mut info else {
             anyhow::bail!("bounce phase is defined only for internal messages");
@@ -44,11 +73,17 @@ impl ExecutorState<'_> {
 
         // Reverse message direction.
         std::mem::swap(&mut int_msg_info.src, &mut int_msg_info.dst);
-        if !check_rewrite_dst_addr(&self.config.workchains, &mut int_msg_info.dst) {
+        if !check_rewrite_dst_addr(
+            &self.config.workchains,
+            self.params.anycast_policy,
+            &mut int_msg_info.dst,
+        ) {
             // FIXME: Just ignore this phase in that case? What if we disable
             // the message origin workchain and this message bounces? However,
             // for that we should at least have other workchains .
-            anyhow::bail!("invalid destination address in a bounced message");
+            anyhow::bail!(ExecutorError::Config(
+                "invalid destination address in a bounced message"
+            ));
         }
 
         // Compute additional full body cell.
@@ -68,10 +103,9 @@ impl ExecutorState<'_> {
 
         // Compute message storage stats.
         let stats = 'stats: {
-            let mut stats = ExtStorageStat::with_limits(StorageStatLimits {
-                bit_count: self.config.size_limits.max_msg_bits,
-                cell_count: self.config.size_limits.max_msg_cells,
-            });
+            let mut stats = ExtStorageStat::with_limits(StorageStatLimits::for_message(
+                &self.config.size_limits,
+            ));
 
             // Root cell is free, but all children must be accounted.
             'valid: {
@@ -107,9 +141,8 @@ impl ExecutorState<'_> {
 
         // Compute forwarding fee.
         let use_mc_prices = self.address.is_masterchain() || int_msg_info.dst.is_masterchain();
-        let prices = self.config.fwd_prices(use_mc_prices);
-
-        let mut fwd_fees = prices.compute_fwd_fee(stats);
+        let fwd_fees =
+            fees::compute_fwd_fee(self.config, self.params.fee_rounding, use_mc_prices, stats);
         let msg_size = StorageUsedShort {
             cells: new_varuint56_truncate(stats.cell_count),
             bits: new_varuint56_truncate(stats.bit_count),
@@ -132,14 +165,14 @@ impl ExecutorState<'_> {
 
         // Take message balance back from the account balance.
         self.balance.try_sub_assign(&msg_value)?;
+        normalize_balance(&mut self.balance)?;
 
         // Take forwarding fee from the message balance.
         msg_value.tokens -= fwd_fees;
 
         // Split forwarding fee.
-        let msg_fees = prices.get_first_part(fwd_fees);
-        fwd_fees -= msg_fees;
-        self.total_fees.try_add_assign(msg_fees)?;
+        let split = fees::split_fwd_fee(self.config, use_mc_prices, fwd_fees);
+        self.total_fees.try_add_assign(split.first_frac)?;
 
         // Finalize message.
         int_msg_info.ihr_disabled = true;
@@ -147,7 +180,7 @@ impl ExecutorState<'_> {
         int_msg_info.bounced = true;
         int_msg_info.value = msg_value;
         int_msg_info.ihr_fee = Tokens::ZERO;
-        int_msg_info.fwd_fee = fwd_fees;
+        int_msg_info.fwd_fee = split.remaining;
         int_msg_info.created_lt = self.end_lt;
         int_msg_info.created_at = self.params.block_unixtime;
 
@@ -198,8 +231,8 @@ impl ExecutorState<'_> {
         // Done
         Ok(BouncePhase::Executed(ExecutedBouncePhase {
             msg_size,
-            msg_fees,
-            fwd_fees,
+            msg_fees: split.first_frac,
+            fwd_fees: split.remaining,
         }))
     }
 }
@@ -210,7 +243,7 @@ mod tests {
     use everscale_types::prelude::*;
 
     use super::*;
-    use crate::tests::{make_default_config, make_default_params, make_message};
+    use crate::testkit::{make_default_config, make_default_params, make_message};
 
     #[test]
     fn bounce_with_enough_funds() {
@@ -384,4 +417,56 @@ mod tests {
         // End LT must not change.
         assert_eq!(state.end_lt, prev_start_lt + 1000 + 2);
     }
+
+    #[test]
+    fn bounce_with_fees_exceeding_value() {
+        let mut params = make_default_params();
+        params.full_body_in_bounced = false;
+
+        let config = make_default_config();
+
+        let src_addr = StdAddr::new(0, HashBytes([0; 32]));
+        let dst_addr = StdAddr::new(0, HashBytes([1; 32]));
+
+        let mut state =
+            ExecutorState::new_uninit(&params, &config, &dst_addr, Tokens::new(1_000_000_000));
+        let prev_balance = state.balance.clone();
+        let prev_total_fees = state.total_fees;
+
+        let value = Tokens::new(1_000_000);
+        let received_msg = state
+            .receive_in_msg(make_message(
+                IntMsgInfo {
+                    src: src_addr.clone().into(),
+                    dst: dst_addr.clone().into(),
+                    value: value.into(),
+                    bounce: true,
+                    ..Default::default()
+                },
+                None,
+                None,
+            ))
+            .unwrap();
+
+        // Gas fees and fine are paid from the message value first.
+        let bounce_phase = state
+            .bounce_phase(BouncePhaseContext {
+                gas_fees: value - Tokens::new(1),
+                action_fine: Tokens::new(2),
+                received_message: &received_msg,
+            })
+            .unwrap();
+
+        let BouncePhase::NoFunds(bounce_phase) = bounce_phase else {
+            panic!("expected bounce phase to be skipped")
+        };
+
+        // Nothing is left for the forwarding fee.
+        let full_fwd_fee = Tokens::new(config.fwd_prices.lump_price as _);
+        assert_eq!(bounce_phase.req_fwd_fees, full_fwd_fee);
+
+        assert_eq!(state.balance.tokens, prev_balance.tokens);
+        assert_eq!(state.total_fees, prev_total_fees);
+        assert_eq!(state.out_msgs.len(), 0);
+    }
 }
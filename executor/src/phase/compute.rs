@@ -6,14 +6,21 @@ use everscale_types::models::{
 use everscale_types::num::Tokens;
 use everscale_types::prelude::*;
 use num_bigint::{BigInt, Sign};
-use tycho_vm::{tuple, SafeRc, SmcInfoBase, Stack, Tuple, UnpackedInMsgSmcInfo, VmState};
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+use tycho_vm::{
+    tuple, AcceptInfo, GasParams, SafeRc, SmcInfoBase, Stack, Tuple, UnpackedInMsgSmcInfo, VmState,
+};
 
 use crate::phase::receive::{MsgStateInit, ReceivedMessage};
 use crate::util::{
     check_state_limits_diff, new_varuint24_truncate, new_varuint56_truncate, unlikely,
-    StateLimitsResult,
+    SharedVmLogBuffer, StateLimitsResult,
+};
+use crate::{
+    codes, fees, metrics, AccountStatusChangeReason, ExecutorError, ExecutorInspector,
+    ExecutorState, PrecompiledContext, PrecompiledContract,
 };
-use crate::{ExecutorInspector, ExecutorState};
 
 /// Compute phase input context.
 pub struct ComputePhaseContext<'a, 'e> {
@@ -58,6 +65,7 @@ impl<'a> TransactionInput<'a> {
 
 /// Executed compute phase with additional info.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComputePhaseFull {
     /// Resulting comput phase.
     pub compute_phase: ComputePhase,
@@ -69,9 +77,24 @@ pub struct ComputePhaseFull {
     /// Original account balance before this phase.
     pub original_balance: CurrencyCollection,
     /// New account state.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::repr"))]
     pub new_state: StateInit,
     /// Resulting actions list.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::cell"))]
     pub actions: Cell,
+    /// Gas credit granted before the message was accepted.
+    pub gas_credit: u64,
+    /// Gas consumed before the message was accepted.
+    ///
+    /// Equals to the total gas consumed if the message was not accepted.
+    pub gas_before_accept: u64,
+    /// VM state at the accepting instruction.
+    ///
+    /// Collected only when [`BehaviourModifiers::stop_on_accept`] is enabled.
+    ///
+    /// [`BehaviourModifiers::stop_on_accept`]: tycho_vm::BehaviourModifiers::stop_on_accept
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub accept_info: Option<AcceptInfo>,
 }
 
 impl ExecutorState<'_> {
@@ -90,7 +113,43 @@ impl ExecutorState<'_> {
     ///
     /// [`Uninit`]: AccountState::Uninit
     /// [`Frozen`]: AccountState::Frozen
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            level = "debug",
+            name = "compute_phase",
+            skip_all,
+            fields(
+                address = %self.address,
+                lt = self.start_lt,
+                exit_code = tracing::field::Empty,
+                skipped = tracing::field::Empty,
+            ),
+        )
+    )]
     pub fn compute_phase(&mut self, ctx: ComputePhaseContext<'_, '_>) -> Result<ComputePhaseFull> {
+        let res = self.compute_phase_impl(ctx);
+
+        #[cfg(feature = "tracing")]
+        if let Ok(res) = &res {
+            let span = tracing::Span::current();
+            match &res.compute_phase {
+                ComputePhase::Executed(phase) => span.record("exit_code", phase.exit_code),
+                ComputePhase::Skipped(phase) => {
+                    span.record("skipped", tracing::field::debug(&phase.reason))
+                }
+            };
+        }
+
+        res
+    }
+
+    fn compute_phase_impl(
+        &mut self,
+        mut ctx: ComputePhaseContext<'_, '_>,
+    ) -> Result<ComputePhaseFull> {
+        let _timer = metrics::PhaseTimer::start("compute");
+
         let is_masterchain = self.address.is_masterchain();
 
         // Compute original balance for the action phase.
@@ -114,6 +173,9 @@ impl ExecutorState<'_> {
             original_balance,
             new_state,
             actions: Cell::empty_cell(),
+            gas_credit: 0,
+            gas_before_accept: 0,
+            accept_info: None,
         };
 
         // Compute VM gas limits.
@@ -130,16 +192,28 @@ impl ExecutorState<'_> {
         };
 
         let gas = if unlikely(ctx.force_accept) {
-            tycho_vm::GasParams::getter()
+            let mut gas = GasParams::getter();
+            if let Some(gas_limit) = self.params.gas_limit_override {
+                gas.max = std::cmp::min(gas.max, gas_limit);
+                gas.limit = gas.max;
+            }
+            gas
         } else {
-            self.config.compute_gas_params(
+            let mut gas = self.config.compute_gas_params_ext(
                 &self.balance.tokens,
                 &msg_balance_remaining.tokens,
                 self.is_special,
                 is_masterchain,
                 ctx.input.is_ordinary(),
                 is_external,
-            )
+                self.params.gas_limit_override,
+            );
+            if let Some(credit) = self.params.ext_msg_gas_credit {
+                if ctx.input.is_ordinary() && is_external {
+                    gas.credit = std::cmp::min(credit, gas.max);
+                }
+            }
+            gas
         };
         if gas.limit == 0 && gas.credit == 0 {
             res.compute_phase = ComputePhase::Skipped(SkippedComputePhase {
@@ -160,12 +234,21 @@ impl ExecutorState<'_> {
                 });
                 return Ok(res);
             }
-            // Frozen account cannot run anything until receives its old state.
+            // Frozen account cannot run anything until receives its old state
+            // (or the old state was recovered externally).
             (None, AccountState::Frozen { .. }) => {
-                res.compute_phase = ComputePhase::Skipped(SkippedComputePhase {
-                    reason: ComputePhaseSkipReason::BadState,
-                });
-                return Ok(res);
+                let Some(recovered) = self.recovered_state else {
+                    res.compute_phase = ComputePhase::Skipped(SkippedComputePhase {
+                        reason: ComputePhaseSkipReason::BadState,
+                    });
+                    return Ok(res);
+                };
+
+                // NOTE: Account status is not changed here.
+                res.new_state = recovered.clone();
+                state_libs = Some(&recovered.libraries);
+                msg_libs = None;
+                msg_state_used = false;
             }
             // Active account simply runs its code. (use libraries from its state).
             (None, AccountState::Active(StateInit { libraries, .. })) => {
@@ -244,6 +327,25 @@ impl ExecutorState<'_> {
             }
         }
 
+        // Run a native implementation of the precompiled contract.
+        let params = self.params;
+        if let Some(contract) = res
+            .new_state
+            .code
+            .as_ref()
+            .and_then(|code| params.precompiled_contracts.get(code.repr_hash()))
+        {
+            self.run_precompiled(
+                contract,
+                ctx,
+                &gas,
+                msg_state_used,
+                is_masterchain,
+                &mut res,
+            )?;
+            return Ok(res);
+        }
+
         // Unpack internal message.
         let unpacked_in_msg = match ctx.input.in_msg() {
             Some(msg) => msg.make_tuple()?,
@@ -255,23 +357,50 @@ impl ExecutorState<'_> {
 
         let code = res.new_state.code.clone();
 
-        let smc_info = SmcInfoBase::new()
+        let mut smc_info = SmcInfoBase::new()
             .with_now(self.params.block_unixtime)
             .with_block_lt(self.params.block_lt)
             .with_tx_lt(self.start_lt)
-            .with_mixed_rand_seed(&self.params.rand_seed, &self.address.address)
+            .with_raw_rand_seed(self.params.rand_seed_derivation.derive(
+                &self.params.rand_seed,
+                &self.address.address,
+                self.start_lt,
+            ))
             .with_account_balance(self.balance.clone())
             .with_account_addr(self.address.clone().into())
             .with_config(self.config.raw.params.clone())
             .require_ton_v4()
             .with_code(code.clone().unwrap_or_default())
             .with_message_balance(msg_balance_remaining.clone())
-            .with_storage_fees(ctx.storage_fee)
+            .with_storage_fees(ctx.storage_fee);
+        if let Some(info) = &self.params.prev_blocks_info {
+            smc_info = smc_info.with_prev_blocks_info(info.as_tuple());
+        }
+        let smc_info = smc_info
             .require_ton_v6()
             .with_unpacked_config(self.config.unpacked.as_tuple())
+            .with_due_payment(self.storage_stat.due_payment.unwrap_or_default())
+            .with_precompiled_gas_usage(
+                code.as_ref()
+                    .and_then(|code| self.config.precompiled_gas_usage(code.repr_hash())),
+            )
             .require_ton_v11()
             .with_unpacked_in_msg(unpacked_in_msg);
 
+        let mut modifiers = self.params.vm_modifiers;
+        if let Some(inspector) = &ctx.inspector {
+            modifiers.record_send_msg_estimates |= inspector.check_send_msg_estimates;
+        }
+
+        // NOTE: Log buffer is shared between the debug output and the VM log
+        // subscriber, so it is moved out of the inspector until the VM is finished.
+        let vm_log = ctx
+            .inspector
+            .as_mut()
+            .and_then(|inspector| inspector.vm_log.take())
+            .map(SharedVmLogBuffer::new);
+        let mut vm_log_sink = vm_log.clone();
+
         let libraries = (msg_libs, state_libs, &self.params.libraries);
         let mut vm = VmState::builder()
             .with_smc_info(smc_info)
@@ -281,29 +410,81 @@ impl ExecutorState<'_> {
             .with_init_selector(false)
             .with_raw_stack(stack)
             .with_gas(gas)
-            .with_modifiers(self.params.vm_modifiers)
+            .with_modifiers(modifiers)
+            .with_limits(self.params.execution_limits.clone())
             .build();
 
         // Connect inspected output as debug.
         let mut inspector_actions = None;
         let mut inspector_exit_code = None;
+        let mut inspector_final_stack = None;
+        let mut inspector_send_msg_estimates = None;
+        let mut inspector_vm_log = None;
         if let Some(inspector) = ctx.inspector {
             inspector_actions = Some(&mut inspector.actions);
             inspector_exit_code = Some(&mut inspector.exit_code);
+            inspector_final_stack = Some(&mut inspector.final_stack);
+            inspector_send_msg_estimates = Some(&mut inspector.send_msg_estimates);
+            inspector_vm_log = Some(&mut inspector.vm_log);
             if let Some(debug) = inspector.debug.as_deref_mut() {
-                vm.debug = Some(debug);
+                vm.debug.push(debug);
             }
         }
+        if let Some(vm_log_sink) = &mut vm_log_sink {
+            vm.debug.push(vm_log_sink);
+        }
+
+        let vm_init_state_hash = self.compute_vm_state_hash(&vm);
 
         // Run VM.
-        let exit_code = !vm.run();
+        let exit_code = {
+            // Capture VM logs into the same buffer as the debug output.
+            #[cfg(feature = "tracing")]
+            let _tracing = vm_log
+                .as_ref()
+                .filter(|_| !modifiers.log_mask.is_empty())
+                .map(|vm_log| {
+                    tracing::subscriber::set_default(
+                        tycho_vm::VmLogSubscriber::new(modifiers.log_mask, 0)
+                            .with_writer(vm_log.0.clone()),
+                    )
+                });
+
+            !vm.run()
+        };
+        if let (Some(target), Some(vm_log)) = (inspector_vm_log, &vm_log) {
+            *target = Some(vm_log.take());
+        }
+        if let Some(reason) = vm.interrupted {
+            return Err(reason.into());
+        }
+        if let Some(hash) = vm.gas.pruned_branch().filter(|_| self.params.pruned_state) {
+            return Err(ExecutorError::PrunedBranchAccess(hash).into());
+        }
+
+        let vm_final_state_hash = self.compute_vm_state_hash(&vm);
 
         if let Some(inspector_exit_code) = inspector_exit_code {
             *inspector_exit_code = Some(exit_code);
         }
+        if let Some(inspector_final_stack) = inspector_final_stack {
+            *inspector_final_stack = Some(vm.stack.clone());
+        }
+        if let Some(estimates) = inspector_send_msg_estimates {
+            *estimates = std::mem::take(&mut vm.send_msg_estimates);
+        }
 
         // Parse VM state.
         res.accepted = ctx.force_accept || vm.gas.credit() == 0;
+        res.gas_credit = gas.credit;
+        res.gas_before_accept = if gas.credit == 0 {
+            0
+        } else {
+            vm.gas
+                .consumed_on_accept()
+                .unwrap_or_else(|| vm.gas.consumed())
+        };
+        res.accept_info = vm.accept_info.take();
         debug_assert!(
             is_external || res.accepted,
             "internal messages must be accepted"
@@ -313,9 +494,7 @@ impl ExecutorState<'_> {
 
         let gas_used = std::cmp::min(vm.gas.consumed(), vm.gas.limit());
         let gas_fees = if res.accepted && !self.is_special {
-            self.config
-                .gas_prices(is_masterchain)
-                .compute_gas_fee(gas_used)
+            fees::compute_gas_fee(self.config, is_masterchain, gas_used)
         } else {
             // We don't add any fees for messages that were not accepted.
             Tokens::ZERO
@@ -325,6 +504,7 @@ impl ExecutorState<'_> {
         if res.accepted && msg_state_used {
             account_activated = self.orig_status != AccountStatus::Active;
             self.end_status = AccountStatus::Active;
+            self.status_change_reason = Some(AccountStatusChangeReason::StateInit);
         }
 
         if let Some(committed) = vm.committed_state {
@@ -342,6 +522,8 @@ impl ExecutorState<'_> {
         self.balance.try_sub_assign_tokens(gas_fees)?;
         self.total_fees.try_add_assign(gas_fees)?;
 
+        metrics::record_compute_phase(gas_used, vm.steps);
+
         res.compute_phase = ComputePhase::Executed(ExecutedComputePhase {
             success,
             msg_state_used,
@@ -360,10 +542,144 @@ impl ExecutorState<'_> {
                 vm.stack.get_exit_arg().filter(|x| *x != 0)
             },
             vm_steps: vm.steps.try_into().unwrap_or(u32::MAX),
+            vm_init_state_hash,
+            vm_final_state_hash,
+        });
+        Ok(res)
+    }
+
+    fn run_precompiled(
+        &mut self,
+        contract: &dyn PrecompiledContract,
+        ctx: ComputePhaseContext<'_, '_>,
+        gas: &GasParams,
+        msg_state_used: bool,
+        is_masterchain: bool,
+        res: &mut ComputePhaseFull,
+    ) -> Result<()> {
+        let output = contract.execute(&PrecompiledContext {
+            address: &self.address,
+            balance: &self.balance,
+            state: &res.new_state,
+            input: ctx.input,
+            block_unixtime: self.params.block_unixtime,
+            start_lt: self.start_lt,
+            config: self.config,
+        });
+
+        let is_external = matches!(ctx.input.in_msg(), Some(msg) if msg.is_external);
+        let accept_called = ctx.force_accept || output.accepted;
+
+        // NOTE: Internal messages are always accepted, but (like in the VM)
+        // the gas limit is raised only after an explicit accept.
+        res.accepted = accept_called || !is_external;
+        res.gas_credit = gas.credit;
+
+        let gas_limit = if accept_called {
+            gas.max
+        } else {
+            gas.limit.saturating_add(gas.credit)
+        };
+
+        // Gas usage from `ConfigParam45` has priority over the default one.
+        let gas_usage = res
+            .new_state
+            .code
+            .as_ref()
+            .and_then(|code| self.config.precompiled_gas_usage(code.repr_hash()))
+            .unwrap_or_else(|| contract.gas_usage());
+        let out_of_gas = gas_usage > gas_limit;
+        let gas_used = std::cmp::min(gas_usage, gas_limit);
+
+        res.gas_before_accept = if gas.credit == 0 { 0 } else { gas_used };
+
+        let mut exit_code = if out_of_gas {
+            codes::COMPUTE_OUT_OF_GAS
+        } else {
+            output.exit_code
+        };
+        let mut success = res.accepted && !out_of_gas && matches!(exit_code, 0 | 1);
+
+        let gas_fees = if res.accepted && !self.is_special {
+            fees::compute_gas_fee(self.config, is_masterchain, gas_used)
+        } else {
+            // We don't add any fees for messages that were not accepted.
+            Tokens::ZERO
+        };
+
+        let mut account_activated = false;
+        if res.accepted && msg_state_used {
+            account_activated = self.orig_status != AccountStatus::Active;
+            self.end_status = AccountStatus::Active;
+            self.status_change_reason = Some(AccountStatusChangeReason::StateInit);
+        }
+
+        if success {
+            if let Some(data) = output.data {
+                let mut new_state = res.new_state.clone();
+                new_state.data = Some(data);
+
+                // Check the new data like the VM does on commit.
+                if !self.is_special
+                    && matches!(
+                        check_state_limits_diff(
+                            &res.new_state,
+                            &new_state,
+                            &self.config.size_limits,
+                            is_masterchain,
+                            &mut self.cached_storage_stat,
+                        ),
+                        StateLimitsResult::Exceeds
+                    )
+                {
+                    exit_code = tycho_vm::VmException::CellOverflow as i32;
+                    success = false;
+                } else {
+                    res.new_state = new_state;
+                }
+            }
+        }
+        if success {
+            res.actions = output.actions;
+        }
+
+        if let Some(inspector) = ctx.inspector {
+            inspector.exit_code = Some(exit_code);
+            if success {
+                inspector.actions = Some(res.actions.clone());
+            }
+        }
+
+        self.balance.try_sub_assign_tokens(gas_fees)?;
+        self.total_fees.try_add_assign(gas_fees)?;
+
+        res.compute_phase = ComputePhase::Executed(ExecutedComputePhase {
+            success,
+            msg_state_used,
+            account_activated,
+            gas_fees,
+            gas_used: new_varuint56_truncate(gas_used),
+            gas_limit: new_varuint56_truncate(gas.limit),
+            gas_credit: (gas.credit != 0).then(|| new_varuint24_truncate(gas.credit)),
+            mode: 0,
+            exit_code,
+            exit_arg: None,
+            // NOTE: Precompiled contracts are executed without the VM.
+            vm_steps: 0,
             vm_init_state_hash: HashBytes::ZERO,
             vm_final_state_hash: HashBytes::ZERO,
         });
-        Ok(res)
+        Ok(())
+    }
+
+    fn compute_vm_state_hash(&self, vm: &VmState<'_>) -> HashBytes {
+        if !self.params.compute_vm_state_hashes {
+            return HashBytes::ZERO;
+        }
+
+        // NOTE: State hash is informational, so serialization errors
+        // (e.g. a too deep stack) are not fatal.
+        vm.state_hash().unwrap_or(HashBytes::ZERO)
     }
 
     fn prepare_vm_stack(&self, input: TransactionInput<'_>) -> SafeRc<Stack> {
@@ -430,12 +746,21 @@ impl ReceivedMessage {
 
 #[cfg(test)]
 mod tests {
+    use std::rc::Rc;
+
     use everscale_asm_macros::tvmasm;
-    use everscale_types::models::{ExtInMsgInfo, IntMsgInfo, LibDescr, SimpleLib, StdAddr};
+    use everscale_crypto::ed25519;
+    use everscale_types::models::{
+        BlockchainConfig, ExtInMsgInfo, GlobalCapabilities, GlobalCapability, IntMsgInfo, LibDescr,
+        SimpleLib, StdAddr,
+    };
     use everscale_types::num::{VarUint24, VarUint56};
 
     use super::*;
-    use crate::tests::{make_default_config, make_default_params, make_message};
+    use crate::testkit::{
+        make_big_tree, make_custom_config, make_default_config, make_default_params, make_message,
+    };
+    use crate::{ParsedConfig, TxError};
 
     const STUB_ADDR: StdAddr = StdAddr::new(0, HashBytes::ZERO);
     const OK_BALANCE: Tokens = Tokens::new(1_000_000_000);
@@ -850,6 +1175,217 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn storage_fees_in_c7() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            Cell::empty_cell(),
+            tvmasm!(
+                r#"
+                STORAGEFEES INT 100 EQUAL THROWIFNOT 100
+                DUEPAYMENT INT 77 EQUAL THROWIFNOT 101
+                ACCEPT
+                "#
+            ),
+        );
+        state.storage_stat.due_payment = Some(Tokens::new(77));
+
+        let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::new(100),
+            force_accept: false,
+            inspector: None,
+        })?;
+        assert!(compute_phase.accepted);
+
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
+        };
+        assert!(compute_phase.success);
+        assert_eq!(compute_phase.exit_code, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn mycode_in_c7() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let code = tvmasm!("ACCEPT MYCODE");
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            Cell::empty_cell(),
+            code,
+        );
+
+        let msg =
+            state.receive_in_msg(empty_int_msg(&state.address, Tokens::new(1_000_000_000)))?;
+        state.credit_phase(&msg)?;
+
+        let mut inspector = ExecutorInspector::default();
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: Some(&mut inspector),
+        })?;
+        assert!(compute_phase.accepted);
+
+        let stack = inspector.final_stack.unwrap();
+        assert_eq!(stack.items.len(), 1);
+        assert_eq!(stack.items[0].as_cell(), Some(&Boc::decode(code)?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn precompiled_gas_in_c7() -> Result<()> {
+        let code = tvmasm!("ACCEPT GETPRECOMPILEDGAS");
+        let code_hash = *Boc::decode(code)?.repr_hash();
+
+        let run = |config: &Rc<ParsedConfig>| -> Result<Option<u64>> {
+            let params = make_default_params();
+            let mut state = ExecutorState::new_active(
+                &params,
+                config,
+                &STUB_ADDR,
+                OK_BALANCE,
+                Cell::empty_cell(),
+                code,
+            );
+
+            let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+
+            let mut inspector = ExecutorInspector::default();
+            let compute_phase = state.compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: Tokens::ZERO,
+                force_accept: false,
+                inspector: Some(&mut inspector),
+            })?;
+            assert!(compute_phase.accepted);
+
+            let stack = inspector.final_stack.unwrap();
+            assert_eq!(stack.items.len(), 1);
+            Ok(stack.items[0].as_int().map(|gas| gas.try_into().unwrap()))
+        };
+
+        // No `ConfigParam45`
+        assert_eq!(run(&make_default_config())?, None);
+
+        // Contract is listed in `ConfigParam45`
+        let config = make_custom_config(|config| {
+            set_precompiled_gas(config, &[(code_hash, 1234), (HashBytes([0x11; 32]), 1)])
+        });
+        assert_eq!(config.precompiled_gas_usage(&code_hash), Some(1234));
+        assert_eq!(run(&config)?, Some(1234));
+
+        // Invalid `ConfigParam45` is rejected
+        let mut raw = make_default_config().raw.clone();
+        raw.params.set_raw(45, CellBuilder::build_from(0xffu8)?)?;
+        assert!(ParsedConfig::parse(raw, u32::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn chksign_with_signature_id() -> Result<()> {
+        let keypair = ed25519::KeyPair::from(&ed25519::SecretKey::from_bytes([0x33; 32]));
+        let data = [0xda_u8; 40];
+
+        // Data layout: `signature:bits512 pubkey:bits256 ^[ data ]`.
+        let make_data = |signature: [u8; 64]| -> Result<Cell> {
+            let mut b = CellBuilder::new();
+            b.store_raw(&signature, 512)?;
+            b.store_raw(keypair.public_key.as_bytes(), 256)?;
+            b.store_reference(CellBuilder::from_raw_data(&data, data.len() as u16 * 8)?.build()?)?;
+            Ok(b.build()?)
+        };
+
+        let code = tvmasm!(
+            r#"
+            ACCEPT
+            PUSHROOT CTOS
+            LDREF SWAP CTOS SWAP
+            INT 512 LDSLICEX
+            PLDU 256
+            CHKSIGNS
+            "#
+        );
+
+        let run = |with_id: bool, signature: [u8; 64]| -> Result<bool> {
+            let config = make_custom_config(|config| {
+                let mut global = config.params.get_global_version()?;
+                let mut caps = global.capabilities.into_inner();
+                if with_id {
+                    caps |= GlobalCapability::CapSignatureWithId as u64;
+                } else {
+                    caps &= !(GlobalCapability::CapSignatureWithId as u64);
+                }
+                global.capabilities = GlobalCapabilities::new(caps);
+                config.params.set_global_version(&global)?;
+                Ok(())
+            });
+
+            let mut params = make_default_params();
+            params.vm_modifiers.chksig_always_succeed = false;
+            params.apply_global_version(&config);
+            assert_eq!(
+                params.vm_modifiers.signature_with_id,
+                with_id.then_some(config.global_id)
+            );
+
+            let mut state = ExecutorState::new_active(
+                &params,
+                &config,
+                &STUB_ADDR,
+                OK_BALANCE,
+                make_data(signature)?,
+                code,
+            );
+
+            let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+
+            let mut inspector = ExecutorInspector::default();
+            let compute_phase = state.compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: Tokens::ZERO,
+                force_accept: false,
+                inspector: Some(&mut inspector),
+            })?;
+            assert!(compute_phase.accepted);
+
+            let stack = inspector.final_stack.unwrap();
+            let is_valid = stack.items.last().and_then(|item| item.as_int()).unwrap();
+            Ok(is_valid.sign() != Sign::NoSign)
+        };
+
+        let global_id = make_default_config().global_id;
+        let signature = keypair.sign_raw(&data);
+        let signature_with_id = {
+            let mut to_sign = global_id.to_be_bytes().to_vec();
+            to_sign.extend_from_slice(&data);
+            keypair.sign_raw(&to_sign)
+        };
+
+        assert!(run(false, signature)?);
+        assert!(!run(false, signature_with_id)?);
+        assert!(run(true, signature_with_id)?);
+        assert!(!run(true, signature)?);
+
+        Ok(())
+    }
+
     #[test]
     fn internal_accept_simple() -> Result<()> {
         let params = make_default_params();
@@ -921,7 +1457,7 @@ mod tests {
     }
 
     #[test]
-    fn internal_no_accept() -> Result<()> {
+    fn internal_in_msg_params() -> Result<()> {
         let params = make_default_params();
         let config = make_default_config();
         let mut state = ExecutorState::new_active(
@@ -930,72 +1466,179 @@ mod tests {
             &STUB_ADDR,
             OK_BALANCE,
             Cell::empty_cell(),
-            tvmasm!("NOP"),
+            tvmasm!(
+                r#"
+                INMSG_BOUNCE THROWIFNOT 100
+                INMSG_BOUNCED THROWIF 101
+                INMSG_SRC MYADDR SDEQ THROWIFNOT 102
+                INMSG_FWDFEE INT 123 EQUAL THROWIFNOT 103
+                INMSG_LT INT 555 EQUAL THROWIFNOT 104
+                INMSG_UTIME INT 777 EQUAL THROWIFNOT 105
+                INMSG_ORIGVALUE INT 1000000000 EQUAL THROWIFNOT 106
+                INMSG_VALUEEXTRA ISNULL THROWIFNOT 107
+                INMSG_STATEINIT ISNULL THROWIFNOT 108
+                ACCEPT
+                "#
+            ),
         );
 
-        let msg = state.receive_in_msg(empty_int_msg(&state.address, Tokens::new(1)))?;
+        let msg = state.receive_in_msg(make_message(
+            IntMsgInfo {
+                bounce: true,
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: Tokens::new(1_000_000_000).into(),
+                fwd_fee: Tokens::new(123),
+                created_lt: 555,
+                created_at: 777,
+                ..Default::default()
+            },
+            None,
+            None,
+        ))?;
 
         state.credit_phase(&msg)?;
 
-        let prev_balance = state.balance.clone();
-        let prev_state = state.state.clone();
-        let prev_total_fees = state.total_fees;
-        let prev_end_status = state.end_status;
-
         let compute_phase = state.compute_phase(ComputePhaseContext {
             input: TransactionInput::Ordinary(&msg),
             storage_fee: Tokens::ZERO,
             force_accept: false,
             inspector: None,
         })?;
+        assert!(compute_phase.accepted);
 
-        // Original balance must be correct.
-        assert_eq!(
-            compute_phase.original_balance,
-            CurrencyCollection::from(OK_BALANCE)
-        );
-        // Message must not be accepted.
-        assert!(!compute_phase.accepted);
-        // State must not change.
-        assert_eq!(state.state, prev_state);
-        // Status must not change.
-        assert_eq!(prev_end_status, state.end_status);
-        // No actions must be produced.
-        assert_eq!(compute_phase.actions, Cell::empty_cell());
-        // No fees must be paid when message was not accepted.
-        assert_eq!(state.total_fees, prev_total_fees);
-        assert_eq!(state.balance, prev_balance);
-
-        let ComputePhase::Skipped(compute_phase) = compute_phase.compute_phase else {
-            panic!("expected skipped compute phase");
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
         };
-        assert_eq!(compute_phase.reason, ComputePhaseSkipReason::NoGas);
+        assert!(compute_phase.success);
+        assert_eq!(compute_phase.exit_code, 0);
 
         Ok(())
     }
 
     #[test]
-    fn internal_no_accept_empty_balance() -> Result<()> {
+    fn internal_set_gas_limit() -> Result<()> {
         let params = make_default_params();
         let config = make_default_config();
         let mut state = ExecutorState::new_active(
             &params,
             &config,
             &STUB_ADDR,
-            Tokens::ZERO,
+            OK_BALANCE,
             Cell::empty_cell(),
-            tvmasm!("NOP"),
+            tvmasm!("INT 500 SETGASLIMIT AGAINEND NOP"),
         );
 
-        let msg = state.receive_in_msg(empty_int_msg(&state.address, Tokens::ZERO))?;
+        let msg =
+            state.receive_in_msg(empty_int_msg(&state.address, Tokens::new(1_000_000_000)))?;
 
         state.credit_phase(&msg)?;
 
-        let prev_balance = state.balance.clone();
-        let prev_state = state.state.clone();
-        let prev_total_fees = state.total_fees;
-        let prev_end_status = state.end_status;
-
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+        assert!(compute_phase.accepted);
+
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
+        };
+
+        assert!(!compute_phase.success);
+        assert_eq!(compute_phase.exit_code, -14);
+        // Gas usage is capped by the adjusted limit.
+        assert_eq!(compute_phase.gas_used, VarUint56::new(500));
+        assert_eq!(
+            compute_phase.gas_fees,
+            fees::compute_gas_fee(&config, false, 500)
+        );
+        // NOTE: Initial gas limit is stored in the compute phase.
+        assert_eq!(
+            compute_phase.gas_limit,
+            VarUint56::new(config.gas_prices.gas_limit)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn internal_no_accept() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            Cell::empty_cell(),
+            tvmasm!("NOP"),
+        );
+
+        let msg = state.receive_in_msg(empty_int_msg(&state.address, Tokens::new(1)))?;
+
+        state.credit_phase(&msg)?;
+
+        let prev_balance = state.balance.clone();
+        let prev_state = state.state.clone();
+        let prev_total_fees = state.total_fees;
+        let prev_end_status = state.end_status;
+
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+
+        // Original balance must be correct.
+        assert_eq!(
+            compute_phase.original_balance,
+            CurrencyCollection::from(OK_BALANCE)
+        );
+        // Message must not be accepted.
+        assert!(!compute_phase.accepted);
+        // State must not change.
+        assert_eq!(state.state, prev_state);
+        // Status must not change.
+        assert_eq!(prev_end_status, state.end_status);
+        // No actions must be produced.
+        assert_eq!(compute_phase.actions, Cell::empty_cell());
+        // No fees must be paid when message was not accepted.
+        assert_eq!(state.total_fees, prev_total_fees);
+        assert_eq!(state.balance, prev_balance);
+
+        let ComputePhase::Skipped(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected skipped compute phase");
+        };
+        assert_eq!(compute_phase.reason, ComputePhaseSkipReason::NoGas);
+
+        Ok(())
+    }
+
+    #[test]
+    fn internal_no_accept_empty_balance() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            Tokens::ZERO,
+            Cell::empty_cell(),
+            tvmasm!("NOP"),
+        );
+
+        let msg = state.receive_in_msg(empty_int_msg(&state.address, Tokens::ZERO))?;
+
+        state.credit_phase(&msg)?;
+
+        let prev_balance = state.balance.clone();
+        let prev_state = state.state.clone();
+        let prev_total_fees = state.total_fees;
+        let prev_end_status = state.end_status;
+
         let compute_phase = state.compute_phase(ComputePhaseContext {
             input: TransactionInput::Ordinary(&msg),
             storage_fee: Tokens::ZERO,
@@ -1800,4 +2443,474 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn ext_in_custom_gas_credit() -> Result<()> {
+        let mut params = make_default_params();
+        params.ext_msg_gas_credit = Some(1000);
+        let config = make_default_config();
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            Cell::empty_cell(),
+            tvmasm!("INT 123 ACCEPT"),
+        );
+
+        let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+
+        assert!(compute_phase.accepted);
+        assert_eq!(compute_phase.gas_credit, 1000);
+        assert_eq!(compute_phase.gas_before_accept, 26 + 26); // pushint, accept
+
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
+        };
+        assert!(compute_phase.success);
+        assert_eq!(compute_phase.gas_credit, Some(VarUint24::new(1000)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn gas_limit_override() -> Result<()> {
+        let config = make_default_config();
+        let balance = Tokens::new(1_000_000_000_000_000);
+
+        let run = |gas_limit_override: Option<u64>| -> Result<ExecutedComputePhase> {
+            let mut params = make_default_params();
+            params.gas_limit_override = gas_limit_override;
+
+            let mut state = ExecutorState::new_active(
+                &params,
+                &config,
+                &STUB_ADDR,
+                balance,
+                Cell::empty_cell(),
+                tvmasm!("INT 123 NOP"),
+            );
+
+            let msg = state.receive_in_msg(empty_int_msg(&state.address, balance))?;
+            let compute_phase = state.compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: Tokens::ZERO,
+                force_accept: false,
+                inspector: None,
+            })?;
+
+            let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+                panic!("expected executed compute phase");
+            };
+            Ok(compute_phase)
+        };
+
+        let compute_phase = run(None)?;
+        assert!(compute_phase.success);
+        assert_eq!(
+            compute_phase.gas_limit,
+            VarUint56::new(config.gas_prices.gas_limit)
+        );
+
+        // Higher limit for a trusted sandbox.
+        let raised = config.gas_prices.gas_limit * 2;
+        let compute_phase = run(Some(raised))?;
+        assert!(compute_phase.success);
+        assert_eq!(compute_phase.gas_limit, VarUint56::new(raised));
+
+        // Lower limit.
+        let compute_phase = run(Some(1000))?;
+        assert!(compute_phase.success);
+        assert_eq!(compute_phase.gas_limit, VarUint56::new(1000));
+
+        // Too low limit.
+        let compute_phase = run(Some(10))?;
+        assert!(!compute_phase.success);
+        assert_eq!(compute_phase.gas_limit, VarUint56::new(10));
+        assert_eq!(compute_phase.exit_code, codes::COMPUTE_OUT_OF_GAS);
+
+        Ok(())
+    }
+
+    #[test]
+    fn interrupted_compute_phase() -> Result<()> {
+        let cancellation = tycho_vm::CancellationFlag::new();
+        cancellation.cancel();
+
+        let mut params = make_default_params();
+        params.execution_limits.cancellation = Some(cancellation);
+        let config = make_default_config();
+
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            Cell::empty_cell(),
+            tvmasm!("AGAINEND NOP"),
+        );
+
+        let msg = state.receive_in_msg(empty_int_msg(&state.address, OK_BALANCE))?;
+        let err = state
+            .compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: Tokens::ZERO,
+                force_accept: false,
+                inspector: None,
+            })
+            .unwrap_err();
+
+        assert!(matches!(
+            TxError::from(err),
+            TxError::Interrupted(tycho_vm::VmInterrupt::Cancelled)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn pruned_branch_access() -> Result<()> {
+        let mut params = make_default_params();
+        params.pruned_state = true;
+        let config = make_default_config();
+
+        let data = CellBuilder::build_from(123u32)?;
+        let pruned =
+            everscale_types::merkle::make_pruned_branch(data.as_ref(), 0, Cell::empty_context())?;
+
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            pruned,
+            tvmasm!("PUSHROOT CTOS DROP"),
+        );
+
+        let msg = state.receive_in_msg(empty_int_msg(&state.address, OK_BALANCE))?;
+        let err = state
+            .compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: Tokens::ZERO,
+                force_accept: false,
+                inspector: None,
+            })
+            .unwrap_err();
+
+        assert!(matches!(
+            TxError::from(err),
+            TxError::Fatal(ExecutorError::PrunedBranchAccess(hash)) if hash == *data.repr_hash()
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn pruned_branch_in_stored_proof() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+
+        let pruned = everscale_types::merkle::make_pruned_branch(
+            CellBuilder::build_from(123u32)?.as_ref(),
+            0,
+            Cell::empty_context(),
+        )?;
+        let proof = {
+            let mut b = CellBuilder::new();
+            b.set_exotic(true);
+            b.store_u8(3)?;
+            b.store_u256(pruned.hash(0))?;
+            b.store_u16(pruned.depth(0))?;
+            b.store_reference(pruned)?;
+            b.build()?
+        };
+
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            CellBuilder::build_from(proof)?,
+            tvmasm!("PUSHROOT CTOS LDREF DROP XCTOS DROP LDREF DROP CTOS"),
+        );
+
+        // Loading a pruned branch of a valid state is a cell underflow.
+        let msg = state.receive_in_msg(empty_int_msg(&state.address, OK_BALANCE))?;
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
+        };
+        assert!(!compute_phase.success);
+        assert_eq!(compute_phase.exit_code, 9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ext_in_stop_on_accept() -> Result<()> {
+        let mut params = make_default_params();
+        params.vm_modifiers.stop_on_accept = true;
+        let config = make_default_config();
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            Cell::empty_cell(),
+            tvmasm!("INT 123 ACCEPT INT 456"),
+        );
+
+        let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+
+        assert!(compute_phase.accepted);
+
+        let accept_info = compute_phase.accept_info.unwrap();
+        assert_eq!(accept_info.offset.bits, 16); // pushint
+        assert_eq!(accept_info.gas_consumed, 26 + 26); // pushint, accept
+        assert_eq!(accept_info.gas_consumed, compute_phase.gas_before_accept);
+
+        // The last item on the stack is the one pushed before ACCEPT.
+        let top = accept_info.stack.items.last().unwrap();
+        assert_eq!(top.as_int().unwrap(), &BigInt::from(123));
+
+        Ok(())
+    }
+
+    #[test]
+    fn vm_state_hashes() -> Result<()> {
+        let mut params = make_default_params();
+        let config = make_default_config();
+
+        for compute_vm_state_hashes in [false, true] {
+            params.compute_vm_state_hashes = compute_vm_state_hashes;
+
+            let mut state = ExecutorState::new_active(
+                &params,
+                &config,
+                &STUB_ADDR,
+                OK_BALANCE,
+                Cell::empty_cell(),
+                tvmasm!("ACCEPT INT 123"),
+            );
+
+            let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+            let compute_phase = state.compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: Tokens::ZERO,
+                force_accept: false,
+                inspector: None,
+            })?;
+
+            let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+                panic!("expected executed compute phase");
+            };
+            assert!(compute_phase.success);
+
+            if compute_vm_state_hashes {
+                assert_ne!(compute_phase.vm_init_state_hash, HashBytes::ZERO);
+                assert_ne!(compute_phase.vm_final_state_hash, HashBytes::ZERO);
+                assert_ne!(
+                    compute_phase.vm_init_state_hash,
+                    compute_phase.vm_final_state_hash
+                );
+            } else {
+                assert_eq!(compute_phase.vm_init_state_hash, HashBytes::ZERO);
+                assert_eq!(compute_phase.vm_final_state_hash, HashBytes::ZERO);
+            }
+        }
+
+        Ok(())
+    }
+
+    struct PrecompiledSmc(u64);
+
+    impl Store for PrecompiledSmc {
+        fn store_into(
+            &self,
+            builder: &mut CellBuilder,
+            _: &dyn CellContext,
+        ) -> Result<(), everscale_types::error::Error> {
+            builder.store_u8(0xb0)?;
+            builder.store_u64(self.0)
+        }
+    }
+
+    fn set_precompiled_gas(
+        config: &mut BlockchainConfig,
+        items: &[(HashBytes, u64)],
+    ) -> Result<()> {
+        let mut list = Dict::<HashBytes, PrecompiledSmc>::new();
+        for (code_hash, gas_usage) in items {
+            list.set(code_hash, PrecompiledSmc(*gas_usage))?;
+        }
+
+        let mut b = CellBuilder::new();
+        b.store_u8(0xc0)?;
+        list.store_into(&mut b, Cell::empty_context())?;
+        config.params.set_raw(45, b.build()?)?;
+        Ok(())
+    }
+
+    struct PrecompiledCounter;
+
+    impl PrecompiledContract for PrecompiledCounter {
+        fn gas_usage(&self) -> u64 {
+            1000
+        }
+
+        fn execute(&self, ctx: &PrecompiledContext<'_>) -> crate::PrecompiledOutput {
+            let counter = match &ctx.state.data {
+                Some(data) => data.parse::<u32>().unwrap_or_default(),
+                None => 0,
+            };
+            crate::PrecompiledOutput {
+                exit_code: 0,
+                accepted: true,
+                data: Some(CellBuilder::build_from(counter + 1).unwrap()),
+                actions: Cell::empty_cell(),
+            }
+        }
+    }
+
+    #[test]
+    fn precompiled_contract() -> Result<()> {
+        let code_boc = tvmasm!("THROW 100");
+        let code = Boc::decode(code_boc)?;
+
+        let mut params = make_default_params();
+        params
+            .precompiled_contracts
+            .insert(*code.repr_hash(), PrecompiledCounter);
+        let config = make_default_config();
+
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            CellBuilder::build_from(10u32)?,
+            code_boc,
+        );
+
+        let prev_balance = state.balance.clone();
+
+        let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+
+        assert!(compute_phase.accepted);
+        assert_eq!(
+            compute_phase.new_state.data,
+            Some(CellBuilder::build_from(11u32)?)
+        );
+
+        let expected_gas_fee = config.gas_prices.compute_gas_fee(1000);
+        assert_eq!(state.total_fees, expected_gas_fee);
+        assert_eq!(state.balance.tokens, prev_balance.tokens - expected_gas_fee);
+
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
+        };
+        assert!(compute_phase.success);
+        assert_eq!(compute_phase.exit_code, 0);
+        assert_eq!(compute_phase.gas_used, 1000);
+        assert_eq!(compute_phase.vm_steps, 0);
+
+        Ok(())
+    }
+
+    struct PrecompiledBigData;
+
+    impl PrecompiledContract for PrecompiledBigData {
+        fn gas_usage(&self) -> u64 {
+            1000
+        }
+
+        fn execute(&self, _: &PrecompiledContext<'_>) -> crate::PrecompiledOutput {
+            crate::PrecompiledOutput {
+                exit_code: 0,
+                accepted: true,
+                data: Some(make_big_tree(2, &mut 0, 10)),
+                actions: Cell::empty_cell(),
+            }
+        }
+    }
+
+    #[test]
+    fn precompiled_contract_limits() -> Result<()> {
+        let code_boc = tvmasm!("THROW 100");
+        let code = Boc::decode(code_boc)?;
+        let code_hash = *code.repr_hash();
+
+        let mut params = make_default_params();
+        params
+            .precompiled_contracts
+            .insert(code_hash, PrecompiledBigData);
+
+        // Allow only the current state (code and data cells).
+        let config = make_custom_config(|config| {
+            let mut limits = config.params.get_size_limits()?;
+            limits.max_acc_state_cells = 4;
+            config.params.set_size_limits(&limits)?;
+            set_precompiled_gas(config, &[(code_hash, 1234)])
+        });
+
+        let data = CellBuilder::build_from(10u32)?;
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            OK_BALANCE,
+            data.clone(),
+            code_boc,
+        );
+
+        let msg = state.receive_in_msg(empty_ext_in_msg(&state.address))?;
+        let compute_phase = state.compute_phase(ComputePhaseContext {
+            input: TransactionInput::Ordinary(&msg),
+            storage_fee: Tokens::ZERO,
+            force_accept: false,
+            inspector: None,
+        })?;
+
+        // New data doesn't fit into the limits.
+        assert!(compute_phase.accepted);
+        assert_eq!(compute_phase.new_state.data, Some(data));
+
+        // Gas usage is taken from `ConfigParam45`.
+        let expected_gas_fee = config.gas_prices.compute_gas_fee(1234);
+        assert_eq!(state.total_fees, expected_gas_fee);
+
+        let ComputePhase::Executed(compute_phase) = compute_phase.compute_phase else {
+            panic!("expected executed compute phase");
+        };
+        assert!(!compute_phase.success);
+        assert_eq!(
+            compute_phase.exit_code,
+            tycho_vm::VmException::CellOverflow as i32
+        );
+        assert_eq!(compute_phase.gas_used, 1234);
+
+        Ok(())
+    }
 }
@@ -1,8 +1,10 @@
 use anyhow::Result;
 use everscale_types::models::CreditPhase;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
 
 use crate::phase::receive::ReceivedMessage;
-use crate::ExecutorState;
+use crate::{metrics, ExecutorState};
 
 impl ExecutorState<'_> {
     /// Credit phase of ordinary transactions.
@@ -20,7 +22,21 @@ impl ExecutorState<'_> {
     ///
     /// [`receive_in_msg`]: Self::receive_in_msg
     /// [`bounce_enabled`]: ReceivedMessage::bounce_enabled
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            level = "debug",
+            name = "credit_phase",
+            skip_all,
+            fields(
+                address = %self.address,
+                lt = self.start_lt,
+            ),
+        )
+    )]
     pub fn credit_phase(&mut self, received: &ReceivedMessage) -> Result<CreditPhase> {
+        let _timer = metrics::PhaseTimer::start("credit");
+
         // Remaining message balance is added to the account balamce.
         self.balance.try_add_assign(&received.balance_remaining)?;
 
@@ -41,7 +57,7 @@ mod tests {
     use everscale_types::num::Tokens;
 
     use super::*;
-    use crate::tests::{make_default_config, make_default_params};
+    use crate::testkit::{make_default_config, make_default_params};
 
     #[test]
     fn credit_phase_works() {
@@ -63,6 +79,7 @@ mod tests {
                 root: Cell::default(),
                 init: None,
                 body: Default::default(),
+                created_lt: 0,
                 is_external: false,
                 bounce_enabled: false,
                 balance_remaining: msg_balance.clone(),
@@ -4,13 +4,14 @@ use everscale_types::num::Tokens;
 use everscale_types::prelude::*;
 
 use crate::util::{ExtStorageStat, StorageStatLimits};
-use crate::ExecutorState;
+use crate::{fees, ExecutorError, ExecutorState};
 
 impl ExecutorState<'_> {
     /// "Pre" phase of ordinary transactions.
     ///
     /// - Validates the inbound message cell;
-    /// - For internal messages updates an LT range;
+    /// - For internal messages updates an LT range
+    ///   (using [`self.in_msg_lt`] if specified);
     /// - For external messages charges a fwd fee
     ///   (updates [`self.balance`] and [`self.total_fees`]).
     ///
@@ -18,6 +19,7 @@ impl ExecutorState<'_> {
     ///
     /// Fails if the message is invalid or can't be imported.
     ///
+    /// [`self.in_msg_lt`]: Self::in_msg_lt
     /// [`self.balance`]: Self::balance
     /// [`self.total_fees`]: Self::total_fees
     pub fn receive_in_msg(&mut self, msg_root: Cell) -> Result<ReceivedMessage> {
@@ -25,6 +27,7 @@ impl ExecutorState<'_> {
 
         let is_external;
         let bounce_enabled;
+        let created_lt;
         let mut msg_balance_remaining;
 
         // Process message header.
@@ -43,8 +46,9 @@ impl ExecutorState<'_> {
                 msg_balance_remaining.try_add_assign_tokens(info.ihr_fee)?;
 
                 // Adjust LT range.
-                if info.created_lt >= self.start_lt {
-                    self.start_lt = info.created_lt + 1;
+                created_lt = self.in_msg_lt.unwrap_or(info.created_lt);
+                if created_lt >= self.start_lt {
+                    self.start_lt = created_lt + 1;
                     self.end_lt = self.start_lt + 1;
                 }
             }
@@ -55,15 +59,14 @@ impl ExecutorState<'_> {
                 // Update flags.
                 is_external = true;
                 bounce_enabled = false;
+                created_lt = 0;
 
                 // Compute forwarding fees.
-                let Some(mut stats) =
-                    ExtStorageStat::compute_for_slice(&slice, StorageStatLimits {
-                        bit_count: self.config.size_limits.max_msg_bits,
-                        cell_count: self.config.size_limits.max_msg_cells,
-                    })
-                else {
-                    anyhow::bail!("inbound message limits exceeded");
+                let Some(mut stats) = ExtStorageStat::compute_for_slice(
+                    &slice,
+                    StorageStatLimits::for_message(&self.config.size_limits),
+                ) else {
+                    anyhow::bail!(ExecutorError::LimitExceeded("inbound message"));
                 };
 
                 stats.cell_count -= 1; // root cell is ignored.
@@ -74,9 +77,12 @@ impl ExecutorState<'_> {
                     // NOTE: We still need to compute and check `ExtStorageStat`.
                     Tokens::ZERO
                 } else {
-                    self.config
-                        .fwd_prices(is_masterchain)
-                        .compute_fwd_fee(stats)
+                    fees::compute_fwd_fee(
+                        self.config,
+                        self.params.fee_rounding,
+                        is_masterchain,
+                        stats,
+                    )
                 };
 
                 // Deduct fees.
@@ -153,6 +159,7 @@ impl ExecutorState<'_> {
             root: msg_root,
             init,
             body,
+            created_lt,
             is_external,
             bounce_enabled,
             balance_remaining: msg_balance_remaining,
@@ -180,6 +187,12 @@ pub struct ReceivedMessage {
     pub init: Option<MsgStateInit>,
     /// Message body.
     pub body: CellSliceParts,
+    /// Effective logical time of the message creation.
+    ///
+    /// Can differ from the `created_lt` of the message when it was
+    /// overridden by [`ExecutorState::in_msg_lt`].
+    /// NOTE: Always zero for external messages.
+    pub created_lt: u64,
 
     /// Whether this message is an `ExtIn`.
     pub is_external: bool,
@@ -214,7 +227,7 @@ mod tests {
     use everscale_types::num::Tokens;
 
     use super::*;
-    use crate::tests::{
+    use crate::testkit::{
         make_big_tree, make_custom_config, make_default_config, make_default_params, make_message,
     };
 
@@ -322,6 +335,42 @@ mod tests {
         assert_eq!(state.state, prev_acc_state);
     }
 
+    #[test]
+    fn receive_int_with_lt_override() {
+        let params = make_default_params();
+        let config = make_default_config();
+
+        let msg_lt = 1000;
+        let msg_root = make_message(
+            IntMsgInfo {
+                dst: STUB_ADDR.into(),
+                value: OK_BALANCE.into(),
+                created_lt: msg_lt,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        for in_msg_lt in [500, 2000] {
+            let mut state = ExecutorState::new_non_existent(&params, &config, &STUB_ADDR);
+            state.in_msg_lt = Some(in_msg_lt);
+
+            let msg = state.receive_in_msg(msg_root.clone()).unwrap();
+            assert_eq!(msg.created_lt, in_msg_lt);
+
+            // LT must change to the effective message LT.
+            assert_eq!(state.start_lt, in_msg_lt + 1);
+            assert_eq!(state.end_lt, state.start_lt + 1);
+        }
+
+        // Embedded LT is used by default.
+        let mut state = ExecutorState::new_non_existent(&params, &config, &STUB_ADDR);
+        let msg = state.receive_in_msg(msg_root).unwrap();
+        assert_eq!(msg.created_lt, msg_lt);
+        assert_eq!(state.start_lt, msg_lt + 1);
+    }
+
     #[test]
     fn receive_int_to_blackhole() {
         let addr = StdAddr::new(-1, HashBytes::ZERO);
@@ -1,9 +1,11 @@
 use anyhow::Result;
 use everscale_types::models::{AccountState, AccountStatus, AccountStatusChange, StoragePhase};
 use everscale_types::num::Tokens;
+#[cfg(feature = "tracing")]
+use tracing::instrument;
 
 use crate::phase::receive::ReceivedMessage;
-use crate::ExecutorState;
+use crate::{metrics, AccountStatusChangeReason, ExecutorState};
 
 /// Storage phase input context.
 pub struct StoragePhaseContext<'a> {
@@ -35,7 +37,22 @@ impl ExecutorState<'_> {
     /// [`bounce_enabled`]: ReceivedMessage::bounce_enabled
     /// [`last_paid`]: everscale_types::models::StorageInfo::last_paid
     /// [`total_fees`]: Self::total_fees
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            level = "debug",
+            name = "storage_phase",
+            skip_all,
+            fields(
+                address = %self.address,
+                lt = self.start_lt,
+                status_change = tracing::field::Empty,
+            ),
+        )
+    )]
     pub fn storage_phase(&mut self, ctx: StoragePhaseContext<'_>) -> Result<StoragePhase> {
+        let _timer = metrics::PhaseTimer::start("storage");
+
         anyhow::ensure!(
             self.params.block_unixtime >= self.storage_stat.last_paid,
             "current unixtime is less than the account last_paid",
@@ -99,12 +116,12 @@ impl ExecutorState<'_> {
             status_change = match &self.state {
                 // Do nothing for special accounts.
                 _ if self.is_special => AccountStatusChange::Unchanged,
-                // Try to delete account.
+                // Try to delete account (only when it has no extra currencies).
                 AccountState::Uninit | AccountState::Frozen { .. }
                     if (matches!(&self.state, AccountState::Uninit)
                         || !self.params.disable_delete_frozen_accounts)
                         && fees_due.into_inner() > config.delete_due_limit as u128
-                        && !self.balance.other.is_empty() =>
+                        && self.balance.other.is_empty() =>
                 {
                     AccountStatusChange::Deleted
                 }
@@ -134,9 +151,13 @@ impl ExecutorState<'_> {
             AccountStatusChange::Frozen => {
                 // NOTE: We are not changing the account state yet, just updating status.
                 self.end_status = AccountStatus::Frozen;
+                self.status_change_reason = Some(AccountStatusChangeReason::StorageDebt);
             }
             AccountStatusChange::Deleted => {
                 self.end_status = AccountStatus::NotExists;
+                self.status_change_reason = Some(AccountStatusChangeReason::StorageDebt);
+                // Deleted account has no state to reuse the stats for.
+                self.cached_storage_stat = None;
             }
         }
 
@@ -150,6 +171,9 @@ impl ExecutorState<'_> {
         // Add fees.
         self.total_fees.try_add_assign(storage_fees_collected)?;
 
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("status_change", tracing::field::debug(&status_change));
+
         // Done
         Ok(StoragePhase {
             storage_fees_collected,
@@ -166,8 +190,8 @@ mod tests {
     use everscale_types::num::VarUint56;
 
     use super::*;
-    use crate::tests::{make_default_config, make_default_params};
-    use crate::util::shift_ceil_price;
+    use crate::testkit::{make_default_config, make_default_params};
+    use crate::util::{shift_ceil_price, OwnedExtStorageStat};
     use crate::ParsedConfig;
 
     const STUB_ADDR: StdAddr = StdAddr::new(0, HashBytes::ZERO);
@@ -346,6 +370,10 @@ mod tests {
 
         // Account status must not change.
         assert_eq!(state.end_status, AccountStatus::Frozen);
+        assert_eq!(
+            state.status_change_reason,
+            Some(AccountStatusChangeReason::StorageDebt)
+        );
         assert_eq!(storage_phase.status_change, AccountStatusChange::Frozen);
         // Account balance in tokens must be empty.
         assert_eq!(state.balance.tokens, Tokens::ZERO);
@@ -377,4 +405,87 @@ mod tests {
             Some(prev_due + target_fee - prev_balance.tokens)
         );
     }
+
+    #[test]
+    fn frozen_account_deleted_with_big_storage_due() {
+        let mut params = make_default_params();
+        let config = make_default_config();
+
+        params.block_unixtime = 2000;
+
+        let mut state = ExecutorState::new_frozen(
+            &params,
+            &config,
+            &STUB_ADDR,
+            Tokens::ZERO,
+            HashBytes([0x11; 32]),
+        );
+        state.storage_stat = StorageInfo {
+            used: StorageUsed {
+                bits: VarUint56::new(1000),
+                cells: VarUint56::new(10),
+                ..Default::default()
+            },
+            last_paid: 1000,
+            due_payment: Some(Tokens::new(config.gas_prices.delete_due_limit as u128 + 1)),
+        };
+        state.cached_storage_stat = Some(OwnedExtStorageStat::unlimited());
+
+        let storage_phase = state
+            .storage_phase(StoragePhaseContext {
+                adjust_msg_balance: false,
+                received_message: None,
+            })
+            .unwrap();
+
+        // Account must be deleted.
+        assert_eq!(state.end_status, AccountStatus::NotExists);
+        assert_eq!(
+            state.status_change_reason,
+            Some(AccountStatusChangeReason::StorageDebt)
+        );
+        assert_eq!(storage_phase.status_change, AccountStatusChange::Deleted);
+        assert!(state.cached_storage_stat.is_none());
+        assert!(
+            storage_phase.storage_fees_due.unwrap().into_inner()
+                > config.gas_prices.delete_due_limit as u128
+        );
+    }
+
+    #[test]
+    fn frozen_account_kept_when_deletion_disabled() {
+        let mut params = make_default_params();
+        let config = make_default_config();
+
+        params.block_unixtime = 2000;
+        params.disable_delete_frozen_accounts = true;
+
+        let mut state = ExecutorState::new_frozen(
+            &params,
+            &config,
+            &STUB_ADDR,
+            Tokens::ZERO,
+            HashBytes([0x11; 32]),
+        );
+        state.storage_stat = StorageInfo {
+            used: StorageUsed::default(),
+            last_paid: 1000,
+            due_payment: Some(Tokens::new(config.gas_prices.delete_due_limit as u128 + 1)),
+        };
+
+        let storage_phase = state
+            .storage_phase(StoragePhaseContext {
+                adjust_msg_balance: false,
+                received_message: None,
+            })
+            .unwrap();
+
+        // Account must stay frozen.
+        assert_eq!(state.end_status, AccountStatus::Frozen);
+        assert_eq!(
+            state.status_change_reason,
+            Some(AccountStatusChangeReason::StorageDebt)
+        );
+        assert_eq!(storage_phase.status_change, AccountStatusChange::Unchanged);
+    }
 }
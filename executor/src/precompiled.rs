@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use ahash::HashMap;
+use everscale_types::models::{CurrencyCollection, StateInit, StdAddr};
+use everscale_types::prelude::*;
+
+use crate::phase::TransactionInput;
+use crate::ParsedConfig;
+
+/// Native implementation of a well-known contract.
+///
+/// When the code hash of the executing account matches, the compute phase
+/// runs this implementation instead of the VM and charges a fixed amount
+/// of gas (from `ConfigParam45` if the contract is listed there).
+///
+/// New data is checked against the account state size limits, and the
+/// execution fails with a cell overflow if it doesn't fit.
+pub trait PrecompiledContract: Send + Sync {
+    /// Fixed amount of gas for each execution.
+    ///
+    /// Used only when the contract is not listed in `ConfigParam45`.
+    fn gas_usage(&self) -> u64;
+
+    /// Executes the contract.
+    fn execute(&self, ctx: &PrecompiledContext<'_>) -> PrecompiledOutput;
+}
+
+/// Precompiled contract execution input.
+pub struct PrecompiledContext<'a> {
+    /// Account address.
+    pub address: &'a StdAddr,
+    /// Account balance (including the inbound message value).
+    pub balance: &'a CurrencyCollection,
+    /// Account state to execute.
+    pub state: &'a StateInit,
+    /// Parsed transaction input.
+    pub input: TransactionInput<'a>,
+    /// Unix timestamp in seconds of the block.
+    pub block_unixtime: u32,
+    /// Logical time of the transaction.
+    pub start_lt: u64,
+    /// Blockchain config.
+    pub config: &'a ParsedConfig,
+}
+
+/// Precompiled contract execution output.
+#[derive(Debug, Clone)]
+pub struct PrecompiledOutput {
+    /// Exit code (`0` or `1` means success).
+    pub exit_code: i32,
+    /// Whether an external message is accepted.
+    ///
+    /// Ignored for internal messages and ticktock transactions.
+    pub accepted: bool,
+    /// New account data (`None` to keep the current data).
+    pub data: Option<Cell>,
+    /// Resulting actions list.
+    pub actions: Cell,
+}
+
+/// Precompiled contracts registry keyed by code hash.
+#[derive(Default, Clone)]
+pub struct PrecompiledContracts {
+    contracts: HashMap<HashBytes, Arc<dyn PrecompiledContract>>,
+}
+
+impl PrecompiledContracts {
+    /// Registers a native implementation for the specified code hash.
+    ///
+    /// Returns the previous implementation if any.
+    pub fn insert<T: PrecompiledContract + 'static>(
+        &mut self,
+        code_hash: HashBytes,
+        contract: T,
+    ) -> Option<Arc<dyn PrecompiledContract>> {
+        self.contracts.insert(code_hash, Arc::new(contract))
+    }
+
+    /// Removes an implementation for the specified code hash.
+    pub fn remove(&mut self, code_hash: &HashBytes) -> Option<Arc<dyn PrecompiledContract>> {
+        self.contracts.remove(code_hash)
+    }
+
+    /// Returns an implementation for the specified code hash.
+    pub fn get(&self, code_hash: &HashBytes) -> Option<&dyn PrecompiledContract> {
+        self.contracts.get(code_hash).map(AsRef::as_ref)
+    }
+
+    /// Returns `true` if there are no registered contracts.
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty()
+    }
+
+    /// Registered code hashes with the addresses of their implementations
+    /// (sorted by code hash).
+    ///
+    /// Clones of the registry share implementations, so they
+    /// have the same fingerprint.
+    pub(crate) fn fingerprint(&self) -> Vec<(HashBytes, usize)> {
+        let mut result = self
+            .contracts
+            .iter()
+            .map(|(code_hash, contract)| (*code_hash, Arc::as_ptr(contract) as *const () as usize))
+            .collect::<Vec<_>>();
+        result.sort_unstable();
+        result
+    }
+}
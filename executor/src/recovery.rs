@@ -0,0 +1,236 @@
+use everscale_types::models::{Account, AccountState, SimpleLib, SpecialFlags, StateInit};
+use everscale_types::num::SplitDepth;
+use everscale_types::prelude::*;
+
+/// Builder for the original state of a frozen account.
+///
+/// Frozen accounts only store a hash of their last active state,
+/// so the code (and the rest of the state) must be supplied externally
+/// to execute such accounts without an unfreezing message.
+#[derive(Debug, Clone)]
+pub struct FrozenStateBuilder {
+    state: StateInit,
+}
+
+impl FrozenStateBuilder {
+    /// Creates a builder for the state with the specified code.
+    pub fn new(code: Cell) -> Self {
+        Self {
+            state: StateInit {
+                split_depth: None,
+                special: None,
+                code: Some(code),
+                data: None,
+                libraries: Dict::new(),
+            },
+        }
+    }
+
+    /// Sets the persistent data of the original state.
+    pub fn with_data(mut self, data: Cell) -> Self {
+        self.state.data = Some(data);
+        self
+    }
+
+    /// Sets the libraries of the original state.
+    pub fn with_libraries(mut self, libraries: Dict<HashBytes, SimpleLib>) -> Self {
+        self.state.libraries = libraries;
+        self
+    }
+
+    /// Sets the special flags of the original state.
+    pub fn with_special(mut self, special: SpecialFlags) -> Self {
+        self.state.special = Some(special);
+        self
+    }
+
+    /// Sets the split depth of the original state.
+    pub fn with_split_depth(mut self, split_depth: SplitDepth) -> Self {
+        self.state.split_depth = Some(split_depth);
+        self
+    }
+
+    /// Returns the state if it matches the frozen state hash.
+    pub fn build_for(self, frozen_hash: &HashBytes) -> Result<StateInit, StateRecoveryError> {
+        check_frozen_state(frozen_hash, &self.state)?;
+        Ok(self.state)
+    }
+
+    /// Returns the state if it matches the frozen account.
+    pub fn build_for_account(self, account: &Account) -> Result<StateInit, StateRecoveryError> {
+        match &account.state {
+            AccountState::Frozen(frozen_hash) => self.build_for(frozen_hash),
+            AccountState::Uninit | AccountState::Active(_) => Err(StateRecoveryError::NotFrozen),
+        }
+    }
+
+    /// Returns the state without any checks.
+    pub fn build(self) -> StateInit {
+        self.state
+    }
+}
+
+/// Checks whether the state matches the frozen state hash.
+pub fn check_frozen_state(
+    frozen_hash: &HashBytes,
+    state: &StateInit,
+) -> Result<(), StateRecoveryError> {
+    let cell = CellBuilder::build_from(state).map_err(StateRecoveryError::InvalidState)?;
+    let actual = *cell.repr_hash();
+    if actual == *frozen_hash {
+        Ok(())
+    } else {
+        Err(StateRecoveryError::HashMismatch {
+            expected: *frozen_hash,
+            actual,
+        })
+    }
+}
+
+/// Frozen state recovery error.
+#[derive(Debug, thiserror::Error)]
+pub enum StateRecoveryError {
+    #[error("account is not frozen")]
+    NotFrozen,
+    #[error("failed to serialize the recovered state")]
+    InvalidState(#[source] everscale_types::error::Error),
+    #[error("recovered state hash mismatch (expected {expected}, got {actual})")]
+    HashMismatch {
+        expected: HashBytes,
+        actual: HashBytes,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_asm_macros::tvmasm;
+    use everscale_types::models::{
+        AccountStatus, ComputePhase, CurrencyCollection, IntMsgInfo, OptionalAccount, ShardAccount,
+        StdAddr, StorageInfo, TxInfo,
+    };
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+    use crate::Executor;
+
+    #[test]
+    fn frozen_state_builder() {
+        let code = Boc::decode(tvmasm!("ACCEPT")).unwrap();
+        let data = CellBuilder::build_from(123u32).unwrap();
+
+        let original = FrozenStateBuilder::new(code.clone())
+            .with_data(data.clone())
+            .build();
+        let frozen_hash = *CellBuilder::build_from(&original).unwrap().repr_hash();
+
+        let recovered = FrozenStateBuilder::new(code.clone())
+            .with_data(data)
+            .build_for(&frozen_hash)
+            .unwrap();
+        assert_eq!(recovered, original);
+
+        // Data is also a part of the frozen state.
+        let res = FrozenStateBuilder::new(code).build_for(&frozen_hash);
+        assert!(matches!(
+            res,
+            Err(StateRecoveryError::HashMismatch { expected, .. }) if expected == frozen_hash
+        ));
+    }
+
+    #[test]
+    fn execute_recovered_state() -> anyhow::Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let code = Boc::decode(tvmasm!("ACCEPT"))?;
+        let data = CellBuilder::build_from(123u32)?;
+
+        let original = FrozenStateBuilder::new(code.clone())
+            .with_data(data.clone())
+            .build();
+        let frozen_hash = *CellBuilder::build_from(&original)?.repr_hash();
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let account = Account {
+            address: address.clone().into(),
+            storage_stat: StorageInfo::default(),
+            last_trans_lt: 1001,
+            balance: CurrencyCollection::new(1_000_000_000),
+            state: AccountState::Frozen(frozen_hash),
+        };
+
+        let recovered = FrozenStateBuilder::new(code)
+            .with_data(data)
+            .build_for_account(&account)?;
+
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(account)))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        // Frozen account is not executed without its state.
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary(&address, false, &msg, &state)?
+            .commit()?;
+        let tx = output.transaction.load()?;
+        assert_eq!(tx.end_status, AccountStatus::Frozen);
+
+        // Recovered state is used only to execute the account.
+        let output = Executor::new(&params, config.as_ref())
+            .with_recovered_state(&recovered)
+            .begin_ordinary(&address, false, &msg, &state)?
+            .commit()?;
+        let tx = output.transaction.load()?;
+        assert_eq!(tx.orig_status, AccountStatus::Frozen);
+        assert_eq!(tx.end_status, AccountStatus::Frozen);
+        let TxInfo::Ordinary(info) = tx.load_info()? else {
+            panic!("expected an ordinary transaction info");
+        };
+        assert!(matches!(info.compute_phase, ComputePhase::Executed(_)));
+
+        let new_state = output.new_state.load_account()?.unwrap();
+        assert_eq!(new_state.state, AccountState::Frozen(frozen_hash));
+
+        // Account is unfreezed only by a message with its state init.
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            Some(original.clone()),
+            None,
+        );
+        let output = Executor::new(&params, config.as_ref())
+            .with_recovered_state(&recovered)
+            .begin_ordinary(&address, false, &msg, &state)?
+            .commit()?;
+        let tx = output.transaction.load()?;
+        assert_eq!(tx.orig_status, AccountStatus::Frozen);
+        assert_eq!(tx.end_status, AccountStatus::Active);
+
+        // Mismatched state is not allowed.
+        let other = FrozenStateBuilder::new(Cell::empty_cell()).build();
+        let res = Executor::new(&params, config.as_ref())
+            .with_recovered_state(&other)
+            .begin_ordinary(&address, false, &msg, &state);
+        assert!(res.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Serde helpers for cells encoded as base64 BOC.
+
+use everscale_types::prelude::*;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// [`Cell`] as a base64 encoded BOC.
+pub mod cell {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(cell: &Cell, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&Boc::encode_base64(cell))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cell, D::Error> {
+        let boc = String::deserialize(deserializer)?;
+        Boc::decode_base64(boc).map_err(D::Error::custom)
+    }
+}
+
+/// [`Lazy`] as a base64 encoded BOC.
+pub mod lazy {
+    use super::*;
+
+    pub fn serialize<T, S: Serializer>(value: &Lazy<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        cell::serialize(value.inner(), serializer)
+    }
+
+    pub fn deserialize<'de, T, D: Deserializer<'de>>(deserializer: D) -> Result<Lazy<T>, D::Error> {
+        let cell = cell::deserialize(deserializer)?;
+        Lazy::from_raw(cell).map_err(D::Error::custom)
+    }
+}
+
+/// A list of [`Lazy`] as base64 encoded BOCs.
+pub mod lazy_vec {
+    use super::*;
+
+    pub fn serialize<T, S: Serializer>(
+        values: &[Lazy<T>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| Boc::encode_base64(value.inner())))
+    }
+
+    pub fn deserialize<'de, T, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Lazy<T>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|boc| {
+                let cell = Boc::decode_base64(boc).map_err(D::Error::custom)?;
+                Lazy::from_raw(cell).map_err(D::Error::custom)
+            })
+            .collect()
+    }
+}
+
+/// [`Dict`] root as an optional base64 encoded BOC.
+pub mod dict {
+    use super::*;
+
+    pub fn serialize<K, V, S: Serializer>(
+        value: &Dict<K, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value
+            .root()
+            .as_ref()
+            .map(Boc::encode_base64)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, K, V, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Dict<K, V>, D::Error> {
+        let root = match Option::<String>::deserialize(deserializer)? {
+            Some(boc) => Some(Boc::decode_base64(boc).map_err(D::Error::custom)?),
+            None => None,
+        };
+        Ok(Dict::from_raw(root))
+    }
+}
+
+/// Any cell model as a base64 encoded BOC of its representation.
+pub mod repr {
+    use super::*;
+
+    pub fn serialize<T: Store, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let cell = CellBuilder::build_from(value).map_err(S::Error::custom)?;
+        cell::serialize(&cell, serializer)
+    }
+
+    pub fn deserialize<'de, T, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error>
+    where
+        for<'a> T: Load<'a>,
+    {
+        let cell = cell::deserialize(deserializer)?;
+        cell.parse::<T>().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, IntMsgInfo, OptionalAccount, ShardAccount,
+        StdAddr, StorageInfo,
+    };
+    use everscale_types::prelude::*;
+
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+    use crate::{Executor, ExecutorOutput};
+
+    #[test]
+    fn executor_output_json() -> anyhow::Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Uninit,
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary(&address, false, &msg, &state)?
+            .commit()?;
+
+        let json = serde_json::to_value(&output)?;
+        assert_eq!(
+            json["transaction"].as_str(),
+            Some(Boc::encode_base64(output.transaction.inner()).as_str())
+        );
+
+        let parsed = serde_json::from_value::<ExecutorOutput>(json)?;
+        assert_eq!(
+            parsed.transaction.repr_hash(),
+            output.transaction.repr_hash()
+        );
+        assert_eq!(
+            CellBuilder::build_from(&parsed.new_state)?,
+            CellBuilder::build_from(&output.new_state)?
+        );
+        assert_eq!(parsed.burned, output.burned);
+        assert_eq!(
+            parsed.transaction_meta.gas_used,
+            output.transaction_meta.gas_used
+        );
+        assert_eq!(
+            parsed.transaction_meta.status_change,
+            output.transaction_meta.status_change
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Fixtures for executor tests.
+//!
+//! All functions panic on invalid input since they are intended
+//! to be used only in tests.
+
+use std::rc::Rc;
+
+use everscale_types::boc::BocRepr;
+use everscale_types::models::{
+    BlockchainConfig, Message, MsgInfo, OwnedRelaxedMessage, RelaxedMessage, RelaxedMsgInfo,
+    SizeLimitsConfig, StateInit,
+};
+use everscale_types::prelude::*;
+
+use crate::{ExecutorParams, ParsedConfig};
+
+/// Returns a shared config with the default params.
+pub fn make_default_config() -> Rc<ParsedConfig> {
+    thread_local! {
+        pub static PARSED_CONFIG: Rc<ParsedConfig> = make_custom_config(|_| Ok(()));
+    }
+
+    PARSED_CONFIG.with(Clone::clone)
+}
+
+/// Builds a config from the default params modified by the closure.
+pub fn make_custom_config<F>(f: F) -> Rc<ParsedConfig>
+where
+    F: FnOnce(&mut BlockchainConfig) -> anyhow::Result<()>,
+{
+    let mut config: BlockchainConfig =
+        BocRepr::decode(include_bytes!("../res/config.boc")).unwrap();
+
+    config.params.set_global_id(100).unwrap();
+
+    // TODO: Update config BOC
+    config
+        .params
+        .set_size_limits(&SizeLimitsConfig {
+            max_msg_bits: 1 << 21,
+            max_msg_cells: 1 << 13,
+            max_library_cells: 1000,
+            max_vm_data_depth: 512,
+            max_ext_msg_size: 65535,
+            max_ext_msg_depth: 512,
+            max_acc_state_cells: 1 << 16,
+            max_acc_state_bits: (1 << 16) * 1023,
+            max_acc_public_libraries: 256,
+            defer_out_queue_size_limit: 256,
+        })
+        .unwrap();
+
+    f(&mut config).unwrap();
+
+    Rc::new(ParsedConfig::parse(config, u32::MAX).unwrap())
+}
+
+/// Returns params with a fixed block time and disabled signature checks.
+pub fn make_default_params() -> ExecutorParams {
+    ExecutorParams {
+        block_unixtime: 1738799198,
+        full_body_in_bounced: false,
+        strict_extra_currency: true,
+        vm_modifiers: tycho_vm::BehaviourModifiers {
+            chksig_always_succeed: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Builds a message cell.
+pub fn make_message(
+    info: impl Into<MsgInfo>,
+    init: Option<StateInit>,
+    body: Option<CellBuilder>,
+) -> Cell {
+    let body = match &body {
+        None => Cell::empty_cell_ref().as_slice_allow_exotic(),
+        Some(cell) => cell.as_full_slice(),
+    };
+    CellBuilder::build_from(Message {
+        info: info.into(),
+        init,
+        body,
+        layout: None,
+    })
+    .unwrap()
+}
+
+/// Builds an outbound message for the `SendMsg` action.
+pub fn make_relaxed_message(
+    info: impl Into<RelaxedMsgInfo>,
+    init: Option<StateInit>,
+    body: Option<CellBuilder>,
+) -> Lazy<OwnedRelaxedMessage> {
+    let body = match &body {
+        None => Cell::empty_cell_ref().as_slice_allow_exotic(),
+        Some(cell) => cell.as_full_slice(),
+    };
+    Lazy::new(&RelaxedMessage {
+        info: info.into(),
+        init,
+        body,
+        layout: None,
+    })
+    .unwrap()
+    .cast_into()
+}
+
+/// Builds an action list (`c5`) with actions in the execution order.
+pub fn make_action_list<I: IntoIterator<Item: Store>>(actions: I) -> Cell {
+    let mut root = Cell::default();
+    for action in actions {
+        root = CellBuilder::build_from((root, action)).unwrap();
+    }
+    root
+}
+
+/// Builds a tree of unique cells with up to 4 refs per cell.
+///
+/// Stops adding new cells when `count` reaches `target`.
+pub fn make_big_tree(depth: u8, count: &mut u16, target: u16) -> Cell {
+    *count += 1;
+
+    if depth == 0 {
+        CellBuilder::build_from(*count).unwrap()
+    } else {
+        let mut b = CellBuilder::new();
+        for _ in 0..4 {
+            if *count < target {
+                b.store_reference(make_big_tree(depth - 1, count, target))
+                    .unwrap();
+            }
+        }
+        b.build().unwrap()
+    }
+}
@@ -0,0 +1,442 @@
+//! Transaction trace export.
+//!
+//! Produces the JSON layout used by explorers and emulator frontends
+//! (toncenter-like): all integers which can exceed `2^53` are strings,
+//! hashes are hex strings and cells are base64 encoded BOCs.
+
+use anyhow::Result;
+use everscale_types::models::{
+    AccountStatus, AccountStatusChange, ActionPhase, BouncePhase, ComputePhase,
+    ComputePhaseSkipReason, CreditPhase, MsgInfo, OwnedMessage, StoragePhase, StorageUsedShort,
+    TickTock, Transaction, TxInfo,
+};
+use everscale_types::prelude::*;
+use serde_json::{json, Value};
+use tycho_vm::{OwnedCellSlice, RcStackValue, StackValueType};
+
+use crate::{ExecutorInspector, ExecutorOutput};
+
+/// Exports an executed transaction as a JSON trace.
+///
+/// Pass the inspector used for the execution to include the final
+/// VM stack of a failed compute phase and the captured VM log.
+pub fn to_json(
+    output: &ExecutorOutput,
+    inspector: Option<&ExecutorInspector<'_>>,
+) -> Result<Value> {
+    let mut trace = transaction_to_json(output.transaction.inner())?;
+    trace["shard_account"] = Value::String(Boc::encode_base64(CellBuilder::build_from(
+        &output.new_state,
+    )?));
+
+    if trace["description"]["compute_ph"]["success"] == false {
+        if let Some(stack) = inspector.and_then(|inspector| inspector.final_stack.as_ref()) {
+            let mut items = Vec::with_capacity(stack.items.len());
+            for item in &stack.items {
+                items.push(stack_value_to_json(item)?);
+            }
+            trace["stack"] = Value::Array(items);
+        }
+    }
+
+    if let Some(vm_log) = inspector.and_then(|inspector| inspector.vm_log.as_ref()) {
+        trace["vm_log"] = Value::String(vm_log.as_str().to_owned());
+        trace["vm_log_truncated"] = Value::Bool(vm_log.is_truncated());
+    }
+
+    Ok(trace)
+}
+
+/// Exports a transaction cell as a JSON trace
+/// (without the account state and the VM stack).
+pub fn transaction_to_json(transaction: &Cell) -> Result<Value> {
+    let tx = transaction.parse::<Transaction>()?;
+
+    let description = match tx.load_info()? {
+        TxInfo::Ordinary(info) => json!({
+            "type": "ord",
+            "aborted": info.aborted,
+            "destroyed": info.destroyed,
+            "credit_first": info.credit_first,
+            "storage_ph": info.storage_phase.as_ref().map(storage_phase_to_json),
+            "credit_ph": info.credit_phase.as_ref().map(credit_phase_to_json),
+            "compute_ph": compute_phase_to_json(&info.compute_phase),
+            "action": info.action_phase.as_ref().map(action_phase_to_json),
+            "bounce": info.bounce_phase.as_ref().map(bounce_phase_to_json),
+        }),
+        TxInfo::TickTock(info) => json!({
+            "type": match info.kind {
+                TickTock::Tick => "tick",
+                TickTock::Tock => "tock",
+            },
+            "aborted": info.aborted,
+            "destroyed": info.destroyed,
+            "storage_ph": storage_phase_to_json(&info.storage_phase),
+            "compute_ph": compute_phase_to_json(&info.compute_phase),
+            "action": info.action_phase.as_ref().map(action_phase_to_json),
+        }),
+    };
+
+    let in_msg = match &tx.in_msg {
+        Some(cell) => message_to_json(cell)?,
+        None => Value::Null,
+    };
+
+    let mut out_msgs = Vec::with_capacity(tx.out_msg_count.into_inner() as usize);
+    for msg in tx.out_msgs.values() {
+        out_msgs.push(message_to_json(&msg?)?);
+    }
+
+    Ok(json!({
+        "account": tx.account.to_string(),
+        "hash": transaction.repr_hash().to_string(),
+        "lt": tx.lt.to_string(),
+        "now": tx.now,
+        "prev_trans_hash": tx.prev_trans_hash.to_string(),
+        "prev_trans_lt": tx.prev_trans_lt.to_string(),
+        "orig_status": status_to_str(tx.orig_status),
+        "end_status": status_to_str(tx.end_status),
+        "total_fees": tokens_to_json(tx.total_fees.tokens),
+        "description": description,
+        "in_msg": in_msg,
+        "out_msgs": out_msgs,
+        "boc": Boc::encode_base64(transaction),
+    }))
+}
+
+fn storage_phase_to_json(phase: &StoragePhase) -> Value {
+    json!({
+        "storage_fees_collected": tokens_to_json(phase.storage_fees_collected),
+        "storage_fees_due": phase.storage_fees_due.map(tokens_to_json),
+        "status_change": status_change_to_str(phase.status_change),
+    })
+}
+
+fn credit_phase_to_json(phase: &CreditPhase) -> Value {
+    json!({
+        "due_fees_collected": phase.due_fees_collected.map(tokens_to_json),
+        "credit": tokens_to_json(phase.credit.tokens),
+    })
+}
+
+fn compute_phase_to_json(phase: &ComputePhase) -> Value {
+    match phase {
+        ComputePhase::Skipped(phase) => json!({
+            "skipped": true,
+            "reason": match phase.reason {
+                ComputePhaseSkipReason::NoState => "no_state",
+                ComputePhaseSkipReason::BadState => "bad_state",
+                ComputePhaseSkipReason::NoGas => "no_gas",
+                ComputePhaseSkipReason::Suspended => "suspended",
+            },
+        }),
+        ComputePhase::Executed(phase) => json!({
+            "skipped": false,
+            "success": phase.success,
+            "msg_state_used": phase.msg_state_used,
+            "account_activated": phase.account_activated,
+            "gas_fees": tokens_to_json(phase.gas_fees),
+            "gas_used": phase.gas_used.into_inner().to_string(),
+            "gas_limit": phase.gas_limit.into_inner().to_string(),
+            "gas_credit": phase.gas_credit.map(|credit| credit.into_inner().to_string()),
+            "mode": phase.mode,
+            "exit_code": phase.exit_code,
+            "exit_arg": phase.exit_arg,
+            "vm_steps": phase.vm_steps,
+            "vm_init_state_hash": phase.vm_init_state_hash.to_string(),
+            "vm_final_state_hash": phase.vm_final_state_hash.to_string(),
+        }),
+    }
+}
+
+fn action_phase_to_json(phase: &ActionPhase) -> Value {
+    json!({
+        "success": phase.success,
+        "valid": phase.valid,
+        "no_funds": phase.no_funds,
+        "status_change": status_change_to_str(phase.status_change),
+        "total_fwd_fees": phase.total_fwd_fees.map(tokens_to_json),
+        "total_action_fees": phase.total_action_fees.map(tokens_to_json),
+        "result_code": phase.result_code,
+        "result_arg": phase.result_arg,
+        "tot_actions": phase.total_actions,
+        "spec_actions": phase.special_actions,
+        "skipped_actions": phase.skipped_actions,
+        "msgs_created": phase.messages_created,
+        "action_list_hash": phase.action_list_hash.to_string(),
+        "tot_msg_size": size_to_json(&phase.total_message_size),
+    })
+}
+
+fn bounce_phase_to_json(phase: &BouncePhase) -> Value {
+    match phase {
+        BouncePhase::NegativeFunds => json!({ "type": "negfunds" }),
+        BouncePhase::NoFunds(phase) => json!({
+            "type": "nofunds",
+            "msg_size": size_to_json(&phase.msg_size),
+            "req_fwd_fees": tokens_to_json(phase.req_fwd_fees),
+        }),
+        BouncePhase::Executed(phase) => json!({
+            "type": "ok",
+            "msg_size": size_to_json(&phase.msg_size),
+            "msg_fees": tokens_to_json(phase.msg_fees),
+            "fwd_fees": tokens_to_json(phase.fwd_fees),
+        }),
+    }
+}
+
+fn message_to_json(cell: &Cell) -> Result<Value> {
+    let msg = cell.parse::<OwnedMessage>()?;
+
+    let mut body = CellBuilder::new();
+    body.store_slice(OwnedCellSlice::from(msg.body).apply())?;
+    let body = body.build()?;
+
+    let mut res = json!({
+        "hash": cell.repr_hash().to_string(),
+        "message_content": {
+            "hash": body.repr_hash().to_string(),
+            "body": Boc::encode_base64(&body),
+        },
+        "init_state": match &msg.init {
+            Some(init) => Value::String(Boc::encode_base64(CellBuilder::build_from(init)?)),
+            None => Value::Null,
+        },
+    });
+
+    let info = match msg.info {
+        MsgInfo::Int(info) => json!({
+            "source": info.src.to_string(),
+            "destination": info.dst.to_string(),
+            "value": tokens_to_json(info.value.tokens),
+            "fwd_fee": tokens_to_json(info.fwd_fee),
+            "ihr_fee": tokens_to_json(info.ihr_fee),
+            "ihr_disabled": info.ihr_disabled,
+            "bounce": info.bounce,
+            "bounced": info.bounced,
+            "created_lt": info.created_lt.to_string(),
+            "created_at": info.created_at,
+        }),
+        MsgInfo::ExtIn(info) => json!({
+            "source": Value::Null,
+            "destination": info.dst.to_string(),
+            "import_fee": tokens_to_json(info.import_fee),
+        }),
+        MsgInfo::ExtOut(info) => json!({
+            "source": info.src.to_string(),
+            "destination": Value::Null,
+            "created_lt": info.created_lt.to_string(),
+            "created_at": info.created_at,
+        }),
+    };
+    if let (Value::Object(res), Value::Object(info)) = (&mut res, info) {
+        res.extend(info);
+    }
+
+    Ok(res)
+}
+
+fn stack_value_to_json(value: &RcStackValue) -> Result<Value> {
+    Ok(match value.ty() {
+        StackValueType::Null => json!({ "type": "null" }),
+        StackValueType::Int => match value.as_int() {
+            Some(int) => json!({ "type": "num", "value": format!("{int:#x}") }),
+            None => json!({ "type": "nan" }),
+        },
+        StackValueType::Cell => json!({
+            "type": "cell",
+            "value": value.as_cell().map(Boc::encode_base64),
+        }),
+        StackValueType::Slice => {
+            let mut b = CellBuilder::new();
+            if let Some(slice) = value.as_cell_slice() {
+                b.store_slice(slice.apply())?;
+            }
+            json!({ "type": "slice", "value": Boc::encode_base64(b.build()?) })
+        }
+        StackValueType::Builder => {
+            let b = value.as_cell_builder().cloned().unwrap_or_default();
+            json!({ "type": "builder", "value": Boc::encode_base64(b.build()?) })
+        }
+        StackValueType::Cont => json!({ "type": "cont" }),
+        StackValueType::Tuple => {
+            let items = value.as_tuple().unwrap_or_default();
+            let mut res = Vec::with_capacity(items.len());
+            for item in items {
+                res.push(stack_value_to_json(item)?);
+            }
+            json!({ "type": "tuple", "value": res })
+        }
+    })
+}
+
+fn tokens_to_json(tokens: everscale_types::num::Tokens) -> Value {
+    Value::String(tokens.into_inner().to_string())
+}
+
+fn size_to_json(size: &StorageUsedShort) -> Value {
+    json!({
+        "cells": size.cells.into_inner().to_string(),
+        "bits": size.bits.into_inner().to_string(),
+    })
+}
+
+fn status_to_str(status: AccountStatus) -> &'static str {
+    match status {
+        AccountStatus::Uninit => "uninit",
+        AccountStatus::Frozen => "frozen",
+        AccountStatus::Active => "active",
+        AccountStatus::NotExists => "nonexist",
+    }
+}
+
+fn status_change_to_str(status_change: AccountStatusChange) -> &'static str {
+    match status_change {
+        AccountStatusChange::Unchanged => "unchanged",
+        AccountStatusChange::Frozen => "frozen",
+        AccountStatusChange::Deleted => "deleted",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_asm_macros::tvmasm;
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, IntMsgInfo, OptionalAccount, ShardAccount,
+        StateInit, StdAddr, StorageInfo,
+    };
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+    use crate::{Executor, VmLogBuffer};
+
+    #[test]
+    fn failed_compute_trace() -> Result<()> {
+        let config = make_default_config();
+        let params = make_default_params();
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Active(StateInit {
+                    code: Some(Boc::decode(tvmasm!("INT 123 DUMPSTK THROW 100"))?),
+                    ..Default::default()
+                }),
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let mut inspector = ExecutorInspector {
+            vm_log: Some(VmLogBuffer::default()),
+            ..Default::default()
+        };
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary_ext(&address, false, &msg, &state, Some(&mut inspector))?
+            .commit()?;
+
+        let trace = to_json(&output, Some(&inspector))?;
+        assert_eq!(trace["hash"], output.transaction.repr_hash().to_string());
+        assert_eq!(trace["orig_status"], "active");
+        assert_eq!(trace["description"]["type"], "ord");
+        assert_eq!(trace["description"]["aborted"], true);
+        assert_eq!(trace["description"]["compute_ph"]["success"], false);
+        assert_eq!(trace["description"]["compute_ph"]["exit_code"], 100);
+        assert_eq!(trace["in_msg"]["destination"], address.to_string());
+        assert_eq!(trace["out_msgs"], json!([]));
+        assert!(!trace["stack"].as_array().unwrap().is_empty());
+        assert_eq!(trace["vm_log"], "#DEBUG#: stack(1 values) : 123\n");
+        assert_eq!(trace["vm_log_truncated"], false);
+
+        // Stack is exported only with the inspector.
+        let trace = to_json(&output, None)?;
+        assert!(trace.get("stack").is_none());
+        assert!(trace.get("vm_log").is_none());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn vm_log_in_trace() -> Result<()> {
+        let config = make_default_config();
+        let mut params = make_default_params();
+        params.vm_modifiers.log_mask = tycho_vm::VmLogMask::MESSAGE;
+
+        let address = StdAddr::new(0, HashBytes([0x33; 32]));
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: address.clone().into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Active(StateInit {
+                    code: Some(Boc::decode(tvmasm!("INT 123 DUMPSTK THROW 100"))?),
+                    ..Default::default()
+                }),
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: address.clone().into(),
+                dst: address.clone().into(),
+                value: CurrencyCollection::new(1_000_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let mut inspector = ExecutorInspector {
+            vm_log: Some(VmLogBuffer::default()),
+            ..Default::default()
+        };
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary_ext(&address, false, &msg, &state, Some(&mut inspector))?
+            .commit()?;
+
+        // Instruction trace and debug output are interleaved.
+        let trace = to_json(&output, Some(&inspector))?;
+        let vm_log = trace["vm_log"].as_str().unwrap();
+        let debug_pos = vm_log.find("#DEBUG#: stack(1 values) : 123").unwrap();
+        let dumpstk_pos = vm_log.find("execute DUMPSTK").unwrap();
+        let throw_pos = vm_log.find("execute THROW 100").unwrap();
+        assert!(dumpstk_pos < debug_pos && debug_pos < throw_pos);
+        assert_eq!(trace["vm_log_truncated"], false);
+
+        // Log is still bounded.
+        let mut inspector = ExecutorInspector {
+            vm_log: Some(VmLogBuffer::new(16)),
+            ..Default::default()
+        };
+        Executor::new(&params, config.as_ref())
+            .begin_ordinary_ext(&address, false, &msg, &state, Some(&mut inspector))?
+            .commit()?;
+
+        let vm_log = inspector.vm_log.unwrap();
+        assert_eq!(vm_log.as_str().len(), 16);
+        assert!(vm_log.is_truncated());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::Context;
+use everscale_types::models::{ComputePhase, ShardAccount, StdAddr};
+
+use crate::phase::{ComputePhaseContext, StoragePhaseContext, TransactionInput};
+use crate::{Executor, ExecutorError, LoadMessage};
+
+/// Result of an external message acceptance pre-check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtMsgCheck {
+    /// Whether the message was accepted by the account.
+    pub accepted: bool,
+    /// Gas consumed before the message was accepted
+    /// (or in total if it was not).
+    pub gas_used: u64,
+    /// Compute phase exit code.
+    ///
+    /// `None` if the message was invalid or the compute phase was skipped.
+    pub exit_code: Option<i32>,
+}
+
+impl ExtMsgCheck {
+    const REJECTED: Self = Self {
+        accepted: false,
+        gas_used: 0,
+        exit_code: None,
+    };
+}
+
+impl Executor<'_> {
+    /// Checks whether an external message will be accepted by the account.
+    ///
+    /// Runs only the storage phase and the compute phase up to the first
+    /// `ACCEPT` (within the gas credit). Nothing is committed.
+    pub fn check_external_message<M>(
+        &self,
+        address: &StdAddr,
+        msg: M,
+        state: &ShardAccount,
+    ) -> Result<ExtMsgCheck, ExecutorError>
+    where
+        M: LoadMessage,
+    {
+        let msg_root = msg.load_message_root()?;
+
+        let mut params = self.params.clone();
+        params.vm_modifiers.stop_on_accept = true;
+        let executor = Executor {
+            params: &params,
+            ..*self
+        };
+
+        let mut exec = executor.begin(address, state.load_account()?)?;
+
+        // Invalid external messages are always rejected.
+        let mut msg = match exec.receive_in_msg(msg_root) {
+            Ok(msg) if msg.is_external => msg,
+            Ok(_) => {
+                return Err(ExecutorError::Invariant(anyhow::anyhow!(
+                    "expected an external inbound message"
+                )))
+            }
+            Err(_) => return Ok(ExtMsgCheck::REJECTED),
+        };
+
+        let storage_phase = exec
+            .storage_phase(StoragePhaseContext {
+                adjust_msg_balance: true,
+                received_message: Some(&mut msg),
+            })
+            .context("storage phase failed")?;
+
+        let res = exec
+            .compute_phase(ComputePhaseContext {
+                input: TransactionInput::Ordinary(&msg),
+                storage_fee: storage_phase.storage_fees_collected,
+                force_accept: false,
+                inspector: None,
+            })
+            .context("compute phase failed")?;
+
+        Ok(ExtMsgCheck {
+            accepted: res.accepted,
+            gas_used: res.gas_before_accept,
+            exit_code: match &res.compute_phase {
+                ComputePhase::Executed(phase) => Some(phase.exit_code),
+                ComputePhase::Skipped(_) => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_asm_macros::tvmasm;
+    use everscale_types::cell::Lazy;
+    use everscale_types::models::{
+        Account, AccountState, CurrencyCollection, ExtInMsgInfo, IntMsgInfo, OptionalAccount,
+        StateInit, StorageInfo,
+    };
+    use everscale_types::prelude::*;
+
+    use super::*;
+    use crate::testkit::{make_default_config, make_default_params, make_message};
+
+    const STUB_ADDR: StdAddr = StdAddr::new(0, HashBytes::ZERO);
+
+    fn make_active(code: &[u8]) -> ShardAccount {
+        ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: STUB_ADDR.into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Active(StateInit {
+                    code: Some(Boc::decode(code).unwrap()),
+                    ..Default::default()
+                }),
+            })))
+            .unwrap(),
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        }
+    }
+
+    #[test]
+    fn check_ext_msg() -> anyhow::Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+        let executor = Executor::new(&params, config.as_ref());
+
+        let msg = make_message(
+            ExtInMsgInfo {
+                dst: STUB_ADDR.into(),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        // Execution stops right after the accept.
+        let state = make_active(tvmasm!("INT 123 ACCEPT INT 0 THROWIFNOT 100"));
+        let res = executor.check_external_message(&STUB_ADDR, &msg, &state)?;
+        assert_eq!(res, ExtMsgCheck {
+            accepted: true,
+            gas_used: 26 + 26, // pushint, accept
+            exit_code: Some(0),
+        });
+
+        let state = make_active(tvmasm!("INT 123 NOP"));
+        let res = executor.check_external_message(&STUB_ADDR, &msg, &state)?;
+        assert_eq!(res, ExtMsgCheck {
+            accepted: false,
+            gas_used: 26 + 18 + 5, // pushint, nop, implicit ret
+            exit_code: Some(0),
+        });
+
+        // Accounts without state are rejected.
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount::EMPTY)?,
+            last_trans_hash: HashBytes::ZERO,
+            last_trans_lt: 0,
+        };
+        let res = executor.check_external_message(&STUB_ADDR, &msg, &state)?;
+        assert_eq!(res, ExtMsgCheck {
+            accepted: false,
+            gas_used: 0,
+            exit_code: None,
+        });
+
+        // Internal messages can't be checked.
+        let msg = make_message(
+            IntMsgInfo {
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection::new(1_000_000),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        assert!(executor
+            .check_external_message(&STUB_ADDR, &msg, &state)
+            .is_err());
+
+        Ok(())
+    }
+}
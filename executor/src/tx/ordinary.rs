@@ -3,7 +3,7 @@ use everscale_types::models::{AccountStatus, ComputePhase, OrdinaryTxInfo};
 use everscale_types::num::Tokens;
 use everscale_types::prelude::*;
 
-use crate::error::{TxError, TxResult};
+use crate::error::{ExecutorError, TxError, TxResult};
 use crate::phase::{
     ActionPhaseContext, BouncePhaseContext, ComputePhaseContext, ComputePhaseFull,
     StoragePhaseContext, TransactionInput,
@@ -21,20 +21,22 @@ impl ExecutorState<'_> {
         let mut msg = match self.receive_in_msg(msg_root) {
             Ok(msg) if msg.is_external == is_external => msg,
             Ok(_) => {
-                return Err(TxError::Fatal(anyhow!(
+                return Err(TxError::Fatal(ExecutorError::Invariant(anyhow!(
                     "received an unexpected inbound message"
-                )))
+                ))))
             }
             // Invalid external messages can be safely skipped.
             Err(_) if is_external => return Err(TxError::Skipped),
-            Err(e) => return Err(TxError::Fatal(e)),
+            Err(e) => return Err(e.into()),
         };
 
         // Order of credit and storage phases depends on the `bounce` flag
-        // of the inbound message.
+        // of the inbound message (unless the legacy ordering is used).
+        let credit_first = !msg.bounce_enabled || self.params.credit_first_for_bounceable;
+
         let storage_phase;
         let credit_phase;
-        if msg.bounce_enabled {
+        if !credit_first {
             // Run storage phase.
             storage_phase = self
                 .storage_phase(StoragePhaseContext {
@@ -73,6 +75,7 @@ impl ExecutorState<'_> {
             original_balance,
             new_state,
             actions,
+            ..
         } = self
             .compute_phase(ComputePhaseContext {
                 input: TransactionInput::Ordinary(&msg),
@@ -143,7 +146,7 @@ impl ExecutorState<'_> {
 
         // Build transaction info.
         Ok(OrdinaryTxInfo {
-            credit_first: !msg.bounce_enabled,
+            credit_first,
             storage_phase: Some(storage_phase),
             credit_phase,
             compute_phase,
@@ -157,19 +160,28 @@ impl ExecutorState<'_> {
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroU32;
+
     use anyhow::Result;
     use everscale_asm_macros::tvmasm;
     use everscale_types::cell::Lazy;
     use everscale_types::models::{
-        Account, AccountState, AccountStatusChange, CurrencyCollection, ExtInMsgInfo, IntMsgInfo,
-        MsgInfo, OptionalAccount, ShardAccount, StateInit, StdAddr, StorageInfo, StorageUsed,
-        TxInfo,
+        Account, AccountState, AccountStatusChange, BurningConfig, CurrencyCollection,
+        ExtInMsgInfo, ExtraCurrencyCollection, IntMsgInfo, MessageLayout, MsgInfo, OptionalAccount,
+        RelaxedExtOutMsgInfo, RelaxedIntMsgInfo, ShardAccount, StateInit, StdAddr, StorageInfo,
+        StorageUsed, TxInfo,
     };
-    use everscale_types::num::VarUint56;
+    use everscale_types::num::{Uint15, VarUint248, VarUint56};
 
     use super::*;
-    use crate::tests::{make_default_config, make_default_params, make_message};
-    use crate::Executor;
+    use crate::testkit::{
+        make_custom_config, make_default_config, make_default_params, make_message,
+        make_relaxed_message,
+    };
+    use crate::{
+        compute_state_stats, AccountStatusChangeReason, AccountStatusEvent, AccountStatusEventKind,
+        Executor, StorageStatLimits,
+    };
 
     const STUB_ADDR: StdAddr = StdAddr::new(0, HashBytes::ZERO);
 
@@ -257,6 +269,25 @@ mod tests {
             .begin_ordinary(&address, true, &msg, &state)?
             .commit()?;
 
+        assert_eq!(
+            output.transaction_meta.status_change,
+            Some(AccountStatusEvent {
+                kind: AccountStatusEventKind::Activated,
+                reason: AccountStatusChangeReason::StateInit,
+            })
+        );
+
+        // State stats must be reused from the state limits check.
+        let Some(Account {
+            state: AccountState::Active(new_state),
+            ..
+        }) = output.new_state.load_account()?
+        else {
+            panic!("expected an active account");
+        };
+        let expected = compute_state_stats(&new_state, StorageStatLimits::UNLIMITED).unwrap();
+        assert_eq!(output.new_state_meta.state_stat, Some(expected.into()));
+
         println!("SHARD_STATE: {:#?}", output.new_state);
         let account = output.new_state.load_account()?;
         println!("ACCOUNT: {:#?}", account);
@@ -401,10 +432,12 @@ mod tests {
 
         let new_account_state = output.new_state.load_account()?;
         assert_eq!(new_account_state, None);
+        assert_eq!(output.new_state_meta.storage_stat, None);
 
         let tx = output.transaction.load()?;
         assert_eq!(tx.orig_status, AccountStatus::NotExists);
         assert_eq!(tx.end_status, AccountStatus::NotExists);
+        assert_eq!(output.transaction_meta.status_change, None);
 
         let TxInfo::Ordinary(info) = tx.load_info()? else {
             panic!("expected an ordinary transaction info");
@@ -447,4 +480,513 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn unfreeze_account() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+
+        let state_init = StateInit {
+            code: Some(Boc::decode(tvmasm!("ACCEPT"))?),
+            ..Default::default()
+        };
+        let state_init_hash = *CellBuilder::build_from(&state_init)?.repr_hash();
+
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: STUB_ADDR.into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::ZERO,
+                state: AccountState::Frozen(state_init_hash),
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection::new(1_000_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            Some(state_init.clone()),
+            None,
+        );
+
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary(&STUB_ADDR, false, msg, &state)?
+            .commit()?;
+
+        let tx = output.transaction.load()?;
+        assert_eq!(tx.orig_status, AccountStatus::Frozen);
+        assert_eq!(tx.end_status, AccountStatus::Active);
+        assert_eq!(
+            output.transaction_meta.status_change,
+            Some(AccountStatusEvent {
+                kind: AccountStatusEventKind::Unfrozen,
+                reason: AccountStatusChangeReason::StateInit,
+            })
+        );
+
+        let TxInfo::Ordinary(info) = tx.load_info()? else {
+            panic!("expected an ordinary transaction info");
+        };
+        assert!(!info.aborted);
+
+        let ComputePhase::Executed(compute_phase) = info.compute_phase else {
+            panic!("expected an executed compute phase");
+        };
+        assert!(compute_phase.success);
+        assert!(compute_phase.msg_state_used);
+        assert!(compute_phase.account_activated);
+
+        let account = output.new_state.load_account()?.unwrap();
+        assert_eq!(account.state, AccountState::Active(state_init));
+
+        Ok(())
+    }
+
+    #[test]
+    fn final_transaction() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+
+        let code = tvmasm!(
+            r#"
+            ACCEPT
+            NEWC
+            // int_msg_info$0 ihr_disabled:Bool bounce:Bool bounced:Bool src:MsgAddress -> 011000
+            INT 0b011000 STUR 6
+            MYADDR
+            STSLICER
+            INT 0 STGRAMS
+            // extra:$0 ihr_fee:Tokens fwd_fee:Tokens created_lt:uint64 created_at:uint32
+            // 1       + 4            + 4            + 64              + 32
+            // init:none$0 body:left$0
+            // 1          + 1
+            INT 107 STZEROES
+            ENDC INT 64 SENDRAWMSG
+            "#
+        );
+        let state_init = StateInit {
+            code: Some(Boc::decode(code)?),
+            ..Default::default()
+        };
+
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: STUB_ADDR.into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Active(state_init),
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary(&STUB_ADDR, false, msg.clone(), &state)?
+            .commit()?;
+
+        let tx = output.transaction.load()?;
+        assert_eq!(tx.account, STUB_ADDR.address);
+        assert_eq!(tx.lt, 1001);
+        assert_eq!(tx.prev_trans_hash, state.last_trans_hash);
+        assert_eq!(tx.prev_trans_lt, state.last_trans_lt);
+        assert_eq!(tx.now, params.block_unixtime);
+        assert_eq!(tx.orig_status, AccountStatus::Active);
+        assert_eq!(tx.end_status, AccountStatus::Active);
+        assert_eq!(output.transaction_meta.status_change, None);
+
+        // Inbound message must be the original message cell.
+        assert_eq!(tx.in_msg, Some(msg));
+
+        // Outbound messages must be the same as in the meta.
+        assert_eq!(tx.out_msg_count.into_inner(), 1);
+        assert_eq!(output.transaction_meta.out_msgs.len(), 1);
+        let out_msg = tx.out_msgs.get(Uint15::new(0))?.unwrap();
+        assert_eq!(
+            out_msg.repr_hash(),
+            output.transaction_meta.out_msgs[0].repr_hash()
+        );
+
+        // Fees must be the same as in the meta and description.
+        assert_eq!(tx.total_fees.tokens, output.transaction_meta.total_fees);
+        let TxInfo::Ordinary(info) = tx.load_info()? else {
+            panic!("expected an ordinary transaction info");
+        };
+        assert!(!info.aborted);
+        assert!(info.action_phase.unwrap().success);
+
+        // State update must point to the old and new account states.
+        let state_update = tx.state_update.load()?;
+        assert_eq!(state_update.old, *state.account.repr_hash());
+        assert_eq!(state_update.new, *output.new_state.account.repr_hash());
+
+        // New shard state must reference this transaction.
+        assert_eq!(output.new_state.last_trans_lt, tx.lt);
+        assert_eq!(
+            output.new_state.last_trans_hash,
+            *output.transaction.repr_hash()
+        );
+
+        // Updated storage info must be the same as in the new state.
+        let account = output.new_state.load_account()?.unwrap();
+        let storage_stat = output.new_state_meta.storage_stat.unwrap();
+        assert_eq!(storage_stat, account.storage_stat);
+        assert_eq!(storage_stat.last_paid, params.block_unixtime);
+        assert_eq!(storage_stat.used.cells, VarUint56::new(2)); // account storage and code
+
+        // State was not changed so its stats were not computed.
+        assert_eq!(output.new_state_meta.state_stat, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_change_events() {
+        use AccountStatus::*;
+
+        for (orig, end, event) in [
+            (NotExists, Uninit, None),
+            (NotExists, Active, Some(AccountStatusEventKind::Activated)),
+            (Uninit, Active, Some(AccountStatusEventKind::Activated)),
+            (Active, Active, None),
+            (Active, Frozen, Some(AccountStatusEventKind::Frozen)),
+            (Frozen, Frozen, None),
+            (Frozen, Active, Some(AccountStatusEventKind::Unfrozen)),
+            (Frozen, NotExists, Some(AccountStatusEventKind::Deleted)),
+            (Active, NotExists, Some(AccountStatusEventKind::Deleted)),
+            (NotExists, NotExists, None),
+        ] {
+            assert_eq!(
+                AccountStatusEventKind::from_statuses(orig, end),
+                event,
+                "{orig:?} -> {end:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn credit_first_for_bounceable() -> Result<()> {
+        let mut params = make_default_params();
+        let config = make_default_config();
+
+        let state = make_uninit_with_balance(&STUB_ADDR, CurrencyCollection::new(1_000_000_000));
+        let msg = make_message(
+            IntMsgInfo {
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: true,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        for credit_first in [false, true] {
+            params.credit_first_for_bounceable = credit_first;
+
+            let output = Executor::new(&params, config.as_ref())
+                .begin_ordinary(&STUB_ADDR, false, &msg, &state)?
+                .commit()?;
+
+            let TxInfo::Ordinary(info) = output.transaction.load()?.load_info()? else {
+                panic!("expected an ordinary transaction info");
+            };
+            assert_eq!(info.credit_first, credit_first);
+            assert!(info.storage_phase.is_some());
+            assert!(info.credit_phase.is_some());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn untouched_extra_balance() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+
+        // Account balance with a zero extra currency entry.
+        let mut other = ExtraCurrencyCollection::new();
+        other.as_dict_mut().set(1, VarUint248::new(0))?;
+        other.as_dict_mut().set(2, VarUint248::new(5))?;
+
+        let balance = CurrencyCollection {
+            tokens: Tokens::new(1_000_000_000),
+            other,
+        };
+        let state = make_uninit_with_balance(&STUB_ADDR, balance.clone());
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection::new(1_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary(&STUB_ADDR, false, msg, &state)?
+            .commit()?;
+
+        // Extra currencies were not touched so they must keep their encoding.
+        let account = output.new_state.load_account()?.unwrap();
+        assert_eq!(account.balance.other, balance.other);
+        assert_eq!(
+            CellBuilder::build_from(&account.balance.other)?.repr_hash(),
+            CellBuilder::build_from(&balance.other)?.repr_hash()
+        );
+        assert_eq!(output.new_state_meta.balance, account.balance);
+
+        Ok(())
+    }
+
+    #[test]
+    fn burn_collected_fees() -> Result<()> {
+        let params = make_default_params();
+        let config = make_custom_config(|config| {
+            config.set_burning_config(&BurningConfig {
+                fee_burn_num: 1,
+                fee_burn_denom: NonZeroU32::new(2).unwrap(),
+                ..Default::default()
+            })?;
+            Ok(())
+        });
+
+        let state = ShardAccount {
+            account: Lazy::new(&OptionalAccount(Some(Account {
+                address: STUB_ADDR.into(),
+                storage_stat: StorageInfo::default(),
+                last_trans_lt: 1001,
+                balance: CurrencyCollection::new(1_000_000_000),
+                state: AccountState::Active(StateInit {
+                    code: Some(Boc::decode(tvmasm!("ACCEPT"))?),
+                    ..Default::default()
+                }),
+            })))?,
+            last_trans_hash: HashBytes([0x11; 32]),
+            last_trans_lt: 1000,
+        };
+
+        let msg = make_message(
+            IntMsgInfo {
+                src: STUB_ADDR.into(),
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection::new(1_000_000_000),
+                bounce: false,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+
+        let output = Executor::new(&params, config.as_ref())
+            .begin_ordinary(&STUB_ADDR, false, msg, &state)?
+            .commit()?;
+
+        let meta = &output.transaction_meta;
+        assert!(!meta.total_fees.is_zero());
+        assert_eq!(
+            meta.fees_burned,
+            Tokens::new(meta.total_fees.into_inner() / 2)
+        );
+        // Burned fees are not mixed with the burned message value.
+        assert_eq!(output.burned, Tokens::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn send_msg_estimates_match() -> Result<()> {
+        let params = make_default_params();
+        let config = make_default_config();
+
+        let int_msg = |body: Option<CellBuilder>| {
+            make_relaxed_message(
+                RelaxedIntMsgInfo {
+                    ihr_disabled: true,
+                    dst: STUB_ADDR.into(),
+                    value: CurrencyCollection::new(1_000_000),
+                    ..Default::default()
+                },
+                None,
+                body,
+            )
+            .into_inner()
+        };
+
+        // Plain internal message.
+        let plain_msg = int_msg(None);
+        // Body doesn't fit into the root cell.
+        let big_body_msg = int_msg(Some({
+            let mut b = CellBuilder::new();
+            b.store_zeros(1000)?;
+            b
+        }));
+        // External outbound message.
+        let ext_out_msg =
+            make_relaxed_message(RelaxedExtOutMsgInfo::default(), None, None).into_inner();
+
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            Tokens::new(1_000_000_000),
+            CellBuilder::build_from((
+                plain_msg.clone(),
+                big_body_msg.clone(),
+                ext_out_msg.clone(),
+            ))?,
+            tvmasm!(
+                r#"
+                ACCEPT
+                PUSHCTR c4 CTOS
+                LDREF SWAP INT 0 SENDMSG DROP
+                LDREF SWAP INT 0 SENDMSG DROP
+                LDREF SWAP INT 0 SENDMSG DROP
+                ENDS
+                "#
+            ),
+        );
+
+        let mut inspector = ExecutorInspector {
+            check_send_msg_estimates: true,
+            ..Default::default()
+        };
+        let info = state.run_ordinary_transaction(
+            false,
+            make_message(
+                IntMsgInfo {
+                    src: STUB_ADDR.into(),
+                    dst: STUB_ADDR.into(),
+                    value: CurrencyCollection::new(1_000_000_000),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            Some(&mut inspector),
+        )?;
+
+        let action_phase = info.action_phase.unwrap();
+        assert!(action_phase.success);
+        assert_eq!(action_phase.messages_created, 3);
+
+        // All estimates are collected and match the action phase.
+        assert_eq!(inspector.send_msg_estimates.len(), 3);
+        assert!(
+            inspector.send_msg_divergences.is_empty(),
+            "{:?}",
+            inspector.send_msg_divergences
+        );
+
+        let big_body_estimate = inspector
+            .send_msg_estimates
+            .iter()
+            .find(|estimate| estimate.msg_hash == *big_body_msg.repr_hash())
+            .unwrap();
+        assert_eq!(big_body_estimate.layout, MessageLayout {
+            init_to_cell: false,
+            body_to_cell: true,
+        });
+
+        // Message with extra currencies.
+        let mut other = ExtraCurrencyCollection::new();
+        other.as_dict_mut().set(1, VarUint248::new(100))?;
+
+        let extra_msg = make_relaxed_message(
+            RelaxedIntMsgInfo {
+                ihr_disabled: true,
+                dst: STUB_ADDR.into(),
+                value: CurrencyCollection {
+                    tokens: Tokens::new(1_000_000),
+                    other: other.clone(),
+                },
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .into_inner();
+
+        let mut state = ExecutorState::new_active(
+            &params,
+            &config,
+            &STUB_ADDR,
+            Tokens::new(1_000_000_000),
+            CellBuilder::build_from(extra_msg.clone())?,
+            tvmasm!(
+                r#"
+                ACCEPT
+                PUSHCTR c4 CTOS
+                LDREF SWAP INT 0 SENDMSG DROP
+                ENDS
+                "#
+            ),
+        );
+        state.balance.other = other;
+
+        let mut inspector = ExecutorInspector {
+            check_send_msg_estimates: true,
+            ..Default::default()
+        };
+        let info = state.run_ordinary_transaction(
+            false,
+            make_message(
+                IntMsgInfo {
+                    src: STUB_ADDR.into(),
+                    dst: STUB_ADDR.into(),
+                    value: CurrencyCollection::new(1_000_000_000),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            Some(&mut inspector),
+        )?;
+
+        let action_phase = info.action_phase.unwrap();
+        assert!(action_phase.success);
+        assert_eq!(action_phase.messages_created, 1);
+
+        // Estimate is the same as the forwarding fee charged in the action phase.
+        assert_eq!(inspector.send_msg_estimates.len(), 1);
+        assert_eq!(
+            inspector.send_msg_estimates[0].msg_hash,
+            *extra_msg.repr_hash()
+        );
+        assert_eq!(
+            Some(inspector.send_msg_estimates[0].total_fee),
+            action_phase.total_fwd_fees
+        );
+        assert!(
+            inspector.send_msg_divergences.is_empty(),
+            "{:?}",
+            inspector.send_msg_divergences
+        );
+
+        Ok(())
+    }
 }
@@ -1,12 +1,20 @@
+//! Executor utilities.
+//!
+//! Contains storage stats, address checks and state limits used by
+//! the executor phases, so tools can reproduce their behaviour.
+
 use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex};
 
 use ahash::HashMap;
-use everscale_types::cell::CellTreeStats;
+use everscale_types::cell::{CellTreeStats, CellType, Lazy};
+use everscale_types::error::Error;
 use everscale_types::models::{
-    IntAddr, ShardIdent, SimpleLib, SizeLimitsConfig, StateInit, StdAddr, WorkchainDescription,
+    Anycast, CurrencyCollection, IntAddr, MsgForwardPrices, OwnedRelaxedMessage, RelaxedMsgInfo,
+    ShardIdent, SimpleLib, SizeLimitsConfig, StateInit, StdAddr, WorkchainDescription,
     WorkchainFormat,
 };
-use everscale_types::num::{VarUint24, VarUint56};
+use everscale_types::num::{Tokens, VarUint24, VarUint56};
 use everscale_types::prelude::*;
 
 /// Brings [unlikely](core::intrinsics::unlikely) to stable rust.
@@ -20,7 +28,7 @@ pub(crate) const fn unlikely(b: bool) -> bool {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct StorageStatLimits {
     pub bit_count: u32,
     pub cell_count: u32,
@@ -31,6 +39,60 @@ impl StorageStatLimits {
         bit_count: u32::MAX,
         cell_count: u32::MAX,
     };
+
+    /// Limits for child cells of inbound and outbound messages.
+    pub const fn for_message(limits: &SizeLimitsConfig) -> Self {
+        Self {
+            bit_count: limits.max_msg_bits,
+            cell_count: limits.max_msg_cells,
+        }
+    }
+
+    /// Limits for a library cell tree (bits are not limited).
+    pub const fn for_library(limits: &SizeLimitsConfig) -> Self {
+        Self {
+            bit_count: u32::MAX,
+            cell_count: limits.max_library_cells,
+        }
+    }
+
+    /// Limits for the account state (code, data and libraries).
+    ///
+    /// NOTE: Masterchain accounts share the same limits, but
+    /// public libraries are additionally checked there
+    /// (see [`check_state_limits`]).
+    pub const fn for_account_state(limits: &SizeLimitsConfig) -> Self {
+        Self {
+            bit_count: limits.max_acc_state_bits,
+            cell_count: limits.max_acc_state_cells,
+        }
+    }
+}
+
+impl From<&SizeLimitsConfig> for StorageStatLimits {
+    /// Uses the account state limits.
+    #[inline]
+    fn from(limits: &SizeLimitsConfig) -> Self {
+        Self::for_account_state(limits)
+    }
+}
+
+/// Unique cells and bits of the account state (code, data and libraries).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateStorageStat {
+    pub cells: u64,
+    pub bits: u64,
+}
+
+impl From<CellTreeStats> for StateStorageStat {
+    #[inline]
+    fn from(value: CellTreeStats) -> Self {
+        Self {
+            cells: value.cell_count,
+            bits: value.bit_count,
+        }
+    }
 }
 
 pub struct OwnedExtStorageStat {
@@ -172,7 +234,21 @@ pub fn new_varuint56_truncate(value: u64) -> VarUint56 {
     VarUint56::new(std::cmp::min(value, VarUint56::MAX.into_inner()))
 }
 
-/// Rewrites message source address.
+/// Removes zero entries from the extra currencies of the balance.
+///
+/// Extra currency arithmetic can leave such entries in the dictionary,
+/// so equal balances could have different encodings otherwise.
+pub fn normalize_balance(balance: &mut CurrencyCollection) -> Result<(), Error> {
+    balance.other.normalize()
+}
+
+/// Validates and rewrites an outbound message source address.
+///
+/// This is exactly the check performed by the action phase:
+/// `addr_none` is replaced with the account address, and any other
+/// address must be an `addr_std` equal to the account address.
+///
+/// Returns `false` if the address is invalid.
 pub fn check_rewrite_src_addr(my_addr: &StdAddr, addr: &mut Option<IntAddr>) -> bool {
     match addr {
         // Replace `addr_none` with the address of the account.
@@ -187,68 +263,214 @@ pub fn check_rewrite_src_addr(my_addr: &StdAddr, addr: &mut Option<IntAddr>) ->
     }
 }
 
-/// Rewrite message destination address.
-pub fn check_rewrite_dst_addr(
-    workchains: &HashMap<i32, WorkchainDescription>,
-    addr: &mut IntAddr,
-) -> bool {
-    const STD_WORKCHAINS: std::ops::Range<i32> = -128..128;
-    const STD_ADDR_LEN: u16 = 256;
+/// Returns the original hash of the first pruned branch in the cell tree.
+///
+/// Pruned branches inside Merkle proofs or updates are ignored.
+pub(crate) fn find_pruned_branch(cell: &DynCell) -> Option<HashBytes> {
+    // NOTE: Only subtrees with pruned branches have non-zero level.
+    if cell.level() == 0 {
+        return None;
+    }
 
-    // Check destination workchain.
-    let mut can_rewrite = false;
-    let workchain = match addr {
-        IntAddr::Std(addr) => {
-            if addr.anycast.is_some() {
-                // Anycasts are not supported for now.
-                return false;
+    let mut stack = vec![cell];
+    while let Some(cell) = stack.pop() {
+        match cell.cell_type() {
+            CellType::PrunedBranch => return Some(*cell.hash(0)),
+            CellType::MerkleProof | CellType::MerkleUpdate => continue,
+            CellType::Ordinary | CellType::LibraryReference => {}
+        }
+
+        for child in cell.references() {
+            if child.level() != 0 {
+                stack.push(child);
             }
+        }
+    }
+
+    None
+}
 
-            addr.workchain as i32
+/// Bounded in-memory target for the VM debug output.
+///
+/// Keeps at most [`limit`] bytes of the output and drops the rest.
+///
+/// [`limit`]: Self::limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmLogBuffer {
+    data: String,
+    limit: usize,
+    truncated: bool,
+}
+
+impl Default for VmLogBuffer {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl VmLogBuffer {
+    /// Default output size limit in bytes.
+    pub const DEFAULT_LIMIT: usize = 64 << 10;
+
+    /// Creates an empty buffer with the specified size limit in bytes.
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            data: String::new(),
+            limit,
+            truncated: false,
         }
-        IntAddr::Var(addr) => {
-            if addr.anycast.is_some() {
-                // Anycasts are not supported for now.
-                return false;
-            }
+    }
+
+    /// Captured output.
+    pub fn as_str(&self) -> &str {
+        &self.data
+    }
+
+    /// Output size limit in bytes.
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns `true` if some output was dropped due to the limit.
+    pub const fn is_truncated(&self) -> bool {
+        self.truncated
+    }
 
-            // `addr_var` of len 256 in a valid workchains range
-            // can be rewritten to `addr_std` if needed.
-            can_rewrite = addr.address_len.into_inner() == STD_ADDR_LEN
-                && STD_WORKCHAINS.contains(&addr.workchain);
+    /// Removes the captured output.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.truncated = false;
+    }
+}
 
-            addr.workchain
+impl std::fmt::Write for VmLogBuffer {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        if self.truncated {
+            return Ok(());
         }
-    };
 
-    if workchain != ShardIdent::MASTERCHAIN.workchain() {
-        let Some(workchain) = workchains.get(&workchain) else {
-            // Cannot send message to an unknown workchain.
-            return false;
-        };
+        let remaining = self.limit.saturating_sub(self.data.len());
+        if s.len() <= remaining {
+            self.data.push_str(s);
+        } else {
+            let mut end = remaining;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            self.data.push_str(&s[..end]);
+            self.truncated = true;
+        }
+
+        // NOTE: Never fail to not interrupt the execution.
+        Ok(())
+    }
+}
+
+/// [`VmLogBuffer`] shared between the VM debug output and the VM log subscriber.
+#[derive(Clone)]
+pub(crate) struct SharedVmLogBuffer(pub Arc<Mutex<VmLogBuffer>>);
+
+impl SharedVmLogBuffer {
+    pub fn new(buffer: VmLogBuffer) -> Self {
+        Self(Arc::new(Mutex::new(buffer)))
+    }
 
-        if !workchain.accept_msgs {
-            // Cannot send messages to disabled workchains.
-            return false;
+    /// Moves the captured output out of the shared buffer.
+    pub fn take(&self) -> VmLogBuffer {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl std::fmt::Write for SharedVmLogBuffer {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.lock().unwrap().write_str(s)
+    }
+}
+
+/// Destination anycast address handling policy.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnycastPolicy {
+    /// Treat anycast destinations as invalid.
+    #[default]
+    Reject,
+    /// Apply the rewrite prefix to the address and drop the anycast.
+    Rewrite,
+    /// Keep anycast destinations as is.
+    Allow,
+}
+
+/// How to derive the VM rand seed from the block rand seed.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RandSeedDerivation {
+    /// `sha256(block_seed || account)` (the on-chain scheme).
+    #[default]
+    Account,
+    /// `sha256(account_seed || uint256(lt))` where `account_seed` is
+    /// derived as in [`Account`] and `lt` is the transaction LT.
+    ///
+    /// Gives different seeds to transactions of the same account in one block.
+    ///
+    /// [`Account`]: RandSeedDerivation::Account
+    Transaction,
+    /// Use the block rand seed as is.
+    Block,
+}
+
+impl RandSeedDerivation {
+    /// Computes the VM rand seed.
+    ///
+    /// Zero block seed always results in zero seed.
+    pub fn derive(self, block_seed: &HashBytes, account: &HashBytes, lt: u64) -> HashBytes {
+        match self {
+            Self::Account => tycho_vm::mix_rand_seed(block_seed, account),
+            Self::Transaction => {
+                let account_seed = tycho_vm::mix_rand_seed(block_seed, account);
+                let mut lt_bytes = HashBytes::ZERO;
+                lt_bytes.0[24..].copy_from_slice(&lt.to_be_bytes());
+                tycho_vm::mix_rand_seed(&account_seed, &lt_bytes)
+            }
+            Self::Block => *block_seed,
         }
+    }
+}
 
-        match (&workchain.format, &*addr) {
-            // `addr_std` is the default address format for basic workchains.
-            (WorkchainFormat::Basic(_), IntAddr::Std(_)) => {}
-            // `addr_var` can be rewritten to `addr_std` for basic workchains.
-            (WorkchainFormat::Basic(_), IntAddr::Var(_)) if can_rewrite => {}
-            // `addr_std` can be used for extended workchains if the length is ok.
-            (WorkchainFormat::Extended(f), IntAddr::Std(_)) if f.check_addr_len(STD_ADDR_LEN) => {}
-            // `addr_var` can be used for extended workchains if the length is ok.
-            (WorkchainFormat::Extended(f), IntAddr::Var(a))
-                if f.check_addr_len(a.address_len.into_inner()) => {}
-            // All other combinations are invalid.
-            _ => return false,
+/// Validates and rewrites an outbound message destination address.
+///
+/// This is exactly the check performed by the action phase (for
+/// `SendMsg` actions) and by the bounce phase:
+/// - anycast info is handled according to the `anycast_policy`;
+/// - the destination workchain must be valid (see [`check_dst_workchain`]);
+/// - `addr_var` of 256 bits in a basic workchain is rewritten to `addr_std`.
+///
+/// Returns `false` if the address is invalid. The address can be partially
+/// modified in this case.
+pub fn check_rewrite_dst_addr(
+    workchains: &HashMap<i32, WorkchainDescription>,
+    anycast_policy: AnycastPolicy,
+    addr: &mut IntAddr,
+) -> bool {
+    // Handle anycast.
+    let anycast_ok = match addr {
+        IntAddr::Std(addr) => {
+            apply_anycast_policy(anycast_policy, &mut addr.anycast, &mut addr.address.0)
+        }
+        IntAddr::Var(addr) => {
+            apply_anycast_policy(anycast_policy, &mut addr.anycast, &mut addr.address)
         }
+    };
+    if !anycast_ok {
+        return false;
+    }
+
+    // Check destination workchain.
+    if !check_dst_workchain(workchains, addr) {
+        return false;
     }
 
     // Rewrite if needed.
-    if can_rewrite {
+    if can_rewrite_to_std(addr) {
         if let IntAddr::Var(var) = addr {
             debug_assert!(STD_WORKCHAINS.contains(&var.workchain));
             debug_assert_eq!(var.address_len.into_inner(), STD_ADDR_LEN);
@@ -259,7 +481,9 @@ pub fn check_rewrite_dst_addr(
             address[..len].copy_from_slice(&var.address[..len]);
 
             // Set type as `addr_std`.
-            *addr = IntAddr::Std(StdAddr::new(var.workchain as i8, HashBytes(address)));
+            let mut std = StdAddr::new(var.workchain as i8, HashBytes(address));
+            std.anycast = var.anycast.take();
+            *addr = IntAddr::Std(std);
         }
     }
 
@@ -267,12 +491,211 @@ pub fn check_rewrite_dst_addr(
     true
 }
 
+/// Checks whether messages can be sent to the address workchain.
+///
+/// Masterchain is always valid. Other workchains must be present in
+/// `workchains` (see [`ParsedConfig::workchains`]), accept messages
+/// and support the address format:
+/// - basic workchains accept `addr_std` and 256-bit `addr_var`
+///   (which is rewritten to `addr_std` by [`check_rewrite_dst_addr`]);
+/// - extended workchains accept addresses of the allowed length.
+///
+/// [`ParsedConfig::workchains`]: crate::ParsedConfig::workchains
+pub fn check_dst_workchain(
+    workchains: &HashMap<i32, WorkchainDescription>,
+    addr: &IntAddr,
+) -> bool {
+    let workchain = match addr {
+        IntAddr::Std(addr) => addr.workchain as i32,
+        IntAddr::Var(addr) => addr.workchain,
+    };
+
+    if workchain == ShardIdent::MASTERCHAIN.workchain() {
+        return true;
+    }
+
+    let Some(workchain) = workchains.get(&workchain) else {
+        // Cannot send message to an unknown workchain.
+        return false;
+    };
+
+    if !workchain.accept_msgs {
+        // Cannot send messages to disabled workchains.
+        return false;
+    }
+
+    match (&workchain.format, addr) {
+        // `addr_std` is the default address format for basic workchains.
+        (WorkchainFormat::Basic(_), IntAddr::Std(_)) => true,
+        // `addr_var` can be rewritten to `addr_std` for basic workchains.
+        (WorkchainFormat::Basic(_), IntAddr::Var(_)) => can_rewrite_to_std(addr),
+        // `addr_std` can be used for extended workchains if the length is ok.
+        (WorkchainFormat::Extended(f), IntAddr::Std(_)) => f.check_addr_len(STD_ADDR_LEN),
+        // `addr_var` can be used for extended workchains if the length is ok.
+        (WorkchainFormat::Extended(f), IntAddr::Var(a)) => {
+            f.check_addr_len(a.address_len.into_inner())
+        }
+    }
+}
+
+const STD_WORKCHAINS: std::ops::Range<i32> = -128..128;
+const STD_ADDR_LEN: u16 = 256;
+
+/// `addr_var` of len 256 in a valid workchains range
+/// can be rewritten to `addr_std` if needed.
+fn can_rewrite_to_std(addr: &IntAddr) -> bool {
+    match addr {
+        IntAddr::Std(_) => false,
+        IntAddr::Var(addr) => {
+            addr.address_len.into_inner() == STD_ADDR_LEN
+                && STD_WORKCHAINS.contains(&addr.workchain)
+        }
+    }
+}
+
+fn apply_anycast_policy(
+    policy: AnycastPolicy,
+    anycast: &mut Option<Box<Anycast>>,
+    address: &mut [u8],
+) -> bool {
+    let Some(info) = anycast.as_deref() else {
+        return true;
+    };
+
+    match policy {
+        AnycastPolicy::Reject => false,
+        AnycastPolicy::Allow => true,
+        AnycastPolicy::Rewrite => {
+            let depth = info.depth.into_bit_len() as usize;
+            if depth > address.len() * 8 || depth > info.rewrite_prefix.len() * 8 {
+                return false;
+            }
+
+            // Replace the highest `depth` bits of the address with the prefix.
+            for i in 0..depth {
+                let mask = 0x80u8 >> (i % 8);
+                if info.rewrite_prefix[i / 8] & mask != 0 {
+                    address[i / 8] |= mask;
+                } else {
+                    address[i / 8] &= !mask;
+                }
+            }
+
+            *anycast = None;
+            true
+        }
+    }
+}
+
+/// Outbound message header parsed without loading its state init or body.
+///
+/// Intended for cheap triage of action lists. Parsing mirrors the
+/// prefix parsing of the `SendMsg` action, so a message which can't be
+/// peeked will also fail in the action phase (but not vice versa).
+#[derive(Debug, Clone)]
+pub struct RelaxedMsgPreview {
+    /// Message info (source address may be empty).
+    pub info: RelaxedMsgInfo,
+    /// Whether the message contains a state init.
+    pub has_state_init: bool,
+}
+
+impl RelaxedMsgPreview {
+    /// Parses the header of the outbound message.
+    pub fn peek(msg: &Lazy<OwnedRelaxedMessage>) -> Result<Self, Error> {
+        // Output message must be an ordinary cell.
+        if msg.is_exotic() {
+            return Err(Error::UnexpectedExoticCell);
+        }
+
+        let mut cs = msg.as_slice_allow_exotic();
+        let info = RelaxedMsgInfo::load_from(&mut cs)?;
+        let has_state_init = cs.load_bit()?;
+
+        Ok(Self {
+            info,
+            has_state_init,
+        })
+    }
+
+    /// Returns `true` for internal messages.
+    pub fn is_internal(&self) -> bool {
+        matches!(&self.info, RelaxedMsgInfo::Int(_))
+    }
+
+    /// Destination of an internal message.
+    pub fn dst(&self) -> Option<&IntAddr> {
+        match &self.info {
+            RelaxedMsgInfo::Int(info) => Some(&info.dst),
+            RelaxedMsgInfo::ExtOut(_) => None,
+        }
+    }
+
+    /// Value attached to an internal message.
+    pub fn value(&self) -> Option<&CurrencyCollection> {
+        match &self.info {
+            RelaxedMsgInfo::Int(info) => Some(&info.value),
+            RelaxedMsgInfo::ExtOut(_) => None,
+        }
+    }
+}
+
+/// Result of the account state size limits check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StateLimitsResult {
+    /// State was not changed (only for the diff checks).
     Unchanged,
+    /// State exceeds the limits.
     Exceeds,
+    /// State fits the limits.
     Fits,
 }
 
+impl StateLimitsResult {
+    /// Returns `true` if the state doesn't exceed the limits.
+    pub const fn is_ok(self) -> bool {
+        !matches!(self, Self::Exceeds)
+    }
+}
+
+/// Checks that the account state fits the size limits.
+///
+/// Performs the same check as the action phase for a new state of a
+/// non-active account: cells of code, data and libraries are counted
+/// once and, for masterchain accounts, the number of public libraries
+/// is limited by `max_acc_public_libraries`.
+///
+/// NOTE: Deploying masterchain accounts with a message state init
+/// doesn't allow public libraries at all.
+pub fn check_state_init_limits(
+    state: &StateInit,
+    limits: &SizeLimitsConfig,
+    is_masterchain: bool,
+) -> StateLimitsResult {
+    check_state_limits(
+        state.code.as_ref(),
+        state.data.as_ref(),
+        &state.libraries,
+        limits,
+        is_masterchain,
+        &mut None,
+    )
+}
+
+/// Checks that the updated account state fits the size limits.
+///
+/// Performs the same check as the action phase for a new state of an
+/// active account. Returns [`StateLimitsResult::Unchanged`] if code,
+/// data and libraries are the same.
+pub fn check_state_init_limits_diff(
+    old_state: &StateInit,
+    new_state: &StateInit,
+    limits: &SizeLimitsConfig,
+    is_masterchain: bool,
+) -> StateLimitsResult {
+    check_state_limits_diff(old_state, new_state, limits, is_masterchain, &mut None)
+}
+
 /// NOTE: `stats_cache` is updated only when `StateLimitsResult::Fits` is returned.
 pub fn check_state_limits_diff(
     old_state: &StateInit,
@@ -318,27 +741,9 @@ pub fn check_state_limits(
     stats_cache: &mut Option<OwnedExtStorageStat>,
 ) -> StateLimitsResult {
     // Compute storage stats.
-    let mut stats = OwnedExtStorageStat::with_limits(StorageStatLimits {
-        bit_count: limits.max_acc_state_bits,
-        cell_count: limits.max_acc_state_cells,
-    });
-
-    if let Some(code) = code {
-        if !stats.add_cell(code.clone()) {
-            return StateLimitsResult::Exceeds;
-        }
-    }
-
-    if let Some(data) = data {
-        if !stats.add_cell(data.clone()) {
-            return StateLimitsResult::Exceeds;
-        }
-    }
-
-    if let Some(libs) = libs.root() {
-        if !stats.add_cell(libs.clone()) {
-            return StateLimitsResult::Exceeds;
-        }
+    let mut stats = OwnedExtStorageStat::with_limits(StorageStatLimits::from(limits));
+    if !add_state_cells(&mut stats, code, data, libs) {
+        return StateLimitsResult::Exceeds;
     }
 
     // Check public libraries (only for masterchain, because in other workchains all
@@ -362,6 +767,31 @@ pub fn check_state_limits(
     StateLimitsResult::Fits
 }
 
+/// Computes storage stats of the account state (code, data and libraries).
+///
+/// Subtrees shared between code, data and libraries are counted once
+/// (the same way as in [`check_state_limits`]).
+///
+/// Returns `None` if the state exceeds the specified limits.
+pub fn compute_state_stats(state: &StateInit, limits: StorageStatLimits) -> Option<CellTreeStats> {
+    let mut stats = OwnedExtStorageStat::with_limits(limits);
+    let (code, data) = (state.code.as_ref(), state.data.as_ref());
+    add_state_cells(&mut stats, code, data, &state.libraries).then(|| stats.stats())
+}
+
+fn add_state_cells(
+    stats: &mut OwnedExtStorageStat,
+    code: Option<&Cell>,
+    data: Option<&Cell>,
+    libs: &StateLibs,
+) -> bool {
+    // NOTE: All roots share the same visited set.
+    code.into_iter()
+        .chain(data)
+        .chain(libs.root())
+        .all(|cell| stats.add_cell(cell.clone()))
+}
+
 type StateLibs = Dict<HashBytes, SimpleLib>;
 
 pub const fn shift_ceil_price(value: u128) -> u128 {
@@ -369,10 +799,91 @@ pub const fn shift_ceil_price(value: u128) -> u128 {
     (value >> 16) + r as u128
 }
 
+/// Rounding policy for sub-unit remainders of fee computations.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeeRounding {
+    /// Round forwarding fees up and fine per cell down.
+    #[default]
+    Reference,
+    /// Always round up.
+    Ceil,
+    /// Always round down.
+    Floor,
+}
+
+impl FeeRounding {
+    /// Computes a forwarding fee for the message of the specified size.
+    pub fn compute_fwd_fee(self, prices: &MsgForwardPrices, stats: CellTreeStats) -> Tokens {
+        match self {
+            Self::Reference | Self::Ceil => prices.compute_fwd_fee(stats),
+            Self::Floor => {
+                let lump = prices.lump_price as u128;
+                let extra = (stats.cell_count as u128 * prices.cell_price as u128)
+                    .saturating_add(stats.bit_count as u128 * prices.bit_price as u128);
+                Tokens::new(lump.saturating_add(extra >> 16))
+            }
+        }
+    }
+
+    /// Computes a fine for each visited cell of an invalid outbound message.
+    pub const fn fine_per_cell(self, prices: &MsgForwardPrices) -> u64 {
+        match self {
+            Self::Reference | Self::Floor => (prices.cell_price >> 16) / 4,
+            Self::Ceil => {
+                let r = prices.cell_price & 0x3ffff != 0;
+                (prices.cell_price >> 18) + r as u64
+            }
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Deliberate deviations from the reference implementation.
+    ///
+    /// Each set flag restores the reference behaviour instead of the
+    /// (more consistent) default one. Use [`Quirks::all`] to replay
+    /// transactions produced by the reference implementation.
+    ///
+    /// NOTE: Some known deviations have no flag yet and are always applied:
+    /// - Library changes of the failed action phase are discarded,
+    ///   while the reference implementation keeps them
+    ///   (this also requires checking state limits on failure).
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Quirks: u32 {
+        /// Keep `total_fwd_fees` of the failed action phase
+        /// although no messages were sent.
+        const KEEP_FWD_FEES_ON_FAIL = 1 << 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn vm_log_buffer_limit() {
+        use std::fmt::Write;
+
+        let mut buffer = VmLogBuffer::new(8);
+        write!(buffer, "abc").unwrap();
+        assert_eq!(buffer.as_str(), "abc");
+        assert!(!buffer.is_truncated());
+
+        // Multibyte chars are never split.
+        write!(buffer, "de\u{44f}\u{44f}").unwrap();
+        assert_eq!(buffer.as_str(), "abcde\u{44f}");
+        assert!(buffer.is_truncated());
+
+        // Output after the truncation is dropped.
+        write!(buffer, "f").unwrap();
+        assert_eq!(buffer.as_str(), "abcde\u{44f}");
+
+        buffer.clear();
+        write!(buffer, "f").unwrap();
+        assert_eq!(buffer.as_str(), "f");
+        assert!(!buffer.is_truncated());
+    }
+
     #[test]
     fn miri_check() {
         // Drop is ok.
@@ -422,4 +933,415 @@ mod tests {
         owned.clear();
         fill(&mut owned);
     }
+
+    #[test]
+    fn fee_rounding() {
+        let prices = MsgForwardPrices {
+            lump_price: 1000,
+            bit_price: 1,
+            cell_price: (1 << 18) + 1,
+            ihr_price_factor: 0,
+            first_frac: 0,
+            next_frac: 0,
+        };
+        let stats = CellTreeStats {
+            bit_count: 1,
+            cell_count: 1,
+        };
+
+        assert_eq!(
+            FeeRounding::Reference.compute_fwd_fee(&prices, stats),
+            Tokens::new(1005)
+        );
+        assert_eq!(
+            FeeRounding::Ceil.compute_fwd_fee(&prices, stats),
+            Tokens::new(1005)
+        );
+        assert_eq!(
+            FeeRounding::Floor.compute_fwd_fee(&prices, stats),
+            Tokens::new(1004)
+        );
+
+        assert_eq!(FeeRounding::Reference.fine_per_cell(&prices), 1);
+        assert_eq!(FeeRounding::Floor.fine_per_cell(&prices), 1);
+        assert_eq!(FeeRounding::Ceil.fine_per_cell(&prices), 2);
+    }
+
+    #[test]
+    fn storage_stat_limits_presets() {
+        let limits = SizeLimitsConfig {
+            max_msg_bits: 1 << 21,
+            max_msg_cells: 1 << 13,
+            max_library_cells: 1000,
+            max_vm_data_depth: 512,
+            max_ext_msg_size: 65535,
+            max_ext_msg_depth: 512,
+            max_acc_state_cells: 1 << 16,
+            max_acc_state_bits: (1 << 16) * 1023,
+            max_acc_public_libraries: 256,
+            defer_out_queue_size_limit: 256,
+        };
+
+        assert_eq!(StorageStatLimits::for_message(&limits), StorageStatLimits {
+            bit_count: 1 << 21,
+            cell_count: 1 << 13,
+        });
+        assert_eq!(StorageStatLimits::for_library(&limits), StorageStatLimits {
+            bit_count: u32::MAX,
+            cell_count: 1000,
+        });
+        assert_eq!(StorageStatLimits::from(&limits), StorageStatLimits {
+            bit_count: (1 << 16) * 1023,
+            cell_count: 1 << 16,
+        });
+    }
+
+    #[test]
+    fn shared_state_cells() {
+        let tree = crate::testkit::make_big_tree(5, &mut 0, 100);
+
+        let mut libraries = Dict::new();
+        libraries
+            .set(*tree.repr_hash(), SimpleLib {
+                public: false,
+                root: tree.clone(),
+            })
+            .unwrap();
+
+        // The same subtree is used in code, data and libraries.
+        let state = StateInit {
+            split_depth: None,
+            special: None,
+            code: Some(tree.clone()),
+            data: Some({
+                let mut b = CellBuilder::new();
+                b.store_u32(123).unwrap();
+                b.store_reference(tree.clone()).unwrap();
+                b.build().unwrap()
+            }),
+            libraries,
+        };
+
+        let stats = compute_state_stats(&state, StorageStatLimits::UNLIMITED).unwrap();
+
+        let mut naive_cells = 0;
+        for root in [
+            state.code.as_ref(),
+            state.data.as_ref(),
+            state.libraries.root().as_ref(),
+        ] {
+            let root = root.unwrap();
+            let stats = compute_state_stats(
+                &StateInit {
+                    code: Some(root.clone()),
+                    ..Default::default()
+                },
+                StorageStatLimits::UNLIMITED,
+            )
+            .unwrap();
+            naive_cells += stats.cell_count;
+        }
+
+        let tree_cells = compute_state_stats(
+            &StateInit {
+                code: Some(tree),
+                ..Default::default()
+            },
+            StorageStatLimits::UNLIMITED,
+        )
+        .unwrap()
+        .cell_count;
+        assert_eq!(naive_cells - stats.cell_count, tree_cells * 2);
+
+        // Dedup-aware stats fit into limits which naive stats exceed.
+        let limits = SizeLimitsConfig {
+            max_msg_bits: 1 << 21,
+            max_msg_cells: 1 << 13,
+            max_library_cells: 1000,
+            max_vm_data_depth: 512,
+            max_ext_msg_size: 65535,
+            max_ext_msg_depth: 512,
+            max_acc_state_cells: stats.cell_count as u32,
+            max_acc_state_bits: stats.bit_count as u32,
+            max_acc_public_libraries: 256,
+            defer_out_queue_size_limit: 256,
+        };
+        assert!(naive_cells > limits.max_acc_state_cells as u64);
+
+        let mut stats_cache = None;
+        let res = check_state_limits_diff(
+            &StateInit::default(),
+            &state,
+            &limits,
+            false,
+            &mut stats_cache,
+        );
+        assert_eq!(res, StateLimitsResult::Fits);
+        assert_eq!(stats_cache.unwrap().stats(), stats);
+
+        // One more cell exceeds limits.
+        let limits = SizeLimitsConfig {
+            max_acc_state_cells: stats.cell_count as u32 - 1,
+            ..limits
+        };
+        assert_eq!(
+            compute_state_stats(&state, StorageStatLimits::from(&limits)),
+            None
+        );
+    }
+
+    #[test]
+    fn normalize_extra_balance() -> anyhow::Result<()> {
+        use everscale_types::models::ExtraCurrencyCollection;
+        use everscale_types::num::VarUint248;
+
+        let mut canonical = ExtraCurrencyCollection::new();
+        canonical.as_dict_mut().set(2, VarUint248::new(5))?;
+
+        let mut balance = CurrencyCollection {
+            tokens: Tokens::new(100),
+            other: {
+                let mut other = canonical.clone();
+                other.as_dict_mut().set(1, VarUint248::new(0))?;
+                other.as_dict_mut().set(3, VarUint248::new(0))?;
+                other
+            },
+        };
+        assert_ne!(balance.other, canonical);
+
+        normalize_balance(&mut balance)?;
+        assert_eq!(balance.tokens, Tokens::new(100));
+        assert_eq!(balance.other, canonical);
+        assert_eq!(
+            CellBuilder::build_from(&balance)?.repr_hash(),
+            CellBuilder::build_from(CurrencyCollection {
+                tokens: Tokens::new(100),
+                other: canonical,
+            })?
+            .repr_hash()
+        );
+
+        // Only zero entries are removed.
+        let mut balance = CurrencyCollection::ZERO;
+        normalize_balance(&mut balance)?;
+        assert_eq!(balance, CurrencyCollection::ZERO);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rand_seed_derivation() {
+        let block_seed = HashBytes([0x55; 32]);
+        let account = HashBytes([0x33; 32]);
+
+        let account_seed = RandSeedDerivation::Account.derive(&block_seed, &account, 1000);
+        assert_eq!(account_seed, tycho_vm::mix_rand_seed(&block_seed, &account));
+        // LT is ignored for the on-chain scheme.
+        assert_eq!(
+            RandSeedDerivation::Account.derive(&block_seed, &account, 2000),
+            account_seed
+        );
+
+        let tx_seed = RandSeedDerivation::Transaction.derive(&block_seed, &account, 1000);
+        assert_ne!(tx_seed, account_seed);
+        assert_ne!(
+            RandSeedDerivation::Transaction.derive(&block_seed, &account, 2000),
+            tx_seed
+        );
+
+        assert_eq!(
+            RandSeedDerivation::Block.derive(&block_seed, &account, 1000),
+            block_seed
+        );
+
+        // Zero seed stays zero.
+        for derivation in [
+            RandSeedDerivation::Account,
+            RandSeedDerivation::Transaction,
+            RandSeedDerivation::Block,
+        ] {
+            assert_eq!(
+                derivation.derive(&HashBytes::ZERO, &account, 1000),
+                HashBytes::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn peek_relaxed_msg() -> anyhow::Result<()> {
+        use everscale_types::models::{RelaxedIntMsgInfo, RelaxedMessage};
+
+        let dst = IntAddr::Std(StdAddr::new(0, HashBytes([0x11; 32])));
+        let msg = Lazy::new(&RelaxedMessage {
+            info: RelaxedMsgInfo::Int(RelaxedIntMsgInfo {
+                dst: dst.clone(),
+                value: CurrencyCollection::new(123),
+                ..Default::default()
+            }),
+            init: None,
+            body: Cell::empty_cell_ref().as_slice_allow_exotic(),
+            layout: None,
+        })?
+        .cast_into::<OwnedRelaxedMessage>();
+
+        let preview = RelaxedMsgPreview::peek(&msg)?;
+        assert!(preview.is_internal());
+        assert!(!preview.has_state_init);
+        assert_eq!(preview.dst(), Some(&dst));
+        assert_eq!(preview.value(), Some(&CurrencyCollection::new(123)));
+
+        // Malformed messages.
+        let truncated = {
+            let cs = msg.as_slice_allow_exotic();
+            CellBuilder::build_from(cs.get_prefix(cs.size_bits() / 2, 0))?
+        };
+        for cell in [Cell::empty_cell(), truncated] {
+            let msg = Lazy::<OwnedRelaxedMessage>::from_raw(cell)?;
+            assert!(RelaxedMsgPreview::peek(&msg).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_init_limits() -> anyhow::Result<()> {
+        let config = crate::testkit::make_default_config();
+        let limits = SizeLimitsConfig {
+            max_acc_state_cells: 100,
+            max_acc_public_libraries: 1,
+            ..config.size_limits.clone()
+        };
+
+        let small = StateInit {
+            code: Some(crate::testkit::make_big_tree(2, &mut 0, 10)),
+            ..Default::default()
+        };
+        assert_eq!(
+            check_state_init_limits(&small, &limits, false),
+            StateLimitsResult::Fits
+        );
+        assert_eq!(
+            check_state_init_limits_diff(&small, &small, &limits, false),
+            StateLimitsResult::Unchanged
+        );
+
+        let big = StateInit {
+            data: Some(crate::testkit::make_big_tree(4, &mut 0, 1000)),
+            ..small.clone()
+        };
+        assert_eq!(
+            check_state_init_limits(&big, &limits, false),
+            StateLimitsResult::Exceeds
+        );
+        assert!(!check_state_init_limits_diff(&small, &big, &limits, false).is_ok());
+
+        // Public libraries are limited only in masterchain.
+        let mut libraries = Dict::new();
+        for i in 0..2u8 {
+            libraries.set(HashBytes([i; 32]), SimpleLib {
+                public: true,
+                root: CellBuilder::build_from(i)?,
+            })?;
+        }
+        let with_libs = StateInit {
+            libraries,
+            ..small.clone()
+        };
+        assert!(check_state_init_limits(&with_libs, &limits, false).is_ok());
+        assert!(!check_state_init_limits(&with_libs, &limits, true).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dst_workchain_checks() {
+        use everscale_types::models::VarAddr;
+        use everscale_types::num::Uint9;
+
+        let config = crate::testkit::make_default_config();
+        let make_var_addr = |workchain: i32, len: u16| {
+            IntAddr::Var(VarAddr {
+                anycast: None,
+                address_len: Uint9::new(len),
+                workchain,
+                address: vec![0x11; len.div_ceil(8) as usize],
+            })
+        };
+
+        // Masterchain and basechain.
+        for workchain in [-1, 0] {
+            let mut addr = IntAddr::Std(StdAddr::new(workchain, HashBytes([0x11; 32])));
+            assert!(check_dst_workchain(&config.workchains, &addr));
+            assert!(check_rewrite_dst_addr(
+                &config.workchains,
+                AnycastPolicy::Reject,
+                &mut addr
+            ));
+        }
+
+        // Unknown workchain.
+        let addr = IntAddr::Std(StdAddr::new(123, HashBytes([0x11; 32])));
+        assert!(!check_dst_workchain(&config.workchains, &addr));
+
+        // `addr_var` of 256 bits is rewritten to `addr_std`.
+        let mut addr = make_var_addr(0, 256);
+        assert!(check_dst_workchain(&config.workchains, &addr));
+        assert!(check_rewrite_dst_addr(
+            &config.workchains,
+            AnycastPolicy::Reject,
+            &mut addr
+        ));
+        assert_eq!(addr, IntAddr::Std(StdAddr::new(0, HashBytes([0x11; 32]))));
+
+        // Other `addr_var` are invalid for basic workchains.
+        let addr = make_var_addr(0, 128);
+        assert!(!check_dst_workchain(&config.workchains, &addr));
+    }
+
+    #[test]
+    fn anycast_dst_policy() -> anyhow::Result<()> {
+        let config = crate::testkit::make_default_config();
+
+        let make_addr = || -> anyhow::Result<IntAddr> {
+            let mut addr = StdAddr::new(0, HashBytes([0x11; 32]));
+            let mut b = CellBuilder::new();
+            b.store_small_uint(3, 5)?; // depth
+            b.store_small_uint(0b101, 3)?; // rewrite_pfx
+            addr.anycast = Some(Box::new(Anycast::load_from(&mut b.as_data_slice())?));
+            Ok(IntAddr::Std(addr))
+        };
+
+        // Reject.
+        let mut addr = make_addr()?;
+        assert!(!check_rewrite_dst_addr(
+            &config.workchains,
+            AnycastPolicy::Reject,
+            &mut addr
+        ));
+
+        // Allow.
+        let mut addr = make_addr()?;
+        assert!(check_rewrite_dst_addr(
+            &config.workchains,
+            AnycastPolicy::Allow,
+            &mut addr
+        ));
+        assert_eq!(addr, make_addr()?);
+
+        // Rewrite.
+        let mut addr = make_addr()?;
+        assert!(check_rewrite_dst_addr(
+            &config.workchains,
+            AnycastPolicy::Rewrite,
+            &mut addr
+        ));
+        let IntAddr::Std(addr) = addr else {
+            panic!("expected std addr");
+        };
+        assert!(addr.anycast.is_none());
+        assert_eq!(addr.address.0[0], 0b1011_0001);
+        assert_eq!(addr.address.0[1..], [0x11; 31]);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,83 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use everscale_types::cell::{Cell, CellFamily, HashBytes};
+use everscale_types::models::{AccountState, AccountStatus, CurrencyCollection, StdAddr};
+use everscale_types::num::Tokens;
+use libfuzzer_sys::fuzz_target;
+use tycho_executor::fuzz::{AnyConfigSubset, AnyOutActions};
+use tycho_executor::phase::{ActionPhaseContext, ActionPhaseFull, ReceivedMessage};
+use tycho_executor::{ExecutorState, ParsedConfig};
+
+use self::common::{make_default_config, make_default_params, stub_compute_phase, GasFees};
+
+mod common;
+
+fuzz_target!(|input: Input| {
+    let params = make_default_params();
+
+    let mut config = ParsedConfig::parse(make_default_config().raw.clone(), u32::MAX).unwrap();
+    if let Some(subset) = &input.config {
+        subset.apply(&mut config);
+    }
+
+    let gas_fees: Tokens = input.gas_fees.into();
+    let balance = CurrencyCollection::new(input.balance as u128);
+    let mut original_balance = balance.clone();
+    original_balance.try_add_assign_tokens(gas_fees).unwrap();
+
+    let mut msg = input.bounce_enabled.map(|bounce_enabled| ReceivedMessage {
+        root: Cell::empty_cell(),
+        init: None,
+        body: Default::default(),
+        created_lt: 0,
+        is_external: false,
+        bounce_enabled,
+        balance_remaining: CurrencyCollection::ZERO,
+    });
+
+    let mut state = ExecutorState {
+        params: &params,
+        config: &config,
+        is_special: false,
+        address: StdAddr::new(if input.is_masterchain { -1 } else { 0 }, HashBytes::ZERO),
+        storage_stat: Default::default(),
+        balance,
+        state: AccountState::Uninit,
+        orig_status: AccountStatus::NotExists,
+        end_status: AccountStatus::Uninit,
+        start_lt: 0,
+        end_lt: 1,
+        in_msg_lt: None,
+        out_msgs: Vec::new(),
+        total_fees: gas_fees,
+        burned: Tokens::ZERO,
+        cached_storage_stat: None,
+    };
+
+    let compute_phase = stub_compute_phase(gas_fees);
+
+    let ActionPhaseFull { action_phase, .. } = state
+        .action_phase(ActionPhaseContext {
+            received_message: msg.as_mut(),
+            original_balance,
+            new_state: Default::default(),
+            actions: input.actions.0,
+            compute_phase: &compute_phase,
+            inspector: None,
+        })
+        .unwrap();
+
+    assert!(action_phase.success || state.out_msgs.is_empty());
+    assert!(state.out_msgs.len() <= action_phase.messages_created as usize);
+});
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    is_masterchain: bool,
+    bounce_enabled: Option<bool>,
+    balance: u64,
+    gas_fees: GasFees,
+    config: Option<AnyConfigSubset>,
+    actions: AnyOutActions,
+}
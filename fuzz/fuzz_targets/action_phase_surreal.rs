@@ -47,6 +47,7 @@ fuzz_target!(|input: Input| {
         end_status: AccountStatus::Uninit,
         start_lt: 0,
         end_lt: 1,
+        in_msg_lt: None,
         out_msgs: Vec::new(),
         total_fees: gas_fees,
         burned: Tokens::ZERO,
@@ -121,6 +122,7 @@ impl From<MessageInput> for ReceivedMessage {
             root: Cell::empty_cell(),
             init: None,
             body: Default::default(),
+            created_lt: 0,
             is_external,
             bounce_enabled,
             balance_remaining,
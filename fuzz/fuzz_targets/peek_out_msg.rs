@@ -0,0 +1,24 @@
+#![no_main]
+
+use everscale_types::arbitrary::OrdinaryCell;
+use everscale_types::models::OwnedRelaxedMessage;
+use everscale_types::prelude::*;
+use libfuzzer_sys::fuzz_target;
+use tycho_executor::RelaxedMsgPreview;
+
+fuzz_target!(|cell: OrdinaryCell| {
+    let OrdinaryCell(cell) = cell;
+    let Ok(msg) = Lazy::<OwnedRelaxedMessage>::from_raw(cell) else {
+        return;
+    };
+
+    // Preview must never panic on malformed input.
+    let preview = RelaxedMsgPreview::peek(&msg);
+
+    // Preview must be consistent with the full parser.
+    if let Ok(full) = msg.load() {
+        let preview = preview.expect("preview must succeed for a valid message");
+        assert_eq!(preview.info, full.info);
+        assert_eq!(preview.has_state_init, full.init.is_some());
+    }
+});
@@ -1,11 +1,18 @@
 use std::collections::{BTreeMap, HashMap};
 
+use darling::ast::NestedMeta;
 use darling::util::{parse_expr, SpannedValue};
 use darling::{Error, FromMeta};
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 use syn::ItemImpl;
 
+#[derive(Debug, Default, FromMeta)]
+struct VmModuleArgs {
+    #[darling(default)]
+    category: Option<SpannedValue<String>>,
+}
+
 #[derive(Debug, FromMeta)]
 struct VmInstrArgs {
     code: SpannedValue<String>,
@@ -36,7 +43,24 @@ struct VmExtRangeInstrArgs {
 }
 
 #[proc_macro_attribute]
-pub fn vm_module(_: TokenStream, input: TokenStream) -> TokenStream {
+pub fn vm_module(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = match NestedMeta::parse_meta_list(args.into()) {
+        Ok(args) => args,
+        Err(e) => return TokenStream::from(Error::from(e).write_errors()),
+    };
+    let args = match VmModuleArgs::from_list(&args) {
+        Ok(args) => args,
+        Err(e) => return TokenStream::from(e.write_errors()),
+    };
+
+    let category = match &args.category {
+        None => None,
+        Some(category) => match parse_category(category) {
+            Ok(category) => Some(category),
+            Err(e) => return TokenStream::from(e.write_errors()),
+        },
+    };
+
     let mut input = syn::parse_macro_input!(input as ItemImpl);
 
     let opcodes_arg = quote::format_ident!("__t");
@@ -89,7 +113,13 @@ pub fn vm_module(_: TokenStream, input: TokenStream) -> TokenStream {
             init_functions.push(fun);
         } else {
             for attr in instr_attrs {
-                match process_instr_definition(&fun, &opcodes_arg, &attr, &mut opcodes) {
+                match process_instr_definition(
+                    &fun,
+                    &opcodes_arg,
+                    &attr,
+                    category.as_ref(),
+                    &mut opcodes,
+                ) {
                     Ok(definition) => definitions.push(definition),
                     Err(e) => errors.push(e.with_span(&attr)),
                 }
@@ -139,6 +169,23 @@ pub fn vm_module(_: TokenStream, input: TokenStream) -> TokenStream {
     .into()
 }
 
+fn parse_category(category: &SpannedValue<String>) -> Result<syn::Ident, Error> {
+    const KNOWN_CATEGORIES: &[&str] = &[
+        "stack", "arith", "compare", "cell", "control", "dict", "tuple", "crypto", "config", "gas",
+        "message", "debug",
+    ];
+
+    if !KNOWN_CATEGORIES.contains(&category.as_str()) {
+        return Err(Error::custom(format!(
+            "Unknown opcode category. Expected one of: {}",
+            KNOWN_CATEGORIES.join(", ")
+        ))
+        .with_span(&category.span()));
+    }
+
+    Ok(quote::format_ident!("{}", category.to_uppercase()))
+}
+
 struct ParsedCode<'a> {
     code: &'a str,
     range_from: Option<&'a str>,
@@ -176,6 +223,7 @@ fn process_instr_definition(
     function: &syn::ImplItemFn,
     opcodes_arg: &syn::Ident,
     attr: &syn::Attribute,
+    category: Option<&syn::Ident>,
     opcodes: &mut Opcodes,
 ) -> Result<syn::Expr, Error> {
     let mut instr = VmInstrArgs::from_meta(&attr.meta)?;
@@ -270,6 +318,10 @@ fn process_instr_definition(
         syn::Expr::Lit(expr) if matches!(&expr.lit, syn::Lit::Str(..)) => expr.into_token_stream(),
         fmt => quote! { "{}", #fmt },
     };
+    let log_op = match category {
+        Some(category) => quote! { vm_log_op!(@#category #fmt); },
+        None => quote! { vm_log_op!(#fmt); },
+    };
 
     let ty = match (!args.is_empty(), parsed.range_from, parsed.range_to) {
         (false, range_from, range_to) => {
@@ -505,7 +557,7 @@ fn process_instr_definition(
             quote! {
                 fn #wrapper_func_name(st: &mut ::tycho_vm::state::VmState) -> ::tycho_vm::error::VmResult<i32> {
                     #(#arg_definitions)*
-                    vm_log_op!(#fmt);
+                    #log_op
                     #function_name(st, #(#arg_idents),*)
                 }
             }
@@ -538,7 +590,7 @@ fn process_instr_definition(
                 fn #wrapper_func_name(st: &mut ::tycho_vm::state::VmState, args: u32) -> ::tycho_vm::error::VmResult<i32> {
                     #(#arg_definitions)*
                     #cond
-                    vm_log_op!(#fmt);
+                    #log_op
                     #function_name(st, #(#arg_idents),*)
                 }
             }
@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use everscale_asm_macros::tvmasm;
+use everscale_types::boc::Boc;
+use tycho_vm::{GasParams, VmState};
+
+fn vm_benchmark(c: &mut Criterion) {
+    let code = Boc::decode(tvmasm!(
+        r#"
+        PUSHINT 0 PUSHINT 1000
+        PUSHCONT { INC DUP PUSHINT 7 MUL PUSHINT 1000 MOD ADD }
+        REPEAT
+        "#
+    ))
+    .unwrap();
+
+    c.bench_function("arith_loop", |b| {
+        b.iter(|| {
+            let mut vm_state = VmState::builder()
+                .with_code(code.clone())
+                .with_gas(GasParams::getter())
+                .build();
+
+            let result = vm_state.run();
+            _ = black_box(result);
+        });
+    });
+}
+
+criterion_group!(benches, vm_benchmark);
+criterion_main!(benches);
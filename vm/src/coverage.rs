@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use ahash::HashMap;
+use everscale_types::cell::HashBytes;
+
+use crate::trace::{TraceCollector, TraceEvent, TraceInstr};
+
+/// Executed code regions keyed by the code cell hash.
+///
+/// Can be used as a [`TraceCollector`] for multiple runs
+/// to accumulate the coverage of a whole test suite.
+#[derive(Default, Debug, Clone)]
+pub struct Coverage {
+    cells: HashMap<HashBytes, CellCoverage>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coverage of all executed cells.
+    pub fn cells(&self) -> &HashMap<HashBytes, CellCoverage> {
+        &self.cells
+    }
+
+    /// Coverage of the specified cell.
+    ///
+    /// Returns `None` if no instructions were executed from this cell.
+    pub fn get(&self, cell_hash: &HashBytes) -> Option<&CellCoverage> {
+        self.cells.get(cell_hash)
+    }
+
+    /// Returns `true` if no instructions were executed.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Adds the coverage collected separately.
+    pub fn merge(&mut self, other: &Self) {
+        for (hash, other) in &other.cells {
+            let cell = self.cells.entry(*hash).or_default();
+            for (offset, other) in &other.instructions {
+                cell.record(*offset, other.bits, other.hits);
+            }
+        }
+    }
+
+    /// Removes all collected data.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+}
+
+impl TraceCollector for Coverage {
+    fn on_step(&mut self, event: &TraceEvent<'_>) {
+        // NOTE: Implicit instructions don't occupy any code bits.
+        if let TraceInstr::Opcode { .. } = event.instr {
+            let cell = self.cells.entry(event.location.cell_hash).or_default();
+            cell.record(event.location.offset, event.instr_bits, 1);
+        }
+    }
+}
+
+/// Executed instructions of a single cell.
+#[derive(Default, Debug, Clone)]
+pub struct CellCoverage {
+    instructions: BTreeMap<u16, InstrCoverage>,
+}
+
+impl CellCoverage {
+    /// Executed instructions by their bit offset.
+    pub fn instructions(&self) -> &BTreeMap<u16, InstrCoverage> {
+        &self.instructions
+    }
+
+    /// Returns `true` if the instruction at the specified offset was executed.
+    pub fn is_covered(&self, offset: u16) -> bool {
+        self.instructions.contains_key(&offset)
+    }
+
+    /// Merged executed bit ranges.
+    ///
+    /// NOTE: Instructions of unknown length (which always transferred
+    /// control) are counted as a single bit at their offset.
+    pub fn ranges(&self) -> Vec<Range<u16>> {
+        let mut result = Vec::<Range<u16>>::new();
+        for (offset, instr) in &self.instructions {
+            let end = offset + instr.bits.unwrap_or(1);
+            match result.last_mut() {
+                Some(last) if last.end >= *offset => last.end = std::cmp::max(last.end, end),
+                _ => result.push(*offset..end),
+            }
+        }
+        result
+    }
+
+    /// Total number of executed bits.
+    pub fn covered_bits(&self) -> u16 {
+        self.ranges()
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+
+    fn record(&mut self, offset: u16, bits: Option<u16>, hits: u64) {
+        let instr = self.instructions.entry(offset).or_default();
+        instr.hits += hits;
+        if instr.bits.is_none() {
+            instr.bits = bits;
+        }
+    }
+}
+
+/// Execution info of a single instruction.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstrCoverage {
+    /// How many times the instruction was executed.
+    pub hits: u64,
+    /// Instruction length in bits (if known).
+    pub bits: Option<u16>,
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::prelude::*;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::gas::GasParams;
+    use crate::state::VmState;
+
+    fn run_with_coverage(code: Cell, arg: i32, coverage: &mut Coverage) {
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_stack(tuple![int arg])
+            .with_gas(GasParams::getter())
+            .with_trace_collector(coverage)
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+    }
+
+    #[test]
+    #[traced_test]
+    fn branch_coverage() {
+        // 0: PUSHCONT (16 bits)
+        // 16: PUSHCONT (16 bits)
+        // 32: IFELSE (8 bits)
+        // 40: PUSHINT 10 (8 bits)
+        //
+        // Branch bodies are inlined at 8 and 24.
+        let code = Boc::decode(tvmasm!(
+            "PUSHCONT { PUSHINT 2 } PUSHCONT { PUSHINT 3 } IFELSE PUSHINT 10"
+        ))
+        .unwrap();
+        let hash = *code.repr_hash();
+
+        let mut coverage = Coverage::new();
+        run_with_coverage(code.clone(), -1, &mut coverage);
+
+        let cell = coverage.get(&hash).unwrap();
+        assert_eq!(coverage.cells().len(), 1);
+        assert!(cell.is_covered(8));
+        assert!(!cell.is_covered(24));
+        assert_eq!(cell.instructions()[&0], InstrCoverage {
+            hits: 1,
+            bits: Some(16),
+        });
+        assert_eq!(cell.instructions()[&32], InstrCoverage {
+            hits: 1,
+            bits: None,
+        });
+        // NOTE: `PUSHCONT` ranges include the inlined bodies.
+        assert_eq!(cell.ranges(), vec![0..33, 40..48]);
+
+        let mut other = Coverage::new();
+        run_with_coverage(code, 0, &mut other);
+        assert!(other.get(&hash).unwrap().is_covered(24));
+
+        coverage.merge(&other);
+        let cell = coverage.get(&hash).unwrap();
+        assert_eq!(cell.instructions()[&0].hits, 2);
+        assert_eq!(cell.instructions()[&8].hits, 1);
+        assert_eq!(cell.instructions()[&24].hits, 1);
+        assert_eq!(cell.ranges(), vec![0..33, 40..48]);
+        assert_eq!(cell.covered_bits(), 41);
+    }
+}
@@ -0,0 +1,314 @@
+use everscale_types::cell::HashBytes;
+
+use crate::error::VmException;
+use crate::state::VmState;
+
+/// Position of an instruction in the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CodeLocation {
+    /// Representation hash of the code cell.
+    pub cell_hash: HashBytes,
+    /// Bit offset of the instruction in the cell.
+    pub offset: u16,
+}
+
+/// A condition to pause the execution at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Pause before the instruction at the specified location.
+    Location(CodeLocation),
+    /// Pause before the instruction with the specified mnemonic
+    /// (the first word of the disassembled instruction, e.g. `PUSHINT`).
+    #[cfg(feature = "dump")]
+    Opcode(String),
+}
+
+impl Breakpoint {
+    /// Returns `true` if the next instruction of the state matches this breakpoint.
+    pub fn matches(&self, st: &VmState<'_>) -> bool {
+        match self {
+            Self::Location(location) => st.code_location() == *location,
+            #[cfg(feature = "dump")]
+            Self::Opcode(name) => opcode_name(st).as_deref() == Some(name.as_str()),
+        }
+    }
+}
+
+/// Debugger execution status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebuggerStatus {
+    /// Execution is paused at the breakpoint with the specified index.
+    Breakpoint(usize),
+    /// Execution is paused after a single step.
+    Step,
+    /// Execution is finished with the specified exit code (`!VmState::run()`).
+    Finished(i32),
+}
+
+/// Interactive VM execution.
+///
+/// Unlike [`VmState::run`] the execution can be paused
+/// at breakpoints or after each step and then resumed.
+pub struct Debugger<'s, 'a> {
+    state: &'s mut VmState<'a>,
+    breakpoints: Vec<Breakpoint>,
+    paused: bool,
+    exit_code: Option<i32>,
+}
+
+impl<'s, 'a> Debugger<'s, 'a> {
+    pub fn new(state: &'s mut VmState<'a>) -> Self {
+        Self {
+            state,
+            breakpoints: Vec::new(),
+            paused: false,
+            exit_code: None,
+        }
+    }
+
+    /// Debugged state.
+    ///
+    /// Can be used to inspect stack and registers while paused.
+    pub fn state(&self) -> &VmState<'a> {
+        self.state
+    }
+
+    /// Mutable debugged state.
+    pub fn state_mut(&mut self) -> &mut VmState<'a> {
+        self.state
+    }
+
+    /// Exit code if the execution is finished.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Returns `true` if the execution is finished.
+    pub fn is_finished(&self) -> bool {
+        self.exit_code.is_some()
+    }
+
+    /// Registered breakpoints.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Adds a new breakpoint and returns its index.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    /// Removes all breakpoints equal to the specified one.
+    ///
+    /// Returns `true` if at least one breakpoint was removed.
+    /// NOTE: Indices of the following breakpoints are shifted.
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|item| item != breakpoint);
+        self.breakpoints.len() != len
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Runs the VM until any breakpoint is hit or the execution is finished.
+    pub fn run(&mut self) -> DebuggerStatus {
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        let mut hit = None;
+        // NOTE: Don't stop at the same breakpoint again.
+        self.run_until(self.paused, |st| {
+            hit = breakpoints.iter().position(|item| item.matches(st));
+            hit.is_some()
+        });
+        self.breakpoints = breakpoints;
+
+        match (self.exit_code, hit) {
+            (Some(exit_code), _) => DebuggerStatus::Finished(exit_code),
+            (None, Some(index)) => DebuggerStatus::Breakpoint(index),
+            (None, None) => unreachable!("execution can only be paused at a breakpoint"),
+        }
+    }
+
+    /// Executes a single instruction (with its exception handling)
+    /// and pauses before the next one.
+    pub fn step(&mut self) -> DebuggerStatus {
+        self.run_until(true, |_| true);
+        match self.exit_code {
+            Some(exit_code) => DebuggerStatus::Finished(exit_code),
+            None => DebuggerStatus::Step,
+        }
+    }
+
+    fn run_until<F>(&mut self, skip_pause: bool, pause: F)
+    where
+        F: FnMut(&VmState<'a>) -> bool,
+    {
+        if self.exit_code.is_some() {
+            return;
+        }
+
+        if self.state.throw_on_code_access {
+            // Same as `VmState::run` which doesn't negate this code.
+            self.exit_code = Some(!(VmException::Fatal as u8 as i32));
+            return;
+        }
+
+        // NOTE: Parent state is never restored on the first iteration
+        // since the execution is either new or was paused inside the current VM.
+        let res = self.state.run_until(true, skip_pause, pause);
+        self.paused = res.is_none();
+        self.exit_code = res.map(|res| !res);
+    }
+}
+
+#[cfg(feature = "dump")]
+fn opcode_name(st: &VmState<'_>) -> Option<String> {
+    use everscale_types::cell::{Cell, CellSlice};
+
+    use crate::dispatch::DumpOutput;
+    use crate::error::DumpResult;
+
+    #[derive(Default)]
+    struct OpcodeName(Option<String>);
+
+    impl DumpOutput for OpcodeName {
+        fn record_gas(&mut self, _: u64) -> DumpResult {
+            Ok(())
+        }
+
+        fn record_opcode(&mut self, value: &dyn std::fmt::Display) -> DumpResult {
+            let value = value.to_string();
+            self.0 = value.split_whitespace().next().map(str::to_owned);
+            Ok(())
+        }
+
+        fn record_cell(&mut self, _: Cell) -> DumpResult {
+            Ok(())
+        }
+
+        fn record_slice(&mut self, _: CellSlice<'_>) -> DumpResult {
+            Ok(())
+        }
+
+        fn record_cont(&mut self, _: Cell) -> DumpResult {
+            Ok(())
+        }
+
+        fn record_cont_slice(&mut self, _: CellSlice<'_>) -> DumpResult {
+            Ok(())
+        }
+
+        fn record_dict(&mut self, _: u16, _: CellSlice<'_>) -> DumpResult {
+            Ok(())
+        }
+    }
+
+    let mut code = st.code.apply();
+    if code.is_data_empty() {
+        return None;
+    }
+
+    let mut name = OpcodeName::default();
+    st.cp.dispatch_dump(&mut code, &mut name).ok()?;
+    name.0
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::prelude::*;
+    use num_bigint::BigInt;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::saferc::SafeRc;
+
+    #[test]
+    #[traced_test]
+    fn pause_at_location() {
+        let code = Boc::decode(tvmasm!("PUSHINT 1 PUSHINT 2 ADD PUSHINT 3 MUL")).unwrap();
+        let code_hash = *code.repr_hash();
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_debug(&mut output)
+            .build();
+
+        let mut debugger = Debugger::new(&mut state);
+        let add = debugger.add_breakpoint(Breakpoint::Location(CodeLocation {
+            cell_hash: code_hash,
+            offset: 16,
+        }));
+
+        assert_eq!(debugger.run(), DebuggerStatus::Breakpoint(add));
+        assert_eq!(debugger.state().stack.depth(), 2);
+        assert_eq!(debugger.state().code_location(), CodeLocation {
+            cell_hash: code_hash,
+            offset: 16,
+        });
+
+        // Step over `ADD`.
+        assert_eq!(debugger.step(), DebuggerStatus::Step);
+        assert_eq!(debugger.state().stack.depth(), 1);
+        assert_eq!(
+            debugger.state().stack.items[0].as_int().unwrap(),
+            &BigInt::from(3)
+        );
+
+        // Patch the stack while paused.
+        SafeRc::make_mut(&mut debugger.state_mut().stack).items[0] =
+            SafeRc::new_dyn_value(BigInt::from(10));
+
+        assert_eq!(debugger.run(), DebuggerStatus::Finished(0));
+        assert!(debugger.is_finished());
+        assert_eq!(debugger.run(), DebuggerStatus::Finished(0));
+
+        assert_eq!(state.stack.items[0].as_int().unwrap(), &BigInt::from(30));
+    }
+
+    #[test]
+    #[traced_test]
+    fn step_through_exception() {
+        let code = Boc::decode(tvmasm!("PUSHINT 1 THROW 10")).unwrap();
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_debug(&mut output)
+            .build();
+
+        let mut debugger = Debugger::new(&mut state);
+        assert_eq!(debugger.step(), DebuggerStatus::Step);
+        assert_eq!(debugger.state().stack.depth(), 1);
+
+        // Exception is handled within the same step.
+        assert_eq!(debugger.step(), DebuggerStatus::Finished(10));
+        assert_eq!(debugger.exit_code(), Some(10));
+        assert_eq!(debugger.step(), DebuggerStatus::Finished(10));
+    }
+
+    #[cfg(feature = "dump")]
+    #[test]
+    #[traced_test]
+    fn pause_at_opcode() {
+        let code = Boc::decode(tvmasm!("PUSHINT 1 PUSHINT 2 ADD PUSHINT 3 ADD")).unwrap();
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_debug(&mut output)
+            .build();
+
+        let mut debugger = Debugger::new(&mut state);
+        debugger.add_breakpoint(Breakpoint::Opcode("ADD".to_owned()));
+
+        assert_eq!(debugger.run(), DebuggerStatus::Breakpoint(0));
+        assert_eq!(debugger.state().stack.depth(), 2);
+        assert_eq!(debugger.run(), DebuggerStatus::Breakpoint(0));
+        assert_eq!(debugger.state().stack.depth(), 2);
+        assert_eq!(debugger.run(), DebuggerStatus::Finished(0));
+    }
+}
@@ -84,7 +84,7 @@ impl DispatchTable {
         op.dump(code, opcode, bits, f)
     }
 
-    fn get_opcode_from_slice(slice: &CellSlice<'_>) -> (u32, u16) {
+    pub(crate) fn get_opcode_from_slice(slice: &CellSlice<'_>) -> (u32, u16) {
         let bits = std::cmp::min(MAX_OPCODE_BITS, slice.size_bits());
         let opcode = (slice.get_uint(0, bits).unwrap() as u32) << (MAX_OPCODE_BITS - bits);
         (opcode, bits)
@@ -275,6 +275,26 @@ impl Opcodes {
         }))
     }
 
+    /// Removes all opcodes with the specified prefix.
+    ///
+    /// Can be used to override the existing handlers.
+    /// Fails if some opcode is only partially covered by the prefix.
+    pub fn remove(&mut self, opcode: u32, bits: u16) -> Result<()> {
+        anyhow::ensure!(bits <= MAX_OPCODE_BITS, "Opcode prefix is too long");
+        let remaining_bits = MAX_OPCODE_BITS - bits;
+        let min = opcode << remaining_bits;
+        let max = (opcode + 1) << remaining_bits;
+
+        Self::check_removed_range(min, max, &self.exec_opcodes)?;
+        #[cfg(feature = "dump")]
+        Self::check_removed_range(min, max, &self.dump_opcodes)?;
+
+        self.exec_opcodes.retain(|k, _| !(min..max).contains(k));
+        #[cfg(feature = "dump")]
+        self.dump_opcodes.retain(|k, _| !(min..max).contains(k));
+        Ok(())
+    }
+
     pub fn add_opcode(&mut self, opcode: Box<dyn OpcodeExec>) -> Result<()> {
         Self::add_opcode_impl(opcode, &mut self.exec_opcodes)
     }
@@ -313,6 +333,30 @@ impl Opcodes {
         opcodes.insert(min, opcode);
         Ok(())
     }
+
+    fn check_removed_range<T: OpcodeBase + ?Sized>(
+        min: u32,
+        max: u32,
+        opcodes: &BTreeMap<u32, Box<T>>,
+    ) -> Result<()> {
+        if let Some((_, prev)) = opcodes.range(..min).next_back() {
+            let (_, prev_max) = prev.range();
+            anyhow::ensure!(
+                prev_max <= min,
+                "Opcode is partially removed, prev max: {prev_max:06x}"
+            );
+        }
+
+        if let Some((_, last)) = opcodes.range(min..max).next_back() {
+            let (_, last_max) = last.range();
+            anyhow::ensure!(
+                last_max <= max,
+                "Opcode is partially removed, last max: {last_max:06x}"
+            );
+        }
+
+        Ok(())
+    }
 }
 
 fn build_opcodes<T, F>(items: BTreeMap<u32, Box<T>>, f: F) -> Vec<(u32, Box<T>)>
@@ -553,10 +597,18 @@ mod tests {
             quit1: SafeRc::from(QuitCont { exit_code: 0 }),
             gas: GasConsumer::new(GasParams::getter()),
             cp: Box::leak(Box::new(cp)),
-            debug: None,
+            debug: Default::default(),
+            codepages: None,
+            rng: None,
+            signature_backend: None,
+            trace: None,
             modifiers: Default::default(),
+            limits: Default::default(),
             version: VmVersion::LATEST_TON,
             parent: None,
+            accept_info: None,
+            send_msg_estimates: Vec::new(),
+            interrupted: None,
         };
 
         let dummy = state.cp.lookup(0x800000);
@@ -566,6 +618,72 @@ mod tests {
         assert!(matches!(*err, VmError::InvalidOpcode));
     }
 
+    #[test]
+    fn override_opcodes() {
+        let mut cp = crate::instr::default_opcodes(123).unwrap();
+
+        // `DEBUG x` is a range opcode which can only be removed entirely.
+        cp.remove(0xfe02, 16).unwrap_err();
+
+        // `NOP` is replaced with a custom opcode.
+        cp.remove(0x00, 8).unwrap();
+        cp.add_simple(
+            0x00,
+            8,
+            |st| {
+                ok!(SafeRc::make_mut(&mut st.stack).push_int(123));
+                Ok(0)
+            },
+            #[cfg(feature = "dump")]
+            |f| f.record_opcode(&"CUSTOM"),
+        )
+        .unwrap();
+
+        // `SETCPX` can't be redefined without removing it first.
+        let double: FnExecInstrSimple = |st| {
+            let stack = SafeRc::make_mut(&mut st.stack);
+            let value = ok!(stack.pop_smallint_range(0, 255));
+            ok!(stack.push_int(value * 2));
+            Ok(0)
+        };
+        #[cfg(feature = "dump")]
+        let double_dump: FnDumpInstrSimple = |f| f.record_opcode(&"DOUBLE");
+
+        cp.add_simple(
+            0xfff0,
+            16,
+            double,
+            #[cfg(feature = "dump")]
+            double_dump,
+        )
+        .unwrap_err();
+        cp.remove(0xfff0, 16).unwrap();
+        cp.add_simple(
+            0xfff0,
+            16,
+            double,
+            #[cfg(feature = "dump")]
+            double_dump,
+        )
+        .unwrap();
+
+        let cp: &'static DispatchTable = Box::leak(Box::new(cp.build()));
+        assert_eq!(cp.id(), 123);
+
+        let mut state = VmState::builder()
+            .with_code(Boc::decode(tvmasm!("NOP NOP SETCPX ADD")).unwrap())
+            .with_gas(GasParams::getter())
+            .build();
+        state.cp = cp;
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.depth(), 1);
+        assert_eq!(
+            state.stack.items[0].as_int().unwrap(),
+            &num_bigint::BigInt::from(369)
+        );
+    }
+
     #[test]
     fn opcode_overlap_check_works() {
         // Simple overlap
@@ -120,6 +120,40 @@ pub enum VmException {
 }
 
 impl VmException {
+    /// All exception codes in ascending order.
+    pub const ALL: [Self; 15] = [
+        Self::Ok,
+        Self::Alternative,
+        Self::StackUnderflow,
+        Self::StackOverflow,
+        Self::IntOverflow,
+        Self::RangeCheck,
+        Self::InvalidOpcode,
+        Self::TypeCheck,
+        Self::CellOverflow,
+        Self::CellUnderflow,
+        Self::DictError,
+        Self::Unknown,
+        Self::Fatal,
+        Self::OutOfGas,
+        Self::VirtError,
+    ];
+
+    /// Returns an exception for the specified code (as thrown by `THROW`).
+    pub const fn from_code(code: i32) -> Option<Self> {
+        if 0 <= code && code < Self::ALL.len() as i32 {
+            Some(Self::ALL[code as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the code of the exception (as thrown by `THROW`).
+    pub const fn code(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Returns an exit code of the unhandled exception.
     pub const fn as_exit_code(&self) -> i32 {
         !(*self as i32)
     }
@@ -3,7 +3,7 @@ use std::num::NonZeroU64;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use ahash::HashSet;
+use ahash::{HashMap, HashSet};
 use everscale_types::cell::{CellParts, LoadMode};
 use everscale_types::error::Error;
 use everscale_types::models::{LibDescr, SimpleLib};
@@ -267,6 +267,133 @@ impl<S: BuildHasher> LibraryProvider for std::collections::HashMap<HashBytes, Si
     }
 }
 
+/// Libraries resolver which searches the providers in order.
+///
+/// Same as a tuple of providers but the list can be built at runtime.
+#[derive(Default)]
+pub struct ChainedLibraries<'a> {
+    providers: Vec<Box<dyn LibraryProvider + 'a>>,
+}
+
+impl<'a> ChainedLibraries<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a provider with the lowest priority.
+    pub fn with<T: LibraryProvider + 'a>(mut self, provider: T) -> Self {
+        self.push(provider);
+        self
+    }
+
+    /// Adds a provider with the lowest priority.
+    pub fn push<T: LibraryProvider + 'a>(&mut self, provider: T) {
+        self.providers.push(Box::new(provider));
+    }
+
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl LibraryProvider for ChainedLibraries<'_> {
+    fn find(&self, library_hash: &HashBytes) -> Result<Option<Cell>, Error> {
+        for provider in &self.providers {
+            if let res @ Some(_) = ok!(provider.find(library_hash)) {
+                return Ok(res);
+            }
+        }
+        Ok(None)
+    }
+
+    fn find_ref<'a>(&'a self, library_hash: &HashBytes) -> Result<Option<&'a DynCell>, Error> {
+        for provider in &self.providers {
+            if let res @ Some(_) = ok!(provider.find_ref(library_hash)) {
+                return Ok(res);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Libraries resolver which remembers all lookups of the inner provider.
+///
+/// Missing libraries are also cached. Errors are not cached.
+pub struct CachedLibraries<T> {
+    inner: T,
+    cache: std::cell::RefCell<HashMap<HashBytes, Option<Cell>>>,
+    stats: std::cell::Cell<LibraryCacheStats>,
+}
+
+impl<T> CachedLibraries<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            cache: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Cache usage statistics.
+    pub fn stats(&self) -> LibraryCacheStats {
+        self.stats.get()
+    }
+
+    /// Removes all cached libraries and resets the statistics.
+    pub fn clear(&mut self) {
+        self.cache.get_mut().clear();
+        self.stats.set(LibraryCacheStats::default());
+    }
+}
+
+impl<T: LibraryProvider> LibraryProvider for CachedLibraries<T> {
+    fn find(&self, library_hash: &HashBytes) -> Result<Option<Cell>, Error> {
+        let mut stats = self.stats.get();
+        if let Some(cell) = self.cache.borrow().get(library_hash) {
+            stats.hits += 1;
+            self.stats.set(stats);
+            return Ok(cell.clone());
+        }
+
+        let cell = ok!(self.inner.find(library_hash));
+        stats.misses += 1;
+        self.stats.set(stats);
+
+        self.cache.borrow_mut().insert(*library_hash, cell.clone());
+        Ok(cell)
+    }
+
+    fn find_ref<'a>(&'a self, library_hash: &HashBytes) -> Result<Option<&'a DynCell>, Error> {
+        let cell = ok!(self.find(library_hash));
+
+        // SAFETY: The same cell is stored in the cache. Cache entries are
+        // never removed or replaced while `self` is borrowed as immutable,
+        // and the cell data itself is not moved when the map grows.
+        Ok(cell.map(|cell| unsafe { &*(cell.as_ref() as *const DynCell) }))
+    }
+}
+
+/// [`CachedLibraries`] usage statistics.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LibraryCacheStats {
+    /// Number of lookups resolved from the cache.
+    pub hits: u64,
+    /// Number of lookups passed to the inner provider.
+    pub misses: u64,
+}
+
 struct SimpleLibRef<'tlb> {
     root: &'tlb DynCell,
 }
@@ -292,6 +419,8 @@ pub struct GasConsumer<'l> {
     gas_credit: std::cell::Cell<u64>,
     /// Initial gas to compute the consumed amount.
     gas_base: std::cell::Cell<u64>,
+    /// Gas consumed when the credit was reset.
+    gas_consumed_on_accept: std::cell::Cell<Option<u64>>,
     /// Remaining gas available.
     gas_remaining: std::cell::Cell<i64>,
     /// Gas price (fixed point with 16 bits for fractional part).
@@ -311,6 +440,10 @@ pub struct GasConsumer<'l> {
 
     // Missing library in case of resolving error occured.
     missing_library: std::cell::Cell<Option<HashBytes>>,
+    // Resolve missing libraries as empty cells.
+    missing_libraries_as_empty: bool,
+    // Original hash of the first accessed pruned branch.
+    pruned_branch: std::cell::Cell<Option<HashBytes>>,
 }
 
 impl<'l> GasConsumer<'l> {
@@ -328,6 +461,39 @@ impl<'l> GasConsumer<'l> {
     pub const TUPLE_ENTRY_GAS_PRICE: u64 = 1;
     pub const HASH_EXT_ENTRY_GAS_PRICE: u64 = 1;
     pub const CHK_SGN_GAS_PRICE: u64 = 4000;
+    pub const ECRECOVER_GAS_PRICE: u64 = 1500;
+    pub const P256_CHK_SGN_GAS_PRICE: u64 = 3500;
+    pub const RIST255_MUL_GAS_PRICE: u64 = 2000;
+    pub const RIST255_MULBASE_GAS_PRICE: u64 = 750;
+    pub const RIST255_ADD_GAS_PRICE: u64 = 600;
+    pub const RIST255_FROMHASH_GAS_PRICE: u64 = 600;
+    pub const RIST255_VALIDATE_GAS_PRICE: u64 = 200;
+    pub const BLS_VERIFY_GAS_PRICE: u64 = 61000;
+    /// Aggregation costs `n * ELEMENT - DISCOUNT` (the base price is negative).
+    pub const BLS_AGGREGATE_BASE_GAS_DISCOUNT: u64 = 2650;
+    pub const BLS_AGGREGATE_ELEMENT_GAS_PRICE: u64 = 4350;
+    pub const BLS_FAST_AGGREGATE_VERIFY_BASE_GAS_PRICE: u64 = 58000;
+    pub const BLS_FAST_AGGREGATE_VERIFY_ELEMENT_GAS_PRICE: u64 = 3000;
+    pub const BLS_AGGREGATE_VERIFY_BASE_GAS_PRICE: u64 = 38500;
+    pub const BLS_AGGREGATE_VERIFY_ELEMENT_GAS_PRICE: u64 = 22500;
+    pub const BLS_G1_ADD_SUB_GAS_PRICE: u64 = 3900;
+    pub const BLS_G1_NEG_GAS_PRICE: u64 = 750;
+    pub const BLS_G1_MUL_GAS_PRICE: u64 = 5200;
+    pub const BLS_MAP_TO_G1_GAS_PRICE: u64 = 2350;
+    pub const BLS_G1_IN_GROUP_GAS_PRICE: u64 = 2950;
+    pub const BLS_G2_ADD_SUB_GAS_PRICE: u64 = 6100;
+    pub const BLS_G2_NEG_GAS_PRICE: u64 = 1550;
+    pub const BLS_G2_MUL_GAS_PRICE: u64 = 10550;
+    pub const BLS_MAP_TO_G2_GAS_PRICE: u64 = 7950;
+    pub const BLS_G2_IN_GROUP_GAS_PRICE: u64 = 4250;
+    pub const BLS_G1_MULTIEXP_BASE_GAS_PRICE: u64 = 11375;
+    pub const BLS_G1_MULTIEXP_COEF1_GAS_PRICE: u64 = 630;
+    pub const BLS_G1_MULTIEXP_COEF2_GAS_PRICE: u64 = 8820;
+    pub const BLS_G2_MULTIEXP_BASE_GAS_PRICE: u64 = 30388;
+    pub const BLS_G2_MULTIEXP_COEF1_GAS_PRICE: u64 = 1280;
+    pub const BLS_G2_MULTIEXP_COEF2_GAS_PRICE: u64 = 22840;
+    pub const BLS_PAIRING_BASE_GAS_PRICE: u64 = 20000;
+    pub const BLS_PAIRING_ELEMENT_GAS_PRICE: u64 = 11800;
     pub const IMPLICIT_JMPREF_GAS_PRICE: u64 = 10;
     pub const IMPLICIT_RET_GAS_PRICE: u64 = 5;
     pub const EXCEPTION_GAS_PRICE: u64 = 50;
@@ -347,6 +513,7 @@ impl<'l> GasConsumer<'l> {
             gas_limit: std::cell::Cell::new(truncate_gas(params.limit)),
             gas_credit: std::cell::Cell::new(truncate_gas(params.credit)),
             gas_base: std::cell::Cell::new(gas_remaining),
+            gas_consumed_on_accept: std::cell::Cell::new(None),
             gas_remaining: std::cell::Cell::new(gas_remaining as i64),
             gas_price: NonZeroU64::new(params.price).unwrap_or(NonZeroU64::MIN),
             loaded_cells: Default::default(),
@@ -355,6 +522,47 @@ impl<'l> GasConsumer<'l> {
             free_gas_consumed: std::cell::Cell::new(0),
             get_extra_balance_counter: std::cell::Cell::new(0),
             missing_library: std::cell::Cell::new(None),
+            missing_libraries_as_empty: false,
+            pruned_branch: std::cell::Cell::new(None),
+        }
+    }
+
+    /// Restores the gas consumer from the saved state.
+    pub fn from_snapshot(snapshot: GasSnapshot, libraries: &'l dyn LibraryProvider) -> Self {
+        Self {
+            gas_max: snapshot.max,
+            gas_limit: std::cell::Cell::new(snapshot.limit),
+            gas_credit: std::cell::Cell::new(snapshot.credit),
+            gas_base: std::cell::Cell::new(snapshot.base),
+            gas_consumed_on_accept: std::cell::Cell::new(snapshot.consumed_on_accept),
+            gas_remaining: std::cell::Cell::new(snapshot.remaining),
+            gas_price: NonZeroU64::new(snapshot.price).unwrap_or(NonZeroU64::MIN),
+            loaded_cells: std::cell::UnsafeCell::new(snapshot.loaded_cells),
+            libraries,
+            chksign_counter: std::cell::Cell::new(snapshot.chksign_counter),
+            free_gas_consumed: std::cell::Cell::new(snapshot.free_gas_consumed),
+            get_extra_balance_counter: std::cell::Cell::new(snapshot.get_extra_balance_counter),
+            missing_library: std::cell::Cell::new(None),
+            missing_libraries_as_empty: false,
+            pruned_branch: std::cell::Cell::new(None),
+        }
+    }
+
+    /// Returns the current state of the gas consumer.
+    pub fn snapshot(&self) -> GasSnapshot {
+        GasSnapshot {
+            max: self.gas_max,
+            limit: self.gas_limit.get(),
+            credit: self.gas_credit.get(),
+            base: self.gas_base.get(),
+            consumed_on_accept: self.gas_consumed_on_accept.get(),
+            remaining: self.gas_remaining.get(),
+            price: self.gas_price.get(),
+            // SAFETY: `loaded_cells` is only borrowed as mut during the cell loading.
+            loaded_cells: unsafe { (*self.loaded_cells.get()).clone() },
+            chksign_counter: self.chksign_counter.get(),
+            free_gas_consumed: self.free_gas_consumed.get(),
+            get_extra_balance_counter: self.get_extra_balance_counter.get(),
         }
     }
 
@@ -390,12 +598,10 @@ impl<'l> GasConsumer<'l> {
                 credit: 0,
                 price: self.price(),
             };
-            let libraries = self.libraries;
+            let mut child = Self::with_libraries(params, self.libraries);
+            child.missing_libraries_as_empty = self.missing_libraries_as_empty;
 
-            ParentGasConsumer::Isolated(std::mem::replace(
-                self,
-                Self::with_libraries(params, libraries),
-            ))
+            ParentGasConsumer::Isolated(std::mem::replace(self, child))
         } else {
             // NOTE: Compute remaining gas only when all operations
             //       with parent consumer are made.
@@ -412,6 +618,7 @@ impl<'l> GasConsumer<'l> {
                 gas_limit: std::mem::replace(&mut self.gas_limit, Cell::new(gas_limit)),
                 gas_credit: std::mem::replace(&mut self.gas_credit, Cell::new(0)),
                 gas_base: std::mem::replace(&mut self.gas_base, Cell::new(gas_limit)),
+                gas_consumed_on_accept: self.gas_consumed_on_accept.clone(),
                 gas_remaining: std::mem::replace(
                     &mut self.gas_remaining,
                     Cell::new(gas_limit as i64),
@@ -423,6 +630,8 @@ impl<'l> GasConsumer<'l> {
                 free_gas_consumed: self.free_gas_consumed.clone(),
                 get_extra_balance_counter: self.get_extra_balance_counter.clone(),
                 missing_library: self.missing_library.clone(),
+                missing_libraries_as_empty: self.missing_libraries_as_empty,
+                pruned_branch: self.pruned_branch.clone(),
             })
         })
     }
@@ -447,6 +656,13 @@ impl<'l> GasConsumer<'l> {
                     *parent_lib = *missing_lib;
                 }
 
+                // Merge pruned branch.
+                let pruned = self.pruned_branch.get_mut();
+                let parent_pruned = parent.pruned_branch.get_mut();
+                if parent_pruned.is_none() && pruned.is_some() {
+                    *parent_pruned = *pruned;
+                }
+
                 // Merge free gas counters.
                 parent.chksign_counter = self.chksign_counter.clone();
                 parent.free_gas_consumed = self.free_gas_consumed.clone();
@@ -471,6 +687,12 @@ impl<'l> GasConsumer<'l> {
         (self.gas_base.get() as i64).saturating_sub(self.gas_remaining.get()) as u64
     }
 
+    /// Returns the amount of gas consumed before the non-zero credit was reset
+    /// (e.g. by `ACCEPT`).
+    pub fn consumed_on_accept(&self) -> Option<u64> {
+        self.gas_consumed_on_accept.get()
+    }
+
     pub fn remaining(&self) -> i64 {
         self.gas_remaining.get()
     }
@@ -487,6 +709,9 @@ impl<'l> GasConsumer<'l> {
         let limit = std::cmp::min(limit, self.gas_max);
         vm_log_trace!("changing gas limit: new_limit={limit}");
 
+        if self.gas_credit.get() != 0 {
+            self.gas_consumed_on_accept.set(Some(self.consumed()));
+        }
         self.gas_credit.set(0);
         self.gas_limit.set(limit);
         self.set_base(limit);
@@ -581,11 +806,54 @@ impl<'l> GasConsumer<'l> {
         self.missing_library.set(Some(*hash));
     }
 
+    /// Whether missing libraries are resolved as empty cells.
+    pub fn missing_libraries_as_empty(&self) -> bool {
+        self.missing_libraries_as_empty
+    }
+
+    /// Resolve missing libraries as empty cells instead of failing.
+    ///
+    /// The hash of the missing library is still recorded.
+    pub fn set_missing_libraries_as_empty(&mut self, value: bool) {
+        self.missing_libraries_as_empty = value;
+    }
+
+    /// Original hash of the first pruned branch which was loaded.
+    ///
+    /// Pruned branches are only present in states reconstructed from
+    /// Merkle proofs, so the execution result is not reliable in this case.
+    pub fn pruned_branch(&self) -> Option<HashBytes> {
+        self.pruned_branch.get()
+    }
+
+    pub fn set_pruned_branch(&self, hash: &HashBytes) {
+        if self.pruned_branch.get().is_none() {
+            self.pruned_branch.set(Some(*hash));
+        }
+    }
+
     pub fn load_cell_as_slice(&self, cell: Cell, mode: LoadMode) -> Result<OwnedCellSlice, Error> {
         let cell = ok!(self.load_cell_impl(cell, mode));
         Ok(OwnedCellSlice::new_allow_exotic(cell))
     }
 
+    fn find_library<'a, T: LoadLibrary<'a>>(
+        &'a self,
+        library_hash: &HashBytes,
+    ) -> Result<T, Error> {
+        match ok!(T::load_library(self, library_hash)) {
+            Some(cell) => Ok(cell),
+            None => {
+                self.missing_library.set(Some(*library_hash));
+                if self.missing_libraries_as_empty {
+                    Ok(T::empty())
+                } else {
+                    Err(Error::CellUnderflow)
+                }
+            }
+        }
+    }
+
     fn load_cell_impl<'s: 'a, 'a, T: LoadLibrary<'a>>(
         &'s self,
         mut cell: T,
@@ -622,14 +890,13 @@ impl<'l> GasConsumer<'l> {
                         .as_slice_allow_exotic()
                         .get_raw(8, &mut library_hash.0, 256));
 
-                    let Some(library_cell) = ok!(T::load_library(self, &library_hash)) else {
-                        self.missing_library.set(Some(library_hash));
-                        return Err(Error::CellUnderflow);
-                    };
-
-                    cell = library_cell;
+                    cell = ok!(self.find_library::<T>(&library_hash));
                     library_loaded = true;
                 }
+                CellType::PrunedBranch => {
+                    self.set_pruned_branch(cell.as_ref().hash(0));
+                    return Err(Error::CellUnderflow);
+                }
                 _ => return Err(Error::CellUnderflow),
             }
         }
@@ -718,14 +985,13 @@ impl LimitedGasConsumer<'_> {
                         .as_slice_allow_exotic()
                         .get_raw(8, &mut library_hash.0, 256));
 
-                    let Some(library_cell) = ok!(T::load_library(self.gas, &library_hash)) else {
-                        self.gas.missing_library.set(Some(library_hash));
-                        return Err(Error::CellUnderflow);
-                    };
-
-                    cell = library_cell;
+                    cell = ok!(self.gas.find_library::<T>(&library_hash));
                     library_loaded = true;
                 }
+                CellType::PrunedBranch => {
+                    self.gas.set_pruned_branch(cell.as_ref().hash(0));
+                    return Err(Error::CellUnderflow);
+                }
                 _ => return Err(Error::CellUnderflow),
             }
         }
@@ -754,6 +1020,10 @@ trait LoadLibrary<'a>: AsRef<DynCell> + 'a {
     fn load_library(gas: &'a GasConsumer, library_hash: &HashBytes) -> Result<Option<Self>, Error>
     where
         Self: Sized;
+
+    fn empty() -> Self
+    where
+        Self: Sized;
 }
 
 impl<'a> LoadLibrary<'a> for &'a DynCell {
@@ -763,6 +1033,13 @@ impl<'a> LoadLibrary<'a> for &'a DynCell {
     {
         gas.libraries.find_ref(library_hash)
     }
+
+    fn empty() -> Self
+    where
+        Self: Sized,
+    {
+        Cell::empty_cell_ref()
+    }
 }
 
 impl LoadLibrary<'_> for Cell {
@@ -772,6 +1049,30 @@ impl LoadLibrary<'_> for Cell {
     {
         gas.libraries.find(library_hash)
     }
+
+    fn empty() -> Self
+    where
+        Self: Sized,
+    {
+        Cell::empty_cell()
+    }
+}
+
+/// Saved state of the [`GasConsumer`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct GasSnapshot {
+    pub max: u64,
+    pub limit: u64,
+    pub credit: u64,
+    pub base: u64,
+    pub consumed_on_accept: Option<u64>,
+    pub remaining: i64,
+    pub price: u64,
+    /// A set of visited cells (affects the price of the next loads).
+    pub loaded_cells: HashSet<HashBytes>,
+    pub chksign_counter: usize,
+    pub free_gas_consumed: u64,
+    pub get_extra_balance_counter: usize,
 }
 
 /// Params to replace the current gas consumer.
@@ -879,4 +1180,49 @@ mod tests {
             libraries.find_ref(lib2.repr_hash()).unwrap().unwrap()
         );
     }
+
+    #[test]
+    fn chained_and_cached_libraries() {
+        let lib1 = Boc::decode(tvmasm!("NOP")).unwrap();
+        let lib2 = Boc::decode(tvmasm!("NOP NOP")).unwrap();
+
+        let make_libs = |lib: &Cell| {
+            let mut libs = Dict::<HashBytes, SimpleLib>::new();
+            libs.set(lib.repr_hash(), SimpleLib {
+                public: true,
+                root: lib.clone(),
+            })
+            .unwrap();
+            libs
+        };
+        let libs1 = make_libs(&lib1);
+        let libs2 = make_libs(&lib2);
+
+        let chained = ChainedLibraries::new()
+            .with(NoLibraries)
+            .with(&libs1)
+            .with(libs2);
+        assert_eq!(chained.len(), 3);
+
+        let libraries = CachedLibraries::new(chained);
+        assert_eq!(libraries.stats(), LibraryCacheStats::default());
+
+        for _ in 0..2 {
+            assert_eq!(
+                libraries.find(lib1.repr_hash()).unwrap().unwrap().as_ref(),
+                lib1.as_ref()
+            );
+            assert_eq!(
+                libraries.find_ref(lib2.repr_hash()).unwrap().unwrap(),
+                lib2.as_ref()
+            );
+            assert!(libraries.find_ref(&HashBytes::ZERO).unwrap().is_none());
+        }
+        assert_eq!(libraries.stats(), LibraryCacheStats { hits: 3, misses: 3 });
+
+        let mut libraries = libraries;
+        libraries.clear();
+        assert!(libraries.find(lib1.repr_hash()).unwrap().is_some());
+        assert_eq!(libraries.stats(), LibraryCacheStats { hits: 0, misses: 1 });
+    }
 }
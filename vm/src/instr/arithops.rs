@@ -13,11 +13,12 @@ use crate::error::VmResult;
 #[cfg(feature = "dump")]
 use crate::error::{DumpError, DumpResult};
 use crate::saferc::SafeRc;
+use crate::stack::StackGuard;
 use crate::state::VmState;
 
 pub struct ArithOps;
 
-#[vm_module]
+#[vm_module(category = "arith")]
 impl ArithOps {
     // === Int constants ===
 
@@ -48,7 +49,7 @@ impl ArithOps {
         let int = cs.load_bigint(value_len, true)?;
         st.code.set_range(cs.range());
 
-        vm_log_op!("PUSHINT {int}");
+        vm_log_op!(@ARITH "PUSHINT {int}");
 
         ok!(SafeRc::make_mut(&mut st.stack).push_int(int));
         Ok(0)
@@ -75,7 +76,7 @@ impl ArithOps {
 
     #[op(code = "83xx @ ..83ff", fmt = "PUSHPOW2 {x}", args(x = (args & 0xff) + 1))]
     pub fn exec_push_pow2(st: &mut VmState, x: u32) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         ok!(stack.push_int(BigInt::from(1) << x));
         Ok(0)
     }
@@ -88,7 +89,7 @@ impl ArithOps {
 
     #[op(code = "84xx", fmt = "PUSHPOW2DEC {x}", args(x = (args & 0xff) + 1))]
     fn exec_push_pow2dec(st: &mut VmState, x: u32) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let mut value = BigInt::from(1) << x;
         value -= 1;
         ok!(stack.push_int(value));
@@ -105,7 +106,7 @@ impl ArithOps {
     #[op(code = "a0", fmt = "ADD", args(quiet = false))]
     #[op(code = "b7a0", fmt = "QADD", args(quiet = true))]
     fn exec_add(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -122,7 +123,7 @@ impl ArithOps {
     #[op(code = "a1", fmt = "SUB", args(quiet = false))]
     #[op(code = "b7a1", fmt = "QSUB", args(quiet = true))]
     fn exec_sub(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -139,7 +140,7 @@ impl ArithOps {
     #[op(code = "a2", fmt = "SUBR", args(quiet = false))]
     #[op(code = "b7a2", fmt = "QSUBR", args(quiet = true))]
     fn exec_subr(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -156,7 +157,7 @@ impl ArithOps {
     #[op(code = "a3", fmt = "NEGATE", args(quiet = false))]
     #[op(code = "b7a3", fmt = "QNEGATE", args(quiet = true))]
     fn exec_negate(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 {
@@ -174,7 +175,7 @@ impl ArithOps {
     #[op(code = "a4", fmt = "INC", args(quiet = false))]
     #[op(code = "b7a4", fmt = "QINC", args(quiet = true))]
     fn exec_inc(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 *SafeRc::make_mut(&mut x) += 1;
@@ -189,7 +190,7 @@ impl ArithOps {
     #[op(code = "a5", fmt = "DEC", args(quiet = false))]
     #[op(code = "b7a5", fmt = "QDEC", args(quiet = true))]
     fn exec_dec(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 *SafeRc::make_mut(&mut x) -= 1;
@@ -204,7 +205,7 @@ impl ArithOps {
     #[op(code = "a6yy", fmt = "ADDINT {y}", args(y = args as i8, quiet = false))]
     #[op(code = "b7a6yy", fmt = "QADDINT {y}", args(y = args as i8, quiet = true))]
     fn exec_addint(st: &mut VmState, y: i8, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 *SafeRc::make_mut(&mut x) += y;
@@ -219,7 +220,7 @@ impl ArithOps {
     #[op(code = "a7yy", fmt = "MULINT {y}", args(y = args as i8, quiet = false))]
     #[op(code = "b7a7yy", fmt = "QMULINT {y}", args(y = args as i8, quiet = true))]
     fn exec_mulint(st: &mut VmState, y: i8, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 *SafeRc::make_mut(&mut x) *= y;
@@ -234,7 +235,7 @@ impl ArithOps {
     #[op(code = "a8", fmt = "MUL", args(quiet = false))]
     #[op(code = "b7a8", fmt = "QMUL", args(quiet = true))]
     fn exec_mul(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -271,7 +272,7 @@ impl ArithOps {
             _ => vm_bail!(InvalidOpcode),
         };
 
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
 
         let y = ok!(stack.pop_int_or_nan());
         let w = if add {
@@ -323,6 +324,7 @@ impl ArithOps {
     #[op(code = "a92m", fmt = DumpShr(m, false), args(imm = false, quiet = false))]
     #[op(code = "a93mmm", fmt = DumpShr(m, true), args(imm = true, quiet = false))]
     #[op(code = "b7a92m", fmt = ("Q{}", DumpShr(m, false)), args(imm = false, quiet = true))]
+    #[op(code = "b7a93mmm", fmt = ("Q{}", DumpShr(m, true)), args(imm = true, quiet = true))]
     fn exec_shrmod(st: &mut VmState, mut m: u32, imm: bool, quiet: bool) -> VmResult<i32> {
         enum Operation {
             RShift,
@@ -351,7 +353,7 @@ impl ArithOps {
             _ => vm_bail!(InvalidOpcode),
         };
 
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = match y {
             Some(y) => y,
             None => ok!(stack.pop_smallint_range(0, 256)),
@@ -429,7 +431,7 @@ impl ArithOps {
             _ => vm_bail!(InvalidOpcode),
         };
 
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
 
         let z = ok!(stack.pop_int_or_nan());
         let w = if add {
@@ -486,6 +488,7 @@ impl ArithOps {
     #[op(code = "a9am", fmt = ("MUL{}", DumpShr(m, false)), args(imm = false, quiet = false))]
     #[op(code = "a9bmmm", fmt = ("MUL{}", DumpShr(m, true)), args(imm = true, quiet = false))]
     #[op(code = "b7a9am", fmt = ("QMUL{}", DumpShr(m, false)), args(imm = false, quiet = true))]
+    #[op(code = "b7a9bmmm", fmt = ("QMUL{}", DumpShr(m, true)), args(imm = true, quiet = true))]
     fn exec_mulshrmod(st: &mut VmState, mut m: u32, imm: bool, quiet: bool) -> VmResult<i32> {
         enum Operation {
             MulRShift,
@@ -514,7 +517,7 @@ impl ArithOps {
             _ => vm_bail!(InvalidOpcode),
         };
 
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let z = match z {
             Some(z) => z,
             None => ok!(stack.pop_smallint_range(0, 256)),
@@ -578,6 +581,7 @@ impl ArithOps {
     #[op(code = "a9cm", fmt = DumpShl(m, false), args(imm = false, quiet = false))]
     #[op(code = "a9dmmm", fmt = DumpShl(m, true), args(imm = true, quiet = false))]
     #[op(code = "b7a9cm", fmt = ("Q{}", DumpShl(m, false)), args(imm = false, quiet = true))]
+    #[op(code = "b7a9dmmm", fmt = ("Q{}", DumpShl(m, true)), args(imm = true, quiet = true))]
     fn exec_shldivmod(st: &mut VmState, mut m: u32, imm: bool, quiet: bool) -> VmResult<i32> {
         enum Operation {
             Div,
@@ -606,7 +610,7 @@ impl ArithOps {
             _ => vm_bail!(InvalidOpcode),
         };
 
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let z = match z {
             Some(z) => z,
             None => ok!(stack.pop_smallint_range(0, 256)),
@@ -672,7 +676,7 @@ impl ArithOps {
     #[op(code = "b7b609", fmt = "QMAX", args(mn = false, mx = true, q = true))]
     #[op(code = "b7b60a", fmt = "QMINMAX", args(mn = true, mx = true, q = true))]
     fn exec_minmax(st: &mut VmState, mn: bool, mx: bool, q: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let x = ok!(stack.pop_int_or_nan());
         let y = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -703,7 +707,7 @@ impl ArithOps {
     #[op(code = "b60b", fmt = "ABS", args(quiet = false))]
     #[op(code = "b7b60b", fmt = "QABS", args(quiet = true))]
     fn exec_abs(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 if x.sign() == Sign::Minus {
@@ -973,9 +977,13 @@ fn update_or_new_rc(mut rc: SafeRc<BigInt>, value: BigInt) -> SafeRc<BigInt> {
 
 #[cfg(test)]
 mod tests {
+    use everscale_types::prelude::*;
+    use num_traits::ToPrimitive;
     use tracing_test::traced_test;
 
     use super::*;
+    use crate::smc_info::VmVersion;
+    use crate::stack::{RcStackValue, Stack};
 
     #[test]
     #[traced_test]
@@ -1243,6 +1251,134 @@ mod tests {
         assert_run_vm!("ADDDIVMOD", [int 1, nan, int 0] => [int 0], exit_code: 4);
     }
 
+    #[test]
+    #[traced_test]
+    fn op_add_divmod_variants() {
+        assert_run_vm!("QUIET ADDDIVMOD", [int 3, int 2, int 5] => [int 1, int 0]);
+        assert_run_vm!("QUIET ADDDIVMOD", [nan, int 2, int 5] => [nan, nan]);
+        assert_run_vm!("QUIET ADDDIVMOD", [int 3, nan, int 5] => [nan, nan]);
+        assert_run_vm!("QUIET ADDDIVMOD", [int 3, int 2, int 0] => [nan, nan]);
+
+        assert_run_vm!("LSHIFTADDDIVMOD", [int 1, int 1, int 3, int 2] => [int 1, int 2]);
+        assert_run_vm!("LSHIFTADDDIVMOD", [int 5, int 2, int 4, int 1] => [int 3, int 0]);
+        assert_run_vm!("LSHIFTADDDIVMODR", [int 1, int 1, int 3, int 2] => [int 2, int -1]);
+        assert_run_vm!("LSHIFTADDDIVMOD", [int 1, nan, int 3, int 2] => [int 0], exit_code: 4);
+        assert_run_vm!("QUIET LSHIFTADDDIVMOD", [int 1, nan, int 3, int 2] => [nan, nan]);
+
+        // Immediate forms.
+        let int = |x: i32| SafeRc::new_dyn_value(BigInt::from(x));
+
+        // ADDRSHIFT#MOD 2
+        let (exit_code, stack) = run_raw(&[0xa9, 0x30, 0x01], [int(3), int(2)], None);
+        assert_eq!(exit_code, 0);
+        assert_eq!(stack_ints(&stack), [1, 1]);
+
+        // MULADDRSHIFT#MOD 2
+        let (exit_code, stack) = run_raw(&[0xa9, 0xb0, 0x01], [int(1), int(3), int(2)], None);
+        assert_eq!(exit_code, 0);
+        assert_eq!(stack_ints(&stack), [1, 1]);
+
+        // LSHIFT#ADDDIVMOD 2
+        let (exit_code, stack) = run_raw(&[0xa9, 0xd0, 0x01], [int(1), int(1), int(3)], None);
+        assert_eq!(exit_code, 0);
+        assert_eq!(stack_ints(&stack), [1, 2]);
+
+        // Combined opcodes are not available before v4.
+        for code in [&[0xa9, 0x00][..], &[0xa9, 0x80], &[0xa9, 0x20], &[
+            0xa9, 0xc0,
+        ]] {
+            let stack = [int(1), int(1), int(1), int(1)];
+            let (exit_code, _) = run_raw(code, stack, Some(VmVersion::Ton(3)));
+            assert_eq!(exit_code, 6);
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn quiet_ops_nan_matrix() {
+        // Unary
+        assert_run_vm!("QNEGATE", [nan] => [nan]);
+        assert_run_vm!("QINC", [nan] => [nan]);
+        assert_run_vm!("QDEC", [nan] => [nan]);
+        assert_run_vm!("QADDINT 2", [nan] => [nan]);
+        assert_run_vm!("QMULINT 123", [nan] => [nan]);
+        assert_run_vm!("QUIET ABS", [nan] => [nan]);
+
+        // Binary
+        assert_run_vm!("QADD", [int 1, nan] => [nan]);
+        assert_run_vm!("QSUB", [int 1, nan] => [nan]);
+        assert_run_vm!("QSUBR", [nan, int 1] => [nan]);
+        assert_run_vm!("QMUL", [nan, int 1] => [nan]);
+        assert_run_vm!("QUIET MIN", [nan, int 1] => [nan]);
+        assert_run_vm!("QUIET MAX", [int 1, nan] => [nan]);
+        assert_run_vm!("QUIET MINMAX", [nan, int 1] => [nan, nan]);
+
+        // Division
+        assert_run_vm!("QUIET DIV", [nan, int 1] => [nan]);
+        assert_run_vm!("QUIET DIV", [int 1, nan] => [nan]);
+        assert_run_vm!("QUIET DIVMOD", [nan, int 1] => [nan, nan]);
+        assert_run_vm!("QUIET ADDDIVMOD", [int 1, nan, int 1] => [nan, nan]);
+        assert_run_vm!("QUIET MULDIVMOD", [int 1, int 1, nan] => [nan, nan]);
+
+        // Shifts
+        assert_run_vm!("QUIET RSHIFT", [nan, int 1] => [nan]);
+        assert_run_vm!("QUIET RSHIFTMOD", [nan, int 1] => [nan, nan]);
+        assert_run_vm!("QUIET MODPOW2", [nan, int 1] => [nan]);
+        assert_run_vm!("QUIET ADDRSHIFTMOD", [int 1, nan, int 1] => [nan, nan]);
+        assert_run_vm!("QUIET MULRSHIFT", [nan, int 1, int 1] => [nan]);
+        assert_run_vm!("QUIET MULRSHIFTMOD", [int 1, nan, int 1] => [nan, nan]);
+        assert_run_vm!("QUIET MULMODPOW2", [nan, int 1, int 1] => [nan]);
+        assert_run_vm!("QUIET MULADDRSHIFTMOD", [int 1, int 1, nan, int 1] => [nan, nan]);
+        assert_run_vm!("QUIET LSHIFTADDDIVMOD", [nan, int 1, int 1, int 1] => [nan, nan]);
+
+        // Immediate forms
+        for code in [
+            // QRSHIFT# 1
+            &[0xb7, 0xa9, 0x34, 0x00][..],
+            // QMULRSHIFT# 1
+            &[0xb7, 0xa9, 0xb4, 0x00],
+            // QLSHIFT#DIV 1
+            &[0xb7, 0xa9, 0xd4, 0x00],
+        ] {
+            let (exit_code, stack) = run_raw(code, [Stack::make_nan(), Stack::make_nan()], None);
+            assert_eq!(exit_code, 0);
+            assert!(stack.items.last().unwrap().as_int().is_none());
+
+            // Non-quiet variants throw an integer overflow.
+            let (exit_code, _) = run_raw(&code[1..], [Stack::make_nan(), Stack::make_nan()], None);
+            assert_eq!(exit_code, 4);
+        }
+    }
+
+    fn run_raw<const N: usize>(
+        code: &[u8],
+        stack: [RcStackValue; N],
+        version: Option<VmVersion>,
+    ) -> (i32, SafeRc<Stack>) {
+        let code = CellBuilder::from_raw_data(code, code.len() as u16 * 8)
+            .and_then(CellBuilder::build)
+            .unwrap();
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut vm = VmState::builder()
+            .with_code(code)
+            .with_stack(stack)
+            .with_version(version.unwrap_or(VmState::DEFAULT_VERSION))
+            .with_debug(&mut output)
+            .build();
+
+        let exit_code = !vm.run();
+        (exit_code, vm.stack.clone())
+    }
+
+    fn stack_ints(stack: &Stack) -> Vec<i32> {
+        stack
+            .items
+            .iter()
+            .map(|item| item.as_int().and_then(|x| x.to_i32()).unwrap())
+            .collect()
+    }
+
     // TODO: Add more tests
     #[test]
     #[traced_test]
@@ -0,0 +1,889 @@
+use std::sync::OnceLock;
+
+use blst::min_pk::{AggregateSignature, PublicKey, Signature};
+use blst::{
+    blst_fp, blst_fp2, blst_p1, blst_p1_affine, blst_p2, blst_p2_affine, Pairing, BLST_ERROR,
+};
+use everscale_types::cell::CellBuilder;
+use everscale_types::error::Error;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use tycho_vm_proc::vm_module;
+
+use crate::error::VmResult;
+use crate::gas::GasConsumer;
+use crate::saferc::SafeRc;
+use crate::stack::Stack;
+use crate::state::VmState;
+use crate::util::OwnedCellSlice;
+
+pub struct BlsOps;
+
+#[vm_module(category = "crypto")]
+impl BlsOps {
+    #[op(code = "f93000", fmt = "BLS_VERIFY")]
+    fn exec_bls_verify(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 3, StackUnderflow(3));
+        st.gas.try_consume(GasConsumer::BLS_VERIFY_GAS_PRICE)?;
+
+        let sig = ok!(pop_bls_bytes::<G2_SIZE>(stack));
+        let msg = ok!(pop_bls_msg(stack));
+        let pk = ok!(pop_bls_bytes::<G1_SIZE>(stack));
+
+        ok!(stack.push_bool(bls_verify(&pk, &msg, &sig)));
+        Ok(0)
+    }
+
+    #[op(code = "f93001", fmt = "BLS_AGGREGATE")]
+    fn exec_bls_aggregate(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let depth = stack.depth().saturating_sub(1) as u32;
+        let n = ok!(stack.pop_smallint_range(1, depth)) as usize;
+        st.gas.try_consume(
+            (GasConsumer::BLS_AGGREGATE_ELEMENT_GAS_PRICE * n as u64)
+                .saturating_sub(GasConsumer::BLS_AGGREGATE_BASE_GAS_DISCOUNT),
+        )?;
+
+        let mut sigs = vec![[0; G2_SIZE]; n];
+        for sig in sigs.iter_mut().rev() {
+            *sig = ok!(pop_bls_bytes::<G2_SIZE>(stack));
+        }
+
+        let res = ok!(bls_aggregate(&sigs));
+        ok!(push_bls_bytes(stack, &res));
+        Ok(0)
+    }
+
+    #[op(code = "f93002", fmt = "BLS_FASTAGGREGATEVERIFY")]
+    fn exec_bls_fast_aggregate_verify(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 3, StackUnderflow(3));
+        let sig = ok!(pop_bls_bytes::<G2_SIZE>(stack));
+        let msg = ok!(pop_bls_msg(stack));
+        let depth = stack.depth().saturating_sub(1) as u32;
+        let n = ok!(stack.pop_smallint_range(0, depth)) as usize;
+        st.gas.try_consume(
+            GasConsumer::BLS_FAST_AGGREGATE_VERIFY_BASE_GAS_PRICE
+                + GasConsumer::BLS_FAST_AGGREGATE_VERIFY_ELEMENT_GAS_PRICE * n as u64,
+        )?;
+
+        let mut pks = vec![[0; G1_SIZE]; n];
+        for pk in pks.iter_mut().rev() {
+            *pk = ok!(pop_bls_bytes::<G1_SIZE>(stack));
+        }
+
+        ok!(stack.push_bool(bls_fast_aggregate_verify(&pks, &msg, &sig)));
+        Ok(0)
+    }
+
+    #[op(code = "f93003", fmt = "BLS_AGGREGATEVERIFY")]
+    fn exec_bls_aggregate_verify(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 2, StackUnderflow(2));
+        let sig = ok!(pop_bls_bytes::<G2_SIZE>(stack));
+        let depth = stack.depth().saturating_sub(1) as u32;
+        let n = ok!(stack.pop_smallint_range(0, depth / 2)) as usize;
+        st.gas.try_consume(
+            GasConsumer::BLS_AGGREGATE_VERIFY_BASE_GAS_PRICE
+                + GasConsumer::BLS_AGGREGATE_VERIFY_ELEMENT_GAS_PRICE * n as u64,
+        )?;
+
+        let mut items = vec![([0; G1_SIZE], Vec::new()); n];
+        for (pk, msg) in items.iter_mut().rev() {
+            *msg = ok!(pop_bls_msg(stack));
+            *pk = ok!(pop_bls_bytes::<G1_SIZE>(stack));
+        }
+
+        ok!(stack.push_bool(bls_aggregate_verify(&items, &sig)));
+        Ok(0)
+    }
+
+    #[op(code = "f93010", fmt = "BLS_G1_ADD", args(sub = false))]
+    #[op(code = "f93011", fmt = "BLS_G1_SUB", args(sub = true))]
+    fn exec_bls_g1_add(st: &mut VmState, sub: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 2, StackUnderflow(2));
+        st.gas.try_consume(GasConsumer::BLS_G1_ADD_SUB_GAS_PRICE)?;
+
+        let b = ok!(G1::load(&ok!(pop_bls_bytes(stack))));
+        let a = ok!(G1::load(&ok!(pop_bls_bytes(stack))));
+        let b = if sub { b.neg() } else { b };
+
+        ok!(push_bls_bytes(stack, &a.add(&b).compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93012", fmt = "BLS_G1_NEG")]
+    fn exec_bls_g1_neg(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 1, StackUnderflow(1));
+        st.gas.try_consume(GasConsumer::BLS_G1_NEG_GAS_PRICE)?;
+
+        let a = ok!(G1::load(&ok!(pop_bls_bytes(stack))));
+        ok!(push_bls_bytes(stack, &a.neg().compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93013", fmt = "BLS_G1_MUL")]
+    fn exec_bls_g1_mul(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 2, StackUnderflow(2));
+        st.gas.try_consume(GasConsumer::BLS_G1_MUL_GAS_PRICE)?;
+
+        let s = ok!(stack.pop_int());
+        let a = ok!(G1::load(&ok!(pop_bls_bytes(stack))));
+        ok!(push_bls_bytes(stack, &a.mul(&to_scalar(&s)).compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93014", fmt = "BLS_G1_MULTIEXP")]
+    fn exec_bls_g1_multiexp(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let depth = stack.depth().saturating_sub(1) as u32;
+        let n = ok!(stack.pop_smallint_range(0, depth / 2)) as usize;
+        st.gas.try_consume(multiexp_gas(
+            n as u64,
+            GasConsumer::BLS_G1_MULTIEXP_BASE_GAS_PRICE,
+            GasConsumer::BLS_G1_MULTIEXP_COEF1_GAS_PRICE,
+            GasConsumer::BLS_G1_MULTIEXP_COEF2_GAS_PRICE,
+        ))?;
+
+        let mut res = G1::zero();
+        for _ in 0..n {
+            let s = ok!(stack.pop_int());
+            let a = ok!(G1::load(&ok!(pop_bls_bytes(stack))));
+            res = res.add(&a.mul(&to_scalar(&s)));
+        }
+
+        ok!(push_bls_bytes(stack, &res.compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93015", fmt = "BLS_G1_ZERO")]
+    fn exec_bls_g1_zero(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        ok!(push_bls_bytes(stack, &G1::zero().compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93016", fmt = "BLS_MAP_TO_G1")]
+    fn exec_bls_map_to_g1(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 1, StackUnderflow(1));
+        st.gas.try_consume(GasConsumer::BLS_MAP_TO_G1_GAS_PRICE)?;
+
+        let fp = ok!(pop_bls_bytes::<FP_SIZE>(stack));
+        ok!(push_bls_bytes(stack, &G1::map_from_fp(&fp).compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93017", fmt = "BLS_G1_INGROUP")]
+    fn exec_bls_g1_in_group(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 1, StackUnderflow(1));
+        st.gas.try_consume(GasConsumer::BLS_G1_IN_GROUP_GAS_PRICE)?;
+
+        let a = ok!(pop_bls_bytes(stack));
+        ok!(stack.push_bool(G1::in_group(&a)));
+        Ok(0)
+    }
+
+    #[op(code = "f93018", fmt = "BLS_G1_ISZERO")]
+    fn exec_bls_g1_is_zero(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let a = ok!(pop_bls_bytes(stack));
+        ok!(stack.push_bool(G1::is_zero(&a)));
+        Ok(0)
+    }
+
+    #[op(code = "f93020", fmt = "BLS_G2_ADD", args(sub = false))]
+    #[op(code = "f93021", fmt = "BLS_G2_SUB", args(sub = true))]
+    fn exec_bls_g2_add(st: &mut VmState, sub: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 2, StackUnderflow(2));
+        st.gas.try_consume(GasConsumer::BLS_G2_ADD_SUB_GAS_PRICE)?;
+
+        let b = ok!(G2::load(&ok!(pop_bls_bytes(stack))));
+        let a = ok!(G2::load(&ok!(pop_bls_bytes(stack))));
+        let b = if sub { b.neg() } else { b };
+
+        ok!(push_bls_bytes(stack, &a.add(&b).compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93022", fmt = "BLS_G2_NEG")]
+    fn exec_bls_g2_neg(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 1, StackUnderflow(1));
+        st.gas.try_consume(GasConsumer::BLS_G2_NEG_GAS_PRICE)?;
+
+        let a = ok!(G2::load(&ok!(pop_bls_bytes(stack))));
+        ok!(push_bls_bytes(stack, &a.neg().compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93023", fmt = "BLS_G2_MUL")]
+    fn exec_bls_g2_mul(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 2, StackUnderflow(2));
+        st.gas.try_consume(GasConsumer::BLS_G2_MUL_GAS_PRICE)?;
+
+        let s = ok!(stack.pop_int());
+        let a = ok!(G2::load(&ok!(pop_bls_bytes(stack))));
+        ok!(push_bls_bytes(stack, &a.mul(&to_scalar(&s)).compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93024", fmt = "BLS_G2_MULTIEXP")]
+    fn exec_bls_g2_multiexp(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let depth = stack.depth().saturating_sub(1) as u32;
+        let n = ok!(stack.pop_smallint_range(0, depth / 2)) as usize;
+        st.gas.try_consume(multiexp_gas(
+            n as u64,
+            GasConsumer::BLS_G2_MULTIEXP_BASE_GAS_PRICE,
+            GasConsumer::BLS_G2_MULTIEXP_COEF1_GAS_PRICE,
+            GasConsumer::BLS_G2_MULTIEXP_COEF2_GAS_PRICE,
+        ))?;
+
+        let mut res = G2::zero();
+        for _ in 0..n {
+            let s = ok!(stack.pop_int());
+            let a = ok!(G2::load(&ok!(pop_bls_bytes(stack))));
+            res = res.add(&a.mul(&to_scalar(&s)));
+        }
+
+        ok!(push_bls_bytes(stack, &res.compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93025", fmt = "BLS_G2_ZERO")]
+    fn exec_bls_g2_zero(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        ok!(push_bls_bytes(stack, &G2::zero().compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93026", fmt = "BLS_MAP_TO_G2")]
+    fn exec_bls_map_to_g2(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 1, StackUnderflow(1));
+        st.gas.try_consume(GasConsumer::BLS_MAP_TO_G2_GAS_PRICE)?;
+
+        let fp2 = ok!(pop_bls_bytes::<{ FP_SIZE * 2 }>(stack));
+        ok!(push_bls_bytes(stack, &G2::map_from_fp2(&fp2).compress()));
+        Ok(0)
+    }
+
+    #[op(code = "f93027", fmt = "BLS_G2_INGROUP")]
+    fn exec_bls_g2_in_group(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        vm_ensure!(stack.depth() >= 1, StackUnderflow(1));
+        st.gas.try_consume(GasConsumer::BLS_G2_IN_GROUP_GAS_PRICE)?;
+
+        let a = ok!(pop_bls_bytes(stack));
+        ok!(stack.push_bool(G2::in_group(&a)));
+        Ok(0)
+    }
+
+    #[op(code = "f93028", fmt = "BLS_G2_ISZERO")]
+    fn exec_bls_g2_is_zero(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let a = ok!(pop_bls_bytes(stack));
+        ok!(stack.push_bool(G2::is_zero(&a)));
+        Ok(0)
+    }
+
+    #[op(code = "f93030", fmt = "BLS_PAIRING")]
+    fn exec_bls_pairing(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let depth = stack.depth().saturating_sub(1) as u32;
+        let n = ok!(stack.pop_smallint_range(0, depth / 2)) as usize;
+        st.gas.try_consume(
+            GasConsumer::BLS_PAIRING_BASE_GAS_PRICE
+                + GasConsumer::BLS_PAIRING_ELEMENT_GAS_PRICE * n as u64,
+        )?;
+
+        let mut pairs = vec![([0; G1_SIZE], [0; G2_SIZE]); n];
+        for (p1, p2) in pairs.iter_mut().rev() {
+            *p2 = ok!(pop_bls_bytes(stack));
+            *p1 = ok!(pop_bls_bytes(stack));
+        }
+
+        let res = ok!(bls_pairing(&pairs));
+        ok!(stack.push_bool(res));
+        Ok(0)
+    }
+
+    #[op(code = "f93031", fmt = "BLS_PUSHR")]
+    fn exec_bls_push_r(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        ok!(stack.push_int(bls_r().clone()));
+        Ok(0)
+    }
+}
+
+const FP_SIZE: usize = 48;
+const G1_SIZE: usize = 48;
+const G2_SIZE: usize = 96;
+
+/// Domain separation tag of the proof-of-possession ciphersuite.
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Order of the G1 and G2 subgroups.
+fn bls_r() -> &'static BigInt {
+    static R: OnceLock<BigInt> = OnceLock::new();
+    R.get_or_init(|| {
+        BigInt::parse_bytes(
+            b"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+            16,
+        )
+        .unwrap()
+    })
+}
+
+/// Reduces the integer modulo `r` (little-endian).
+fn to_scalar(s: &BigInt) -> [u8; 32] {
+    let s = s.mod_floor(bls_r());
+    let bytes = s.magnitude().to_bytes_le();
+    let mut res = [0; 32];
+    res[..bytes.len()].copy_from_slice(&bytes);
+    res
+}
+
+/// Multi-scalar multiplication gas: `base + n * coef1 + n * coef2 / max(4, log2(n))`.
+fn multiexp_gas(n: u64, base: u64, coef1: u64, coef2: u64) -> u64 {
+    let mut l = 4;
+    while (1u64 << (l + 1)) <= n {
+        l += 1;
+    }
+    base + n * coef1 + n * coef2 / l
+}
+
+fn pop_bls_bytes<const N: usize>(stack: &mut Stack) -> VmResult<[u8; N]> {
+    let cs = ok!(stack.pop_cs());
+    let mut res = [0; N];
+    cs.apply().load_raw(&mut res, (N * 8) as u16)?;
+    Ok(res)
+}
+
+fn pop_bls_msg(stack: &mut Stack) -> VmResult<Vec<u8>> {
+    let cs = ok!(stack.pop_cs());
+    let mut cs = cs.apply();
+
+    let bits = cs.size_bits();
+    vm_ensure!(bits % 8 == 0, CellError(Error::CellUnderflow));
+
+    let mut data = [0; 128];
+    Ok(cs.load_raw(&mut data, bits)?.to_vec())
+}
+
+fn push_bls_bytes(stack: &mut Stack, bytes: &[u8]) -> VmResult<()> {
+    let cell = CellBuilder::from_raw_data(bytes, (bytes.len() * 8) as u16)?.build()?;
+    stack.push(OwnedCellSlice::new_allow_exotic(cell))
+}
+
+fn blst_error(e: BLST_ERROR) -> Box<crate::error::VmError> {
+    Box::new(crate::error::VmError::Unknown(format!("blst error {e:?}")))
+}
+
+fn bls_verify(pk: &[u8; G1_SIZE], msg: &[u8], sig: &[u8; G2_SIZE]) -> bool {
+    let (Ok(pk), Ok(sig)) = (PublicKey::from_bytes(pk), Signature::from_bytes(sig)) else {
+        return false;
+    };
+    sig.verify(true, msg, DST, &[], &pk, true) == BLST_ERROR::BLST_SUCCESS
+}
+
+fn bls_aggregate(sigs: &[[u8; G2_SIZE]]) -> VmResult<[u8; G2_SIZE]> {
+    let sigs = sigs
+        .iter()
+        .map(|sig| Signature::from_bytes(sig))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(blst_error)?;
+    let sigs = sigs.iter().collect::<Vec<_>>();
+
+    let res = AggregateSignature::aggregate(&sigs, true).map_err(blst_error)?;
+    Ok(res.to_signature().compress())
+}
+
+fn bls_fast_aggregate_verify(pks: &[[u8; G1_SIZE]], msg: &[u8], sig: &[u8; G2_SIZE]) -> bool {
+    if pks.is_empty() {
+        return false;
+    }
+
+    let mut keys = Vec::with_capacity(pks.len());
+    for pk in pks {
+        match PublicKey::from_bytes(pk) {
+            Ok(pk) if pk.validate().is_ok() => keys.push(pk),
+            _ => return false,
+        }
+    }
+    let Ok(sig) = Signature::from_bytes(sig) else {
+        return false;
+    };
+
+    let keys = keys.iter().collect::<Vec<_>>();
+    sig.fast_aggregate_verify(true, msg, DST, &keys) == BLST_ERROR::BLST_SUCCESS
+}
+
+fn bls_aggregate_verify(items: &[([u8; G1_SIZE], Vec<u8>)], sig: &[u8; G2_SIZE]) -> bool {
+    if items.is_empty() {
+        return false;
+    }
+
+    let mut keys = Vec::with_capacity(items.len());
+    for (pk, _) in items {
+        match PublicKey::from_bytes(pk) {
+            Ok(pk) => keys.push(pk),
+            Err(_) => return false,
+        }
+    }
+    let Ok(sig) = Signature::from_bytes(sig) else {
+        return false;
+    };
+
+    let keys = keys.iter().collect::<Vec<_>>();
+    let msgs = items
+        .iter()
+        .map(|(_, msg)| msg.as_slice())
+        .collect::<Vec<_>>();
+    sig.aggregate_verify(true, &msgs, DST, &keys, true) == BLST_ERROR::BLST_SUCCESS
+}
+
+fn bls_pairing(pairs: &[([u8; G1_SIZE], [u8; G2_SIZE])]) -> VmResult<bool> {
+    let mut pairing = Pairing::new(true, DST);
+    for (p1, p2) in pairs {
+        let p1 = G1::load_affine(p1).map_err(blst_error)?;
+        let p2 = G2::load_affine(p2).map_err(blst_error)?;
+        pairing.raw_aggregate(&p2, &p1);
+    }
+    pairing.commit();
+    Ok(pairing.finalverify(None))
+}
+
+macro_rules! define_bls_group {
+    (
+        $ident:ident($point:ident, $affine:ident, $size:ident) {
+            uncompress: $uncompress:ident,
+            from_affine: $from_affine:ident,
+            add: $add:ident,
+            cneg: $cneg:ident,
+            mult: $mult:ident,
+            compress: $compress:ident,
+            in_group: $in_group:ident,
+            is_inf: $is_inf:ident,
+        }
+    ) => {
+        #[derive(Default, Clone, Copy)]
+        struct $ident($point);
+
+        impl $ident {
+            fn zero() -> Self {
+                Self::default()
+            }
+
+            /// Deserializes a point in the compressed form.
+            ///
+            /// NOTE: Only checks that the point is on the curve.
+            fn load_affine(bytes: &[u8; $size]) -> Result<$affine, BLST_ERROR> {
+                let mut res = $affine::default();
+                // SAFETY: `bytes` has the size of a compressed point.
+                match unsafe { blst::$uncompress(&mut res, bytes.as_ptr()) } {
+                    BLST_ERROR::BLST_SUCCESS => Ok(res),
+                    e => Err(e),
+                }
+            }
+
+            fn load(bytes: &[u8; $size]) -> VmResult<Self> {
+                let affine = Self::load_affine(bytes).map_err(blst_error)?;
+                let mut res = $point::default();
+                // SAFETY: Both pointers are valid.
+                unsafe { blst::$from_affine(&mut res, &affine) };
+                Ok(Self(res))
+            }
+
+            fn in_group(bytes: &[u8; $size]) -> bool {
+                match Self::load_affine(bytes) {
+                    // SAFETY: The pointer is valid.
+                    Ok(affine) => unsafe { blst::$in_group(&affine) },
+                    Err(_) => false,
+                }
+            }
+
+            fn is_zero(bytes: &[u8; $size]) -> bool {
+                match Self::load_affine(bytes) {
+                    // SAFETY: The pointer is valid.
+                    Ok(affine) => unsafe { blst::$is_inf(&affine) },
+                    Err(_) => false,
+                }
+            }
+
+            fn add(&self, other: &Self) -> Self {
+                let mut res = $point::default();
+                // SAFETY: All pointers are valid.
+                unsafe { blst::$add(&mut res, &self.0, &other.0) };
+                Self(res)
+            }
+
+            fn neg(mut self) -> Self {
+                // SAFETY: The pointer is valid.
+                unsafe { blst::$cneg(&mut self.0, true) };
+                self
+            }
+
+            fn mul(&self, scalar: &[u8; 32]) -> Self {
+                let mut res = $point::default();
+                // SAFETY: All pointers are valid, `r` fits into 255 bits.
+                unsafe { blst::$mult(&mut res, &self.0, scalar.as_ptr(), 255) };
+                Self(res)
+            }
+
+            fn compress(&self) -> [u8; $size] {
+                let mut res = [0; $size];
+                // SAFETY: `res` has the size of a compressed point.
+                unsafe { blst::$compress(res.as_mut_ptr(), &self.0) };
+                res
+            }
+        }
+    };
+}
+
+define_bls_group! {
+    G1(blst_p1, blst_p1_affine, G1_SIZE) {
+        uncompress: blst_p1_uncompress,
+        from_affine: blst_p1_from_affine,
+        add: blst_p1_add_or_double,
+        cneg: blst_p1_cneg,
+        mult: blst_p1_mult,
+        compress: blst_p1_compress,
+        in_group: blst_p1_affine_in_g1,
+        is_inf: blst_p1_affine_is_inf,
+    }
+}
+
+define_bls_group! {
+    G2(blst_p2, blst_p2_affine, G2_SIZE) {
+        uncompress: blst_p2_uncompress,
+        from_affine: blst_p2_from_affine,
+        add: blst_p2_add_or_double,
+        cneg: blst_p2_cneg,
+        mult: blst_p2_mult,
+        compress: blst_p2_compress,
+        in_group: blst_p2_affine_in_g2,
+        is_inf: blst_p2_affine_is_inf,
+    }
+}
+
+impl G1 {
+    /// Maps a field element (big-endian) to a curve point.
+    fn map_from_fp(bytes: &[u8; FP_SIZE]) -> Self {
+        let mut fp = blst_fp::default();
+        let mut res = blst_p1::default();
+        // SAFETY: All pointers are valid, `v` is allowed to be null.
+        unsafe {
+            blst::blst_fp_from_bendian(&mut fp, bytes.as_ptr());
+            blst::blst_map_to_g1(&mut res, &fp, std::ptr::null());
+        }
+        Self(res)
+    }
+}
+
+impl G2 {
+    /// Maps an extension field element (two big-endian parts) to a curve point.
+    fn map_from_fp2(bytes: &[u8; FP_SIZE * 2]) -> Self {
+        let mut fp2 = blst_fp2::default();
+        let mut res = blst_p2::default();
+        // SAFETY: All pointers are valid, `v` is allowed to be null.
+        unsafe {
+            blst::blst_fp_from_bendian(&mut fp2.fp[0], bytes.as_ptr());
+            blst::blst_fp_from_bendian(&mut fp2.fp[1], bytes[FP_SIZE..].as_ptr());
+            blst::blst_map_to_g2(&mut res, &fp2, std::ptr::null());
+        }
+        Self(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blst::min_pk::{AggregatePublicKey, SecretKey};
+    use everscale_types::cell::CellBuilder;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::stack::RcStackValue;
+
+    #[test]
+    #[traced_test]
+    fn bls_signatures() {
+        let sk1 = SecretKey::key_gen(&[0x11; 32], &[]).unwrap();
+        let sk2 = SecretKey::key_gen(&[0x22; 32], &[]).unwrap();
+        let pk1 = sk1.sk_to_pk().compress();
+        let pk2 = sk2.sk_to_pk().compress();
+
+        let msg = b"hello world";
+        let other_msg = b"hello there";
+        let sig1 = sk1.sign(msg, DST, &[]).compress();
+        let sig2 = sk2.sign(msg, DST, &[]).compress();
+        let other_sig2 = sk2.sign(other_msg, DST, &[]).compress();
+
+        assert_run_vm!(
+            "BLS_VERIFY",
+            [raw build_slice(pk1), raw build_slice(msg), raw build_slice(sig1)] => [int -1]
+        );
+        assert_run_vm!(
+            "BLS_VERIFY",
+            [raw build_slice(pk2), raw build_slice(msg), raw build_slice(sig1)] => [int 0]
+        );
+        assert_run_vm!(
+            "BLS_VERIFY",
+            [raw build_slice(pk1), raw build_slice(msg), raw build_slice(&sig1[..64])] => [int 0],
+            exit_code: 9
+        );
+
+        // Aggregated signatures.
+        let sig12 = AggregateSignature::aggregate(
+            &[
+                &Signature::from_bytes(&sig1).unwrap(),
+                &Signature::from_bytes(&sig2).unwrap(),
+            ],
+            true,
+        )
+        .unwrap()
+        .to_signature()
+        .compress();
+
+        assert_run_vm!(
+            "INT 2 BLS_AGGREGATE",
+            [raw build_slice(sig1), raw build_slice(sig2)] => [raw build_slice(sig12)]
+        );
+        assert_run_vm!(
+            "INT 0 BLS_AGGREGATE",
+            [raw build_slice(sig1)] => [int 0],
+            exit_code: 5
+        );
+
+        assert_run_vm!(
+            "INT 2 ROT ROT BLS_FASTAGGREGATEVERIFY",
+            [
+                raw build_slice(pk1),
+                raw build_slice(pk2),
+                raw build_slice(msg),
+                raw build_slice(sig12),
+            ] => [int -1]
+        );
+        assert_run_vm!(
+            "INT 1 ROT ROT BLS_FASTAGGREGATEVERIFY",
+            [raw build_slice(pk1), raw build_slice(msg), raw build_slice(sig12)] => [int 0]
+        );
+        assert_run_vm!(
+            "INT 0 ROT ROT BLS_FASTAGGREGATEVERIFY",
+            [raw build_slice(msg), raw build_slice(sig12)] => [int 0]
+        );
+
+        let sig12_other = AggregateSignature::aggregate(
+            &[
+                &Signature::from_bytes(&sig1).unwrap(),
+                &Signature::from_bytes(&other_sig2).unwrap(),
+            ],
+            true,
+        )
+        .unwrap()
+        .to_signature()
+        .compress();
+
+        assert_run_vm!(
+            "INT 2 SWAP BLS_AGGREGATEVERIFY",
+            [
+                raw build_slice(pk1),
+                raw build_slice(msg),
+                raw build_slice(pk2),
+                raw build_slice(other_msg),
+                raw build_slice(sig12_other),
+            ] => [int -1]
+        );
+        assert_run_vm!(
+            "INT 2 SWAP BLS_AGGREGATEVERIFY",
+            [
+                raw build_slice(pk1),
+                raw build_slice(msg),
+                raw build_slice(pk2),
+                raw build_slice(msg),
+                raw build_slice(sig12_other),
+            ] => [int 0]
+        );
+
+        // Public keys arithmetic.
+        let pk12 = AggregatePublicKey::aggregate(
+            &[
+                &PublicKey::from_bytes(&pk1).unwrap(),
+                &PublicKey::from_bytes(&pk2).unwrap(),
+            ],
+            true,
+        )
+        .unwrap()
+        .to_public_key()
+        .compress();
+
+        assert_run_vm!(
+            "BLS_G1_ADD",
+            [raw build_slice(pk1), raw build_slice(pk2)] => [raw build_slice(pk12)]
+        );
+        assert_run_vm!(
+            "BLS_G1_SUB",
+            [raw build_slice(pk12), raw build_slice(pk2)] => [raw build_slice(pk1)]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn bls_group_ops() {
+        let sk = SecretKey::key_gen(&[0x33; 32], &[]).unwrap();
+        let p1 = sk.sk_to_pk().compress();
+        let p2 = sk.sign(b"msg", DST, &[]).compress();
+        let g1_zero = G1::zero().compress();
+        let g2_zero = G2::zero().compress();
+
+        // G1
+        assert_run_vm!("BLS_G1_ZERO", [] => [raw build_slice(g1_zero)]);
+        assert_run_vm!("BLS_G1_ZERO BLS_G1_ISZERO", [] => [int -1]);
+        assert_run_vm!("BLS_G1_ISZERO", [raw build_slice(p1)] => [int 0]);
+        assert_run_vm!("BLS_G1_INGROUP", [raw build_slice(p1)] => [int -1]);
+        assert_run_vm!("BLS_G1_INGROUP", [raw build_slice([0x9f; 48])] => [int 0]);
+        assert_run_vm!(
+            "DUP BLS_G1_NEG BLS_G1_ADD",
+            [raw build_slice(p1)] => [raw build_slice(g1_zero)]
+        );
+        assert_run_vm!(
+            "DUP BLS_G1_SUB",
+            [raw build_slice(p1)] => [raw build_slice(g1_zero)]
+        );
+        assert_run_vm!(
+            "DUP DUP BLS_G1_ADD SWAP INT 2 BLS_G1_MUL SDEQ",
+            [raw build_slice(p1)] => [int -1]
+        );
+        assert_run_vm!(
+            "BLS_PUSHR BLS_G1_MUL BLS_G1_ISZERO",
+            [raw build_slice(p1)] => [int -1]
+        );
+        assert_run_vm!(
+            "DUP INT -1 BLS_G1_MUL SWAP BLS_G1_NEG SDEQ",
+            [raw build_slice(p1)] => [int -1]
+        );
+        assert_run_vm!(
+            r#"
+            DUP DUP INT 3 ROT INT 4 INT 2 BLS_G1_MULTIEXP
+            SWAP INT 7 BLS_G1_MUL
+            SDEQ
+            "#,
+            [raw build_slice(p1)] => [int -1]
+        );
+        assert_run_vm!("INT 0 BLS_G1_MULTIEXP", [] => [raw build_slice(g1_zero)]);
+        assert_run_vm!(
+            "BLS_MAP_TO_G1 BLS_G1_INGROUP",
+            [raw build_slice([0x12; 48])] => [int -1]
+        );
+        assert_run_vm!(
+            "BLS_G1_NEG",
+            [raw build_slice([0x9f; 48])] => [int 0],
+            exit_code: 11
+        );
+
+        // G2
+        assert_run_vm!("BLS_G2_ZERO", [] => [raw build_slice(g2_zero)]);
+        assert_run_vm!("BLS_G2_ZERO BLS_G2_ISZERO", [] => [int -1]);
+        assert_run_vm!("BLS_G2_INGROUP", [raw build_slice(p2)] => [int -1]);
+        assert_run_vm!(
+            "DUP BLS_G2_NEG BLS_G2_ADD",
+            [raw build_slice(p2)] => [raw build_slice(g2_zero)]
+        );
+        assert_run_vm!(
+            "DUP DUP BLS_G2_ADD SWAP INT 2 BLS_G2_MUL SDEQ",
+            [raw build_slice(p2)] => [int -1]
+        );
+        assert_run_vm!(
+            r#"
+            DUP DUP INT 3 ROT INT 4 INT 2 BLS_G2_MULTIEXP
+            SWAP INT 7 BLS_G2_MUL
+            SDEQ
+            "#,
+            [raw build_slice(p2)] => [int -1]
+        );
+        assert_run_vm!(
+            "BLS_MAP_TO_G2 BLS_G2_INGROUP",
+            [raw build_slice([0x12; 96])] => [int -1]
+        );
+
+        // Pairing: e(p1, p2) * e(-p1, p2) == 1
+        assert_run_vm!(
+            "OVER BLS_G1_NEG OVER INT 2 BLS_PAIRING",
+            [raw build_slice(p1), raw build_slice(p2)] => [int -1]
+        );
+        assert_run_vm!(
+            "INT 1 BLS_PAIRING",
+            [raw build_slice(p1), raw build_slice(p2)] => [int 0]
+        );
+        assert_run_vm!("INT 0 BLS_PAIRING", [] => [int -1]);
+
+        assert_run_vm!("BLS_PUSHR", [] => [int bls_r().clone()]);
+    }
+
+    #[test]
+    fn bls_multiexp_gas() {
+        assert_eq!(multiexp_gas(0, 100, 10, 40), 100);
+        assert_eq!(multiexp_gas(1, 100, 10, 40), 100 + 10 + 10);
+        assert_eq!(multiexp_gas(32, 100, 10, 40), 100 + 320 + 32 * 40 / 5);
+        assert_eq!(multiexp_gas(63, 100, 10, 40), 100 + 630 + 63 * 40 / 5);
+        assert_eq!(multiexp_gas(64, 100, 10, 40), 100 + 640 + 64 * 40 / 6);
+    }
+
+    fn build_slice<T: AsRef<[u8]>>(data: T) -> RcStackValue {
+        let data = data.as_ref();
+        let b = CellBuilder::from_raw_data(data, data.len() as u16 * 8).unwrap();
+        SafeRc::new_dyn_value(OwnedCellSlice::new_allow_exotic(b.build().unwrap()))
+    }
+}
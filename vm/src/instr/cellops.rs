@@ -22,7 +22,7 @@ use crate::util::{remove_trailing, OwnedCellSlice};
 
 pub struct CellOps;
 
-#[vm_module]
+#[vm_module(category = "cell")]
 impl CellOps {
     // === Const ops ===
 
@@ -149,7 +149,7 @@ impl CellOps {
         code_range.skip_first(data_bits, refs).ok();
 
         let code = OwnedCellSlice::from((slice_range, st.code.cell().clone()));
-        vm_log_op!("PUSHCONT {}", code);
+        vm_log_op!(@CELL "PUSHCONT {}", code);
 
         let cont = SafeRc::new(OrdCont::simple(code, st.cp.id()));
         ok!(SafeRc::make_mut(&mut st.stack).push_raw(cont));
@@ -193,7 +193,7 @@ impl CellOps {
         code_range.skip_first(data_bits, 0).ok();
 
         let code = OwnedCellSlice::from((slice_range, st.code.cell().clone()));
-        vm_log_op!("PUSHCONT {}", code);
+        vm_log_op!(@CELL "PUSHCONT {}", code);
 
         let cont = SafeRc::new(OrdCont::simple(code, st.cp.id()));
         ok!(SafeRc::make_mut(&mut st.stack).push_raw(cont));
@@ -503,7 +503,7 @@ impl CellOps {
         vm_ensure!(code_range.has_remaining(bits, refs), InvalidOpcode);
         code_range.skip_first(bits, 0).ok();
 
-        vm_log_op!("STREF{refs}CONST");
+        vm_log_op!(@CELL "STREF{refs}CONST");
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let mut builder = ok!(stack.pop_builder());
@@ -762,7 +762,7 @@ impl CellOps {
         let mut slice = slice_range.apply(st.code.cell())?;
         remove_trailing(&mut slice)?;
 
-        vm_log_op!("STSLICECONST {}", slice.display_as_stack_value());
+        vm_log_op!(@CELL "STSLICECONST {}", slice.display_as_stack_value());
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let mut builder = ok!(stack.pop_builder());
@@ -1041,7 +1041,7 @@ impl CellOps {
         remove_trailing(&mut slice)?;
 
         vm_log_op!(
-            "SDBEGINS{} {}",
+            @CELL "SDBEGINS{} {}",
             if quiet { "Q" } else { "" },
             slice.display_as_stack_value()
         );
@@ -1217,6 +1217,9 @@ impl CellOps {
 
             // Only library cells can be loaded.
             if !descr.is_library() {
+                if descr.is_pruned_branch() {
+                    st.gas.set_pruned_branch(cell.hash(0));
+                }
                 return handle_error(stack, Error::CellUnderflow);
             }
 
@@ -1232,7 +1235,10 @@ impl CellOps {
                 Ok(Some(lib)) => SafeRc::new(lib),
                 Ok(None) => {
                     st.gas.set_missing_library(&library_hash);
-                    return handle_error(stack, Error::CellUnderflow);
+                    if !st.gas.missing_libraries_as_empty() {
+                        return handle_error(stack, Error::CellUnderflow);
+                    }
+                    SafeRc::new(Cell::empty_cell())
                 }
                 Err(e) => return handle_error(stack, e),
             }
@@ -1427,6 +1433,8 @@ impl CellOps {
 
     #[op(code = "d766", fmt = "CLEVEL")]
     fn exec_cell_level(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(6..));
+
         let stack = SafeRc::make_mut(&mut st.stack);
         let cell = ok!(stack.pop_cell());
         let level = cell.descriptor().level_mask().level();
@@ -1436,6 +1444,8 @@ impl CellOps {
 
     #[op(code = "d767", fmt = "CLEVELMASK")]
     fn exec_cell_level_mask(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(6..));
+
         let stack = SafeRc::make_mut(&mut st.stack);
         let cell = ok!(stack.pop_cell());
         let mask = cell.descriptor().level_mask().to_byte();
@@ -1446,12 +1456,15 @@ impl CellOps {
     #[op(code = "d76$10xx", fmt = "CHASHI {x}", args(op = LevelOp::Hash))]
     #[op(code = "d76$11xx", fmt = "CDEPTHI {x}", args(op = LevelOp::Depth))]
     fn exec_cell_level_op(st: &mut VmState, x: u32, op: LevelOp) -> VmResult<i32> {
+        ok!(st.version.require_ton(6..));
         exec_cell_level_op_common(SafeRc::make_mut(&mut st.stack), x as _, op)
     }
 
     #[op(code = "d770", fmt = "CHASHIX", args(op = LevelOp::Hash))]
     #[op(code = "d771", fmt = "CDEPTHIX", args(op = LevelOp::Depth))]
     fn exec_cell_level_op_var(st: &mut VmState, op: LevelOp) -> VmResult<i32> {
+        ok!(st.version.require_ton(6..));
+
         let stack = SafeRc::make_mut(&mut st.stack);
         let x = ok!(stack.pop_smallint_range(0, 3));
         exec_cell_level_op_common(stack, x as _, op)
@@ -1502,7 +1515,7 @@ fn exec_push_ref_common(
     let ok = st.code.range_mut().skip_first(0, 1).is_ok();
     debug_assert!(ok);
 
-    vm_log_op!("{name} ({})", cell.repr_hash());
+    vm_log_op!(@CELL "{name} ({})", cell.repr_hash());
 
     let stack = SafeRc::make_mut(&mut st.stack);
     ok!(match mode {
@@ -1541,7 +1554,7 @@ fn exec_push_slice_common(st: &mut VmState, bits: u16, data_bits: u16, refs: u8)
     }
 
     let slice = OwnedCellSlice::from((slice_range, st.code.cell().clone()));
-    vm_log_op!("PUSHSLICE {}", slice);
+    vm_log_op!(@CELL "PUSHSLICE {}", slice);
 
     ok!(SafeRc::make_mut(&mut st.stack).push(slice));
     Ok(0)
@@ -2080,6 +2093,7 @@ mod tests {
     use tracing_test::traced_test;
 
     use super::*;
+    use crate::stack::RcStackValue;
 
     #[test]
     #[traced_test]
@@ -2655,6 +2669,88 @@ mod tests {
         assert_run_vm!("SDBEGINSXQ", [slice slice.clone(), slice prefix.clone()] => [slice slice.clone(), int 0]);
     }
 
+    #[test]
+    #[traced_test]
+    fn begins_const_tests() {
+        let slice = make_uint_cell_slice(0b10101011, 8);
+        let prefix = make_uint_cell_slice(0b1010, 4);
+        let result = skip_common(&slice, &prefix);
+
+        // SDBEGINS x{A} / SDBEGINSQ x{A}
+        assert_run_vm!("@inline x{d7280d40}", [slice slice.clone()] => [slice result.clone()]);
+        assert_run_vm!("@inline x{d72c0d40}", [slice slice.clone()] => [slice result, int -1]);
+
+        let slice = make_uint_cell_slice(0b1011, 4);
+        assert_run_vm!("@inline x{d7280d40}", [slice slice.clone()] => [int 0], exit_code: 9);
+        assert_run_vm!("@inline x{d72c0d40}", [slice slice.clone()] => [slice slice, int 0]);
+
+        // Truncated data
+        assert_run_vm!("@inline x{d7280d}", [slice prefix] => [int 0], exit_code: 6);
+    }
+
+    #[test]
+    #[traced_test]
+    fn prefix_suffix_tests() {
+        let full = make_uint_cell_slice(0b10101011, 8);
+        let head = make_uint_cell_slice(0b1010, 4);
+        let tail = make_uint_cell_slice(0b1011, 4);
+        let head_with_refs = make_cell_slice_with_refs(0b1010, 4, 2);
+
+        assert_run_vm!("SDEQ", [slice head.clone(), slice head.clone()] => [int -1]);
+        assert_run_vm!("SDEQ", [slice head.clone(), slice head_with_refs.clone()] => [int -1]);
+        assert_run_vm!("SDEQ", [slice head.clone(), slice tail.clone()] => [int 0]);
+        assert_run_vm!("SDEQ", [slice head.clone(), slice full.clone()] => [int 0]);
+
+        assert_run_vm!("SDPFX", [slice head.clone(), slice full.clone()] => [int -1]);
+        assert_run_vm!("SDPFX", [slice full.clone(), slice head.clone()] => [int 0]);
+        assert_run_vm!("SDPFX", [slice head.clone(), slice head_with_refs.clone()] => [int -1]);
+        assert_run_vm!("SDPFXREV", [slice full.clone(), slice head.clone()] => [int -1]);
+        assert_run_vm!("SDPFXREV", [slice tail.clone(), slice full.clone()] => [int 0]);
+        assert_run_vm!("SDPPFX", [slice head.clone(), slice full.clone()] => [int -1]);
+        assert_run_vm!("SDPPFX", [slice head.clone(), slice head.clone()] => [int 0]);
+        assert_run_vm!("SDPPFXREV", [slice full.clone(), slice head.clone()] => [int -1]);
+        assert_run_vm!("SDPPFXREV", [slice head.clone(), slice head.clone()] => [int 0]);
+
+        assert_run_vm!("SDSFX", [slice tail.clone(), slice full.clone()] => [int -1]);
+        assert_run_vm!("SDSFX", [slice head.clone(), slice full.clone()] => [int 0]);
+        assert_run_vm!("SDSFXREV", [slice full.clone(), slice tail.clone()] => [int -1]);
+        assert_run_vm!("SDSFXREV", [slice tail.clone(), slice full.clone()] => [int 0]);
+        assert_run_vm!("SDPSFX", [slice tail.clone(), slice full.clone()] => [int -1]);
+        assert_run_vm!("SDPSFX", [slice tail.clone(), slice tail.clone()] => [int 0]);
+        assert_run_vm!("SDPSFXREV", [slice full.clone(), slice tail.clone()] => [int -1]);
+        assert_run_vm!("SDPSFXREV", [slice tail.clone(), slice tail] => [int 0]);
+
+        assert_run_vm!("SDEQ", [slice head] => [int 0], exit_code: 2);
+        assert_run_vm!("SDPFX", [slice full, int 1] => [int 0], exit_code: 7);
+    }
+
+    #[test]
+    #[traced_test]
+    fn slice_unary_tests() {
+        let empty = make_uint_cell_slice(0, 0);
+        let only_refs = make_cell_slice_with_refs(0, 0, 1);
+        let slice = make_uint_cell_slice(0b00101100, 8);
+        let ones = make_uint_cell_slice(0b1110, 4);
+
+        assert_run_vm!("SEMPTY", [slice empty.clone()] => [int -1]);
+        assert_run_vm!("SEMPTY", [slice only_refs.clone()] => [int 0]);
+        assert_run_vm!("SDEMPTY", [slice only_refs.clone()] => [int -1]);
+        assert_run_vm!("SDEMPTY", [slice slice.clone()] => [int 0]);
+        assert_run_vm!("SREMPTY", [slice slice.clone()] => [int -1]);
+        assert_run_vm!("SREMPTY", [slice only_refs] => [int 0]);
+        assert_run_vm!("SDFIRST", [slice slice.clone()] => [int 0]);
+        assert_run_vm!("SDFIRST", [slice ones.clone()] => [int -1]);
+        assert_run_vm!("SDFIRST", [slice empty.clone()] => [int 0]);
+
+        assert_run_vm!("SDCNTLEAD0", [slice slice.clone()] => [int 2]);
+        assert_run_vm!("SDCNTLEAD1", [slice slice.clone()] => [int 0]);
+        assert_run_vm!("SDCNTTRAIL0", [slice slice.clone()] => [int 2]);
+        assert_run_vm!("SDCNTTRAIL1", [slice slice] => [int 0]);
+        assert_run_vm!("SDCNTLEAD1", [slice ones.clone()] => [int 3]);
+        assert_run_vm!("SDCNTTRAIL0", [slice ones] => [int 1]);
+        assert_run_vm!("SDCNTLEAD0", [slice empty] => [int 0]);
+    }
+
     #[test]
     #[traced_test]
     fn load_library_cell() {
@@ -2695,8 +2791,90 @@ mod tests {
             .with_debug(&mut output)
             .build();
 
-        let result = state.run();
-        println!("execution result {:?}", !result);
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.items.len(), 2);
+        assert_eq!(state.stack.items[0].as_int().unwrap(), &BigInt::from(10));
+        assert_eq!(state.stack.items[1].as_int().unwrap(), &BigInt::from(11));
+        assert!(state.gas.missing_library().is_none());
+    }
+
+    #[test]
+    #[traced_test]
+    fn load_missing_library_cell() -> anyhow::Result<()> {
+        let library_code = Boc::decode(tvmasm!("NOP"))?;
+        let library = {
+            let mut b = CellBuilder::new();
+            b.set_exotic(true);
+            b.store_u8(CellType::LibraryReference.to_byte())?;
+            b.store_u256(library_code.repr_hash())?;
+            b.build()?
+        };
+
+        let cases: [(&[u8], i32); 2] = [(tvmasm!("XLOAD"), 9), (tvmasm!("XLOADQ"), 0)];
+        for (code, expected_exit_code) in cases {
+            let code = Boc::decode(code)?;
+
+            let mut output = crate::tests::TracingOutput::default();
+            let mut state = VmState::builder()
+                .with_version(crate::VmVersion::LATEST_TON)
+                .with_code(code)
+                .with_stack([SafeRc::new_dyn_value(library.clone())])
+                .with_debug(&mut output)
+                .build();
+
+            assert_eq!(!state.run(), expected_exit_code);
+            assert_eq!(state.gas.missing_library(), Some(*library_code.repr_hash()));
+        }
+
+        // Exotic cells are returned as is before v5.
+        let code = Boc::decode(tvmasm!("XLOADQ"))?;
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_version(crate::VmVersion::Ton(4))
+            .with_code(code)
+            .with_stack([SafeRc::new_dyn_value(library.clone())])
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.items.len(), 2);
+        assert_eq!(state.stack.items[0].as_cell(), Some(&library));
+        assert_eq!(state.stack.get_exit_arg(), Some(-1));
+        assert!(state.gas.missing_library().is_none());
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn load_missing_library_as_empty() -> anyhow::Result<()> {
+        let library_code = Boc::decode(tvmasm!("NOP"))?;
+        let library = {
+            let mut b = CellBuilder::new();
+            b.set_exotic(true);
+            b.store_u8(CellType::LibraryReference.to_byte())?;
+            b.store_u256(library_code.repr_hash())?;
+            b.build()?
+        };
+
+        let code = Boc::decode(tvmasm!("DUP XLOAD SWAP CTOS SBITS"))?;
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_version(crate::VmVersion::LATEST_TON)
+            .with_code(code)
+            .with_stack([SafeRc::new_dyn_value(library.clone())])
+            .with_modifiers(crate::BehaviourModifiers {
+                missing_libraries_as_empty: true,
+                ..Default::default()
+            })
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.items.len(), 2);
+        assert_eq!(state.stack.items[0].as_cell(), Some(&Cell::empty_cell()));
+        assert_eq!(state.stack.items[1].as_int().unwrap(), &BigInt::from(0));
+        assert_eq!(state.gas.missing_library(), Some(*library_code.repr_hash()));
+        Ok(())
     }
 
     #[test]
@@ -2764,6 +2942,91 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[traced_test]
+    fn cell_level_ops() -> anyhow::Result<()> {
+        let child = {
+            let mut b = CellBuilder::new();
+            b.store_u32(0xdeadbeef)?;
+            b.store_reference(Cell::default())?;
+            b.build()?
+        };
+        let pruned =
+            everscale_types::merkle::make_pruned_branch(child.as_ref(), 0, Cell::empty_context())?;
+        let hash =
+            |cell: &Cell, level: u8| BigInt::from_bytes_be(Sign::Plus, cell.hash(level).as_array());
+
+        // Ordinary cell
+        assert_run_vm!("CLEVEL", [cell child.clone()] => [int 0]);
+        assert_run_vm!("CLEVELMASK", [cell child.clone()] => [int 0]);
+        assert_run_vm!("CHASHI 0", [cell child.clone()] => [int hash(&child, 0)]);
+        assert_run_vm!("CHASHI 3", [cell child.clone()] => [int hash(&child, 0)]);
+        assert_run_vm!("CDEPTHI 2", [cell child.clone()] => [int 1]);
+
+        // Pruned branch
+        assert_run_vm!("CLEVEL", [cell pruned.clone()] => [int 1]);
+        assert_run_vm!("CLEVELMASK", [cell pruned.clone()] => [int 1]);
+        assert_run_vm!("CHASHI 0", [cell pruned.clone()] => [int hash(&child, 0)]);
+        assert_run_vm!("CHASHI 1", [cell pruned.clone()] => [int hash(&pruned, 1)]);
+        assert_run_vm!("CDEPTHI 0", [cell pruned.clone()] => [int 1]);
+        assert_run_vm!("CDEPTHI 1", [cell pruned.clone()] => [int 0]);
+        assert_run_vm!("CHASHIX", [cell pruned.clone(), int 0] => [int hash(&child, 0)]);
+        assert_run_vm!("CDEPTHIX", [cell pruned.clone(), int 1] => [int 0]);
+        assert_run_vm!("CHASHIX", [cell pruned.clone(), int 4] => [int 0], exit_code: 5);
+        assert_run_vm!("CLEVEL", [null] => [int 0], exit_code: 7);
+
+        // Gas and version checks
+        let cases: [(&[u8], [RcStackValue; 2]); 6] = [
+            (tvmasm!("CLEVEL"), [
+                Stack::make_null(),
+                SafeRc::new_dyn_value(pruned.clone()),
+            ]),
+            (tvmasm!("CLEVELMASK"), [
+                Stack::make_null(),
+                SafeRc::new_dyn_value(pruned.clone()),
+            ]),
+            (tvmasm!("CHASHI 1"), [
+                Stack::make_null(),
+                SafeRc::new_dyn_value(pruned.clone()),
+            ]),
+            (tvmasm!("CDEPTHI 1"), [
+                Stack::make_null(),
+                SafeRc::new_dyn_value(pruned.clone()),
+            ]),
+            (tvmasm!("CHASHIX"), [
+                SafeRc::new_dyn_value(pruned.clone()),
+                SafeRc::new_dyn_value(BigInt::from(1)),
+            ]),
+            (tvmasm!("CDEPTHIX"), [
+                SafeRc::new_dyn_value(pruned.clone()),
+                SafeRc::new_dyn_value(BigInt::from(1)),
+            ]),
+        ];
+        for (code, stack) in cases {
+            let code = Boc::decode(code)?;
+            for (version, exit_code) in [
+                (crate::VmVersion::Ton(5), 6),
+                (crate::VmVersion::LATEST_TON, 0),
+            ] {
+                let mut output = crate::tests::TracingOutput::default();
+                let mut state = VmState::builder()
+                    .with_version(version)
+                    .with_code(code.clone())
+                    .with_stack(stack.clone())
+                    .with_debug(&mut output)
+                    .build();
+
+                assert_eq!(!state.run(), exit_code);
+                if exit_code == 0 {
+                    // 10 + 16 bits of opcode + implicit RET
+                    assert_eq!(state.gas.consumed(), 26 + 5);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn skip_common(slice: &OwnedCellSlice, prefix: &OwnedCellSlice) -> OwnedCellSlice {
         let common = get_common_prefix(slice, prefix);
         let mut slice = slice.clone();
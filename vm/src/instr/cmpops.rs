@@ -5,17 +5,17 @@ use num_traits::ToPrimitive;
 use tycho_vm_proc::vm_module;
 
 use crate::error::VmResult;
-use crate::saferc::SafeRc;
+use crate::stack::StackGuard;
 use crate::state::VmState;
 
 pub struct CmpOps;
 
-#[vm_module]
+#[vm_module(category = "compare")]
 impl CmpOps {
     #[op(code = "b8", fmt = "SGN", args(quiet = false))]
     #[op(code = "b7b8", fmt = "QSGN", args(quiet = true))]
     fn exec_sgn(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let Some(x) = ok!(stack.pop_int_or_nan()) else {
             vm_ensure!(quiet, IntegerOverflow);
             ok!(stack.push_nan());
@@ -44,7 +44,7 @@ impl CmpOps {
     #[op(code = "b7be", fmt = "QGEQ", args(mode = 0x778, quiet = true))]
     #[op(code = "b7bf", fmt = "QCMP", args(mode = 0x987, quiet = true))]
     fn exec_cmp(st: &mut VmState, mode: i32, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
 
@@ -68,7 +68,7 @@ impl CmpOps {
     #[op(code = "b7c2yy", fmt = "QGTINT {y}", args(y = args as i8, mode = 0x788, quiet = true))]
     #[op(code = "b7c3yy", fmt = "QNEQINT {y}", args(y = args as i8, mode = 0x787, quiet = true))]
     fn exec_cmp_int(st: &mut VmState, y: i8, mode: i32, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(x) => {
                 ok!(stack.push_int(check_cmp(as_truncated_i64(&x).cmp(&(y as i64)), mode)));
@@ -82,7 +82,7 @@ impl CmpOps {
 
     #[op(code = "c4", fmt = "ISNAN")]
     fn exec_is_nan(st: &mut VmState) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let x = ok!(stack.pop_int_or_nan());
         ok!(stack.push_bool(x.is_none()));
         Ok(0)
@@ -90,7 +90,7 @@ impl CmpOps {
 
     #[op(code = "c5", fmt = "CHKNAN")]
     fn exec_chk_nan(st: &mut VmState) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let int = ok!(stack.pop_int());
         ok!(stack.push_raw(int));
         Ok(0)
@@ -196,6 +196,27 @@ mod tests {
         assert_run_vm!("INT 1 INT 2 QCMP", [] => [int -1]);
         assert_run_vm!("INT 123 PUSHNAN QCMP", [] => [nan]);
         assert_run_vm!("PUSHNAN INT 123 QCMP", [] => [nan]);
+
+        assert_run_vm!(
+            r#"
+            INT 1 PUSHNAN QLESS
+            INT 1 PUSHNAN QEQUAL
+            INT 1 PUSHNAN QLEQ
+            PUSHNAN INT 1 QGREATER
+            PUSHNAN INT 1 QNEQ
+            PUSHNAN INT 1 QGEQ
+            "#,
+            [] => [nan, nan, nan, nan, nan, nan]
+        );
+        assert_run_vm!(
+            r#"
+            PUSHNAN QEQINT 1
+            PUSHNAN QLESSINT 1
+            PUSHNAN QGTINT 1
+            PUSHNAN QNEQINT 1
+            "#,
+            [] => [nan, nan, nan, nan]
+        );
     }
 
     #[test]
@@ -1,24 +1,25 @@
 use std::fmt::Formatter;
 
-use everscale_types::cell::{CellTreeStats, LoadMode};
+use everscale_types::cell::CellTreeStats;
 use everscale_types::dict;
 use everscale_types::models::{GasLimitsPrices, MsgForwardPrices, StoragePrices};
 use everscale_types::prelude::*;
-use num_bigint::Sign;
+use num_bigint::{BigInt, Sign};
+use num_traits::ToPrimitive;
 use tycho_vm_proc::vm_module;
 
 use crate::cont::ControlRegs;
 use crate::error::VmResult;
 use crate::gas::GasConsumer;
 use crate::saferc::SafeRc;
-use crate::smc_info::{SmcInfoBase, SmcInfoTonV11, SmcInfoTonV4, SmcInfoTonV6};
+use crate::smc_info::{SmcInfoBase, SmcInfoTonV11, SmcInfoTonV4, SmcInfoTonV6, VmVersion};
 use crate::stack::{RcStackValue, Stack, TupleExt};
 use crate::state::VmState;
 use crate::util::{shift_ceil_price, OwnedCellSlice};
 
 pub struct ConfigOps;
 
-#[vm_module]
+#[vm_module(category = "config")]
 impl ConfigOps {
     #[op(code = "f82i", fmt = DisplayConfigOpsArgs(i))]
     fn exec_get_param(st: &mut VmState, i: u32) -> VmResult<i32> {
@@ -44,21 +45,32 @@ impl ConfigOps {
         let stack = SafeRc::make_mut(&mut st.stack);
         let idx = ok!(stack.pop_int());
 
-        ok!(get_and_push_param(
-            &mut st.cr,
-            stack,
-            SmcInfoBase::CONFIG_IDX
-        ));
-        let dict = ok!(stack.pop_cell_opt());
-
-        let mut builder = CellDataBuilder::new();
-        builder.store_bigint(&idx, CONFIG_KEY_BITS, true)?;
-        let key = builder.as_data_slice();
+        let unpacked = if st.modifiers.unpacked_config_params {
+            find_unpacked_config_param(&st.cr, st.version, &idx)
+        } else {
+            None
+        };
 
-        let value = dict::dict_get(dict.as_deref(), CONFIG_KEY_BITS, key, &st.gas)?;
-        let param = match value {
-            Some(mut value) => Some(value.load_reference_cloned()?),
-            None => None,
+        let param = match unpacked {
+            Some(param) => Some(param),
+            None => {
+                ok!(get_and_push_param(
+                    &mut st.cr,
+                    stack,
+                    SmcInfoBase::CONFIG_IDX
+                ));
+                let dict = ok!(stack.pop_cell_opt());
+
+                let mut builder = CellDataBuilder::new();
+                builder.store_bigint(&idx, CONFIG_KEY_BITS, true)?;
+                let key = builder.as_data_slice();
+
+                let value = dict::dict_get(dict.as_deref(), CONFIG_KEY_BITS, key, &st.gas)?;
+                match value {
+                    Some(mut value) => Some(value.load_reference_cloned()?),
+                    None => None,
+                }
+            }
         };
 
         if opt {
@@ -77,8 +89,9 @@ impl ConfigOps {
 
     #[op(code = "f83400", fmt = "PREVMCBLOCKS", args(i = 0))]
     #[op(code = "f83401", fmt = "PREVKEYBLOCK", args(i = 1))]
+    #[op(code = "f83402", fmt = "PREVMCBLOCKS_100", args(i = 2))]
     fn exec_get_prev_blocks_info(st: &mut VmState, i: u32) -> VmResult<i32> {
-        ok!(st.version.require_ton(4..));
+        ok!(st.version.require_ton(if i == 2 { 9.. } else { 4.. }));
         let t1 = ok!(st.cr.get_c7_params());
         let t2 = ok!(t1.try_get_tuple_range(SmcInfoTonV4::PREV_BLOCKS_IDX, 0..=255));
         let param = ok!(t2.try_get((i as usize) & 0b11));
@@ -105,15 +118,18 @@ impl ConfigOps {
             builder.store_u32(19).unwrap(); // ConfigParam 19 contains global id
             let key = builder.as_data_slice();
 
-            let Some(mut value) = dict::dict_get(Some(config_root), CONFIG_KEY_BITS, key, &st.gas)?
+            // NOTE: Config lookup is free and doesn't count as cell loads.
+            let context = Cell::empty_context();
+            let Some(mut value) = dict::dict_get(Some(config_root), CONFIG_KEY_BITS, key, context)?
             else {
                 vm_bail!(Unknown("invalid global id config".to_owned()));
             };
 
             let param = value.load_reference()?;
-            st.gas
-                .load_dyn_cell(param, LoadMode::Full)?
-                .parse::<u32>()? as i32
+            match param.parse::<u32>() {
+                Ok(global_id) => global_id as i32,
+                Err(_) => vm_bail!(Unknown("invalid global id config".to_owned())),
+            }
         };
 
         ok!(SafeRc::make_mut(&mut st.stack).push_int(global_id));
@@ -126,7 +142,7 @@ impl ConfigOps {
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let is_masterchain = ok!(stack.pop_bool());
-        let gas = ok!(stack.pop_long_range(0, u64::MAX));
+        let gas = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
 
         let t2 = ok!(get_parsed_config(&st.cr));
         let cs = ok!(t2.try_get_ref::<OwnedCellSlice>(if is_masterchain { 2 } else { 3 }));
@@ -143,9 +159,9 @@ impl ConfigOps {
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let is_masterchain = ok!(stack.pop_bool());
-        let delta = ok!(stack.pop_long_range(0, u64::MAX));
-        let bits = ok!(stack.pop_long_range(0, u64::MAX));
-        let cells = ok!(stack.pop_long_range(0, u64::MAX));
+        let delta = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
+        let bits = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
+        let cells = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
 
         let t2 = ok!(get_parsed_config(&st.cr));
         match t2.first().and_then(|t| t.as_cell_slice()) {
@@ -169,8 +185,8 @@ impl ConfigOps {
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let is_masterchain = ok!(stack.pop_bool());
-        let bits = ok!(stack.pop_long_range(0, u64::MAX));
-        let cells = ok!(stack.pop_long_range(0, u64::MAX));
+        let bits = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
+        let cells = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
 
         let t2 = ok!(get_parsed_config(&st.cr));
         let cs = ok!(t2.try_get_ref::<OwnedCellSlice>(if is_masterchain { 4 } else { 5 }));
@@ -233,7 +249,7 @@ impl ConfigOps {
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let is_masterchain = ok!(stack.pop_bool());
-        let gas = ok!(stack.pop_long_range(0, u64::MAX));
+        let gas = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
 
         let t2 = ok!(get_parsed_config(&st.cr));
         let cs = ok!(t2.try_get_ref::<OwnedCellSlice>(if is_masterchain { 2 } else { 3 }));
@@ -250,8 +266,8 @@ impl ConfigOps {
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let is_masterchain = ok!(stack.pop_bool());
-        let bits = ok!(stack.pop_long_range(0, u64::MAX));
-        let cells = ok!(stack.pop_long_range(0, u64::MAX));
+        let bits = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
+        let cells = ok!(stack.pop_long_range(0, MAX_FEE_ARG));
 
         let t2 = ok!(get_parsed_config(&st.cr));
         let cs = ok!(t2.try_get_ref::<OwnedCellSlice>(if is_masterchain { 4 } else { 5 }));
@@ -453,17 +469,55 @@ fn get_parsed_config(regs: &ControlRegs) -> VmResult<&[RcStackValue]> {
     ok!(regs.get_c7_params()).try_get_tuple_range(SmcInfoTonV6::PARSED_CONFIG_IDX, 0..=255)
 }
 
+/// Tries to get a config param from the unpacked config tuple (since v6).
+///
+/// Returns `None` if the param must be loaded from the config dict.
+///
+/// NOTE: Unlike the dict lookup, no cell loads are charged here,
+/// so it is only used with [`BehaviourModifiers::unpacked_config_params`].
+///
+/// [`BehaviourModifiers::unpacked_config_params`]: crate::BehaviourModifiers::unpacked_config_params
+fn find_unpacked_config_param(
+    regs: &ControlRegs,
+    version: VmVersion,
+    idx: &BigInt,
+) -> Option<Cell> {
+    if !version.is_ton(6..) {
+        return None;
+    }
+
+    let item_idx = SmcInfoTonV6::unpacked_config_idx(idx.to_u32()?)?;
+    let param = get_parsed_config(regs)
+        .ok()?
+        .get(item_idx)?
+        .as_cell_slice()?;
+
+    // Only the whole param cell can be returned as is.
+    let cell = param.cell();
+    (param.range() == CellSliceRange::full(cell.as_ref())).then(|| cell.clone())
+}
+
 const CONFIG_KEY_BITS: u16 = 32;
 
+/// Fee opcodes accept only non-negative `int64` arguments (as in the reference VM).
+const MAX_FEE_ARG: u64 = i64::MAX as u64;
+
 #[cfg(test)]
 mod test {
-    use everscale_types::models::{CurrencyCollection, ExtraCurrencyCollection, IntAddr};
+    use everscale_types::models::{
+        BlockId, CurrencyCollection, ExtraCurrencyCollection, IntAddr, ShardIdent,
+    };
     use everscale_types::num::{Tokens, VarUint248};
     use everscale_types::prelude::*;
+    use num_bigint::BigInt;
     use tracing_test::traced_test;
     use tycho_vm::smc_info::SmcInfoTonV11;
 
-    use crate::{OwnedCellSlice, RcStackValue, SafeRc, SmcInfoBase, Stack, VmState};
+    use crate::stack::Tuple;
+    use crate::{
+        BehaviourModifiers, CustomSmcInfo, OwnedCellSlice, PrevBlocksInfo, RcStackValue, SafeRc,
+        SmcInfo, SmcInfoBase, SmcInfoBuilder, VmState, VmVersion,
+    };
 
     #[test]
     #[traced_test]
@@ -472,11 +526,11 @@ mod test {
             tuple![int 0, int 1, int 2, int 3, int 4, int 5, int 6, int 7, int 8, int 9],
         );
 
-        let mut t1 = Vec::<RcStackValue>::with_capacity(SmcInfoTonV11::IN_MSG_PARAMS_IDX + 1);
-        t1.resize_with(SmcInfoTonV11::IN_MSG_PARAMS_IDX, Stack::make_null);
-        t1.push(t2.clone());
-
-        let c7 = tuple![raw RcStackValue::from(t1)];
+        let c7 = SmcInfoBuilder::new(VmVersion::LATEST_TON)
+            .with_entry(SmcInfoTonV11::IN_MSG_PARAMS_IDX, t2.clone())
+            .unwrap()
+            .build_c7();
+        let c7 = Tuple::clone(&c7);
 
         assert_run_vm!("INMSGPARAMS", c7: c7.clone(), [] => [raw t2.clone()]);
         assert_run_vm!("GETPARAMLONG 17", c7: c7.clone(), [] => [raw t2]);
@@ -544,11 +598,11 @@ mod test {
                 .into_tuple()
                 .into_dyn_value();
 
-        let mut t1 = Vec::<RcStackValue>::with_capacity(SmcInfoTonV11::IN_MSG_PARAMS_IDX + 1);
-        t1.resize_with(SmcInfoTonV11::IN_MSG_PARAMS_IDX, Stack::make_null);
-        t1.push(t2.clone());
-
-        let c7 = tuple![raw RcStackValue::from(t1)];
+        let c7 = SmcInfoBuilder::new(VmVersion::LATEST_TON)
+            .with_entry(SmcInfoTonV11::IN_MSG_PARAMS_IDX, t2.clone())
+            .unwrap()
+            .build_c7();
+        let c7 = Tuple::clone(&c7);
 
         let expected_src = "0:cfc857bf789e991903c273a68e9d5a10f4f9647d79fb669f9a8ab96a2eb6894b"
             .parse::<IntAddr>()?;
@@ -583,6 +637,154 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    #[traced_test]
+    pub fn fee_args_range() {
+        let too_big = 1u64 << 63;
+
+        assert_run_vm!("GETGASFEE", [int too_big, int 0] => [int 0], exit_code: 5);
+        assert_run_vm!("GETGASFEE", [int -1, int 0] => [int 0], exit_code: 5);
+        assert_run_vm!("GETGASFEESIMPLE", [int too_big, int 0] => [int 0], exit_code: 5);
+
+        assert_run_vm!("GETSTORAGEFEE", [int 0, int 0, int too_big, int 0] => [int 0], exit_code: 5);
+        assert_run_vm!("GETSTORAGEFEE", [int 0, int too_big, int 0, int 0] => [int 0], exit_code: 5);
+        assert_run_vm!("GETSTORAGEFEE", [int too_big, int 0, int 0, int 0] => [int 0], exit_code: 5);
+
+        assert_run_vm!("GETFORWARDFEE", [int 0, int too_big, int 0] => [int 0], exit_code: 5);
+        assert_run_vm!("GETFORWARDFEE", [int too_big, int 0, int 0] => [int 0], exit_code: 5);
+        assert_run_vm!("GETFORWARDFEESIMPLE", [int 0, int too_big, int 0] => [int 0], exit_code: 5);
+    }
+
+    #[test]
+    #[traced_test]
+    pub fn global_id_works() -> anyhow::Result<()> {
+        // Parsed config (since v6).
+        let make_c7 = |global_id: RcStackValue| {
+            let c7 = SmcInfoBuilder::new(VmVersion::Ton(6))
+                .with_unpacked_config(SafeRc::new(tuple![null, raw global_id]))
+                .unwrap()
+                .build_c7();
+            Tuple::clone(&c7)
+        };
+
+        let global_id = CellBuilder::build_from(-239i32)?;
+        let global_id = SafeRc::new_dyn_value(OwnedCellSlice::new_allow_exotic(global_id));
+        assert_run_vm!("GLOBALID", c7: make_c7(global_id), [] => [int -239]);
+
+        let global_id = SafeRc::new_dyn_value(BigInt::from(-239));
+        assert_run_vm!("GLOBALID", c7: make_c7(global_id), [] => [int 0], exit_code: 7);
+
+        // Config dict (before v6).
+        let mut config = Dict::<u32, Cell>::new();
+        config.set(19, CellBuilder::build_from(-239i32)?)?;
+        let smc_info = SmcInfoBuilder::new(VmVersion::Ton(4)).with_entry(
+            SmcInfoBase::CONFIG_IDX,
+            SafeRc::new_dyn_value(config.into_root().unwrap()),
+        )?;
+
+        let code = Boc::decode(tvmasm!("GLOBALID"))?;
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_smc_info(smc_info.build())
+            .with_code(code)
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.get_exit_arg(), Some(-239));
+
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    pub fn config_param_from_unpacked_config() -> anyhow::Result<()> {
+        let gas_prices = CellBuilder::build_from(0xdeadbeefu32)?;
+        let unpacked = tuple![
+            null,
+            null,
+            null,
+            raw SafeRc::new_dyn_value(OwnedCellSlice::new_allow_exotic(gas_prices.clone())),
+            null,
+            null,
+            null,
+        ];
+
+        let c7 = SmcInfoBuilder::new(VmVersion::Ton(6))
+            .with_unpacked_config(SafeRc::new(unpacked))?
+            .build_c7();
+        let c7 = Tuple::clone(&c7);
+
+        assert_run_vm!("UNPACKEDCONFIGTUPLE TLEN", c7: c7.clone(), [] => [int 7]);
+
+        // Config dict is the source of truth (even if it is `null`).
+        assert_run_vm!("CONFIGPARAM", c7: c7.clone(), [int 21] => [int 0]);
+        assert_run_vm!("CONFIGOPTPARAM", c7: c7.clone(), [int 21] => [null]);
+
+        // Unpacked config is used only when explicitly enabled.
+        let code = Boc::decode(tvmasm!("CONFIGOPTPARAM"))?;
+        for (idx, expected) in [(21, Some(gas_prices)), (20, None), (12, None)] {
+            let mut output = crate::tests::TracingOutput::default();
+            let mut state = VmState::builder()
+                .with_smc_info(CustomSmcInfo {
+                    version: VmVersion::Ton(6),
+                    c7: SafeRc::new(c7.clone()),
+                })
+                .with_modifiers(BehaviourModifiers {
+                    unpacked_config_params: true,
+                    ..Default::default()
+                })
+                .with_stack(tuple![int idx])
+                .with_code(code.clone())
+                .with_debug(&mut output)
+                .build();
+
+            assert_eq!(!state.run(), 0);
+            assert_eq!(state.stack.depth(), 1);
+            match expected {
+                Some(cell) => assert_eq!(state.stack.items[0].as_cell(), Some(&cell)),
+                // Missing params fall back to the dict lookup.
+                None => assert!(state.stack.items[0].is_null()),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    pub fn prev_blocks_info_works() {
+        let block_id = |seqno: u32| BlockId {
+            shard: ShardIdent::MASTERCHAIN,
+            seqno,
+            root_hash: HashBytes([seqno as u8; 32]),
+            file_hash: HashBytes::ZERO,
+        };
+
+        let info = PrevBlocksInfo {
+            last_mc_blocks: vec![block_id(10), block_id(9)],
+            prev_key_block: block_id(5),
+            last_mc_blocks_100: Some(vec![block_id(0)]),
+        };
+        let c7 = SmcInfoBase::new()
+            .require_ton_v4()
+            .with_prev_blocks_info(info.as_tuple())
+            .build_c7();
+        let c7 = Tuple::clone(&c7);
+
+        assert_run_vm!("PREVBLOCKSINFOTUPLE TLEN", c7: c7.clone(), [] => [int 3]);
+        assert_run_vm!("PREVMCBLOCKS TLEN", c7: c7.clone(), [] => [int 2]);
+        assert_run_vm!("PREVMCBLOCKS FIRST INDEX 2", c7: c7.clone(), [] => [int 10]);
+        assert_run_vm!("PREVKEYBLOCK INDEX 2", c7: c7.clone(), [] => [int 5]);
+        assert_run_vm!("PREVKEYBLOCK FIRST", c7: c7.clone(), [] => [int -1]);
+        assert_run_vm!("PREVMCBLOCKS_100 FIRST INDEX 2", c7: c7.clone(), [] => [int 0]);
+
+        // No info at all.
+        let c7 = Tuple::clone(&SmcInfoBase::new().require_ton_v4().build_c7());
+        assert_run_vm!("PREVBLOCKSINFOTUPLE", c7: c7.clone(), [] => [null]);
+        assert_run_vm!("PREVMCBLOCKS", c7: c7, [] => [int 0], exit_code: 7);
+    }
+
     #[test]
     #[traced_test]
     pub fn balance_test() -> anyhow::Result<()> {
@@ -7,15 +7,14 @@ use crate::dispatch::DumpOutput;
 use crate::error::VmResult;
 #[cfg(feature = "dump")]
 use crate::error::{DumpError, DumpResult};
-use crate::gas::{GasConsumer, GasConsumerDeriveParams};
 use crate::instr::codepage0;
 use crate::saferc::SafeRc;
 use crate::stack::{Stack, StackValueType};
-use crate::state::{ParentVmState, SaveCr, VmState, EXC_QUIT, QUIT0, QUIT1, QUIT11};
+use crate::state::{SaveCr, VmState};
 
 pub struct ContOps;
 
-#[vm_module]
+#[vm_module(category = "control")]
 impl ContOps {
     // === Jump ops ===
 
@@ -190,17 +189,6 @@ impl ContOps {
         st.ret()
     }
 
-    #[op(code = "db4xxx", fmt = "RUNVM {x}")]
-    fn exec_runvm(st: &mut VmState, x: u32) -> VmResult<i32> {
-        exec_runvm_common(st, RunVmArgs(x))
-    }
-
-    #[op(code = "db50", fmt = "RUNVMX")]
-    fn exec_runvmx(st: &mut VmState) -> VmResult<i32> {
-        let x = ok!(SafeRc::make_mut(&mut st.stack).pop_smallint_range(0, 0xfff));
-        exec_runvm_common(st, RunVmArgs(x))
-    }
-
     // === Conditions and loops ===
 
     #[op(code = "dc", fmt = "IFRET")]
@@ -462,7 +450,7 @@ impl ContOps {
             debug_assert!(ok);
 
             vm_log_op!(
-                "IFREFELSEREF ({}) ({})",
+                @CONTROL "IFREFELSEREF ({}) ({})",
                 cell1.repr_hash(),
                 cell0.repr_hash()
             );
@@ -535,7 +523,7 @@ impl ContOps {
         let negate = (args & 0x20) != 0;
         let bit = args & 0x1f;
         vm_log_op!(
-            "{}BITJMPREF {bit} ({})",
+            @CONTROL "{}BITJMPREF {bit} ({})",
             if negate { "N" } else { "" },
             cell.repr_hash()
         );
@@ -1176,7 +1164,7 @@ fn exec_ref_prefix(st: &mut VmState, bits: u16, name: &str) -> VmResult<RcCont>
     let ok = st.code.range_mut().skip_first(0, 1).is_ok();
     debug_assert!(ok);
 
-    vm_log_op!("{name} ({})", code.repr_hash());
+    vm_log_op!(@CONTROL "{name} ({})", code.repr_hash());
     st.ref_to_cont(code)
 }
 
@@ -1209,7 +1197,7 @@ fn exec_cell_prefix(st: &mut VmState, bits: u16, name: &str) -> VmResult<Cell> {
     let ok = st.code.range_mut().skip_first(0, 1).is_ok();
     debug_assert!(ok);
 
-    vm_log_op!("{name} ({})", cell.repr_hash());
+    vm_log_op!(@CONTROL "{name} ({})", cell.repr_hash());
     Ok(cell)
 }
 
@@ -1231,7 +1219,7 @@ fn exec_ifelse_ref_impl(st: &mut VmState, bits: u16, ref_first: bool) -> VmResul
             false => "IFELSEREF",
         };
 
-        vm_log_op!("{name} ({})", cell.repr_hash());
+        vm_log_op!(@CONTROL "{name} ({})", cell.repr_hash());
 
         let stack = SafeRc::make_mut(&mut st.stack);
         let cont = ok!(stack.pop_cont());
@@ -1419,177 +1407,22 @@ impl std::fmt::Display for ThrowAnyArgs {
     }
 }
 
-#[derive(Clone, Copy)]
-struct RunVmArgs(u32);
-
-impl RunVmArgs {
-    /// +1 = same_c3 (set c3 to code).
-    const fn same_c3(self) -> bool {
-        self.0 & 0b000000001 != 0
-    }
-
-    /// +2 = push_0 (push an implicit 0 before running the code).
-    const fn push_0(self) -> bool {
-        self.0 & 0b000000010 != 0
-    }
-
-    /// +4 = load c4 (persistent data) from stack and return its final value.
-    const fn load_c4(self) -> bool {
-        self.0 & 0b000000100 != 0
-    }
-
-    /// +8 = load gas limit from stack and return consumed gas.
-    const fn load_gas(self) -> bool {
-        self.0 & 0b000001000 != 0
-    }
-
-    /// +16 = load c7 (smart-contract context).
-    const fn load_c7(self) -> bool {
-        self.0 & 0b000010000 != 0
-    }
-
-    /// +32 = return c5 (actions).
-    const fn return_c5(self) -> bool {
-        self.0 & 0b000100000 != 0
-    }
-
-    /// +64 = pop hard gas limit (enabled by ACCEPT) from stack as well.
-    const fn load_hard_gas_limit(self) -> bool {
-        self.0 & 0b001000000 != 0
-    }
-
-    /// +128 = isolated gas consumption (separate set of visited cells, reset chksgn counter).
-    const fn isolate_gas(self) -> bool {
-        self.0 & 0b010000000 != 0
-    }
-
-    /// +256 = pop number N, return exactly N values from stack
-    /// (only if res=0 or 1; if not enough then res=stk_und).
-    const fn ret_n(self) -> bool {
-        self.0 & 0b100000000 != 0
-    }
-}
-
-fn exec_runvm_common(st: &mut VmState, args: RunVmArgs) -> VmResult<i32> {
-    // Check possible args range (all other bits are reserved for future).
-    vm_ensure!(args.0 < 512, IntegerOutOfRange {
-        min: 0,
-        max: 511,
-        actual: args.0.to_string()
-    });
-
-    // Compensate vm creation.
-    st.gas.try_consume(GasConsumer::RUNVM_GAS_PRICE)?;
-
-    // Read args from the stack.
-    let stack = SafeRc::make_mut(&mut st.stack);
-
-    let mut gas_max = if args.load_hard_gas_limit() {
-        ok!(stack.pop_long_range(0, u64::MAX))
-    } else {
-        u64::MAX
-    };
-    let gas_limit = if args.load_gas() {
-        ok!(stack.pop_long_range(0, u64::MAX))
-    } else {
-        u64::MAX
-    };
-
-    if args.load_hard_gas_limit() {
-        gas_max = std::cmp::max(gas_max, gas_limit);
-    } else {
-        gas_max = gas_limit;
-    }
-
-    let child_c7 = if args.load_c7() {
-        ok!(stack.pop_tuple())
-    } else {
-        SafeRc::new(Vec::new())
-    };
-
-    let child_data = if args.load_c4() {
-        SafeRc::unwrap_or_clone(ok!(stack.pop_cell()))
-    } else {
-        Cell::default()
-    };
-
-    let return_values = if args.ret_n() {
-        Some(ok!(stack.pop_smallint_range(0, 1 << 30)))
-    } else {
-        None
-    };
-
-    let child_code = SafeRc::unwrap_or_clone(ok!(stack.pop_cs()));
-    let stack_size = ok!(stack.pop_long_range(0, stack.depth().saturating_sub(1) as u64));
-    let mut child_stack = ok!(stack.split_top(stack_size as usize));
-    st.gas.try_consume_stack_gas(Some(&child_stack))?;
-
-    // Build child VM state.
-
-    let parent_gas = ok!(st.gas.derive(GasConsumerDeriveParams {
-        gas_max,
-        gas_limit,
-        isolate: args.isolate_gas(),
-    }));
-
-    // === ↓↓↓ Must not return any error afterwards ↓↓↓ ===
-
-    let child_quit0 = QUIT0.with(SafeRc::clone);
-    let child_quit1 = QUIT1.with(SafeRc::clone);
-    let child_cp = codepage0();
-    let child_c3 = if args.same_c3() {
-        if args.push_0() {
-            vm_log_trace!("implicit PUSH 0 at start");
-            SafeRc::make_mut(&mut child_stack)
-                .items
-                .push(Stack::make_zero());
-        }
-        SafeRc::from(OrdCont::simple(child_code.clone(), child_cp.id()))
-    } else {
-        QUIT11.with(SafeRc::clone).into_dyn_cont()
-    };
-
-    let child_cr = ControlRegs {
-        c: [
-            Some(child_quit0.clone().into_dyn_cont()),
-            Some(child_quit1.clone().into_dyn_cont()),
-            Some(EXC_QUIT.with(SafeRc::clone).into_dyn_cont()),
-            Some(child_c3),
-        ],
-        d: [Some(child_data), Some(Cell::empty_cell())],
-        c7: Some(child_c7),
-    };
-
-    st.parent = Some(Box::new(ParentVmState {
-        code: std::mem::replace(&mut st.code, child_code),
-        stack: std::mem::replace(&mut st.stack, child_stack),
-        cr: std::mem::replace(&mut st.cr, child_cr),
-        committed_state: std::mem::take(&mut st.committed_state),
-        steps: std::mem::take(&mut st.steps),
-        quit0: std::mem::replace(&mut st.quit0, child_quit0),
-        quit1: std::mem::replace(&mut st.quit1, child_quit1),
-        gas: parent_gas,
-        cp: std::mem::replace(&mut st.cp, child_cp),
-        return_data: args.load_c4(),
-        return_actions: args.return_c5(),
-        return_gas: args.load_gas(),
-        return_values,
-        parent: st.parent.take(),
-    }));
-
-    Ok(0)
-}
-
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
     use everscale_types::boc::Boc;
+    use everscale_types::models::SimpleLib;
     use num_bigint::BigInt;
     use tracing_test::traced_test;
 
     use super::*;
     use crate::cont::QuitCont;
-    use crate::instr::codepage0;
-    use crate::state::IntoCode;
+    use crate::error::VmException;
+    use crate::gas::{GasConsumer, GasParams};
+    use crate::instr::{codepage0, Codepages};
+    use crate::state::{CancellationFlag, IntoCode, VmInterrupt};
     use crate::OwnedCellSlice;
 
     #[test]
@@ -2197,449 +2030,55 @@ mod tests {
 
     #[test]
     #[traced_test]
-    fn runvm_simple() {
-        let child_code = make_code(tvmasm!(
-            r#"
-            ADD
-            DEPTH
-            PUSH c4 CTOS SBITREFS
-            PUSH c5 CTOS SBITREFS
-            PUSH c7
-            PUSHREF x{99991111} POP c4
-            PUSHREF x{aaaabbbb} POP c5
-
-            NIL
-            INT 100
-            TPUSH
-            INT 200
-            TPUSH
-            POP c7
-
-            INT 123
-            "#
-        ));
-
-        assert_run_vm!(
-            r#"
-            PUSHREF x{1234} POP c4
-            PUSHREF x{5678} POP c5
-            NIL
-            INT 5
-            TPUSH
-            INT 6
-            TPUSH
-            POP c7
-
-            RUNVM 0
-
-            PUSH c4 CTOS
-            PUSH c5 CTOS
-            PUSH c7
-            "#,
-            [int 111, int 10, int 20, int 2, slice child_code] => [
-                int 111,
-                int 30,
-                int 1,
-                int 0,
-                int 0,
-                int 0,
-                int 0,
-                [],
-                int 123,
-                int 0,
-                slice make_slice(0x1234_u16),
-                slice make_slice(0x5678_u16),
-                [int 5, int 6],
-            ],
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_exception() {
-        // === Simple exception ===
-
-        let child_code = make_code(tvmasm!(
-            r#"
-            INT 22
-            INT 55 THROWARG 66
-            "#
-        ));
-
-        assert_run_vm!(
-            "RUNVM 0",
-            [int 111, int 10, int 20, int 2, slice child_code] => [
-                int 111,
-                int 55,
-                int 66,
-            ],
-        );
-
-        // === c4, c5 with exception ===
-
-        let child_code = make_code(tvmasm!(
-            r#"
-            PUSHREF x{abcdaaaa} POP c4
-            PUSHREF x{abcdbbbb} POP c5
-            THROW 55
-            "#,
-        ));
-
-        assert_run_vm!(
-            r#"
-            PUSHREF x{1234aaaa} POP c4
-            PUSHREF x{1234bbbb} POP c5
-            RUNVM 36
-            PUSH c4 CTOS
-            PUSH c5 CTOS
-            "#,
-            [int 0, slice child_code, cell make_cell(0x5678_u16)] => [
-                int 0,
-                int 55,
-                null,
-                null,
-                slice make_slice(0x1234aaaa_u32),
-                slice make_slice(0x1234bbbb_u32),
-            ],
-        );
-
-        // === c4, c5 with exception and commit ===
-
-        let child_code = make_code(tvmasm!(
-            r#"
-            PUSHREF x{abcdaaaa} POP c4
-            PUSHREF x{abcdbbbb} POP c5
-            COMMIT
-            PUSHREF x{} POP c4
-            @newcell
-            PUSHREF x{} POP c5
-            THROW 55
-            "#,
-        ));
-
-        assert_run_vm!(
-            r#"
-            PUSHREF x{1234aaaa} POP c4
-            PUSHREF x{1234bbbb} POP c5
-            RUNVM 36
-            CTOS SWAP CTOS SWAP
-            PUSH c4 CTOS
-            PUSH c5 CTOS
-            "#,
-            [int 0, slice child_code, cell make_cell(0x5678_u16)] => [
-                int 0,
-                int 55,
-                slice make_slice(0xabcdaaaa_u32),
-                slice make_slice(0xabcdbbbb_u32),
-                slice make_slice(0x1234aaaa_u32),
-                slice make_slice(0x1234bbbb_u32),
-            ],
-        );
-
-        // === Gas limit of parent VM is too low ===
-
-        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
-        assert_run_vm!(
-            "RUNVM 8 INT 1234",
-            gas: 300,
-            [int 0, slice child_code, int 1000000] => [int 301],
-            exit_code: -14,
-        )
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flags_1_2() {
-        // === flag +1: same c3 ===
-
-        assert_run_vm!(
-            r#"
-            @define(simpleProg) {
-                SETCP 0
-                DICTPUSHCONST 19, [
-                    0 => {
-                        DUP
-                        CALLDICT 22
-                        INC
-                    }
-                    22 => {
-                        MUL
-                    }
-                ]
-                DICTIGETJMPZ
-                THROWARG 11
-            }
-
-            INT 10 INT 0 INT 2 PUSHSLICE @use(simpleProg) RUNVM 1
-            INT 10 INT 0 INT 2 PUSHSLICE @use(simpleProg) RUNVM 0
-            "#,
-            [] => [int 101, int 0, int 22, int 11],
-        );
-
-        // === flag +2(+1): push0 ===
-
-        assert_run_vm!(
-            "INT 10 INT 1 PUSHSLICE {
-                SETCP 0
-                DICTPUSHCONST 19, [
-                    0 => {
-                        DUP
-                        CALLDICT 22
-                        INC
-                    }
-                    22 => {
-                        MUL
-                    }
-                ]
-                DICTIGETJMPZ
-                THROWARG 11
-            } RUNVM 3",
-            [] => [int 101, int 0]
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_4() {
-        // flag +4 - load and return c4
-
-        let child_code = make_code(tvmasm!(
-            r#"
-            PUSHCTR c4 CTOS
-            PUSHREF x{abcd} POPCTR c4
-            INT 1000
-            "#
-        ));
-
+    fn setcont_ctr_many() {
+        // SETCONTCTRMANY 128 (c7)
         assert_run_vm!(
             r#"
-            PUSHREF x{1234} POP c4
-            RUNVM 4
-            CTOS
-            PUSH c4 CTOS
-            "#,
-            [int 0, slice child_code, cell make_cell(0x5678_u16)] => [
-                slice make_slice(0x5678_u16),
-                int 1000,
-                int 0,
-                slice make_slice(0xabcd_u16),
-                slice make_slice(0x1234_u16),
-            ]
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_8() {
-        // flag: +8 - gas limit
-
-        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
-
-        assert_run_vm!(
-            r#"RUNVM 8 INT 1234"#,
-            [int 0, slice child_code, int 200] => [
-                int 215,
-                int -14,
-                int 215,
-                int 1234,
-            ]
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_16() {
-        // flag +16 - load c7
-
-        let child_code = make_code(tvmasm!(
-            r#"
-            PUSH c7
-            NIL
-            INT 111 TPUSH
-            INT 222 TPUSH
-            INT 3333 TPUSH
-            POP c7
-            INT 1000
-            "#
-        ));
-
-        assert_run_vm!(
-            r#"
-            NIL
-            INT 1 TPUSH
-            INT 2 TPUSH
-            INT 3 TPUSH
-            POP c7
-            RUNVM 16
-            PUSH c7
-            "#,
-            [int 0, slice child_code, [int 10, int 15, int 20]] => [
-                [int 10, int 15, int 20],
-                int 1000,
-                int 0,
-                [int 1, int 2, int 3],
-            ],
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_32() {
-        // flag +32 - return c5
-
-        let child_code = make_code(tvmasm!(
-            r#"
-            PUSH c5 CTOS SBITREFS
-            PUSHREF x{5678} POP c5
-            INT 1000
-            "#
-        ));
-
-        assert_run_vm!(
-            r#"
-            PUSHREF x{1234} POP c5
-            RUNVM 32
-            CTOS
-            PUSH c5 CTOS
+            PUSHCONT { PUSHCTR c7 FIRST }
+            @inline x{ede380}
+            INT 5 SINGLE POPCTR c7
+            EXECUTE
+            PUSHCTR c7 FIRST
             "#,
-            [int 0, slice child_code] => [
-                int 0,
-                int 0,
-                int 1000,
-                int 0,
-                slice make_slice(0x5678_u16),
-                slice make_slice(0x1234_u16),
-            ],
+            c7: tuple![int 1],
+            [] => [int 1, int 5],
         );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_64() {
-        // flag +64 - hard gas limit
-
-        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
-        assert_run_vm!(
-            "RUNVM 72 INT 1234",
-            [int 0, slice child_code, int 200, int 500] => [int 215, int -14, int 215, int 1234],
-        );
-
-        let child_code = make_code(tvmasm!("ACCEPT PUSHCONT { NOP } AGAIN"));
-        assert_run_vm!(
-            "RUNVM 72 INT 1234",
-            [int 0, slice child_code, int 200, int 500] => [int 517, int -14, int 517, int 1234],
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_128() {
-        // flag +128 - separate loaded_cells
 
+        // SETCONTCTRMANYX with c7 mask
         assert_run_vm!(
             r#"
-            DUP CTOS DROP
-            INT 2
-            PUSHSLICE { CTOS DROP CTOS DROP }
-            INT 10000
-            RUNVM 8
+            PUSHCONT { PUSHCTR c7 FIRST }
+            INT 128
+            @inline x{ede4}
+            INT 5 SINGLE POPCTR c7
+            EXECUTE
             "#,
-            [cell make_cell(0x12345678_u32), cell make_cell(0x87654321_u32)] => [
-                int 0,
-                int 202,
-            ],
-        );
-    }
-
-    #[test]
-    #[traced_test]
-    fn runvm_flag_256() {
-        // +256 - fixed number of return values
-
-        let child_code = make_code(tvmasm!("INT 1 INT 2 INT 3 INT 4 INT 5"));
-        assert_run_vm!(
-            "RUNVM 256",
-            [int 11, int 22, int 33, int 3, slice child_code.clone(), int 3] => [
-                int 3,
-                int 4,
-                int 5,
-                int 0,
-            ]
-        );
-        assert_run_vm!(
-            "RUNVM 256",
-            [int 11, int 22, int 33, int 3, slice child_code.clone(), int 0] => [int 0]
-        );
-        assert_run_vm!(
-            "RUNVM 256",
-            [int 11, int 22, int 33, int 3, slice child_code, int 20] => [int 0, int -3]
-        );
-
-        let child_code = make_code(tvmasm!("INT 1 INT 2 INT 3 INT 4 INT 5 THROW 77"));
-        assert_run_vm!(
-            "RUNVM 256",
-            [int 11, int 22, int 33, int 3, slice child_code, int 3] => [int 0, int 77]
+            c7: tuple![int 1],
+            [] => [int 1],
         );
-    }
 
-    #[test]
-    // #[traced_test]
-    fn runvm_compute_big() {
+        // Empty mask keeps continuation as is
         assert_run_vm!(
             r#"
-            @define(slice) {
-                DUP EQINT 0
-                PUSHCONT {
-                    DROP DROP
-                    ZERO
-                } IFJMP
-                OVER OVER DEC
-                INT 2
-                PUSH s2
-                RUNVM 0 THROWIF 11
-                ADD NIP
-            }
-            PUSHSLICE @use(slice)
-            INT 10000
-            INT 2
-            PUSHSLICE @use(slice) RUNVM 0
+            PUSHCONT { PUSHCTR c7 FIRST }
+            @inline x{ede300}
+            INT 5 SINGLE POPCTR c7
+            EXECUTE
             "#,
-            gas: 10000000,
-            [] => [int 50005000, int 0],
+            c7: tuple![int 1],
+            [] => [int 5],
         );
 
-        assert_run_vm!(
-            r#"
-            @define(slice) {
-                DUP EQINT 0
-                PUSHCONT {
-                    DROP DROP
-                    ZERO
-                } IFJMP
-                OVER OVER DEC
-                INT 2
-                PUSH s2
-                RUNVM 0 THROWIF 11
-                ADD NIP
-            }
-            PUSHSLICE @use(slice)
-            INT 10000
-            INT 2
-            PUSHSLICE @use(slice) RUNVM 0
-            "#,
-            gas: 100000,
-            [] => [int 100001],
-            exit_code: -14,
-        );
-    }
+        // c6 is not a control register
+        assert_run_vm!("PUSHCONT { } @inline x{ede340}", [] => [int 0], exit_code: 5);
+        assert_run_vm!("PUSHCONT { } INT 64 @inline x{ede4}", [] => [int 0], exit_code: 5);
+        assert_run_vm!("PUSHCONT { } INT 256 @inline x{ede4}", [] => [int 0], exit_code: 5);
 
-    #[test]
-    #[traced_test]
-    fn runvmx() {
-        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
+        // Already defined register
         assert_run_vm!(
-            r#"INT 8 RUNVMX INT 1234"#,
-            [int 0, slice child_code, int 200] => [int 215, int -14, int 215, int 1234],
+            "PUSHCONT { } @inline x{ede301} @inline x{ede301}",
+            [] => [int 0],
+            exit_code: 7
         );
     }
 
@@ -2807,15 +2246,214 @@ mod tests {
         );
     }
 
-    fn make_code(code: &[u8]) -> OwnedCellSlice {
-        Boc::decode(code).unwrap().into_code().unwrap()
+    #[test]
+    #[traced_test]
+    fn interrupted_execution() {
+        let code = Boc::decode(tvmasm!("AGAINEND NOP")).unwrap();
+
+        // Cancelled before the first step.
+        let cancellation = CancellationFlag::new();
+        cancellation.cancel();
+
+        let mut vm = VmState::builder()
+            .with_code(code.clone())
+            .with_gas(GasParams::unlimited())
+            .with_cancellation(cancellation)
+            .build();
+        assert_eq!(vm.run(), VmException::Fatal as u8 as i32);
+        assert_eq!(vm.interrupted, Some(VmInterrupt::Cancelled));
+        assert_eq!(vm.steps, 0);
+
+        // Infinite loop is aborted by the deadline.
+        let mut vm = VmState::builder()
+            .with_code(code.clone())
+            .with_gas(GasParams::unlimited())
+            .with_deadline(Instant::now() + Duration::from_millis(10))
+            .build();
+        assert_eq!(vm.run(), VmException::Fatal as u8 as i32);
+        assert_eq!(vm.interrupted, Some(VmInterrupt::DeadlineExceeded));
+        assert!(vm.steps > 0);
+
+        // Child VM interruption is not handled by the parent.
+        let cancellation = CancellationFlag::new();
+        let mut vm = VmState::builder()
+            .with_code(Boc::decode(tvmasm!("ZERO PUSHSLICE { AGAINEND NOP } RUNVM 0")).unwrap())
+            .with_gas(GasParams::unlimited())
+            .with_cancellation(cancellation.clone())
+            .build();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(10));
+                cancellation.cancel();
+            });
+            assert_eq!(vm.run(), VmException::Fatal as u8 as i32);
+        });
+        assert_eq!(vm.interrupted, Some(VmInterrupt::Cancelled));
+    }
+
+    #[test]
+    #[traced_test]
+    fn custom_codepages() {
+        let code = Boc::decode(tvmasm!("NOP @inline x{ff01} NOP SETCP0 NOP")).unwrap();
+
+        // Unknown codepage.
+        let mut vm = VmState::builder()
+            .with_code(code.clone())
+            .with_gas(GasParams::getter())
+            .build();
+        assert_eq!(!vm.run(), VmException::InvalidOpcode as i32);
+
+        // `NOP` pushes a value in the codepage 1.
+        let mut cp1 = crate::instr::default_opcodes(1).unwrap();
+        cp1.remove(0x00, 8).unwrap();
+        cp1.add_simple(
+            0x00,
+            8,
+            |st| {
+                ok!(SafeRc::make_mut(&mut st.stack).push_int(123));
+                Ok(0)
+            },
+            #[cfg(feature = "dump")]
+            |f| f.record_opcode(&"CUSTOM"),
+        )
+        .unwrap();
+
+        let mut codepages = Codepages::new();
+        assert!(codepages
+            .register(Box::leak(Box::new(cp1.build())))
+            .is_none());
+        assert_eq!(codepages.get(1).unwrap().id(), 1);
+        assert_eq!(codepages.get(0).unwrap().id(), 0);
+        assert!(codepages.get(2).is_none());
+
+        let mut vm = VmState::builder()
+            .with_code(code)
+            .with_gas(GasParams::getter())
+            .with_codepages(&codepages)
+            .build();
+        assert_eq!(!vm.run(), 0);
+        assert_eq!(vm.stack.depth(), 1);
+        assert_eq!(vm.stack.items[0].as_int().unwrap(), &BigInt::from(123));
+        assert_eq!(vm.cp.id(), 0);
     }
 
-    fn make_cell<T: Store>(value: T) -> Cell {
-        CellBuilder::build_from(value).unwrap()
+    #[test]
+    #[traced_test]
+    fn step_limit() {
+        let code = Boc::decode(tvmasm!("AGAINEND NOP")).unwrap();
+
+        let mut vm = VmState::builder()
+            .with_code(code)
+            .with_gas(GasParams::unlimited())
+            .with_max_steps(100)
+            .build();
+        assert_eq!(vm.run(), VmException::Fatal as u8 as i32);
+        assert_eq!(vm.interrupted, Some(VmInterrupt::StepLimitExceeded));
+        assert_eq!(vm.steps, 100);
+
+        // Child VM steps are included.
+        let mut vm = VmState::builder()
+            .with_code(Boc::decode(tvmasm!("ZERO PUSHSLICE { AGAINEND NOP } RUNVM 0")).unwrap())
+            .with_gas(GasParams::unlimited())
+            .with_max_steps(100)
+            .build();
+        assert_eq!(vm.run(), VmException::Fatal as u8 as i32);
+        assert_eq!(vm.interrupted, Some(VmInterrupt::StepLimitExceeded));
+        assert_eq!(vm.total_steps(), 100);
+
+        // Execution within the limit is not affected.
+        let mut vm = VmState::builder()
+            .with_code(Boc::decode(tvmasm!("INT 1 INT 2 ADD")).unwrap())
+            .with_gas(GasParams::unlimited())
+            .with_max_steps(4)
+            .build();
+        assert_eq!(vm.run(), 0);
+        assert_eq!(vm.interrupted, None);
+    }
+
+    #[test]
+    #[traced_test]
+    fn jump_into_library_cell() -> anyhow::Result<()> {
+        type Libraries = HashMap<HashBytes, SimpleLib>;
+
+        fn run(
+            opcode: &[u8],
+            target: Cell,
+            libraries: &Libraries,
+        ) -> anyhow::Result<(i32, Vec<i32>, u64, Option<HashBytes>)> {
+            let mut b = CellBuilder::new();
+            b.store_raw(opcode, opcode.len() as u16 * 8)?;
+            b.store_reference(target)?;
+            let code = b.build()?;
+
+            let mut output = crate::tests::TracingOutput::default();
+            let mut state = VmState::builder()
+                .with_code(code)
+                .with_libraries(libraries)
+                .with_debug(&mut output)
+                .build();
+
+            let exit_code = !state.run();
+            let stack = state
+                .stack
+                .items
+                .iter()
+                .map(|item| i32::try_from(item.as_int().unwrap()).unwrap())
+                .collect();
+
+            Ok((
+                exit_code,
+                stack,
+                state.gas.consumed(),
+                state.gas.missing_library(),
+            ))
+        }
+
+        let library_code = Boc::decode(tvmasm!("PUSHINT 10 PUSHINT 11"))?;
+        let library = {
+            let mut b = CellBuilder::new();
+            b.set_exotic(true);
+            b.store_u8(CellType::LibraryReference.to_byte())?;
+            b.store_u256(library_code.repr_hash())?;
+            b.build()?
+        };
+
+        let libraries = Libraries::from([(*library_code.repr_hash(), SimpleLib {
+            public: true,
+            root: library_code.clone(),
+        })]);
+
+        // CALLREF + PUSHINT 1, JMPREF, implicit JMPREF
+        let cases: [(&[u8], &[i32]); 3] = [
+            (&[0xdb, 0x3c, 0x71], &[10, 11, 1]),
+            (&[0xdb, 0x3d], &[10, 11]),
+            (&[], &[10, 11]),
+        ];
+
+        for (opcode, expected_stack) in cases {
+            let (exit_code, stack, gas, missing) = run(opcode, library.clone(), &libraries)?;
+            assert_eq!(exit_code, 0);
+            assert_eq!(stack, expected_stack);
+            assert_eq!(missing, None);
+
+            // Only loading of the library cell itself is charged additionally.
+            let (exit_code, stack, ordinary_gas, _) =
+                run(opcode, library_code.clone(), &libraries)?;
+            assert_eq!(exit_code, 0);
+            assert_eq!(stack, expected_stack);
+            assert_eq!(gas, ordinary_gas + GasConsumer::NEW_CELL_GAS);
+
+            // Unknown library.
+            let (exit_code, stack, _, missing) = run(opcode, library.clone(), &Libraries::new())?;
+            assert_eq!(exit_code, 9);
+            assert_eq!(stack, [0]);
+            assert_eq!(missing, Some(*library_code.repr_hash()));
+        }
+
+        Ok(())
     }
 
-    fn make_slice<T: Store>(value: T) -> OwnedCellSlice {
-        OwnedCellSlice::new_allow_exotic(CellBuilder::build_from(value).unwrap())
+    fn make_code(code: &[u8]) -> OwnedCellSlice {
+        Boc::decode(code).unwrap().into_code().unwrap()
     }
 }
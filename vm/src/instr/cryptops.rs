@@ -1,6 +1,5 @@
 use std::ops::Range;
 
-use everscale_crypto::ed25519;
 use everscale_types::cell::{CellBuilder, CellSlice};
 use everscale_types::error::Error;
 use num_bigint::{BigInt, Sign};
@@ -10,12 +9,15 @@ use tycho_vm_proc::vm_module;
 use crate::error::VmResult;
 use crate::gas::GasConsumer;
 use crate::saferc::SafeRc;
+use crate::signature::{
+    DefaultSignatureBackend, SignatureAlgorithm, SignatureBackend, SignatureContext,
+};
 use crate::stack::{Stack, StackValueType, Tuple};
 use crate::state::VmState;
 
 pub struct CryptOps;
 
-#[vm_module]
+#[vm_module(category = "crypto")]
 impl CryptOps {
     #[op(code = "f900", fmt = "HASHCU", args(src = HashSource::Cell))]
     #[op(code = "f901", fmt = "HASHSU", args(src = HashSource::Slice))]
@@ -158,20 +160,16 @@ impl CryptOps {
 
         st.gas.try_consume_check_signature_gas()?;
 
-        let is_valid = 'valid: {
-            let Some(pubkey) =
-                ed25519::PublicKey::from_bytes(key_bytes.as_slice().try_into().unwrap())
-            else {
-                break 'valid false;
-            };
-
-            pubkey.verify(
-                ToSign {
-                    signature_id: st.modifiers.signature_with_id.filter(|_| can_use_id),
-                    data: &data[..data_len],
-                },
-                &signature,
-            )
+        let ctx = SignatureContext {
+            algorithm: SignatureAlgorithm::Ed25519,
+            data: &data[..data_len],
+            public_key: &key_bytes,
+            signature: &signature,
+            signature_id: st.modifiers.signature_with_id.filter(|_| can_use_id),
+        };
+        let is_valid = match st.signature_backend.as_deref_mut() {
+            Some(backend) => backend.verify(&ctx),
+            None => DefaultSignatureBackend.verify(&ctx),
         };
 
         ok!(stack.push_bool(is_valid || st.modifiers.chksig_always_succeed));
@@ -179,31 +177,6 @@ impl CryptOps {
     }
 }
 
-struct ToSign<'a> {
-    signature_id: Option<i32>,
-    data: &'a [u8],
-}
-
-impl tl_proto::TlWrite for ToSign<'_> {
-    type Repr = tl_proto::Bare;
-
-    #[inline]
-    fn max_size_hint(&self) -> usize {
-        (if self.signature_id.is_some() { 4 } else { 0 }) + self.data.len()
-    }
-
-    #[inline]
-    fn write_to<P>(&self, packet: &mut P)
-    where
-        P: tl_proto::TlPacket,
-    {
-        if let Some(id) = self.signature_id {
-            packet.write_raw_slice(&id.to_be_bytes());
-        }
-        packet.write_raw_slice(self.data);
-    }
-}
-
 macro_rules! define_compute_hash_ext {
     ($($hash_id:literal => $fn:ident($bpg:literal, $hash:path) -> $out:ty),*$(,)?) => {
         fn compute_hash_ext(
@@ -401,14 +374,17 @@ enum HashSource {
 #[cfg(test)]
 mod tests {
     use everscale_crypto::ed25519;
+    use everscale_types::boc::Boc;
     use everscale_types::cell::{CellBuilder, HashBytes};
     use num_bigint::{BigInt, Sign};
     use sha2::Digest;
     use tracing_test::traced_test;
 
     use crate::saferc::SafeRc;
+    use crate::signature::{SignatureAlgorithm, SignatureBackend, SignatureContext};
     use crate::stack::RcStackValue;
     use crate::util::OwnedCellSlice;
+    use crate::{BehaviourModifiers, VmState};
 
     #[test]
     #[traced_test]
@@ -592,6 +568,142 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[traced_test]
+    fn chksign_with_id() -> anyhow::Result<()> {
+        const SIGNATURE_ID: i32 = -239;
+
+        let secret = "403cbda795d10f129d81ac9963840f6100f8025e9341d486b247602e4b11f404"
+            .parse::<HashBytes>()?;
+        let keypair = ed25519::KeyPair::from(&ed25519::SecretKey::from_bytes(secret.0));
+
+        let data = [0xda_u8; 40];
+        let sig = keypair.sign_raw(&data);
+        let sig_with_id = {
+            let mut to_sign = SIGNATURE_ID.to_be_bytes().to_vec();
+            to_sign.extend_from_slice(&data);
+            keypair.sign_raw(&to_sign)
+        };
+
+        let data_hash = sha2::Sha256::digest(data);
+        let hash_sig_with_id = {
+            let mut to_sign = SIGNATURE_ID.to_be_bytes().to_vec();
+            to_sign.extend_from_slice(&data_hash);
+            keypair.sign_raw(&to_sign)
+        };
+
+        let check = |code: &[u8], data: RcStackValue, signature: [u8; 64], id: Option<i32>| {
+            let pubkey = build_int(keypair.public_key.as_bytes());
+            run_with_signature_id(code, vec![data, build_slice(signature), pubkey], id)
+        };
+
+        let chksigns: &[u8] = tvmasm!("CHKSIGNS");
+        let chksignu: &[u8] = tvmasm!("CHKSIGNU");
+        let ed25519_chksigns: &[u8] = tvmasm!("ED25519_CHKSIGNS");
+        let id = Some(SIGNATURE_ID);
+
+        // Signature id is prepended to the data when enabled.
+        assert!(check(chksigns, build_slice(data), sig_with_id, id));
+        assert!(!check(chksigns, build_slice(data), sig, id));
+        assert!(!check(chksigns, build_slice(data), sig_with_id, None));
+        assert!(check(chksigns, build_slice(data), sig, None));
+        assert!(check(chksignu, build_int(data_hash), hash_sig_with_id, id));
+        assert!(!check(
+            chksignu,
+            build_int(data_hash),
+            hash_sig_with_id,
+            None
+        ));
+
+        // `ED25519_CHKSIGN*` opcodes never use the signature id.
+        assert!(check(ed25519_chksigns, build_slice(data), sig, id));
+        assert!(!check(ed25519_chksigns, build_slice(data), sig_with_id, id));
+
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn custom_signature_backend() {
+        #[derive(Default)]
+        struct RecordingBackend {
+            checked: Vec<(Vec<u8>, Vec<u8>, [u8; 64], Option<i32>)>,
+        }
+
+        impl SignatureBackend for RecordingBackend {
+            fn verify(&mut self, ctx: &SignatureContext<'_>) -> bool {
+                assert_eq!(ctx.algorithm, SignatureAlgorithm::Ed25519);
+                self.checked.push((
+                    ctx.data.to_vec(),
+                    ctx.public_key.to_vec(),
+                    *ctx.signature,
+                    ctx.signature_id,
+                ));
+                ctx.public_key == [0x11; 32]
+            }
+        }
+
+        let code = Boc::decode(tvmasm!("CHKSIGNS BLKSWAP 3, 1 CHKSIGNS")).unwrap();
+
+        let mut backend = RecordingBackend::default();
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_stack([
+                build_slice([0xda; 40]),
+                build_slice([0x22; 64]),
+                build_int([0x33; 32]),
+                build_slice([0xdb; 8]),
+                build_slice([0x44; 64]),
+                build_int([0x11; 32]),
+            ])
+            .with_modifiers(BehaviourModifiers {
+                signature_with_id: Some(123),
+                ..Default::default()
+            })
+            .with_signature_backend(&mut backend)
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.items.len(), 2);
+        assert_eq!(state.stack.items[0].as_int().unwrap(), &BigInt::from(-1));
+        assert_eq!(state.stack.items[1].as_int().unwrap(), &BigInt::from(0));
+        drop(state);
+
+        assert_eq!(backend.checked, vec![
+            (vec![0xdb; 8], vec![0x11; 32], [0x44; 64], Some(123)),
+            (vec![0xda; 40], vec![0x33; 32], [0x22; 64], Some(123)),
+        ]);
+    }
+
+    fn run_with_signature_id(
+        code: &[u8],
+        stack: Vec<RcStackValue>,
+        signature_with_id: Option<i32>,
+    ) -> bool {
+        let code = Boc::decode(code).unwrap();
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_stack(stack)
+            .with_modifiers(BehaviourModifiers {
+                signature_with_id,
+                ..Default::default()
+            })
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.items.len(), 1);
+        match state.stack.get_exit_arg() {
+            Some(0) => false,
+            Some(-1) => true,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
     fn build_slice<T: AsRef<[u8]>>(data: T) -> RcStackValue {
         let data = data.as_ref();
         let b = CellBuilder::from_raw_data(data, data.len() as u16 * 8).unwrap();
@@ -15,7 +15,7 @@ use crate::util::{load_uint_leq, OwnedCellSlice};
 
 pub struct CurrencyOps;
 
-#[vm_module]
+#[vm_module(category = "message")]
 impl CurrencyOps {
     #[op(code = "fa00", fmt = "LDGRAMS", args(len_bits = 4, signed = false))]
     #[op(code = "fa01", fmt = "LDVARINT16", args(len_bits = 4, signed = true))]
@@ -393,7 +393,8 @@ fn skip_maybe_anycast(cs: &mut CellSlice, version: &VmVersion) -> Result<(), Err
 
 #[cfg(test)]
 mod test {
-    use everscale_types::models::{Anycast, StdAddr};
+    use everscale_types::models::{Anycast, IntAddr, StdAddr, VarAddr};
+    use everscale_types::num::Uint9;
     use tracing_test::traced_test;
 
     use super::*;
@@ -460,6 +461,40 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn parse_var_address_workchains() -> anyhow::Result<()> {
+        for workchain in [i32::MIN, -129, 128, 0x7fffffff] {
+            let addr = IntAddr::Var(VarAddr {
+                anycast: None,
+                address_len: Uint9::new(100),
+                workchain,
+                address: vec![0x55; 13],
+            });
+            let cell = CellBuilder::build_from(addr)?;
+
+            for version in [VmVersion::Everscale(1), VmVersion::Ton(9)] {
+                let mut range = CellSliceRange::full(cell.as_ref());
+                let parts = parse_message_addr(&cell, &mut range, &version)?;
+                let AddrParts::Var {
+                    workchain: parsed,
+                    addr,
+                    ..
+                } = parts
+                else {
+                    panic!("unexpected address type");
+                };
+                assert_eq!(parsed, workchain);
+                assert_eq!(addr.range().size_bits(), 100);
+            }
+
+            // Variable addresses are not supported since TON v10.
+            let mut range = CellSliceRange::full(cell.as_ref());
+            assert!(parse_message_addr(&cell, &mut range, &VmVersion::Ton(10)).is_err());
+        }
+
+        Ok(())
+    }
+
     #[test]
     #[traced_test]
     fn test_anycast_error() -> anyhow::Result<()> {
@@ -6,7 +6,7 @@ use crate::dispatch::DumpOutput;
 use crate::error::VmResult;
 #[cfg(feature = "dump")]
 use crate::error::{DumpError, DumpResult};
-use crate::state::VmState;
+use crate::state::{DebugEvent, VmState};
 #[cfg(any(feature = "dump", feature = "tracing"))]
 use crate::util::CellSliceExt;
 
@@ -14,26 +14,15 @@ pub struct DebugOps;
 
 // TODO: Decide whether to panic on debug write errors
 
-#[vm_module]
+#[vm_module(category = "debug")]
 impl DebugOps {
     #[op(code = "fe00", fmt = "DUMPSTK")]
     fn exec_dump_stack(st: &mut VmState) -> VmResult<i32> {
-        let Some(debug) = &mut st.debug else {
+        let Some(debug) = st.debug.as_active() else {
             return Ok(0);
         };
 
-        let mut depth = st.stack.depth();
-        write!(&mut *debug, "#DEBUG#: stack({depth} values) :").unwrap();
-        if depth > 255 {
-            write!(&mut *debug, " ...").unwrap();
-            depth = 255;
-        }
-
-        for value in st.stack.items.iter().rev().take(depth).rev() {
-            write!(&mut *debug, " {}", value.display_list()).unwrap();
-        }
-
-        writeln!(&mut *debug).unwrap();
+        debug.emit(DebugEvent::DumpStack(&st.stack.items)).unwrap();
         Ok(0)
     }
 
@@ -47,46 +36,31 @@ impl DebugOps {
 
     #[op(code = "fe14", fmt = "STRDUMP")]
     fn exec_dump_string(st: &mut VmState) -> VmResult<i32> {
-        let Some(debug) = &mut st.debug else {
+        let Some(debug) = st.debug.as_active() else {
             return Ok(0);
         };
 
-        if let Some(value) = st.stack.items.last() {
-            if let Some(slice) = value.as_cell_slice() {
-                writeln!(
-                    &mut *debug,
-                    "#DEBUG#: {}",
-                    DisplaySliceString(slice.apply())
-                )
-                .unwrap();
-            } else {
-                writeln!(&mut *debug, "#DEBUG#: is not a slice").unwrap();
-            }
-        } else {
-            writeln!(&mut *debug, "#DEBUG#: s0 is absent").unwrap();
-        }
+        let value = match st.stack.items.last() {
+            Some(value) => match value.as_cell_slice() {
+                Some(slice) => Ok(slice.apply()),
+                None => Err(Some(value)),
+            },
+            None => Err(None),
+        };
+        debug.emit(DebugEvent::StrDump(value)).unwrap();
         Ok(0)
     }
 
     #[op(code = "fe2x", fmt = "DUMP s{x}")]
     fn exec_dump_value(st: &mut VmState, x: u32) -> VmResult<i32> {
-        let Some(debug) = &mut st.debug else {
+        let Some(debug) = st.debug.as_active() else {
             return Ok(0);
         };
 
         let x = x as usize;
         let depth = st.stack.depth();
-        if x < depth {
-            writeln!(
-                &mut *debug,
-                "#DEBUG#: s{x} = {}",
-                st.stack.items[depth - x - 1].display_list()
-            )
-            .unwrap();
-        } else {
-            writeln!(&mut *debug, "#DEBUG#: s{x} is absent").unwrap();
-        }
-
+        let value = (x < depth).then(|| &st.stack.items[depth - x - 1]);
+        debug.emit(DebugEvent::DumpValue(x, value)).unwrap();
         Ok(0)
     }
 
@@ -98,14 +72,14 @@ impl DebugOps {
             InvalidOpcode
         );
 
-        if let Some(debug) = &mut st.debug {
+        if let Some(debug) = st.debug.as_active() {
             let mut slice = st.code.apply();
             slice.skip_first(bits, 0)?;
             slice.only_first(data_bits, 0)?;
-            vm_log_op!("DEBUGSTR {}", slice.display_as_stack_value());
-            writeln!(&mut *debug, "#DEBUG#: {}", DisplaySliceString(slice)).unwrap();
+            vm_log_op!(@DEBUG "DEBUGSTR {}", slice.display_as_stack_value());
+            debug.emit(DebugEvent::DumpString(slice)).unwrap();
         } else {
-            vm_log_op!("DEBUGSTR");
+            vm_log_op!(@DEBUG "DEBUGSTR");
         }
 
         let ok = st.code.range_mut().skip_first(bits + data_bits, 0).is_ok();
@@ -136,37 +110,6 @@ impl DebugOps {
     }
 }
 
-struct DisplaySliceString<'a>(CellSlice<'a>);
-
-impl std::fmt::Display for DisplaySliceString<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        const MAX_BIT_LEN: u16 = 127 * 8;
-
-        match (|bytes| {
-            let bit_len = self.0.size_bits();
-            if bit_len > MAX_BIT_LEN || bit_len % 8 != 0 {
-                return None;
-            }
-
-            let mut slice = self.0;
-            let mut bytes = &*slice.load_raw(bytes, bit_len).ok()?;
-            while let [rest @ .., last] = bytes {
-                if *last == 0 || last.is_ascii_whitespace() {
-                    bytes = rest;
-                } else {
-                    break;
-                }
-            }
-
-            std::str::from_utf8(bytes).ok()
-        })(&mut [0; 128])
-        {
-            Some(bytes) => f.write_str(bytes),
-            None => write!(f, "x{:X}", self.0.display_data()),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use tracing_test::traced_test;
@@ -233,6 +176,85 @@ mod tests {
         assert_eq!(output, "#DEBUG#: x0000ABAB\n");
     }
 
+    #[test]
+    #[traced_test]
+    fn multiple_sinks() {
+        let code = Boc::decode(tvmasm!("INT 123 DUMPSTK")).unwrap();
+
+        let mut first = String::new();
+        let mut second = String::new();
+        let mut vm = VmState::builder()
+            .with_code(code)
+            .with_debug(&mut first)
+            .with_debug(&mut second)
+            .build();
+        assert_eq!(!vm.run(), 0);
+        drop(vm);
+
+        assert_eq!(first, "#DEBUG#: stack(1 values) : 123\n");
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    #[traced_test]
+    fn event_sinks() {
+        let code = Boc::decode(tvmasm!(
+            r#"
+            INT 123
+            SLICE x{48656C6C6F}
+            DUMPSTK
+            DUMP s1
+            DUMP s5
+            STRDUMP
+            DROP
+            STRDUMP
+            @inline x{fef14869}
+            "#
+        ))
+        .unwrap();
+
+        let mut events = Vec::new();
+        let mut on_event = |event: DebugEvent<'_>| {
+            events.push(match event {
+                DebugEvent::DumpStack(items) => format!("stack:{}", items.len()),
+                DebugEvent::DumpValue(idx, value) => {
+                    format!("value:{idx}:{}", value.is_some())
+                }
+                DebugEvent::StrDump(Ok(slice)) => format!("str:{}", slice.size_bits()),
+                DebugEvent::StrDump(Err(value)) => format!("str:{}", value.is_some()),
+                DebugEvent::DumpString(slice) => format!("debugstr:{}", slice.size_bits()),
+            });
+        };
+
+        let mut text = String::new();
+        let mut vm = VmState::builder()
+            .with_code(code)
+            .with_debug(&mut text)
+            .with_debug_events(&mut on_event)
+            .build();
+        assert_eq!(!vm.run(), 0);
+        drop(vm);
+
+        assert_eq!(events, [
+            "stack:2",
+            "value:1:true",
+            "value:5:false",
+            "str:40",
+            "str:true",
+            "debugstr:16",
+        ]);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].starts_with("#DEBUG#: stack(2 values) : 123 "));
+        assert_eq!(&lines[1..], [
+            "#DEBUG#: s1 = 123",
+            "#DEBUG#: s5 is absent",
+            "#DEBUG#: Hello",
+            "#DEBUG#: is not a slice",
+            "#DEBUG#: Hi",
+        ]);
+    }
+
     fn run_get_dump(code: &[u8]) -> String {
         let code = Boc::decode(code).unwrap();
 
@@ -1,3 +1,4 @@
+use everscale_types::cell::{CellContext, LoadMode};
 use everscale_types::dict::{self, DictBound, SetMode};
 use everscale_types::error::Error;
 use everscale_types::prelude::*;
@@ -17,7 +18,7 @@ use crate::util::OwnedCellSlice;
 
 pub struct DictOps;
 
-#[vm_module]
+#[vm_module(category = "dict")]
 impl DictOps {
     #[op(code = "f400", fmt = "STDICT")]
     fn exec_stdict(st: &mut VmState) -> VmResult<i32> {
@@ -437,6 +438,51 @@ impl DictOps {
         Ok(0)
     }
 
+    #[op(code = "f470", fmt = "PFXDICTSET", args(mode = SetMode::Set))]
+    #[op(code = "f471", fmt = "PFXDICTREPLACE", args(mode = SetMode::Replace))]
+    #[op(code = "f472", fmt = "PFXDICTADD", args(mode = SetMode::Add))]
+    fn exec_pfx_dict_set(st: &mut VmState, mode: SetMode) -> VmResult<i32> {
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let n = ok!(stack.pop_smallint_range(0, 1023)) as u16;
+        let dict = ok!(stack.pop_cell_opt());
+        let key = ok!(stack.pop_cs());
+        let value = ok!(stack.pop_cs());
+
+        let key = key.apply();
+        let new_dict = if key.size_bits() <= n {
+            pfx_dict_set(dict.as_deref(), &key, n, &value.apply(), mode, &st.gas)?
+        } else {
+            None
+        };
+
+        let updated = new_dict.is_some();
+        ok!(stack.push_opt(new_dict.or_else(|| dict.as_deref().cloned())));
+        ok!(stack.push_bool(updated));
+        Ok(0)
+    }
+
+    #[op(code = "f473", fmt = "PFXDICTDEL")]
+    fn exec_pfx_dict_delete(st: &mut VmState) -> VmResult<i32> {
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let n = ok!(stack.pop_smallint_range(0, 1023)) as u16;
+        let dict = ok!(stack.pop_cell_opt());
+        let key = ok!(stack.pop_cs());
+
+        let key = key.apply();
+        let removed = match dict.as_deref() {
+            Some(root) if key.size_bits() <= n => pfx_dict_remove(root, &key, n, &st.gas)?,
+            _ => None,
+        };
+
+        let found = removed.is_some();
+        ok!(stack.push_opt(match removed {
+            Some(new_dict) => new_dict,
+            None => dict.as_deref().cloned(),
+        }));
+        ok!(stack.push_bool(found));
+        Ok(0)
+    }
+
     #[op(code = "f4ss @ f474..f480", fmt = s, args(s = DictGetNearArgs(args)))]
     fn exec_dict_get_near(st: &mut VmState, s: DictGetNearArgs) -> VmResult<i32> {
         let stack = SafeRc::make_mut(&mut st.stack);
@@ -627,7 +673,7 @@ impl DictOps {
         st.code.set_range(code.range());
 
         vm_log_op!(
-            "DICTPUSHCONST {n} {}",
+            @DICT "DICTPUSHCONST {n} {}",
             OwnedCellSlice::from((slice_range, st.code.cell().clone()))
         );
         ok!(stack.push(dict));
@@ -659,6 +705,116 @@ impl DictOps {
         ))
     }
 
+    #[op(code = "f4a8", fmt = "PFXDICTGETQ", args(cont = false, quiet = true))]
+    #[op(code = "f4a9", fmt = "PFXDICTGET", args(cont = false, quiet = false))]
+    #[op(code = "f4aa", fmt = "PFXDICTGETJMP", args(cont = true, quiet = true))]
+    #[op(
+        code = "f4ab",
+        fmt = "PFXDICTGETEXEC",
+        args(cont = true, quiet = false)
+    )]
+    fn exec_pfx_dict_get(st: &mut VmState, cont: bool, quiet: bool) -> VmResult<i32> {
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let n = ok!(stack.pop_smallint_range(0, 1023)) as u16;
+        let dict = ok!(stack.pop_cell_opt());
+        let mut cs = ok!(stack.pop_cs());
+
+        let Some((value, prefix_len)) = pfx_dict_get(dict.as_deref(), n, cs.apply(), &st.gas)?
+        else {
+            vm_ensure!(quiet, CellError(Error::CellUnderflow));
+            ok!(stack.push_raw(cs));
+            if !cont {
+                ok!(stack.push_bool(false));
+            }
+            return Ok(0);
+        };
+
+        let prefix = ok!(split_prefix(&mut cs, prefix_len));
+        ok!(stack.push(prefix));
+        if !cont {
+            ok!(stack.push(value));
+            ok!(stack.push_raw(cs));
+            if quiet {
+                ok!(stack.push_bool(true));
+            }
+            return Ok(0);
+        }
+
+        ok!(stack.push_raw(cs));
+        let cont = SafeRc::from(OrdCont::simple(value, st.cp.id()));
+        if quiet {
+            st.jump(cont)
+        } else {
+            st.call(cont)
+        }
+    }
+
+    // f4ac00..f4b000
+    #[op_ext_range(
+        code_min = 0xf4ac00,
+        code_max = 0xf4b000,
+        total_bits = 24,
+        dump_with = dump_pfx_dict_switch,
+    )]
+    fn exec_pfx_dict_switch(st: &mut VmState, _: u32, bits: u16) -> VmResult<i32> {
+        vm_ensure!(st.code.range().has_remaining(bits, 1), InvalidOpcode);
+        let ok = st.code.range_mut().skip_first(bits - 11, 0).is_ok();
+        debug_assert!(ok);
+
+        let mut code = st.code.apply();
+
+        let slice = code.load_prefix(1, 1)?;
+        let slice_range = slice.range();
+        let dict = slice.get_reference_cloned(0)?;
+
+        let n = code.load_uint(10)? as u16;
+        st.code.set_range(code.range());
+
+        vm_log_op!(
+            @DICT "PFXDICTSWITCH {n} {}",
+            OwnedCellSlice::from((slice_range, st.code.cell().clone()))
+        );
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut cs = ok!(stack.pop_cs());
+
+        let Some((value, prefix_len)) = pfx_dict_get(Some(&dict), n, cs.apply(), &st.gas)? else {
+            ok!(stack.push_raw(cs));
+            return Ok(0);
+        };
+
+        let prefix = ok!(split_prefix(&mut cs, prefix_len));
+        ok!(stack.push(prefix));
+        ok!(stack.push_raw(cs));
+
+        let cont = SafeRc::from(OrdCont::simple(value, st.cp.id()));
+        st.jump(cont)
+    }
+
+    #[cfg(feature = "dump")]
+    fn dump_pfx_dict_switch(
+        code: &mut CellSlice<'_>,
+        _: u32,
+        bits: u16,
+        f: &mut dyn DumpOutput,
+    ) -> DumpResult {
+        use crate::util::CellSliceExt;
+
+        if !code.has_remaining(bits, 1) {
+            return Err(DumpError::InvalidOpcode);
+        }
+        code.skip_first(bits - 11, 0)?;
+
+        let slice = code.load_prefix(1, 1)?;
+        let n = code.load_uint(10)? as u16;
+
+        f.record_cell(slice.get_reference_cloned(0)?)?;
+        f.record_opcode(&format_args!(
+            "PFXDICTSWITCH {n} {}",
+            slice.display_as_stack_value()
+        ))
+    }
+
     // TODO: Implement a proper subdictionary cut.
     // #[op(code = "f4ss @ f4b1..f4b4", fmt = s.display("GET"), args(s = SubDictOpArgs(args)))]
     // #[op(code = "f4ss @ f4b5..f4b8", fmt = s.display("RPGET"), args(s = SubDictOpArgs(args)))]
@@ -936,13 +1092,258 @@ fn to_value_ref(mut cs: CellSlice<'_>) -> VmResult<RcStackValue> {
     Ok(SafeRc::new_dyn_value(cell))
 }
 
+/// Splits the slice at `bits` and returns the first part.
+fn split_prefix(cs: &mut SafeRc<OwnedCellSlice>, bits: u16) -> VmResult<OwnedCellSlice> {
+    let (prefix_range, rest_range) = {
+        let mut slice = cs.apply();
+        let prefix = slice.load_prefix(bits, 0)?;
+        (prefix.range(), slice.range())
+    };
+
+    let prefix = OwnedCellSlice::from((prefix_range, cs.cell().clone()));
+    SafeRc::make_mut(cs).set_range(rest_range);
+    Ok(prefix)
+}
+
+// === Prefix dictionaries ===
+//
+// phm_edge#_ {n:#} {X:Type} {l:#} {m:#} label:(HmLabel ~l n)
+//     {n = (~m) + l} node:(PfxHashmapNode m X) = PfxHashmap n X;
+// phmn_leaf$0 {n:#} {X:Type} value:X = PfxHashmapNode n X;
+// phmn_fork$1 {n:#} {X:Type} left:^(PfxHashmap n X)
+//     right:^(PfxHashmap n X) = PfxHashmapNode (n + 1) X;
+
+/// Finds a leaf which key is a prefix of the specified key.
+///
+/// Returns the leaf value and the length of the matched prefix.
+fn pfx_dict_get(
+    root: Option<&Cell>,
+    mut key_bit_len: u16,
+    mut key: CellSlice<'_>,
+    context: &dyn CellContext,
+) -> Result<Option<(OwnedCellSlice, u16)>, Error> {
+    let Some(root) = root else {
+        return Ok(None);
+    };
+
+    let mut prefix_len = 0;
+    let mut cell = ok!(context.load_cell(root.clone(), LoadMode::Full));
+    loop {
+        let mut cs = ok!(cell.as_slice());
+        let label = ok!(dict::read_label(&mut cs, key_bit_len));
+        let Some(rest) = key.strip_data_prefix(&label) else {
+            return Ok(None);
+        };
+        key = rest;
+        prefix_len += label.size_bits();
+        key_bit_len -= label.size_bits();
+
+        if !ok!(cs.load_bit()) {
+            // Leaf
+            let range = cs.range();
+            return Ok(Some((OwnedCellSlice::from((range, cell)), prefix_len)));
+        }
+
+        // Fork
+        if key.is_data_empty() {
+            return Ok(None);
+        } else if key_bit_len == 0 {
+            return Err(Error::CellUnderflow);
+        }
+
+        let next = ok!(cs.get_reference_cloned(ok!(key.load_bit()) as u8));
+        prefix_len += 1;
+        key_bit_len -= 1;
+
+        cell = ok!(context.load_cell(next, LoadMode::Full));
+    }
+}
+
+/// Inserts a new leaf into the prefix dictionary.
+///
+/// Returns `None` if the dictionary was not modified.
+fn pfx_dict_set(
+    root: Option<&Cell>,
+    key: &CellSlice<'_>,
+    key_bit_len: u16,
+    value: &CellSlice<'_>,
+    mode: SetMode,
+    context: &dyn CellContext,
+) -> Result<Option<Cell>, Error> {
+    let Some(root) = root else {
+        if mode == SetMode::Replace {
+            return Ok(None);
+        }
+        return make_pfx_leaf(key, key_bit_len, value, context);
+    };
+
+    let root = ok!(context.load_dyn_cell(root.as_ref(), LoadMode::Full));
+    let mut cs = ok!(root.as_slice());
+    let label = ok!(dict::read_label(&mut cs, key_bit_len));
+
+    let prefix = label.longest_common_data_prefix(key);
+    let prefix_len = prefix.size_bits();
+    let label_len = label.size_bits();
+
+    let mut b = CellBuilder::new();
+    if prefix_len < label_len {
+        // Key is a prefix of some existing key.
+        if prefix_len == key.size_bits() || mode == SetMode::Replace {
+            return Ok(None);
+        }
+
+        // Split the edge into a fork.
+        let child_bit_len = key_bit_len - prefix_len - 1;
+
+        let mut old_label = label;
+        let old_bit = ok!(old_label.get_bit(prefix_len));
+        ok!(old_label.skip_first(prefix_len + 1, 0));
+        let mut old = CellBuilder::new();
+        ok!(dict::write_label(&old_label, child_bit_len, &mut old));
+        ok!(old.store_slice(cs));
+        let old = ok!(old.build_ext(context));
+
+        let mut new_key = *key;
+        ok!(new_key.skip_first(prefix_len + 1, 0));
+        let Some(new) = ok!(make_pfx_leaf(&new_key, child_bit_len, value, context)) else {
+            return Ok(None);
+        };
+
+        let (left, right) = if old_bit { (new, old) } else { (old, new) };
+        ok!(dict::write_label(&prefix, key_bit_len, &mut b));
+        ok!(b.store_bit_one());
+        ok!(b.store_reference(left));
+        ok!(b.store_reference(right));
+    } else if !ok!(cs.load_bit()) {
+        // Leaf
+        if key.size_bits() != label_len || mode == SetMode::Add {
+            return Ok(None);
+        }
+        return make_pfx_leaf(key, key_bit_len, value, context);
+    } else {
+        // Fork
+        if key.size_bits() == label_len || key_bit_len == label_len {
+            return Ok(None);
+        }
+
+        let mut rest = *key;
+        ok!(rest.skip_first(label_len, 0));
+        let bit = ok!(rest.load_bit());
+
+        let child = ok!(cs.get_reference_cloned(bit as u8));
+        let child_bit_len = key_bit_len - label_len - 1;
+        let Some(child) = ok!(pfx_dict_set(
+            Some(&child),
+            &rest,
+            child_bit_len,
+            value,
+            mode,
+            context
+        )) else {
+            return Ok(None);
+        };
+
+        let other = ok!(cs.get_reference_cloned(!bit as u8));
+        let (left, right) = if bit { (other, child) } else { (child, other) };
+        ok!(dict::write_label(&label, key_bit_len, &mut b));
+        ok!(b.store_bit_one());
+        ok!(b.store_reference(left));
+        ok!(b.store_reference(right));
+    }
+
+    b.build_ext(context).map(Some)
+}
+
+/// Removes a leaf with exactly the specified key.
+///
+/// Returns `None` if the key was not found, `Some(None)` if
+/// the whole subtree must be removed.
+fn pfx_dict_remove(
+    root: &Cell,
+    key: &CellSlice<'_>,
+    key_bit_len: u16,
+    context: &dyn CellContext,
+) -> Result<Option<Option<Cell>>, Error> {
+    let root = ok!(context.load_dyn_cell(root.as_ref(), LoadMode::Full));
+    let mut cs = ok!(root.as_slice());
+    let label = ok!(dict::read_label(&mut cs, key_bit_len));
+    let Some(mut rest) = key.strip_data_prefix(&label) else {
+        return Ok(None);
+    };
+
+    if !ok!(cs.load_bit()) {
+        // Leaf
+        return Ok(rest.is_data_empty().then_some(None));
+    }
+
+    // Fork
+    let label_len = label.size_bits();
+    if rest.is_data_empty() || key_bit_len == label_len {
+        return Ok(None);
+    }
+
+    let bit = ok!(rest.load_bit());
+    let child = ok!(cs.get_reference_cloned(bit as u8));
+    let other = ok!(cs.get_reference_cloned(!bit as u8));
+    let child_bit_len = key_bit_len - label_len - 1;
+
+    let mut b = CellBuilder::new();
+    match ok!(pfx_dict_remove(&child, &rest, child_bit_len, context)) {
+        None => return Ok(None),
+        Some(Some(child)) => {
+            let (left, right) = if bit { (other, child) } else { (child, other) };
+            ok!(dict::write_label(&label, key_bit_len, &mut b));
+            ok!(b.store_bit_one());
+            ok!(b.store_reference(left));
+            ok!(b.store_reference(right));
+        }
+        Some(None) => {
+            // Merge the remaining child into the current node.
+            let other = ok!(context.load_dyn_cell(other.as_ref(), LoadMode::Full));
+            let mut other_cs = ok!(other.as_slice());
+            let other_label = ok!(dict::read_label(&mut other_cs, child_bit_len));
+
+            let mut merged_label = CellBuilder::new();
+            ok!(merged_label.store_slice_data(label));
+            ok!(merged_label.store_bit(!bit));
+            ok!(merged_label.store_slice_data(other_label));
+
+            ok!(dict::write_label(
+                &merged_label.as_data_slice(),
+                key_bit_len,
+                &mut b
+            ));
+            ok!(b.store_slice(other_cs));
+        }
+    }
+
+    b.build_ext(context).map(|cell| Some(Some(cell)))
+}
+
+fn make_pfx_leaf(
+    key: &CellSlice<'_>,
+    key_bit_len: u16,
+    value: &CellSlice<'_>,
+    context: &dyn CellContext,
+) -> Result<Option<Cell>, Error> {
+    let mut b = CellBuilder::new();
+    ok!(dict::write_label(key, key_bit_len, &mut b));
+    ok!(b.store_bit_zero());
+    if b.store_slice(value).is_err() {
+        return Ok(None);
+    }
+    b.build_ext(context).map(Some)
+}
+
 #[cfg(test)]
 pub mod tests {
     use everscale_types::cell::Lazy;
+    use num_bigint::BigInt;
     use tracing_test::traced_test;
 
     use self::dict::{Dict, DictKey};
     use super::*;
+    use crate::gas::GasParams;
 
     #[test]
     #[traced_test]
@@ -1375,6 +1776,937 @@ pub mod tests {
         );
     }
 
+    #[test]
+    #[traced_test]
+    fn unbalanced_dict_gas() {
+        // 32 cells on the path to the key `0`, 2 cells on the path to the key `1 << 30`.
+        let dict = make_unbalanced_dict(32, 31, false);
+
+        // NOTE: Gas is 26 for the opcode + 5 for the implicit RET
+        // + 100 per loaded cell + 500 per built cell.
+        let cases = [
+            // Lookups load each cell on the path once.
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int 0, raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int 1 << 30, raw dict.clone(), int 32],
+                0,
+                231,
+            ),
+            // Missing key `3` diverges at the leaf of the key `2` (31 cells).
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int 3, raw dict.clone(), int 32],
+                0,
+                3131,
+            ),
+            // Each cell on the path is rebuilt.
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 32],
+                0,
+                19231,
+            ),
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw new_slice(1), int 1 << 30, raw dict.clone(), int 32],
+                0,
+                1231,
+            ),
+            // Split of the leaf builds a new leaf, the shortened leaf and a fork.
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw new_slice(1), int 3, raw dict.clone(), int 32],
+                0,
+                19631,
+            ),
+            (
+                tvmasm!("DICTUREPLACE"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 32],
+                0,
+                19231,
+            ),
+            // Nothing is built for a missing key.
+            (
+                tvmasm!("DICTUREPLACE"),
+                tuple![raw new_slice(1), int 3, raw dict.clone(), int 32],
+                0,
+                3131,
+            ),
+            // Nothing is built for an existing key.
+            (
+                tvmasm!("DICTUADD"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+            (
+                tvmasm!("DICTUADD"),
+                tuple![raw new_slice(1), int 3, raw dict.clone(), int 32],
+                0,
+                19631,
+            ),
+            // Sibling is loaded and merged with its parent, ancestors are rebuilt.
+            (
+                tvmasm!("DICTUDEL"),
+                tuple![int 0, raw dict.clone(), int 32],
+                0,
+                18831,
+            ),
+            (
+                tvmasm!("DICTUDEL"),
+                tuple![int 1 << 30, raw dict.clone(), int 32],
+                0,
+                831,
+            ),
+            (
+                tvmasm!("DICTUDEL"),
+                tuple![int 3, raw dict.clone(), int 32],
+                0,
+                3131,
+            ),
+            (
+                tvmasm!("DICTUMIN"),
+                tuple![raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+            (
+                tvmasm!("DICTUMAX"),
+                tuple![raw dict.clone(), int 32],
+                0,
+                231,
+            ),
+            // Fork of the last branch is not reloaded, only its other child.
+            (
+                tvmasm!("DICTUGETNEXT"),
+                tuple![int 0, raw dict.clone(), int 32],
+                0,
+                3331,
+            ),
+            (
+                tvmasm!("DICTUGETPREV"),
+                tuple![int 1 << 30, raw dict.clone(), int 32],
+                0,
+                431,
+            ),
+            // No smaller keys, the whole path is visited.
+            (
+                tvmasm!("DICTUGETPREV"),
+                tuple![int 0, raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+        ];
+        for (i, (code, stack, exit_code, gas)) in cases.into_iter().enumerate() {
+            assert_eq!(run_dict_op(code, stack), (exit_code, gas), "case #{i}");
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn deep_label_dict_gas() {
+        // Root label is 248 bits long, 9 cells on the path to the key `0`,
+        // 2 cells on the path to the key `1 << 7`. All values are max-size.
+        let dict = make_unbalanced_dict(256, 8, true);
+        let far_key = || BigInt::from(1) << 200;
+
+        // NOTE: Gas is 26 for the opcode + 5 for the implicit RET (or 50 for
+        // an exception) + 100 per loaded cell + 500 per built cell.
+        let cases = [
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int 0, raw dict.clone(), int 256],
+                0,
+                931,
+            ),
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int 1 << 7, raw dict.clone(), int 256],
+                0,
+                231,
+            ),
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int 3, raw dict.clone(), int 256],
+                0,
+                831,
+            ),
+            // Key diverges in the root label.
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int far_key(), raw dict.clone(), int 256],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 256],
+                0,
+                5431,
+            ),
+            // Leaf of the key `0` has a 2-bit label, so it fits at most 1021 bits.
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw make_zeros_slice(1021), int 0, raw dict.clone(), int 256],
+                0,
+                5431,
+            ),
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw make_zeros_slice(1022), int 0, raw dict.clone(), int 256],
+                8,
+                976,
+            ),
+            // Root label is split.
+            (
+                tvmasm!("DICTUSET"),
+                tuple![raw new_slice(1), int far_key(), raw dict.clone(), int 256],
+                0,
+                1631,
+            ),
+            (
+                tvmasm!("DICTUREPLACE"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 256],
+                0,
+                5431,
+            ),
+            (
+                tvmasm!("DICTUREPLACE"),
+                tuple![raw new_slice(1), int far_key(), raw dict.clone(), int 256],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTUADD"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 256],
+                0,
+                931,
+            ),
+            (
+                tvmasm!("DICTUADD"),
+                tuple![raw new_slice(1), int far_key(), raw dict.clone(), int 256],
+                0,
+                1631,
+            ),
+            // Merged leaf of the key `1` gets a 4-bit label which doesn't fit
+            // with the max-size value.
+            (
+                tvmasm!("DICTUDEL"),
+                tuple![int 0, raw dict.clone(), int 256],
+                8,
+                1076,
+            ),
+            // Root label is merged with the label of the sibling.
+            (
+                tvmasm!("DICTUDEL"),
+                tuple![int 1 << 7, raw dict.clone(), int 256],
+                0,
+                831,
+            ),
+            (
+                tvmasm!("DICTUDEL"),
+                tuple![int far_key(), raw dict.clone(), int 256],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTUMIN"),
+                tuple![raw dict.clone(), int 256],
+                0,
+                931,
+            ),
+            (
+                tvmasm!("DICTUMAX"),
+                tuple![raw dict.clone(), int 256],
+                0,
+                231,
+            ),
+            (
+                tvmasm!("DICTUGETNEXT"),
+                tuple![int 0, raw dict.clone(), int 256],
+                0,
+                1031,
+            ),
+            (
+                tvmasm!("DICTUGETPREV"),
+                tuple![int 1 << 7, raw dict.clone(), int 256],
+                0,
+                431,
+            ),
+        ];
+        for (i, (code, stack, exit_code, gas)) in cases.into_iter().enumerate() {
+            assert_eq!(run_dict_op(code, stack), (exit_code, gas), "case #{i}");
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn dict_key_kinds_gas() {
+        // Same layout as in `unbalanced_dict_gas`. Key `1 << 30` is also
+        // a valid signed key, all keys have the sign bit in the root label.
+        let dict = make_unbalanced_dict(32, 31, false);
+        let ref_dict = build_dict::<u32, Cell, _>(|d| {
+            d.set(0, Cell::empty_cell())?;
+            Ok(())
+        });
+
+        // NOTE: Gas is 26 for the opcode + 5 for the implicit RET (or 50 for
+        // an exception) + 100 per loaded cell + 500 per built cell.
+        let cases = [
+            // Signed keys follow the same paths.
+            (
+                tvmasm!("DICTIGET"),
+                tuple![int 0, raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+            (
+                tvmasm!("DICTIGET"),
+                tuple![int 1 << 30, raw dict.clone(), int 32],
+                0,
+                231,
+            ),
+            // Negative key diverges in the root label.
+            (
+                tvmasm!("DICTIGET"),
+                tuple![int -1, raw dict.clone(), int 32],
+                0,
+                131,
+            ),
+            // Keys out of range are not looked up.
+            (
+                tvmasm!("DICTIGET"),
+                tuple![int 1u64 << 31, raw dict.clone(), int 32],
+                0,
+                31,
+            ),
+            (
+                tvmasm!("DICTUGET"),
+                tuple![int -1, raw dict.clone(), int 32],
+                0,
+                31,
+            ),
+            (
+                tvmasm!("DICTISET"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 32],
+                0,
+                19231,
+            ),
+            // Root label is split.
+            (
+                tvmasm!("DICTISET"),
+                tuple![raw new_slice(1), int -1, raw dict.clone(), int 32],
+                0,
+                1631,
+            ),
+            (
+                tvmasm!("DICTIREPLACE"),
+                tuple![raw new_slice(1), int -1, raw dict.clone(), int 32],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTIADD"),
+                tuple![raw new_slice(1), int -1, raw dict.clone(), int 32],
+                0,
+                1631,
+            ),
+            (
+                tvmasm!("DICTIDEL"),
+                tuple![int 0, raw dict.clone(), int 32],
+                0,
+                18831,
+            ),
+            (
+                tvmasm!("DICTIDEL"),
+                tuple![int 1u64 << 31, raw dict.clone(), int 32],
+                0,
+                31,
+            ),
+            (
+                tvmasm!("DICTIMIN"),
+                tuple![raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+            (
+                tvmasm!("DICTIMAX"),
+                tuple![raw dict.clone(), int 32],
+                0,
+                231,
+            ),
+            // Slice keys follow the same paths.
+            (
+                tvmasm!("DICTGET"),
+                tuple![raw new_slice(0), raw dict.clone(), int 32],
+                0,
+                3231,
+            ),
+            (
+                tvmasm!("DICTGET"),
+                tuple![raw new_slice(1 << 30), raw dict.clone(), int 32],
+                0,
+                231,
+            ),
+            // Key is too short.
+            (
+                tvmasm!("DICTGET"),
+                tuple![raw make_zeros_slice(31), raw dict.clone(), int 32],
+                9,
+                76,
+            ),
+            (
+                tvmasm!("DICTSET"),
+                tuple![raw new_slice(1), raw new_slice(3), raw dict.clone(), int 32],
+                0,
+                19631,
+            ),
+            (
+                tvmasm!("DICTDEL"),
+                tuple![raw new_slice(0), raw dict.clone(), int 32],
+                0,
+                18831,
+            ),
+            // Found slice keys are built into new cells.
+            (
+                tvmasm!("DICTMIN"),
+                tuple![raw dict.clone(), int 32],
+                0,
+                3731,
+            ),
+            (tvmasm!("DICTMAX"), tuple![raw dict.clone(), int 32], 0, 731),
+            (
+                tvmasm!("DICTGETNEXT"),
+                tuple![raw new_slice(0), raw dict.clone(), int 32],
+                0,
+                3831,
+            ),
+            // Values without a single reference are not valid for `*REF` variants.
+            (
+                tvmasm!("DICTUGETREF"),
+                tuple![int 0, raw dict.clone(), int 32],
+                10,
+                3276,
+            ),
+            (
+                tvmasm!("DICTUGETREF"),
+                tuple![int 1 << 30, raw dict.clone(), int 32],
+                10,
+                276,
+            ),
+            (
+                tvmasm!("DICTUGETREF"),
+                tuple![int 0, raw ref_dict.clone(), int 32],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTUGETREF"),
+                tuple![int 5, raw ref_dict.clone(), int 32],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTIGETREF"),
+                tuple![int 0, raw ref_dict.clone(), int 32],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTGETREF"),
+                tuple![raw new_slice(0), raw ref_dict.clone(), int 32],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("DICTUSETREF"),
+                tuple![raw new_cell(1), int 0, raw dict.clone(), int 32],
+                0,
+                19231,
+            ),
+            (
+                tvmasm!("DICTSETREF"),
+                tuple![raw new_cell(1), raw new_slice(0), raw dict.clone(), int 32],
+                0,
+                19231,
+            ),
+            // Value must be a cell.
+            (
+                tvmasm!("DICTUSETREF"),
+                tuple![raw new_slice(1), int 0, raw dict.clone(), int 32],
+                7,
+                76,
+            ),
+        ];
+        for (i, (code, stack, exit_code, gas)) in cases.into_iter().enumerate() {
+            assert_eq!(run_dict_op(code, stack), (exit_code, gas), "case #{i}");
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn pfx_dict_gas() {
+        // Keys: `0` -> x{71}, `1` -> x{72}
+        let dict = make_pfx_dict();
+
+        // NOTE: Gas is 26 for the opcode + 5 for the implicit RET (or 50 for
+        // an exception) + 100 per loaded cell + 500 per built cell.
+        let cases = [
+            (
+                tvmasm!("PFXDICTGET"),
+                tuple![slice make_bits_slice("1011"), raw dict.clone(), int 8],
+                0,
+                231,
+            ),
+            // Key ends at the root fork.
+            (
+                tvmasm!("PFXDICTGET"),
+                tuple![slice make_bits_slice(""), raw dict.clone(), int 8],
+                9,
+                176,
+            ),
+            (
+                tvmasm!("PFXDICTGETQ"),
+                tuple![slice make_bits_slice(""), raw dict.clone(), int 8],
+                0,
+                131,
+            ),
+            (
+                tvmasm!("PFXDICTSET"),
+                tuple![
+                    slice make_bits_slice("11"),
+                    slice make_bits_slice("0"),
+                    null,
+                    int 8
+                ],
+                0,
+                531,
+            ),
+            // Existing key `1` is a prefix of the new key.
+            (
+                tvmasm!("PFXDICTSET"),
+                tuple![
+                    slice make_bits_slice("11"),
+                    slice make_bits_slice("10"),
+                    raw dict.clone(),
+                    int 8
+                ],
+                0,
+                231,
+            ),
+            // Keys longer than `n` are not looked up.
+            (
+                tvmasm!("PFXDICTSET"),
+                tuple![
+                    slice make_bits_slice("11"),
+                    slice make_bits_slice("101010101"),
+                    raw dict.clone(),
+                    int 8
+                ],
+                0,
+                31,
+            ),
+            // Leaf and its parent are rebuilt.
+            (
+                tvmasm!("PFXDICTREPLACE"),
+                tuple![
+                    slice make_bits_slice("11"),
+                    slice make_bits_slice("1"),
+                    raw dict.clone(),
+                    int 8
+                ],
+                0,
+                1231,
+            ),
+            (
+                tvmasm!("PFXDICTADD"),
+                tuple![
+                    slice make_bits_slice("11"),
+                    slice make_bits_slice("1"),
+                    raw dict.clone(),
+                    int 8
+                ],
+                0,
+                231,
+            ),
+            // Sibling is loaded and merged with the root.
+            (
+                tvmasm!("PFXDICTDEL"),
+                tuple![slice make_bits_slice("0"), raw dict.clone(), int 8],
+                0,
+                831,
+            ),
+            (
+                tvmasm!("PFXDICTDEL"),
+                tuple![slice make_bits_slice("11"), raw dict.clone(), int 8],
+                0,
+                231,
+            ),
+        ];
+        for (i, (code, stack, exit_code, gas)) in cases.into_iter().enumerate() {
+            assert_eq!(run_dict_op(code, stack), (exit_code, gas), "case #{i}");
+        }
+
+        // NOTE: `SUBDICT*` opcodes are not implemented yet, so they cost
+        // the same as any invalid opcode (10 + 50 for an exception).
+        for opcode in [0xf4b1u16, 0xf4b2, 0xf4b3, 0xf4b5, 0xf4b6, 0xf4b7] {
+            let code = CellBuilder::from_raw_data(&opcode.to_be_bytes(), 16)
+                .and_then(CellBuilder::build)
+                .unwrap();
+            let stack = tuple![raw new_slice(0), int 32, raw dict.clone(), int 32];
+            assert_eq!(
+                run_dict_op(&Boc::encode(code), stack),
+                (6, 60),
+                "opcode {opcode:04x}"
+            );
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn max_size_dict_values() {
+        // Label of a single `0u32` key takes 9 bits (`hml_same$11 v:0 n:6`).
+        let max_value_bits = 1023 - 9;
+
+        // Opcode + new leaf + implicit RET.
+        let (exit_code, gas) = run_dict_op(tvmasm!("DICTUSET"), tuple![
+            raw make_zeros_slice(max_value_bits),
+            int 0,
+            null,
+            int 32
+        ]);
+        assert_eq!((exit_code, gas), (0, 531));
+
+        // Opcode + exception.
+        let (exit_code, gas) = run_dict_op(tvmasm!("DICTUSET"), tuple![
+            raw make_zeros_slice(max_value_bits + 1),
+            int 0,
+            null,
+            int 32
+        ]);
+        assert_eq!((exit_code, gas), (8, 76)); // cell overflow
+    }
+
+    #[test]
+    #[traced_test]
+    fn pfx_dict_set_get() {
+        // Keys: `10` -> x{AA}, `0` -> x{BB}, `111` -> x{CC}
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{BB} PUSHSLICE b{0} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{CC} PUSHSLICE b{111} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE b{10110} SWAP INT 8 PFXDICTGETQ
+            "#,
+            [] => [
+                slice make_bits_slice("10"),
+                slice make_bits_slice("10101010"),
+                slice make_bits_slice("110"),
+                int -1,
+            ]
+        );
+
+        // Not found
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{CC} PUSHSLICE b{111} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE b{110} SWAP INT 8 PFXDICTGETQ
+            "#,
+            [] => [slice make_bits_slice("110"), int 0]
+        );
+        assert_run_vm!(
+            "PUSHSLICE b{110} NEWDICT INT 8 PFXDICTGET",
+            [] => [int 0],
+            exit_code: 9
+        );
+
+        // Keys must be prefix-free
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTSET DROP
+            DUP PUSHSLICE x{BB} PUSHSLICE b{1} ROT INT 8 PFXDICTSET NIP
+            SWAP DUP PUSHSLICE x{BB} PUSHSLICE b{1011} ROT INT 8 PFXDICTADD NIP
+            SWAP DUP PUSHSLICE x{BB} PUSHSLICE b{10} ROT INT 8 PFXDICTADD NIP
+            SWAP PUSHSLICE x{BB} PUSHSLICE b{10} ROT INT 8 PFXDICTREPLACE NIP
+            "#,
+            [] => [int 0, int 0, int 0, int -1]
+        );
+
+        // Keys longer than `n` are not allowed
+        assert_run_vm!(
+            "PUSHSLICE x{AA} PUSHSLICE b{101} NEWDICT INT 2 PFXDICTSET",
+            [] => [null, int 0]
+        );
+
+        // Replace and add
+        assert_run_vm!(
+            "PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTREPLACE",
+            [] => [null, int 0]
+        );
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTADD DROP
+            PUSHSLICE x{BB} PUSHSLICE b{10} ROT INT 8 PFXDICTREPLACE DROP
+            PUSHSLICE x{CC} PUSHSLICE b{1100} ROT INT 8 PFXDICTADD DROP
+            PUSHSLICE b{10} SWAP INT 8 PFXDICTGET
+            "#,
+            [] => [
+                slice make_bits_slice("10"),
+                slice make_bits_slice("10111011"),
+                slice make_bits_slice(""),
+            ]
+        );
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTADD DROP
+            PUSHSLICE x{BB} PUSHSLICE b{10} ROT INT 8 PFXDICTREPLACE DROP
+            PUSHSLICE x{CC} PUSHSLICE b{1100} ROT INT 8 PFXDICTADD DROP
+            PUSHSLICE b{11001} SWAP INT 8 PFXDICTGET
+            "#,
+            [] => [
+                slice make_bits_slice("1100"),
+                slice make_bits_slice("11001100"),
+                slice make_bits_slice("1"),
+            ]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn pfx_dict_delete() {
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{BB} PUSHSLICE b{0} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{CC} PUSHSLICE b{111} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE b{10} SWAP INT 8 PFXDICTDEL
+            SWAP PUSHSLICE b{10} SWAP INT 8 PFXDICTDEL
+            SWAP PUSHSLICE b{1} SWAP INT 8 PFXDICTDEL
+            SWAP PUSHSLICE b{1110} SWAP INT 8 PFXDICTGETQ
+            "#,
+            [] => [
+                int -1,
+                int 0,
+                int 0,
+                slice make_bits_slice("111"),
+                slice make_bits_slice("11001100"),
+                slice make_bits_slice("0"),
+                int -1,
+            ]
+        );
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{BB} PUSHSLICE b{0} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{CC} PUSHSLICE b{111} ROT INT 8 PFXDICTSET DROP
+            PUSHSLICE b{10} SWAP INT 8 PFXDICTDEL DROP
+            PUSHSLICE b{10110} SWAP INT 8 PFXDICTGETQ
+            "#,
+            [] => [slice make_bits_slice("10110"), int 0]
+        );
+
+        // Removing the last key results in an empty dictionary
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{AA} PUSHSLICE b{10} NEWDICT INT 8 PFXDICTSET DROP
+            PUSHSLICE b{10} SWAP INT 8 PFXDICTDEL
+            "#,
+            [] => [null, int -1]
+        );
+        assert_run_vm!("PUSHSLICE b{10} NEWDICT INT 8 PFXDICTDEL", [] => [null, int 0]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn pfx_dict_jumps() -> anyhow::Result<()> {
+        // Keys: `0` -> PUSHINT 1, `1` -> PUSHINT 2
+        let dict = {
+            let mut left = CellBuilder::new();
+            left.store_zeros(3)?;
+            left.store_u8(0x71)?;
+
+            let mut right = CellBuilder::new();
+            right.store_zeros(3)?;
+            right.store_u8(0x72)?;
+
+            let mut root = CellBuilder::new();
+            root.store_zeros(2)?;
+            root.store_bit_one()?;
+            root.store_reference(left.build()?)?;
+            root.store_reference(right.build()?)?;
+            root.build()?
+        };
+
+        assert_run_vm!(
+            r#"
+            PUSHSLICE x{71} PUSHSLICE b{0} NEWDICT INT 8 PFXDICTSET DROP
+            PUSHSLICE x{72} PUSHSLICE b{1} ROT INT 8 PFXDICTSET
+            "#,
+            [] => [cell dict.clone(), int -1]
+        );
+
+        assert_run_vm!(
+            "PFXDICTGETJMP PUSHINT 3",
+            [slice make_bits_slice("1011"), cell dict.clone(), int 8] => [
+                slice make_bits_slice("1"),
+                slice make_bits_slice("011"),
+                int 2,
+            ]
+        );
+        assert_run_vm!(
+            "PFXDICTGETEXEC PUSHINT 3",
+            [slice make_bits_slice("0"), cell dict.clone(), int 8] => [
+                slice make_bits_slice("0"),
+                slice make_bits_slice(""),
+                int 1,
+                int 3,
+            ]
+        );
+        assert_run_vm!(
+            "PFXDICTGETJMP PUSHINT 3",
+            [slice make_bits_slice(""), cell dict.clone(), int 8] => [
+                slice make_bits_slice(""),
+                int 3,
+            ]
+        );
+        assert_run_vm!(
+            "PFXDICTGETEXEC PUSHINT 3",
+            [slice make_bits_slice(""), cell dict.clone(), int 8] => [int 0],
+            exit_code: 9
+        );
+
+        // PFXDICTSWITCH
+        let code = {
+            let mut b = CellBuilder::new();
+            b.store_uint(0xf4ac08, 24)?;
+            b.store_reference(dict)?;
+            b.store_u8(0x73)?; // PUSHINT 3
+            b.build()?
+        };
+
+        for (key, expected) in [
+            ("1011", vec![make_bits_slice("1"), make_bits_slice("011")]),
+            ("", vec![make_bits_slice("")]),
+        ] {
+            let mut vm = VmState::builder()
+                .with_code(code.clone())
+                .with_stack([SafeRc::new_dyn_value(make_bits_slice(key))])
+                .with_gas(GasParams::getter())
+                .build();
+            assert_eq!(!vm.run(), 0);
+
+            let stack = &vm.stack.items;
+            assert_eq!(stack.len(), expected.len() + 1);
+            for (item, expected) in std::iter::zip(stack, &expected) {
+                let item = item.as_cell_slice().map(ToString::to_string);
+                assert_eq!(item, Some(expected.to_string()));
+            }
+
+            let last = stack.last().and_then(|item| item.as_int());
+            let last = last.map(|int| i32::try_from(int).unwrap());
+            assert_eq!(last, Some(if key.is_empty() { 3 } else { 2 }));
+        }
+
+        Ok(())
+    }
+
+    fn make_bits_slice(bits: &str) -> OwnedCellSlice {
+        let mut builder = CellBuilder::new();
+        for bit in bits.chars() {
+            builder.store_bit(bit == '1').unwrap();
+        }
+        OwnedCellSlice::new_allow_exotic(builder.build().unwrap())
+    }
+
+    /// Builds a dict with `key_bits`-bit keys `0` and `1 << i` for `i` in `0..forks`.
+    ///
+    /// All keys share a `key_bits - forks` bit prefix, so the root has a deep label.
+    /// The path to the key `0` contains all `forks` forks, while the path to the key
+    /// `1 << (forks - 1)` contains only the root fork. With `max_values` each value
+    /// takes all bits left in its leaf (32 bits otherwise).
+    fn make_unbalanced_dict(key_bits: u16, forks: u16, max_values: bool) -> RcStackValue {
+        let keys = std::iter::once(BigInt::from(0))
+            .chain((0..forks).map(|i| BigInt::from(1) << i))
+            .collect::<Vec<_>>();
+
+        let insert = |root: &mut Option<Cell>, key: &BigInt, value_bits: u16, mode: SetMode| {
+            let mut cb = CellDataBuilder::new();
+            cb.store_bigint(key, key_bits, false).unwrap();
+            let mut value = CellBuilder::new();
+            value.store_zeros(value_bits).unwrap();
+
+            let mut key = cb.as_data_slice();
+            let value = value.as_full_slice();
+            dict::dict_insert(
+                root,
+                &mut key,
+                key_bits,
+                &value,
+                mode,
+                Cell::empty_context(),
+            )
+            .is_ok()
+        };
+
+        let mut root = None;
+        for key in &keys {
+            assert!(insert(&mut root, key, 32, SetMode::Set));
+        }
+
+        // NOTE: Labels are final only after all keys are inserted.
+        if max_values {
+            for key in &keys {
+                let fits = (0..=1023)
+                    .rev()
+                    .any(|bits| insert(&mut root, key, bits, SetMode::Replace));
+                assert!(fits);
+            }
+        }
+
+        SafeRc::new_dyn_value(root.unwrap())
+    }
+
+    /// Builds a prefix dict with 8-bit keys `0` -> x{71} and `1` -> x{72}.
+    fn make_pfx_dict() -> RcStackValue {
+        let leaf = |value: u8| {
+            let mut b = CellBuilder::new();
+            b.store_zeros(3).unwrap();
+            b.store_u8(value).unwrap();
+            b.build().unwrap()
+        };
+
+        let mut root = CellBuilder::new();
+        root.store_zeros(2).unwrap();
+        root.store_bit_one().unwrap();
+        root.store_reference(leaf(0x71)).unwrap();
+        root.store_reference(leaf(0x72)).unwrap();
+        SafeRc::new_dyn_value(root.build().unwrap())
+    }
+
+    fn make_zeros_slice(bits: u16) -> RcStackValue {
+        let mut builder = CellBuilder::new();
+        builder.store_zeros(bits).unwrap();
+        SafeRc::new_dyn_value(OwnedCellSlice::new_allow_exotic(builder.build().unwrap()))
+    }
+
+    fn run_dict_op(code: &[u8], stack: Vec<RcStackValue>) -> (i32, u64) {
+        let code = Boc::decode(code).unwrap();
+        let mut vm = VmState::builder()
+            .with_code(code)
+            .with_stack(stack)
+            .with_gas(GasParams::getter())
+            .build();
+        let exit_code = !vm.run();
+        (exit_code, vm.gas.consumed())
+    }
+
     fn new_slice(value: i32) -> RcStackValue {
         let mut builder = CellBuilder::new();
         builder.store_u32(value as u32).unwrap();
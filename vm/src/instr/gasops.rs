@@ -1,14 +1,15 @@
+use everscale_types::cell::Size;
 use num_traits::{Signed, ToPrimitive};
 use tycho_vm_proc::vm_module;
 
 use crate::cont::QuitCont;
 use crate::error::VmResult;
 use crate::saferc::SafeRc;
-use crate::state::VmState;
+use crate::state::{AcceptInfo, VmState};
 
 pub struct GasOps;
 
-#[vm_module]
+#[vm_module(category = "gas")]
 impl GasOps {
     #[op(code = "f800", fmt = "ACCEPT")]
     fn exec_accept(st: &mut VmState) -> VmResult<i32> {
@@ -99,9 +100,22 @@ impl GasOps {
 }
 
 fn exec_set_gas(st: &mut VmState, gas_limit: u64) -> VmResult<i32> {
+    // NOTE: All gas limit setters are 16-bit opcodes.
+    const OPCODE_BITS: u16 = 16;
+
     vm_ensure!(gas_limit >= st.gas.consumed(), OutOfGas);
     st.gas.set_limit(gas_limit);
     if st.modifiers.stop_on_accept {
+        let Size { bits, refs } = st.code.range().offset();
+        st.accept_info = Some(AcceptInfo {
+            code: st.code.cell().clone(),
+            offset: Size {
+                bits: bits.saturating_sub(OPCODE_BITS),
+                refs,
+            },
+            gas_consumed: st.gas.consumed(),
+            stack: st.stack.clone(),
+        });
         st.jump(SafeRc::from(QuitCont { exit_code: 0 }))
     } else {
         Ok(0)
@@ -114,6 +128,9 @@ mod tests {
     use num_bigint::BigInt;
     use tracing_test::traced_test;
 
+    use super::*;
+    use crate::gas::GasParams;
+    use crate::state::BehaviourModifiers;
     use crate::IntoCode;
 
     #[test]
@@ -181,4 +198,37 @@ mod tests {
             [int 0, slice child_code] => [int 106, int 0, int 367],
         );
     }
+
+    #[test]
+    #[traced_test]
+    fn stop_on_accept() {
+        let code = Boc::decode(tvmasm!("INT 10 INT 20 ACCEPT ADD")).unwrap();
+
+        let mut vm = VmState::builder()
+            .with_code(code.clone())
+            .with_gas(GasParams {
+                max: 1000,
+                limit: 0,
+                credit: 1000,
+                ..GasParams::getter()
+            })
+            .with_modifiers(BehaviourModifiers {
+                stop_on_accept: true,
+                ..Default::default()
+            })
+            .build();
+        vm.run();
+
+        let info = vm
+            .accept_info
+            .as_ref()
+            .expect("accept info must be collected");
+        assert_eq!(info.code, code);
+        assert_eq!(info.offset, Size { bits: 24, refs: 0 }); // pushint(8), pushint(16)
+        assert_eq!(info.gas_consumed, 18 + 26 + 26); // pushint, pushint, accept
+        assert_eq!(info.stack.items.len(), 2);
+
+        // ADD must not be executed.
+        assert_eq!(vm.stack.items.len(), 2);
+    }
 }
@@ -5,16 +5,17 @@ use tycho_vm_proc::vm_module;
 
 use crate::error::VmResult;
 use crate::saferc::SafeRc;
+use crate::stack::StackGuard;
 use crate::state::VmState;
 
 pub struct LogicOps;
 
-#[vm_module]
+#[vm_module(category = "arith")]
 impl LogicOps {
     #[op(code = "aayy", fmt = "LSHIFT {y}", args(y = (args & 0xff) + 1, quiet = false))]
     #[op(code = "b7aayy", fmt = "QLSHIFT {y}", args(y = (args & 0xff) + 1, quiet = true))]
     fn exec_lshift_tinyint8(st: &mut VmState, y: u32, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 *SafeRc::make_mut(&mut x) <<= y;
@@ -29,7 +30,7 @@ impl LogicOps {
     #[op(code = "abyy", fmt = "RSHIFT {y}", args(y = (args & 0xff) + 1, quiet = false))]
     #[op(code = "b7abyy", fmt = "QRSHIFT {y}", args(y = (args & 0xff) + 1, quiet = true))]
     fn exec_rshift_tinyint8(st: &mut VmState, y: u32, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 *SafeRc::make_mut(&mut x) >>= y;
@@ -44,7 +45,7 @@ impl LogicOps {
     #[op(code = "ac", fmt = "LSHIFT", args(quiet = false))]
     #[op(code = "b7ac", fmt = "QLSHIFT", args(quiet = true))]
     fn exec_lshift(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_smallint_range(0, 1023));
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
@@ -60,7 +61,7 @@ impl LogicOps {
     #[op(code = "ad", fmt = "RSHIFT", args(quiet = false))]
     #[op(code = "b7ad", fmt = "QRSHIFT", args(quiet = true))]
     fn exec_rshift(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_smallint_range(0, 1023));
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
@@ -76,7 +77,7 @@ impl LogicOps {
     #[op(code = "ae", fmt = "POW2", args(quiet = false))]
     #[op(code = "b7ae", fmt = "QPOW2", args(quiet = true))]
     fn exec_pow2(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_smallint_range(0, 1023));
         ok!(stack.push_raw_int(SafeRc::new(BigInt::from(1) << y), quiet));
         Ok(0)
@@ -85,7 +86,7 @@ impl LogicOps {
     #[op(code = "b0", fmt = "AND", args(quiet = false))]
     #[op(code = "b7b0", fmt = "QAND", args(quiet = true))]
     fn exec_and(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -107,7 +108,7 @@ impl LogicOps {
     #[op(code = "b1", fmt = "OR", args(quiet = false))]
     #[op(code = "b7b1", fmt = "QOR", args(quiet = true))]
     fn exec_or(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -131,7 +132,7 @@ impl LogicOps {
     #[op(code = "b2", fmt = "XOR", args(quiet = false))]
     #[op(code = "b7b2", fmt = "QXOR", args(quiet = true))]
     fn exec_xor(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_int_or_nan());
         let x = ok!(stack.pop_int_or_nan());
         match (x, y) {
@@ -148,7 +149,7 @@ impl LogicOps {
     #[op(code = "b3", fmt = "NOT", args(quiet = false))]
     #[op(code = "b7b3", fmt = "QNOT", args(quiet = true))]
     fn exec_not(st: &mut VmState, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(mut x) => {
                 {
@@ -168,7 +169,7 @@ impl LogicOps {
     #[op(code = "b5yy", fmt = "UFITS {y}", args(y = (args & 0xff) + 1, s = false, quiet = false))]
     #[op(code = "b7b5yy", fmt = "QUFITS {y}", args(y = (args & 0xff) + 1, s = false, quiet = true))]
     fn exec_fits_tinyint8(st: &mut VmState, y: u32, s: bool, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(x) if x.has_correct_sign(s) && x.bitsize(s) as u32 <= y => {
                 ok!(stack.push_raw(x));
@@ -184,7 +185,7 @@ impl LogicOps {
     #[op(code = "b601", fmt = "UFITSX", args(s = false, quiet = false))]
     #[op(code = "b7b601", fmt = "QUFITSX", args(s = false, quiet = true))]
     fn exec_fits(st: &mut VmState, s: bool, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         let y = ok!(stack.pop_smallint_range(0, 1023));
         match ok!(stack.pop_int_or_nan()) {
             Some(x) if x.has_correct_sign(s) && x.bitsize(s) as u32 <= y => {
@@ -201,7 +202,7 @@ impl LogicOps {
     #[op(code = "b603", fmt = "UBITSIZE", args(s = false, quiet = false))]
     #[op(code = "b7b603", fmt = "QUBITSIZE", args(s = false, quiet = true))]
     fn exec_bitsize(st: &mut VmState, s: bool, quiet: bool) -> VmResult<i32> {
-        let stack = SafeRc::make_mut(&mut st.stack);
+        let mut stack = StackGuard::new(&mut st.stack);
         match ok!(stack.pop_int_or_nan()) {
             Some(x) => {
                 if !x.has_correct_sign(s) {
@@ -235,6 +236,9 @@ mod tests {
         assert_run_vm!("PUSHPOW2 255 LSHIFT# 10", [] => [int 0], exit_code: 4);
         assert_run_vm!("RSHIFT# 5", [int 32] => [int 1]);
         assert_run_vm!("RSHIFT# 10", [int 1] => [int 0]);
+        assert_run_vm!("LSHIFT# 5", [nan] => [int 0], exit_code: 4);
+        assert_run_vm!("QUIET LSHIFT# 5", [nan] => [nan]);
+        assert_run_vm!("QUIET RSHIFT# 5", [nan] => [nan]);
 
         assert_run_vm!("RSHIFT", [int 32, int 5] => [int 1]);
         assert_run_vm!("RSHIFT", [int 5] => [int 0], exit_code: 2);
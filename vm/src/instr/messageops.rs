@@ -15,23 +15,23 @@ use crate::cont::ControlRegs;
 use crate::error::VmResult;
 use crate::gas::GasConsumer;
 use crate::saferc::SafeRc;
-use crate::smc_info::{SmcInfoBase, SmcInfoTonV4, SmcInfoTonV6, VmVersion};
+use crate::smc_info::{SmcInfoBase, SmcInfoTonV4, SmcInfoTonV6, UnpackedConfig, VmVersion};
 use crate::stack::{Stack, Tuple, TupleExt};
-use crate::state::VmState;
+use crate::state::{SendMsgEstimate, VmState};
 use crate::util::{load_uint_leq, OwnedCellSlice};
 
 pub struct MessageOps;
 
-#[vm_module]
+#[vm_module(category = "message")]
 impl MessageOps {
     #[op(code = "fb00", fmt = "SENDRAWMSG")]
     fn exec_send_message_raw(st: &mut VmState) -> VmResult<i32> {
         let stack = SafeRc::make_mut(&mut st.stack);
-        let mode = ok!(stack.pop_smallint_range(0, 255)) as u8;
+        let mode = ok!(pop_send_msg_mode_raw(stack));
         let cell = ok!(stack.pop_cell());
 
         add_action(&mut st.cr, &st.gas, OutAction::SendMsg {
-            mode: SendMsgFlags::from_bits_retain(mode),
+            mode,
             out_msg: Lazy::from_raw(SafeRc::unwrap_or_clone(cell))?,
         })
     }
@@ -182,7 +182,7 @@ impl MessageOps {
                 let limits = SizeLimitsConfig::load_from(&mut cs.apply())?;
                 limits.max_msg_cells
             }
-            None => 1 << 13,
+            None => UnpackedConfig::DEFAULT_MAX_MSG_CELLS,
         };
         let mut stats = {
             let mut stats = StorageStat::with_limit(max_cells as _);
@@ -317,21 +317,36 @@ impl MessageOps {
         }
 
         // Adjust layout for body.
+        let mut body_to_cell = msg_layout.body_to_cell;
         if !msg_layout.body_to_cell
             && (ok!(compute_msg_root_bits(&msg_layout, fwd_fee, ihr_fee)) > cell::MAX_BIT_LEN
                 || compute_msg_root_refs(&msg_layout) > cell::MAX_REF_COUNT)
         {
-            // msg_layout.body_to_cell = true;
+            // NOTE: Layout is not updated here to compute the same
+            // root bits as the reference implementation.
+            body_to_cell = true;
             stats.bit_count += msg.body.size_bits() as u64;
             stats.cell_count += 1;
             update_fees(stats, &mut fwd_fee, &mut ihr_fee);
         }
 
         // Push the total fee to the stack.
-        ok!(stack.push_int(fwd_fee.into_inner().saturating_add(ihr_fee.into_inner())));
+        let total_fee = Tokens::new(fwd_fee.into_inner().saturating_add(ihr_fee.into_inner()));
+        ok!(stack.push_int(total_fee.into_inner()));
 
         // Done
         if send {
+            if st.modifiers.record_send_msg_estimates {
+                st.send_msg_estimates.push(SendMsgEstimate {
+                    msg_hash: *raw_msg_cell.repr_hash(),
+                    total_fee,
+                    layout: MessageLayout {
+                        init_to_cell: msg_layout.init_to_cell,
+                        body_to_cell,
+                    },
+                });
+            }
+
             drop(msg_cell);
             add_action(&mut st.cr, &st.gas, OutAction::SendMsg {
                 mode,
@@ -343,6 +358,16 @@ impl MessageOps {
     }
 }
 
+/// Pops a mode for `SENDRAWMSG`.
+///
+/// Unlike `SENDMSG`, there are no extended bits here (including the dry-run
+/// bit), so any mode above 255 is a range check error in all versions.
+/// Unknown bits below 256 are kept as is and validated by the action phase.
+fn pop_send_msg_mode_raw(stack: &mut Stack) -> VmResult<SendMsgFlags> {
+    let raw_mode = ok!(stack.pop_smallint_range(0, 255));
+    Ok(SendMsgFlags::from_bits_retain(raw_mode as u8))
+}
+
 /// Returns a tuple of mode and `send` flag.
 fn pop_send_msg_mode_ext(stack: &mut Stack) -> VmResult<(SendMsgFlags, bool)> {
     const DRY_RUN_BIT: u32 = 1 << 10;
@@ -377,6 +402,8 @@ fn pop_change_library_mode(version: VmVersion, stack: &mut Stack) -> VmResult<Ch
     Ok(ChangeLibraryMode::from_bits_retain(mode as u8))
 }
 
+/// Returns a sign-extended workchain of an internal address
+/// (`int8` for `addr_std` and `int32` for `addr_var`).
 fn parse_addr_workchain(addr: &OwnedCellSlice) -> VmResult<i32> {
     let mut cs = addr.apply();
     if !cs.load_bit()? {
@@ -453,16 +480,85 @@ fn add_action(regs: &mut ControlRegs, gas: &GasConsumer, action: OutAction) -> V
 mod tests {
     use everscale_types::cell::{Cell, CellBuilder};
     use everscale_types::models::{
-        Account, AccountState, CurrencyCollection, IntAddr, OwnedMessage,
+        Account, AccountState, Anycast, CurrencyCollection, IntAddr, OwnedMessage, StdAddr, VarAddr,
     };
-    use everscale_types::prelude::{Boc, Load};
+    use everscale_types::num::{SplitDepth, Uint9};
+    use everscale_types::prelude::{Boc, HashBytes, Load};
     use tracing_test::traced_test;
 
     use crate::gas::GasParams;
-    use crate::smc_info::SmcInfoBase;
+    use crate::saferc::SafeRc;
+    use crate::smc_info::{CustomSmcInfo, SmcInfoBase, VmVersion};
     use crate::state::VmState;
     use crate::util::OwnedCellSlice;
 
+    #[test]
+    fn parse_addr_workchain_boundaries() -> anyhow::Result<()> {
+        fn parse(addr: IntAddr) -> i32 {
+            let cell = CellBuilder::build_from(addr).unwrap();
+            super::parse_addr_workchain(&OwnedCellSlice::new_allow_exotic(cell)).unwrap()
+        }
+
+        for workchain in [i8::MIN, -1, 0, 1, i8::MAX] {
+            let addr = StdAddr::new(workchain, HashBytes([0x11; 32]));
+            assert_eq!(parse(addr.clone().into()), workchain as i32);
+
+            let addr = StdAddr {
+                anycast: Some(Box::new(Anycast {
+                    depth: SplitDepth::new(30)?,
+                    rewrite_prefix: vec![0xff; 4],
+                })),
+                ..addr
+            };
+            assert_eq!(parse(addr.into()), workchain as i32);
+        }
+
+        for workchain in [i32::MIN, -1, 0, 0x7f, 0x80, 0x7fffffff] {
+            for (address_len, address) in [(0, vec![]), (64, vec![0xaa; 8]), (511, vec![0xff; 64])]
+            {
+                let addr = VarAddr {
+                    anycast: None,
+                    address_len: Uint9::new(address_len),
+                    workchain,
+                    address,
+                };
+                assert_eq!(parse(addr.into()), workchain);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn send_raw_msg_mode_range() -> anyhow::Result<()> {
+        let code = Boc::decode(tvmasm!("PUSHREF x{} SWAP SENDRAWMSG"))?;
+
+        for version in [VmVersion::Ton(3), VmVersion::Ton(4), VmVersion::LATEST_TON] {
+            for (mode, expected_exit_code) in [(0, 0), (0b1100, 0), (255, 0), (256, 5), (1024, 5)] {
+                let mut output = crate::tests::TracingOutput::default();
+                let mut vm_state = VmState::builder()
+                    .with_smc_info(CustomSmcInfo {
+                        version,
+                        c7: SafeRc::new(tuple![]),
+                    })
+                    .with_stack(tuple![int mode])
+                    .with_code(code.clone())
+                    .with_gas(GasParams::getter())
+                    .with_debug(&mut output)
+                    .build();
+
+                assert_eq!(
+                    !vm_state.run(),
+                    expected_exit_code,
+                    "version: {version:?}, mode: {mode}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     #[test]
     #[traced_test]
     fn send_msg_test() -> anyhow::Result<()> {
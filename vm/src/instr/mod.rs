@@ -1,8 +1,11 @@
 use std::sync::OnceLock;
 
+use ahash::HashMap;
 use anyhow::Result;
 
 use self::arithops::ArithOps;
+#[cfg(feature = "bls")]
+use self::blsops::BlsOps;
 use self::cellops::CellOps;
 use self::cmpops::CmpOps;
 use self::configops::ConfigOps;
@@ -14,13 +17,21 @@ use self::dictops::DictOps;
 use self::gasops::GasOps;
 use self::logicops::LogicOps;
 use self::messageops::MessageOps;
+#[cfg(feature = "p256")]
+use self::p256ops::P256Ops;
 use self::randops::RandOps;
+use self::rist255ops::Rist255Ops;
+use self::runvmops::RunVmOps;
+#[cfg(feature = "secp256k1")]
+use self::secp256k1ops::Secp256k1Ops;
 use self::sizeops::SizeOps;
 use self::stackops::StackOps;
 use self::tupleops::TupleOps;
 use crate::dispatch::{DispatchTable, Opcodes};
 
 mod arithops;
+#[cfg(feature = "bls")]
+mod blsops;
 mod cellops;
 mod cmpops;
 mod configops;
@@ -32,7 +43,13 @@ mod dictops;
 mod gasops;
 mod logicops;
 mod messageops;
+#[cfg(feature = "p256")]
+mod p256ops;
 mod randops;
+mod rist255ops;
+mod runvmops;
+#[cfg(feature = "secp256k1")]
+mod secp256k1ops;
 mod sizeops;
 mod stackops;
 mod tupleops;
@@ -45,31 +62,83 @@ pub fn codepage(n: u16) -> Option<&'static DispatchTable> {
     }
 }
 
-/// Default codepage.
-pub fn codepage0() -> &'static DispatchTable {
-    fn build() -> Result<DispatchTable> {
-        let mut cp = DispatchTable::builder(0);
-        ArithOps.init(&mut cp)?;
-        CmpOps.init(&mut cp)?;
-        LogicOps.init(&mut cp)?;
-        CellOps.init(&mut cp)?;
-        ContOps.init(&mut cp)?;
-        StackOps.init(&mut cp)?;
-        TupleOps.init(&mut cp)?;
-        DebugOps.init(&mut cp)?;
-        DictOps.init(&mut cp)?;
-        GasOps.init(&mut cp)?;
-        RandOps.init(&mut cp)?;
-        ConfigOps.init(&mut cp)?;
-        MessageOps.init(&mut cp)?;
-        CryptOps.init(&mut cp)?;
-        CurrencyOps.init(&mut cp)?;
-        SizeOps.init(&mut cp)?;
-        Ok(cp.build())
+/// Registry of additional codepages.
+///
+/// Registered codepages take precedence over the builtin ones,
+/// so the default codepage can also be replaced.
+#[derive(Default, Clone)]
+pub struct Codepages {
+    tables: HashMap<u16, &'static DispatchTable>,
+}
+
+impl Codepages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a codepage with its id.
+    ///
+    /// Returns the previously registered codepage with the same id.
+    pub fn register(&mut self, cp: &'static DispatchTable) -> Option<&'static DispatchTable> {
+        self.tables.insert(cp.id(), cp)
+    }
+
+    /// Removes the registered codepage.
+    pub fn unregister(&mut self, id: u16) -> Option<&'static DispatchTable> {
+        self.tables.remove(&id)
+    }
+
+    /// Resolves a registered or a builtin codepage.
+    pub fn get(&self, id: u16) -> Option<&'static DispatchTable> {
+        match self.tables.get(&id) {
+            Some(cp) => Some(*cp),
+            None => codepage(id),
+        }
     }
+}
+
+impl std::fmt::Debug for Codepages {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.tables.keys()).finish()
+    }
+}
 
+/// Default codepage.
+pub fn codepage0() -> &'static DispatchTable {
     static CP0: OnceLock<DispatchTable> = OnceLock::new();
-    CP0.get_or_init(|| build().unwrap())
+    CP0.get_or_init(|| default_opcodes(0).unwrap().build())
+}
+
+/// Builder with all opcodes of the default codepage.
+///
+/// Can be used to extend or override the default instruction set.
+pub fn default_opcodes(id: u16) -> Result<Opcodes> {
+    let mut cp = DispatchTable::builder(id);
+    ArithOps.init(&mut cp)?;
+    CmpOps.init(&mut cp)?;
+    LogicOps.init(&mut cp)?;
+    CellOps.init(&mut cp)?;
+    ContOps.init(&mut cp)?;
+    RunVmOps.init(&mut cp)?;
+    StackOps.init(&mut cp)?;
+    TupleOps.init(&mut cp)?;
+    DebugOps.init(&mut cp)?;
+    DictOps.init(&mut cp)?;
+    GasOps.init(&mut cp)?;
+    RandOps.init(&mut cp)?;
+    ConfigOps.init(&mut cp)?;
+    MessageOps.init(&mut cp)?;
+    CryptOps.init(&mut cp)?;
+    Rist255Ops.init(&mut cp)?;
+    #[cfg(feature = "secp256k1")]
+    Secp256k1Ops.init(&mut cp)?;
+    #[cfg(feature = "p256")]
+    P256Ops.init(&mut cp)?;
+    #[cfg(feature = "bls")]
+    BlsOps.init(&mut cp)?;
+    CurrencyOps.init(&mut cp)?;
+    SizeOps.init(&mut cp)?;
+    Ok(cp)
 }
 
 trait Module {
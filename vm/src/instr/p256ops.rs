@@ -0,0 +1,196 @@
+use everscale_types::error::Error;
+use num_bigint::Sign;
+use tycho_vm_proc::vm_module;
+
+use crate::error::VmResult;
+use crate::gas::GasConsumer;
+use crate::saferc::SafeRc;
+use crate::signature::{
+    DefaultSignatureBackend, SignatureAlgorithm, SignatureBackend, SignatureContext,
+};
+use crate::state::VmState;
+
+pub struct P256Ops;
+
+#[vm_module(category = "crypto")]
+impl P256Ops {
+    #[op(code = "f914", fmt = "P256_CHKSIGNU", args(from_slice = false))]
+    #[op(code = "f915", fmt = "P256_CHKSIGNS", args(from_slice = true))]
+    fn exec_p256_check_signature(st: &mut VmState, from_slice: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let key_cs = ok!(stack.pop_cs());
+        let signature_cs = ok!(stack.pop_cs());
+
+        let mut data = [0; 128];
+        let data_len = if from_slice {
+            let cs = ok!(stack.pop_cs());
+            let mut cs = cs.apply();
+
+            let cs_bits = cs.size_bits();
+            vm_ensure!(cs_bits % 8 == 0, CellError(Error::CellUnderflow));
+            cs.load_raw(&mut data, cs_bits)?;
+
+            (cs_bits / 8) as usize
+        } else {
+            let int = ok!(stack.pop_int());
+            vm_ensure!(
+                int.sign() != Sign::Minus && int.bits() <= 256,
+                IntegerOutOfRange {
+                    min: 0,
+                    max: isize::MAX,
+                    actual: int.to_string(),
+                }
+            );
+
+            let bytes = int.magnitude().to_bytes_be();
+            data[32 - bytes.len()..32].copy_from_slice(&bytes);
+
+            32
+        };
+
+        let mut signature = [0; 64];
+        signature_cs.apply().load_raw(&mut signature, 512)?;
+
+        // NOTE: Public key is in the compressed SEC1 form.
+        let mut key = [0; 33];
+        key_cs.apply().load_raw(&mut key, 264)?;
+
+        st.gas.try_consume(GasConsumer::P256_CHK_SGN_GAS_PRICE)?;
+
+        let ctx = SignatureContext {
+            algorithm: SignatureAlgorithm::P256,
+            data: &data[..data_len],
+            public_key: &key,
+            signature: &signature,
+            signature_id: None,
+        };
+        let is_valid = match st.signature_backend.as_deref_mut() {
+            Some(backend) => backend.verify(&ctx),
+            None => DefaultSignatureBackend.verify(&ctx),
+        };
+
+        ok!(stack.push_bool(is_valid || st.modifiers.chksig_always_succeed));
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::prelude::*;
+    use num_bigint::{BigInt, Sign};
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::{Signature, SigningKey};
+    use sha2::Digest;
+    use tracing_test::traced_test;
+
+    use crate::saferc::SafeRc;
+    use crate::stack::RcStackValue;
+    use crate::util::OwnedCellSlice;
+    use crate::{BehaviourModifiers, VmState};
+
+    #[test]
+    #[traced_test]
+    fn p256_chksign() {
+        let key = SigningKey::from_slice(&[0x33; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true);
+
+        let data = [0xda_u8; 40];
+        let data_signature: Signature = key.sign(&data);
+
+        let data_hash = sha2::Sha256::digest(data);
+        let data_hash_signature: Signature = key.sign(&data_hash);
+
+        assert_run_vm!(
+            "P256_CHKSIGNS",
+            [
+                raw build_slice(data),
+                raw build_slice(data_signature.to_bytes()),
+                raw build_slice(pubkey.as_bytes()),
+            ] => [int -1]
+        );
+        assert_run_vm!(
+            "P256_CHKSIGNU",
+            [
+                raw build_int(data_hash),
+                raw build_slice(data_hash_signature.to_bytes()),
+                raw build_slice(pubkey.as_bytes()),
+            ] => [int -1]
+        );
+
+        // Invalid signature
+        assert_run_vm!(
+            "P256_CHKSIGNS",
+            [
+                raw build_slice(data),
+                raw build_slice(data_hash_signature.to_bytes()), // <--
+                raw build_slice(pubkey.as_bytes()),
+            ] => [int 0]
+        );
+        assert_run_vm!(
+            "P256_CHKSIGNU",
+            [
+                int 123,
+                raw build_slice(data_hash_signature.to_bytes()),
+                raw build_slice(pubkey.as_bytes()),
+            ] => [int 0]
+        );
+
+        // Invalid pubkey
+        assert_run_vm!(
+            "P256_CHKSIGNS",
+            [
+                raw build_slice(data),
+                raw build_slice(data_signature.to_bytes()),
+                raw build_slice([0xff; 33]),
+            ] => [int 0]
+        );
+
+        // Not enough bits for the pubkey
+        assert_run_vm!(
+            "P256_CHKSIGNS",
+            [
+                raw build_slice(data),
+                raw build_slice(data_signature.to_bytes()),
+                raw build_slice(&pubkey.as_bytes()[..32]),
+            ] => [int 0],
+            exit_code: 9
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn p256_chksign_always_succeed() {
+        let code = Boc::decode(tvmasm!("P256_CHKSIGNS")).unwrap();
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_stack([
+                build_slice([0xda; 40]),
+                build_slice([0x11; 64]),
+                build_slice([0xff; 33]),
+            ])
+            .with_modifiers(BehaviourModifiers {
+                chksig_always_succeed: true,
+                ..Default::default()
+            })
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        assert_eq!(state.stack.items.len(), 1);
+        assert_eq!(state.stack.get_exit_arg(), Some(-1));
+    }
+
+    fn build_slice<T: AsRef<[u8]>>(data: T) -> RcStackValue {
+        let data = data.as_ref();
+        let b = CellBuilder::from_raw_data(data, data.len() as u16 * 8).unwrap();
+        SafeRc::new_dyn_value(OwnedCellSlice::new_allow_exotic(b.build().unwrap()))
+    }
+
+    fn build_int<T: AsRef<[u8]>>(data: T) -> RcStackValue {
+        SafeRc::new_dyn_value(BigInt::from_bytes_be(Sign::Plus, data.as_ref()))
+    }
+}
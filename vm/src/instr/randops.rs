@@ -1,11 +1,11 @@
 use everscale_types::cell::HashBytes;
 use num_bigint::{BigInt, Sign};
-use sha2::Digest;
 use tycho_vm_proc::vm_module;
 
 use crate::cont::ControlRegs;
 use crate::error::VmResult;
 use crate::gas::GasConsumer;
+use crate::rng::{DefaultRng, RngProvider};
 use crate::saferc::SafeRc;
 use crate::smc_info::SmcInfoBase;
 use crate::stack::{Stack, StackValueType};
@@ -13,12 +13,16 @@ use crate::state::VmState;
 
 pub struct RandOps;
 
-#[vm_module]
+#[vm_module(category = "config")]
 impl RandOps {
     #[op(code = "f810", fmt = "RANDU256")]
     fn exec_randu256(st: &mut VmState) -> VmResult<i32> {
         let stack = SafeRc::make_mut(&mut st.stack);
-        let random_bytes = ok!(generate_random_u256(&mut st.cr, &st.gas));
+        let random_bytes = ok!(generate_random_u256(
+            &mut st.cr,
+            &st.gas,
+            st.rng.as_deref_mut()
+        ));
         let random = BigInt::from_bytes_be(Sign::Plus, random_bytes.as_ref());
         ok!(stack.push_int(random));
         Ok(0)
@@ -28,7 +32,11 @@ impl RandOps {
     fn exec_rand_int(st: &mut VmState) -> VmResult<i32> {
         let stack = SafeRc::make_mut(&mut st.stack);
         let mut int = ok!(stack.pop_int());
-        let random_bytes = ok!(generate_random_u256(&mut st.cr, &st.gas));
+        let random_bytes = ok!(generate_random_u256(
+            &mut st.cr,
+            &st.gas,
+            st.rng.as_deref_mut()
+        ));
         let random = BigInt::from_bytes_be(Sign::Plus, random_bytes.as_ref());
 
         {
@@ -74,27 +82,23 @@ impl RandOps {
         };
 
         if mix {
-            let bytes = match t1.get(SmcInfoBase::RANDSEED_IDX) {
+            let seed = match t1.get(SmcInfoBase::RANDSEED_IDX) {
                 Some(value) => {
                     let value = ok!(value.clone().into_int());
-                    ok!(to_bytes_be(&value))
+                    ok!(to_hash_bytes(&value))
                 }
                 None => vm_bail!(InvalidType {
                     expected: StackValueType::Int,
                     actual: StackValueType::Null
                 }),
             };
+            let value = ok!(to_hash_bytes(&int));
 
-            let mut buffer = [0u8; 64];
-            buffer[32 - bytes.len()..32].copy_from_slice(&bytes);
-            drop(bytes);
-
-            let bytes = ok!(to_bytes_be(&int));
-            buffer[64 - bytes.len()..64].copy_from_slice(&bytes);
-            drop(bytes);
-
-            let new_seed = sha2::Sha256::digest(buffer);
-            int = SafeRc::new(BigInt::from_bytes_be(Sign::Plus, &new_seed));
+            let new_seed = match st.rng.as_deref_mut() {
+                Some(rng) => rng.mix(&seed, &value),
+                None => DefaultRng.mix(&seed, &value),
+            };
+            int = SafeRc::new(BigInt::from_bytes_be(Sign::Plus, new_seed.as_array()));
         }
 
         // NOTE: Make sure that we have a unique instance of the `c7` tuple
@@ -127,7 +131,11 @@ impl RandOps {
     }
 }
 
-fn generate_random_u256(regs: &mut ControlRegs, gas: &GasConsumer) -> VmResult<HashBytes> {
+fn generate_random_u256(
+    regs: &mut ControlRegs,
+    gas: &GasConsumer,
+    rng: Option<&mut (dyn RngProvider + '_)>,
+) -> VmResult<HashBytes> {
     let Some(c7) = regs.c7.as_ref() else {
         vm_bail!(ControlRegisterOutOfRange(7))
     };
@@ -145,14 +153,10 @@ fn generate_random_u256(regs: &mut ControlRegs, gas: &GasConsumer) -> VmResult<H
         })
     };
 
-    let hash: [u8; 64] = match t1.get(SmcInfoBase::RANDSEED_IDX) {
+    let seed = match t1.get(SmcInfoBase::RANDSEED_IDX) {
         Some(value) => {
             let value = ok!(value.clone().into_int());
-            let bytes = ok!(to_bytes_be(&value));
-
-            let mut seed_bytes = [0u8; 32];
-            seed_bytes[32 - bytes.len()..].copy_from_slice(&bytes);
-            sha2::Sha512::digest(seed_bytes).into()
+            ok!(to_hash_bytes(&value))
         }
         None => vm_bail!(InvalidType {
             expected: StackValueType::Int,
@@ -160,8 +164,11 @@ fn generate_random_u256(regs: &mut ControlRegs, gas: &GasConsumer) -> VmResult<H
         }),
     };
 
-    let new_seedv = SafeRc::new_dyn_value(BigInt::from_bytes_be(Sign::Plus, &hash[..32]));
-    let res = HashBytes::from_slice(&hash[32..]);
+    let (new_seed, res) = match rng {
+        Some(rng) => rng.next(&seed),
+        None => DefaultRng.next(&seed),
+    };
+    let new_seedv = SafeRc::new_dyn_value(BigInt::from_bytes_be(Sign::Plus, new_seed.as_array()));
 
     // NOTE: Make sure that we have a unique instance of the `c7` tuple
     //       (at least make sure that this situation is possible).
@@ -186,7 +193,7 @@ fn generate_random_u256(regs: &mut ControlRegs, gas: &GasConsumer) -> VmResult<H
     Ok(res)
 }
 
-fn to_bytes_be(int: &BigInt) -> VmResult<Vec<u8>> {
+fn to_hash_bytes(int: &BigInt) -> VmResult<HashBytes> {
     vm_ensure!(int.sign() != Sign::Minus, IntegerOutOfRange {
         min: 0,
         max: isize::MAX,
@@ -196,14 +203,20 @@ fn to_bytes_be(int: &BigInt) -> VmResult<Vec<u8>> {
     let mut bytes = int.magnitude().to_bytes_le();
     bytes.truncate(32);
     bytes.reverse();
-    Ok(bytes)
+
+    let mut res = HashBytes::ZERO;
+    res.0[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(res)
 }
 
 #[cfg(test)]
 pub mod test {
+    use everscale_types::boc::Boc;
+    use sha2::Digest;
     use tracing_test::traced_test;
 
     use super::*;
+    use crate::smc_info::CustomSmcInfo;
 
     fn uint256(str: &str) -> BigInt {
         let value = hex::decode(str).unwrap();
@@ -274,4 +287,64 @@ pub mod test {
             [] => [int new_rand],
         );
     }
+
+    #[test]
+    #[traced_test]
+    fn custom_rng() {
+        struct CounterRng {
+            calls: u8,
+        }
+
+        impl RngProvider for CounterRng {
+            fn next(&mut self, seed: &HashBytes) -> (HashBytes, HashBytes) {
+                self.calls += 1;
+                let mut new_seed = *seed;
+                new_seed.0[31] += 1;
+                (new_seed, HashBytes([self.calls; 32]))
+            }
+
+            fn mix(&mut self, seed: &HashBytes, value: &HashBytes) -> HashBytes {
+                let mut res = *seed;
+                for (a, b) in std::iter::zip(&mut res.0, value.0) {
+                    *a ^= b;
+                }
+                res
+            }
+        }
+
+        let code = Boc::decode(tvmasm!("RANDU256 RANDU256 INT 7 ADDRAND")).unwrap();
+
+        let mut rng = CounterRng { calls: 0 };
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_smc_info(CustomSmcInfo {
+                version: VmState::DEFAULT_VERSION,
+                c7: SafeRc::new(tuple![[null, null, null, null, null, null, int 10]]),
+            })
+            .with_rng(&mut rng)
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+
+        let stack = &state.stack.items;
+        assert_eq!(stack.len(), 2);
+        assert_eq!(
+            stack[0].as_int(),
+            Some(&BigInt::from_bytes_be(Sign::Plus, &[1; 32]))
+        );
+        assert_eq!(
+            stack[1].as_int(),
+            Some(&BigInt::from_bytes_be(Sign::Plus, &[2; 32]))
+        );
+
+        // 10 -> 11 -> 12, then mixed with 7.
+        let mut expected_seed = HashBytes::ZERO;
+        expected_seed.0[31] = 12 ^ 7;
+        assert_eq!(state.rand_seed(), Some(expected_seed));
+
+        drop(state);
+        assert_eq!(rng.calls, 2);
+    }
 }
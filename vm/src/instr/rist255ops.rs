@@ -0,0 +1,319 @@
+use std::sync::OnceLock;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::Scalar;
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer;
+use tycho_vm_proc::vm_module;
+
+use crate::error::VmResult;
+use crate::gas::GasConsumer;
+use crate::saferc::SafeRc;
+use crate::stack::Stack;
+use crate::state::VmState;
+
+pub struct Rist255Ops;
+
+#[vm_module(category = "crypto")]
+impl Rist255Ops {
+    #[op(code = "f920", fmt = "RIST255_FROMHASH")]
+    fn exec_rist255_from_hash(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let x2 = ok!(stack.pop_int());
+        let x1 = ok!(stack.pop_int());
+
+        st.gas
+            .try_consume(GasConsumer::RIST255_FROMHASH_GAS_PRICE)?;
+
+        let (Some(x1), Some(x2)) = (to_uint256_bytes(&x1), to_uint256_bytes(&x2)) else {
+            vm_bail!(IntegerOutOfRange {
+                min: 0,
+                max: isize::MAX,
+                actual: "x1 and x2 must fit into uint256".to_owned(),
+            });
+        };
+
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&x1);
+        bytes[32..].copy_from_slice(&x2);
+
+        let point = RistrettoPoint::from_uniform_bytes(&bytes);
+        ok!(stack.push_int(encode_point(&point)));
+        Ok(0)
+    }
+
+    #[op(code = "f921", fmt = "RIST255_VALIDATE", args(quiet = false))]
+    #[op(code = "b7f921", fmt = "RIST255_QVALIDATE", args(quiet = true))]
+    fn exec_rist255_validate(st: &mut VmState, quiet: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let x = ok!(stack.pop_int());
+
+        st.gas
+            .try_consume(GasConsumer::RIST255_VALIDATE_GAS_PRICE)?;
+
+        let is_valid = decode_point(&x).is_some();
+        if quiet {
+            ok!(stack.push_bool(is_valid));
+        } else {
+            vm_ensure!(is_valid, IntegerOutOfRange {
+                min: 0,
+                max: isize::MAX,
+                actual: "x is not a valid encoded element".to_owned(),
+            });
+        }
+        Ok(0)
+    }
+
+    #[op(code = "f922", fmt = "RIST255_ADD", args(sub = false, quiet = false))]
+    #[op(code = "f923", fmt = "RIST255_SUB", args(sub = true, quiet = false))]
+    #[op(code = "b7f922", fmt = "RIST255_QADD", args(sub = false, quiet = true))]
+    #[op(code = "b7f923", fmt = "RIST255_QSUB", args(sub = true, quiet = true))]
+    fn exec_rist255_add(st: &mut VmState, sub: bool, quiet: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let y = ok!(stack.pop_int());
+        let x = ok!(stack.pop_int());
+
+        st.gas.try_consume(GasConsumer::RIST255_ADD_GAS_PRICE)?;
+
+        let (Some(x), Some(y)) = (decode_point(&x), decode_point(&y)) else {
+            return push_invalid_point(stack, quiet);
+        };
+
+        let res = if sub { x - y } else { x + y };
+        push_point(stack, &res, quiet)
+    }
+
+    #[op(code = "f924", fmt = "RIST255_MUL", args(quiet = false))]
+    #[op(code = "b7f924", fmt = "RIST255_QMUL", args(quiet = true))]
+    fn exec_rist255_mul(st: &mut VmState, quiet: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let n = ok!(stack.pop_int());
+        let x = ok!(stack.pop_int());
+
+        st.gas.try_consume(GasConsumer::RIST255_MUL_GAS_PRICE)?;
+
+        // NOTE: Zero scalar results in the identity element
+        //       even if `x` is not a valid point.
+        let Some(n) = to_scalar(&n) else {
+            return push_identity(stack, quiet);
+        };
+        let Some(x) = decode_point(&x) else {
+            return push_invalid_point(stack, quiet);
+        };
+
+        push_point(stack, &(x * n), quiet)
+    }
+
+    #[op(code = "f925", fmt = "RIST255_MULBASE", args(quiet = false))]
+    #[op(code = "b7f925", fmt = "RIST255_QMULBASE", args(quiet = true))]
+    fn exec_rist255_mul_base(st: &mut VmState, quiet: bool) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let n = ok!(stack.pop_int());
+
+        st.gas.try_consume(GasConsumer::RIST255_MULBASE_GAS_PRICE)?;
+
+        let Some(n) = to_scalar(&n) else {
+            return push_identity(stack, quiet);
+        };
+
+        push_point(stack, &RistrettoPoint::mul_base(&n), quiet)
+    }
+
+    #[op(code = "f926", fmt = "RIST255_PUSHL")]
+    fn exec_rist255_push_l(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        ok!(stack.push_int(rist255_l().clone()));
+        Ok(0)
+    }
+}
+
+/// Order of the Ristretto255 group (`2^252 + 27742317777372353535851937790883648493`).
+fn rist255_l() -> &'static BigInt {
+    static L: OnceLock<BigInt> = OnceLock::new();
+    L.get_or_init(|| {
+        (BigInt::from(1) << 252)
+            + "27742317777372353535851937790883648493"
+                .parse::<BigInt>()
+                .unwrap()
+    })
+}
+
+/// Reduces the integer modulo `l`. Returns `None` for a zero scalar.
+fn to_scalar(n: &BigInt) -> Option<Scalar> {
+    let n = n.mod_floor(rist255_l());
+    if n.sign() == Sign::NoSign {
+        return None;
+    }
+
+    let bytes = n.magnitude().to_bytes_le();
+    let mut res = [0; 32];
+    res[..bytes.len()].copy_from_slice(&bytes);
+    Some(Scalar::from_bytes_mod_order(res))
+}
+
+fn to_uint256_bytes(int: &BigInt) -> Option<[u8; 32]> {
+    if int.sign() == Sign::Minus || int.bits() > 256 {
+        return None;
+    }
+
+    let bytes = int.magnitude().to_bytes_be();
+    let mut res = [0; 32];
+    res[32 - bytes.len()..].copy_from_slice(&bytes);
+    Some(res)
+}
+
+fn decode_point(int: &BigInt) -> Option<RistrettoPoint> {
+    CompressedRistretto(to_uint256_bytes(int)?).decompress()
+}
+
+fn encode_point(point: &RistrettoPoint) -> BigInt {
+    BigInt::from_bytes_be(Sign::Plus, point.compress().as_bytes())
+}
+
+fn push_point(stack: &mut Stack, point: &RistrettoPoint, quiet: bool) -> VmResult<i32> {
+    ok!(stack.push_int(encode_point(point)));
+    if quiet {
+        ok!(stack.push_bool(true));
+    }
+    Ok(0)
+}
+
+fn push_identity(stack: &mut Stack, quiet: bool) -> VmResult<i32> {
+    ok!(stack.push_zero());
+    if quiet {
+        ok!(stack.push_bool(true));
+    }
+    Ok(0)
+}
+
+fn push_invalid_point(stack: &mut Stack, quiet: bool) -> VmResult<i32> {
+    vm_ensure!(quiet, IntegerOutOfRange {
+        min: 0,
+        max: isize::MAX,
+        actual: "x is not a valid encoded element".to_owned(),
+    });
+    ok!(stack.push_bool(false));
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use num_bigint::{BigInt, Sign};
+    use tracing_test::traced_test;
+
+    use super::{encode_point, rist255_l};
+
+    #[test]
+    #[traced_test]
+    fn rist255_mul_base() {
+        let base = BigInt::parse_bytes(
+            b"e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+            16,
+        )
+        .unwrap();
+
+        assert_run_vm!("RIST255_MULBASE", [int 1] => [int base.clone()]);
+        assert_run_vm!("RIST255_QMULBASE", [int 1] => [int base.clone(), int -1]);
+        assert_run_vm!("RIST255_MULBASE", [int 0] => [int 0]);
+        assert_run_vm!("RIST255_PUSHL RIST255_MULBASE", [] => [int 0]);
+        assert_run_vm!("RIST255_PUSHL INC RIST255_MULBASE", [] => [int base.clone()]);
+        assert_run_vm!("RIST255_MULBASE", [int -1] => [
+            int encode_point(&-RistrettoPoint::mul_base(&1u64.into()))
+        ]);
+
+        assert_run_vm!("RIST255_PUSHL", [] => [int rist255_l().clone()]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn rist255_arithmetic() {
+        assert_run_vm!(
+            r#"
+            INT 2 RIST255_MULBASE
+            INT 3 RIST255_MULBASE
+            RIST255_ADD
+            INT 5 RIST255_MULBASE
+            EQUAL
+            "#,
+            [] => [int -1]
+        );
+        assert_run_vm!(
+            r#"
+            INT 2 RIST255_MULBASE
+            INT 7 RIST255_MULBASE
+            RIST255_SUB
+            INT -5 RIST255_MULBASE
+            EQUAL
+            "#,
+            [] => [int -1]
+        );
+        assert_run_vm!(
+            r#"
+            INT 3 RIST255_MULBASE
+            INT 11 RIST255_MUL
+            INT 33 RIST255_MULBASE
+            EQUAL
+            "#,
+            [] => [int -1]
+        );
+        assert_run_vm!(
+            "INT 3 RIST255_MULBASE DUP RIST255_SUB",
+            [] => [int 0]
+        );
+        assert_run_vm!(
+            "INT 3 RIST255_MULBASE DUP RIST255_QSUB",
+            [] => [int 0, int -1]
+        );
+
+        // Zero scalar ignores the point.
+        assert_run_vm!("RIST255_MUL", [int -1, int 0] => [int 0]);
+        assert_run_vm!("RIST255_QMUL", [int -1, int 0] => [int 0, int -1]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn rist255_invalid_points() {
+        // Negative field element.
+        let invalid = BigInt::from(1) << 248;
+
+        assert_run_vm!("RIST255_VALIDATE", [int 0] => []);
+        assert_run_vm!("INT 5 RIST255_MULBASE RIST255_VALIDATE", [] => []);
+        assert_run_vm!("RIST255_VALIDATE", [int invalid.clone()] => [int 0], exit_code: 5);
+        assert_run_vm!("RIST255_VALIDATE", [int -1] => [int 0], exit_code: 5);
+        assert_run_vm!("RIST255_QVALIDATE", [int invalid.clone()] => [int 0]);
+        assert_run_vm!("RIST255_QVALIDATE", [int 0] => [int -1]);
+
+        assert_run_vm!("RIST255_ADD", [int 0, int invalid.clone()] => [int 0], exit_code: 5);
+        assert_run_vm!("RIST255_QADD", [int 0, int invalid.clone()] => [int 0]);
+        assert_run_vm!("RIST255_QSUB", [int invalid.clone(), int 0] => [int 0]);
+        assert_run_vm!("RIST255_MUL", [int invalid.clone(), int 1] => [int 0], exit_code: 5);
+        assert_run_vm!("RIST255_QMUL", [int invalid, int 1] => [int 0]);
+    }
+
+    #[test]
+    #[traced_test]
+    fn rist255_from_hash() {
+        let mut bytes = [0; 64];
+        bytes[..32].fill(0x11);
+        bytes[32..].fill(0x22);
+        let expected = encode_point(&RistrettoPoint::from_uniform_bytes(&bytes));
+
+        let x1 = BigInt::from_bytes_be(Sign::Plus, &bytes[..32]);
+        let x2 = BigInt::from_bytes_be(Sign::Plus, &bytes[32..]);
+        assert_run_vm!("RIST255_FROMHASH", [int x1, int x2] => [int expected]);
+        assert_run_vm!("RIST255_FROMHASH", [int -1, int 0] => [int 0], exit_code: 5);
+    }
+}
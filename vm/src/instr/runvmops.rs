@@ -0,0 +1,657 @@
+use everscale_types::prelude::*;
+use tycho_vm_proc::vm_module;
+
+use crate::cont::{ControlRegs, OrdCont};
+use crate::error::VmResult;
+use crate::gas::{GasConsumer, GasConsumerDeriveParams};
+use crate::instr::codepage0;
+use crate::saferc::SafeRc;
+use crate::stack::Stack;
+use crate::state::{ParentVmState, VmState, EXC_QUIT, QUIT0, QUIT1, QUIT11};
+
+pub struct RunVmOps;
+
+#[vm_module(category = "control")]
+impl RunVmOps {
+    #[op(code = "db4xxx", fmt = "RUNVM {x}")]
+    fn exec_runvm(st: &mut VmState, x: u32) -> VmResult<i32> {
+        exec_runvm_common(st, RunVmArgs(x))
+    }
+
+    #[op(code = "db50", fmt = "RUNVMX")]
+    fn exec_runvmx(st: &mut VmState) -> VmResult<i32> {
+        let x = ok!(SafeRc::make_mut(&mut st.stack).pop_smallint_range(0, 0xfff));
+        exec_runvm_common(st, RunVmArgs(x))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RunVmArgs(u32);
+
+impl RunVmArgs {
+    /// +1 = same_c3 (set c3 to code).
+    const fn same_c3(self) -> bool {
+        self.0 & 0b000000001 != 0
+    }
+
+    /// +2 = push_0 (push an implicit 0 before running the code).
+    const fn push_0(self) -> bool {
+        self.0 & 0b000000010 != 0
+    }
+
+    /// +4 = load c4 (persistent data) from stack and return its final value.
+    const fn load_c4(self) -> bool {
+        self.0 & 0b000000100 != 0
+    }
+
+    /// +8 = load gas limit from stack and return consumed gas.
+    const fn load_gas(self) -> bool {
+        self.0 & 0b000001000 != 0
+    }
+
+    /// +16 = load c7 (smart-contract context).
+    const fn load_c7(self) -> bool {
+        self.0 & 0b000010000 != 0
+    }
+
+    /// +32 = return c5 (actions).
+    const fn return_c5(self) -> bool {
+        self.0 & 0b000100000 != 0
+    }
+
+    /// +64 = pop hard gas limit (enabled by ACCEPT) from stack as well.
+    const fn load_hard_gas_limit(self) -> bool {
+        self.0 & 0b001000000 != 0
+    }
+
+    /// +128 = isolated gas consumption (separate set of visited cells, reset chksgn counter).
+    const fn isolate_gas(self) -> bool {
+        self.0 & 0b010000000 != 0
+    }
+
+    /// +256 = pop number N, return exactly N values from stack
+    /// (only if res=0 or 1; if not enough then res=stk_und).
+    const fn ret_n(self) -> bool {
+        self.0 & 0b100000000 != 0
+    }
+}
+
+fn exec_runvm_common(st: &mut VmState, args: RunVmArgs) -> VmResult<i32> {
+    // Check possible args range (all other bits are reserved for future).
+    vm_ensure!(args.0 < 512, IntegerOutOfRange {
+        min: 0,
+        max: 511,
+        actual: args.0.to_string()
+    });
+
+    // Compensate vm creation.
+    st.gas.try_consume(GasConsumer::RUNVM_GAS_PRICE)?;
+
+    // Read args from the stack.
+    let stack = SafeRc::make_mut(&mut st.stack);
+
+    let mut gas_max = if args.load_hard_gas_limit() {
+        ok!(stack.pop_long_range(0, u64::MAX))
+    } else {
+        u64::MAX
+    };
+    let gas_limit = if args.load_gas() {
+        ok!(stack.pop_long_range(0, u64::MAX))
+    } else {
+        u64::MAX
+    };
+
+    if args.load_hard_gas_limit() {
+        gas_max = std::cmp::max(gas_max, gas_limit);
+    } else {
+        gas_max = gas_limit;
+    }
+
+    let child_c7 = if args.load_c7() {
+        ok!(stack.pop_tuple())
+    } else {
+        SafeRc::new(Vec::new())
+    };
+
+    let child_data = if args.load_c4() {
+        SafeRc::unwrap_or_clone(ok!(stack.pop_cell()))
+    } else {
+        Cell::default()
+    };
+
+    let return_values = if args.ret_n() {
+        Some(ok!(stack.pop_smallint_range(0, 1 << 30)))
+    } else {
+        None
+    };
+
+    let child_code = SafeRc::unwrap_or_clone(ok!(stack.pop_cs()));
+    let stack_size = ok!(stack.pop_long_range(0, stack.depth().saturating_sub(1) as u64));
+    let mut child_stack = ok!(stack.split_top(stack_size as usize));
+    st.gas.try_consume_stack_gas(Some(&child_stack))?;
+
+    // Build child VM state.
+
+    let parent_gas = ok!(st.gas.derive(GasConsumerDeriveParams {
+        gas_max,
+        gas_limit,
+        isolate: args.isolate_gas(),
+    }));
+
+    // === ↓↓↓ Must not return any error afterwards ↓↓↓ ===
+
+    let child_quit0 = QUIT0.with(SafeRc::clone);
+    let child_quit1 = QUIT1.with(SafeRc::clone);
+    let child_cp = st.resolve_cp(0).unwrap_or_else(codepage0);
+    let child_c3 = if args.same_c3() {
+        if args.push_0() {
+            vm_log_trace!("implicit PUSH 0 at start");
+            SafeRc::make_mut(&mut child_stack)
+                .items
+                .push(Stack::make_zero());
+        }
+        SafeRc::from(OrdCont::simple(child_code.clone(), child_cp.id()))
+    } else {
+        QUIT11.with(SafeRc::clone).into_dyn_cont()
+    };
+
+    let child_cr = ControlRegs {
+        c: [
+            Some(child_quit0.clone().into_dyn_cont()),
+            Some(child_quit1.clone().into_dyn_cont()),
+            Some(EXC_QUIT.with(SafeRc::clone).into_dyn_cont()),
+            Some(child_c3),
+        ],
+        d: [Some(child_data), Some(Cell::empty_cell())],
+        c7: Some(child_c7),
+    };
+
+    st.parent = Some(Box::new(ParentVmState {
+        code: std::mem::replace(&mut st.code, child_code),
+        stack: std::mem::replace(&mut st.stack, child_stack),
+        cr: std::mem::replace(&mut st.cr, child_cr),
+        committed_state: std::mem::take(&mut st.committed_state),
+        steps: std::mem::take(&mut st.steps),
+        quit0: std::mem::replace(&mut st.quit0, child_quit0),
+        quit1: std::mem::replace(&mut st.quit1, child_quit1),
+        gas: parent_gas,
+        cp: std::mem::replace(&mut st.cp, child_cp),
+        return_data: args.load_c4(),
+        return_actions: args.return_c5(),
+        return_gas: args.load_gas(),
+        return_values,
+        parent: st.parent.take(),
+    }));
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::boc::Boc;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::state::IntoCode;
+    use crate::OwnedCellSlice;
+
+    #[test]
+    #[traced_test]
+    fn runvm_simple() {
+        let child_code = make_code(tvmasm!(
+            r#"
+            ADD
+            DEPTH
+            PUSH c4 CTOS SBITREFS
+            PUSH c5 CTOS SBITREFS
+            PUSH c7
+            PUSHREF x{99991111} POP c4
+            PUSHREF x{aaaabbbb} POP c5
+
+            NIL
+            INT 100
+            TPUSH
+            INT 200
+            TPUSH
+            POP c7
+
+            INT 123
+            "#
+        ));
+
+        assert_run_vm!(
+            r#"
+            PUSHREF x{1234} POP c4
+            PUSHREF x{5678} POP c5
+            NIL
+            INT 5
+            TPUSH
+            INT 6
+            TPUSH
+            POP c7
+
+            RUNVM 0
+
+            PUSH c4 CTOS
+            PUSH c5 CTOS
+            PUSH c7
+            "#,
+            [int 111, int 10, int 20, int 2, slice child_code] => [
+                int 111,
+                int 30,
+                int 1,
+                int 0,
+                int 0,
+                int 0,
+                int 0,
+                [],
+                int 123,
+                int 0,
+                slice make_slice(0x1234_u16),
+                slice make_slice(0x5678_u16),
+                [int 5, int 6],
+            ],
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_exception() {
+        // === Simple exception ===
+
+        let child_code = make_code(tvmasm!(
+            r#"
+            INT 22
+            INT 55 THROWARG 66
+            "#
+        ));
+
+        assert_run_vm!(
+            "RUNVM 0",
+            [int 111, int 10, int 20, int 2, slice child_code] => [
+                int 111,
+                int 55,
+                int 66,
+            ],
+        );
+
+        // === c4, c5 with exception ===
+
+        let child_code = make_code(tvmasm!(
+            r#"
+            PUSHREF x{abcdaaaa} POP c4
+            PUSHREF x{abcdbbbb} POP c5
+            THROW 55
+            "#,
+        ));
+
+        assert_run_vm!(
+            r#"
+            PUSHREF x{1234aaaa} POP c4
+            PUSHREF x{1234bbbb} POP c5
+            RUNVM 36
+            PUSH c4 CTOS
+            PUSH c5 CTOS
+            "#,
+            [int 0, slice child_code, cell make_cell(0x5678_u16)] => [
+                int 0,
+                int 55,
+                null,
+                null,
+                slice make_slice(0x1234aaaa_u32),
+                slice make_slice(0x1234bbbb_u32),
+            ],
+        );
+
+        // === c4, c5 with exception and commit ===
+
+        let child_code = make_code(tvmasm!(
+            r#"
+            PUSHREF x{abcdaaaa} POP c4
+            PUSHREF x{abcdbbbb} POP c5
+            COMMIT
+            PUSHREF x{} POP c4
+            @newcell
+            PUSHREF x{} POP c5
+            THROW 55
+            "#,
+        ));
+
+        assert_run_vm!(
+            r#"
+            PUSHREF x{1234aaaa} POP c4
+            PUSHREF x{1234bbbb} POP c5
+            RUNVM 36
+            CTOS SWAP CTOS SWAP
+            PUSH c4 CTOS
+            PUSH c5 CTOS
+            "#,
+            [int 0, slice child_code, cell make_cell(0x5678_u16)] => [
+                int 0,
+                int 55,
+                slice make_slice(0xabcdaaaa_u32),
+                slice make_slice(0xabcdbbbb_u32),
+                slice make_slice(0x1234aaaa_u32),
+                slice make_slice(0x1234bbbb_u32),
+            ],
+        );
+
+        // === Gas limit of parent VM is too low ===
+
+        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
+        assert_run_vm!(
+            "RUNVM 8 INT 1234",
+            gas: 300,
+            [int 0, slice child_code, int 1000000] => [int 301],
+            exit_code: -14,
+        )
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flags_1_2() {
+        // === flag +1: same c3 ===
+
+        assert_run_vm!(
+            r#"
+            @define(simpleProg) {
+                SETCP 0
+                DICTPUSHCONST 19, [
+                    0 => {
+                        DUP
+                        CALLDICT 22
+                        INC
+                    }
+                    22 => {
+                        MUL
+                    }
+                ]
+                DICTIGETJMPZ
+                THROWARG 11
+            }
+
+            INT 10 INT 0 INT 2 PUSHSLICE @use(simpleProg) RUNVM 1
+            INT 10 INT 0 INT 2 PUSHSLICE @use(simpleProg) RUNVM 0
+            "#,
+            [] => [int 101, int 0, int 22, int 11],
+        );
+
+        // === flag +2(+1): push0 ===
+
+        assert_run_vm!(
+            "INT 10 INT 1 PUSHSLICE {
+                SETCP 0
+                DICTPUSHCONST 19, [
+                    0 => {
+                        DUP
+                        CALLDICT 22
+                        INC
+                    }
+                    22 => {
+                        MUL
+                    }
+                ]
+                DICTIGETJMPZ
+                THROWARG 11
+            } RUNVM 3",
+            [] => [int 101, int 0]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_4() {
+        // flag +4 - load and return c4
+
+        let child_code = make_code(tvmasm!(
+            r#"
+            PUSHCTR c4 CTOS
+            PUSHREF x{abcd} POPCTR c4
+            INT 1000
+            "#
+        ));
+
+        assert_run_vm!(
+            r#"
+            PUSHREF x{1234} POP c4
+            RUNVM 4
+            CTOS
+            PUSH c4 CTOS
+            "#,
+            [int 0, slice child_code, cell make_cell(0x5678_u16)] => [
+                slice make_slice(0x5678_u16),
+                int 1000,
+                int 0,
+                slice make_slice(0xabcd_u16),
+                slice make_slice(0x1234_u16),
+            ]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_8() {
+        // flag: +8 - gas limit
+
+        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
+
+        assert_run_vm!(
+            r#"RUNVM 8 INT 1234"#,
+            [int 0, slice child_code, int 200] => [
+                int 215,
+                int -14,
+                int 215,
+                int 1234,
+            ]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_16() {
+        // flag +16 - load c7
+
+        let child_code = make_code(tvmasm!(
+            r#"
+            PUSH c7
+            NIL
+            INT 111 TPUSH
+            INT 222 TPUSH
+            INT 3333 TPUSH
+            POP c7
+            INT 1000
+            "#
+        ));
+
+        assert_run_vm!(
+            r#"
+            NIL
+            INT 1 TPUSH
+            INT 2 TPUSH
+            INT 3 TPUSH
+            POP c7
+            RUNVM 16
+            PUSH c7
+            "#,
+            [int 0, slice child_code, [int 10, int 15, int 20]] => [
+                [int 10, int 15, int 20],
+                int 1000,
+                int 0,
+                [int 1, int 2, int 3],
+            ],
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_32() {
+        // flag +32 - return c5
+
+        let child_code = make_code(tvmasm!(
+            r#"
+            PUSH c5 CTOS SBITREFS
+            PUSHREF x{5678} POP c5
+            INT 1000
+            "#
+        ));
+
+        assert_run_vm!(
+            r#"
+            PUSHREF x{1234} POP c5
+            RUNVM 32
+            CTOS
+            PUSH c5 CTOS
+            "#,
+            [int 0, slice child_code] => [
+                int 0,
+                int 0,
+                int 1000,
+                int 0,
+                slice make_slice(0x5678_u16),
+                slice make_slice(0x1234_u16),
+            ],
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_64() {
+        // flag +64 - hard gas limit
+
+        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
+        assert_run_vm!(
+            "RUNVM 72 INT 1234",
+            [int 0, slice child_code, int 200, int 500] => [int 215, int -14, int 215, int 1234],
+        );
+
+        let child_code = make_code(tvmasm!("ACCEPT PUSHCONT { NOP } AGAIN"));
+        assert_run_vm!(
+            "RUNVM 72 INT 1234",
+            [int 0, slice child_code, int 200, int 500] => [int 517, int -14, int 517, int 1234],
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_128() {
+        // flag +128 - separate loaded_cells
+
+        assert_run_vm!(
+            r#"
+            DUP CTOS DROP
+            INT 2
+            PUSHSLICE { CTOS DROP CTOS DROP }
+            INT 10000
+            RUNVM 8
+            "#,
+            [cell make_cell(0x12345678_u32), cell make_cell(0x87654321_u32)] => [
+                int 0,
+                int 202,
+            ],
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvm_flag_256() {
+        // +256 - fixed number of return values
+
+        let child_code = make_code(tvmasm!("INT 1 INT 2 INT 3 INT 4 INT 5"));
+        assert_run_vm!(
+            "RUNVM 256",
+            [int 11, int 22, int 33, int 3, slice child_code.clone(), int 3] => [
+                int 3,
+                int 4,
+                int 5,
+                int 0,
+            ]
+        );
+        assert_run_vm!(
+            "RUNVM 256",
+            [int 11, int 22, int 33, int 3, slice child_code.clone(), int 0] => [int 0]
+        );
+        assert_run_vm!(
+            "RUNVM 256",
+            [int 11, int 22, int 33, int 3, slice child_code, int 20] => [int 0, int -3]
+        );
+
+        let child_code = make_code(tvmasm!("INT 1 INT 2 INT 3 INT 4 INT 5 THROW 77"));
+        assert_run_vm!(
+            "RUNVM 256",
+            [int 11, int 22, int 33, int 3, slice child_code, int 3] => [int 0, int 77]
+        );
+    }
+
+    #[test]
+    // #[traced_test]
+    fn runvm_compute_big() {
+        assert_run_vm!(
+            r#"
+            @define(slice) {
+                DUP EQINT 0
+                PUSHCONT {
+                    DROP DROP
+                    ZERO
+                } IFJMP
+                OVER OVER DEC
+                INT 2
+                PUSH s2
+                RUNVM 0 THROWIF 11
+                ADD NIP
+            }
+            PUSHSLICE @use(slice)
+            INT 10000
+            INT 2
+            PUSHSLICE @use(slice) RUNVM 0
+            "#,
+            gas: 10000000,
+            [] => [int 50005000, int 0],
+        );
+
+        assert_run_vm!(
+            r#"
+            @define(slice) {
+                DUP EQINT 0
+                PUSHCONT {
+                    DROP DROP
+                    ZERO
+                } IFJMP
+                OVER OVER DEC
+                INT 2
+                PUSH s2
+                RUNVM 0 THROWIF 11
+                ADD NIP
+            }
+            PUSHSLICE @use(slice)
+            INT 10000
+            INT 2
+            PUSHSLICE @use(slice) RUNVM 0
+            "#,
+            gas: 100000,
+            [] => [int 100001],
+            exit_code: -14,
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn runvmx() {
+        let child_code = make_code(tvmasm!("PUSHCONT { NOP } AGAIN"));
+        assert_run_vm!(
+            r#"INT 8 RUNVMX INT 1234"#,
+            [int 0, slice child_code, int 200] => [int 215, int -14, int 215, int 1234],
+        );
+    }
+
+    fn make_code(code: &[u8]) -> OwnedCellSlice {
+        Boc::decode(code).unwrap().into_code().unwrap()
+    }
+
+    fn make_cell<T: Store>(value: T) -> Cell {
+        CellBuilder::build_from(value).unwrap()
+    }
+
+    fn make_slice<T: Store>(value: T) -> OwnedCellSlice {
+        OwnedCellSlice::new_allow_exotic(CellBuilder::build_from(value).unwrap())
+    }
+}
@@ -0,0 +1,142 @@
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use num_bigint::{BigInt, Sign};
+use tycho_vm_proc::vm_module;
+
+use crate::error::VmResult;
+use crate::gas::GasConsumer;
+use crate::saferc::SafeRc;
+use crate::stack::Stack;
+use crate::state::VmState;
+
+pub struct Secp256k1Ops;
+
+#[vm_module(category = "crypto")]
+impl Secp256k1Ops {
+    #[op(code = "f912", fmt = "ECRECOVER")]
+    fn exec_ecrecover(st: &mut VmState) -> VmResult<i32> {
+        ok!(st.version.require_ton(4..));
+
+        let stack = SafeRc::make_mut(&mut st.stack);
+        let s = ok!(pop_uint256(stack));
+        let r = ok!(pop_uint256(stack));
+        let v = ok!(stack.pop_smallint_range(0, 255)) as u8;
+        let hash = ok!(pop_uint256(stack));
+
+        st.gas.try_consume(GasConsumer::ECRECOVER_GAS_PRICE)?;
+
+        match ecrecover(&hash, &r, &s, v) {
+            Some(key) => {
+                ok!(stack.push_int(key[0]));
+                ok!(stack.push_int(BigInt::from_bytes_be(Sign::Plus, &key[1..33])));
+                ok!(stack.push_int(BigInt::from_bytes_be(Sign::Plus, &key[33..65])));
+                ok!(stack.push_bool(true));
+            }
+            None => ok!(stack.push_bool(false)),
+        }
+        Ok(0)
+    }
+}
+
+/// Recovers an uncompressed public key (`0x04 || x || y`)
+/// from the recoverable signature.
+fn ecrecover(hash: &[u8; 32], r: &[u8; 32], s: &[u8; 32], v: u8) -> Option<[u8; 65]> {
+    let mut recovery_id = RecoveryId::from_byte(v)?;
+
+    let mut signature = [0; 64];
+    signature[..32].copy_from_slice(r);
+    signature[32..].copy_from_slice(s);
+    let mut signature = Signature::from_slice(&signature).ok()?;
+
+    // NOTE: `libsecp256k1` accepts signatures with high `s` during the recovery,
+    //       and `k256` rejects them. Negating `s` (together with the parity of `R`)
+    //       results in the same public key.
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+    }
+
+    let key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id).ok()?;
+    key.to_encoded_point(false).as_bytes().try_into().ok()
+}
+
+fn pop_uint256(stack: &mut Stack) -> VmResult<[u8; 32]> {
+    let int = ok!(stack.pop_int());
+    vm_ensure!(
+        int.sign() != Sign::Minus && int.bits() <= 256,
+        IntegerOutOfRange {
+            min: 0,
+            max: isize::MAX,
+            actual: int.to_string(),
+        }
+    );
+
+    let bytes = int.magnitude().to_bytes_be();
+    let mut res = [0; 32];
+    res[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::SigningKey;
+    use num_bigint::{BigInt, Sign};
+    use tracing_test::traced_test;
+
+    #[test]
+    #[traced_test]
+    fn ecrecover_works() {
+        let key = SigningKey::from_slice(&[0x11; 32]).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let point = point.as_bytes();
+
+        let hash = [0x22; 32];
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&hash).unwrap();
+        let signature = signature.to_bytes();
+
+        let hash = uint(&hash);
+        let r = uint(&signature[..32]);
+        let s = uint(&signature[32..]);
+        let v = recovery_id.to_byte();
+        let x1 = uint(&point[1..33]);
+        let x2 = uint(&point[33..65]);
+
+        assert_run_vm!(
+            "ECRECOVER",
+            [int hash.clone(), int v, int r.clone(), int s.clone()] =>
+            [int 4, int x1.clone(), int x2.clone(), int -1]
+        );
+
+        // Signature with high `s` is also accepted.
+        let n = BigInt::parse_bytes(
+            b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .unwrap();
+        let high_s = n - &s;
+        assert_run_vm!(
+            "ECRECOVER",
+            [int hash.clone(), int v ^ 1, int r.clone(), int high_s] =>
+            [int 4, int x1, int x2, int -1]
+        );
+
+        // Invalid signatures.
+        assert_run_vm!("ECRECOVER", [int hash.clone(), int 4, int r.clone(), int s.clone()] => [int 0]);
+        assert_run_vm!("ECRECOVER", [int hash.clone(), int v, int 0, int s.clone()] => [int 0]);
+
+        // Out of range arguments.
+        assert_run_vm!(
+            "ECRECOVER",
+            [int hash.clone(), int 256, int r.clone(), int s.clone()] => [int 0],
+            exit_code: 5
+        );
+        assert_run_vm!(
+            "ECRECOVER",
+            [int hash, int v, int r, int -1] => [int 0],
+            exit_code: 5
+        );
+    }
+
+    fn uint(bytes: &[u8]) -> BigInt {
+        BigInt::from_bytes_be(Sign::Plus, bytes)
+    }
+}
@@ -12,7 +12,7 @@ use crate::GasConsumer;
 
 pub struct SizeOps;
 
-#[vm_module]
+#[vm_module(category = "cell")]
 impl SizeOps {
     #[op(code = "f940", fmt = "CDATASIZEQ", args(is_slice = false, q = true))]
     #[op(code = "f941", fmt = "CDATASIZE", args(is_slice = false, q = false))]
@@ -8,7 +8,7 @@ use crate::state::VmState;
 
 pub struct StackOps;
 
-#[vm_module]
+#[vm_module(category = "stack")]
 impl StackOps {
     #[op(code = "00", fmt = "NOP")]
     fn exec_nop(_: &mut VmState) -> VmResult<i32> {
@@ -9,7 +9,7 @@ use crate::state::VmState;
 
 pub struct TupleOps;
 
-#[vm_module]
+#[vm_module(category = "tuple")]
 impl TupleOps {
     #[op(code = "6d", fmt = "PUSHNULL")]
     fn exec_push_null(st: &mut VmState) -> VmResult<i32> {
@@ -448,7 +448,84 @@ mod tests {
     use num_bigint::BigInt;
     use tracing_test::traced_test;
 
+    use crate::gas::NoLibraries;
     use crate::saferc::SafeRc;
+    use crate::stack::RcStackValue;
+
+    #[test]
+    #[traced_test]
+    fn var_tuple_ops() {
+        assert_run_vm!("TUPLEVAR", [int 1, int 2, int 2] => [[int 1, int 2]]);
+        assert_run_vm!("TUPLEVAR", [int 1, int 0] => [int 1, []]);
+        assert_run_vm!("TUPLEVAR", [int 1, int 2] => [int 0], exit_code: 2);
+        assert_run_vm!("TUPLEVAR", [int 256] => [int 0], exit_code: 5);
+
+        assert_run_vm!("INDEXVAR", [[int 1, int 2], int 1] => [int 2]);
+        assert_run_vm!("INDEXVAR", [[int 1, int 2], int 2] => [int 0], exit_code: 5);
+        assert_run_vm!("INDEXVAR", [[int 1, int 2], int 255] => [int 0], exit_code: 5);
+        assert_run_vm!("INDEXVARQ", [[int 1, int 2], int 1] => [int 2]);
+        assert_run_vm!("INDEXVARQ", [[int 1, int 2], int 2] => [null]);
+        assert_run_vm!("INDEXVARQ", [null, int 0] => [null]);
+
+        assert_run_vm!("UNTUPLEVAR", [[int 1, int 2], int 2] => [int 1, int 2]);
+        assert_run_vm!("UNTUPLEVAR", [[int 1, int 2], int 1] => [int 0], exit_code: 7);
+        assert_run_vm!("UNPACKFIRSTVAR", [[int 1, int 2], int 1] => [int 1]);
+        assert_run_vm!("UNPACKFIRSTVAR", [[int 1, int 2], int 3] => [int 0], exit_code: 7);
+        assert_run_vm!("EXPLODEVAR", [[int 1, int 2], int 3] => [int 1, int 2, int 2]);
+        assert_run_vm!("EXPLODEVAR", [[int 1, int 2], int 1] => [int 0], exit_code: 7);
+
+        assert_run_vm!("SETINDEXVAR", [[int 1, int 2], int 3, int 0] => [[int 3, int 2]]);
+        assert_run_vm!("SETINDEXVAR", [[int 1, int 2], int 3, int 2] => [int 0], exit_code: 5);
+        assert_run_vm!("SETINDEXVARQ", [null, int 3, int 2] => [[null, null, int 3]]);
+        assert_run_vm!("SETINDEXVARQ", [[int 1], null, int 5] => [[int 1]]);
+        assert_run_vm!("SETINDEXVARQ", [[int 1], int 1, int 255] => [int 0], exit_code: 5);
+    }
+
+    #[test]
+    #[traced_test]
+    fn tuple_gas_scales_with_len() {
+        fn run_tuple_op(code: &[u8], stack: Vec<RcStackValue>) -> u64 {
+            let mut output = crate::tests::TracingOutput::default();
+            let (exit_code, vm) = crate::tests::run_vm_with_stack(
+                code,
+                Vec::new(),
+                stack,
+                1000000,
+                &NoLibraries,
+                &mut output,
+            );
+            assert_eq!(exit_code, 0);
+            vm.gas.consumed()
+        }
+
+        fn int(value: usize) -> RcStackValue {
+            SafeRc::new_dyn_value(BigInt::from(value))
+        }
+
+        // 16-bit opcode + implicit RET
+        const BASE_GAS: u64 = 26 + 5;
+
+        for n in [0, 1, 10, 255] {
+            let items = (0..n).map(int).collect::<Vec<_>>();
+            let tuple = SafeRc::new_dyn_value(items.clone());
+
+            let mut stack = items;
+            stack.push(int(n));
+            let gas = run_tuple_op(tvmasm!("TUPLEVAR"), stack);
+            assert_eq!(gas, BASE_GAS + n as u64);
+
+            let gas = run_tuple_op(tvmasm!("UNTUPLEVAR"), vec![tuple.clone(), int(n)]);
+            assert_eq!(gas, BASE_GAS + n as u64);
+
+            let gas = run_tuple_op(tvmasm!("EXPLODEVAR"), vec![tuple.clone(), int(255)]);
+            assert_eq!(gas, BASE_GAS + n as u64);
+
+            if n < 255 {
+                let gas = run_tuple_op(tvmasm!("TPUSH"), vec![tuple, int(0)]);
+                assert_eq!(gas, BASE_GAS + n as u64 + 1);
+            }
+        }
+    }
 
     #[test]
     #[traced_test]
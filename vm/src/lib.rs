@@ -166,6 +166,8 @@ pub use self::cont::{
     AgainCont, ArgContExt, Cont, ControlData, ControlRegs, ExcQuitCont, OrdCont, PushIntCont,
     QuitCont, RcCont, RepeatCont, UntilCont, WhileCont,
 };
+pub use self::coverage::{CellCoverage, Coverage, InstrCoverage};
+pub use self::debugger::{Breakpoint, CodeLocation, Debugger, DebuggerStatus};
 pub use self::dispatch::{
     DispatchTable, FnExecInstrArg, FnExecInstrFull, FnExecInstrSimple, OpcodeBase, OpcodeExec,
     Opcodes,
@@ -178,40 +180,56 @@ pub use self::dispatch::{
 pub use self::error::{DumpError, DumpResult};
 pub use self::error::{VmError, VmException, VmResult};
 pub use self::gas::{
-    GasConsumer, GasConsumerDeriveParams, GasParams, LibraryProvider, LimitedGasConsumer,
-    NoLibraries, ParentGasConsumer, RestoredGasConsumer,
+    CachedLibraries, ChainedLibraries, GasConsumer, GasConsumerDeriveParams, GasParams,
+    GasSnapshot, LibraryCacheStats, LibraryProvider, LimitedGasConsumer, NoLibraries,
+    ParentGasConsumer, RestoredGasConsumer,
 };
-pub use self::instr::{codepage, codepage0};
+pub use self::instr::{codepage, codepage0, default_opcodes, Codepages};
 #[cfg(feature = "tracing")]
 pub use self::log::{VmLogRows, VmLogRowsGuard, VmLogSubscriber, VM_LOG_TARGET};
+pub use self::rng::{DefaultRng, RngProvider};
 pub use self::saferc::{SafeDelete, SafeRc, SafeRcMakeMut};
+pub use self::signature::{
+    DefaultSignatureBackend, SignatureAlgorithm, SignatureBackend, SignatureContext,
+};
 pub use self::smc_info::{
-    CustomSmcInfo, SmcInfo, SmcInfoBase, SmcInfoTonV11, SmcInfoTonV4, SmcInfoTonV6, UnpackedConfig,
-    UnpackedInMsgSmcInfo, VmVersion,
+    mix_rand_seed, CustomSmcInfo, PrevBlocksInfo, SmcInfo, SmcInfoBase, SmcInfoBuilder,
+    SmcInfoError, SmcInfoTonV11, SmcInfoTonV4, SmcInfoTonV6, UnpackedConfig, UnpackedInMsgSmcInfo,
+    VmVersion,
 };
+pub use self::snapshot::VmSnapshot;
 pub use self::stack::{
-    NaN, RcStackValue, Stack, StackValue, StackValueType, StaticStackValue, Tuple, TupleExt,
+    NaN, RcStackValue, Stack, StackGuard, StackValue, StackValueType, StaticStackValue, Tuple,
+    TupleExt,
 };
-#[cfg(feature = "tracing")]
-pub use self::state::VmLogMask;
 pub use self::state::{
-    BehaviourModifiers, CommittedState, InitSelectorParams, IntoCode, ParentVmState, SaveCr,
-    VmState, VmStateBuilder,
+    AcceptInfo, BehaviourModifiers, CancellationFlag, CommittedState, DebugEvent, DebugSink,
+    DebugSinks, ExecutionLimits, InitSelectorParams, IntoCode, ParentVmState, SaveCr,
+    SendMsgEstimate, VmInterrupt, VmState, VmStateBuilder,
 };
+#[cfg(feature = "tracing")]
+pub use self::state::{OpCategory, VmLogMask};
+pub use self::trace::{TraceCollector, TraceEvent, TraceInstr};
 pub use self::util::OwnedCellSlice;
 
 #[macro_use]
 mod log;
 
 mod cont;
+mod coverage;
+mod debugger;
 mod dispatch;
 mod error;
 mod gas;
 mod instr;
+mod rng;
 mod saferc;
+mod signature;
 mod smc_info;
+mod snapshot;
 mod stack;
 mod state;
+mod trace;
 mod util;
 
 #[doc(hidden)]
@@ -462,6 +480,73 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[traced_test]
+    fn data_depth_limit_is_version_independent() -> anyhow::Result<()> {
+        fn make_deep_cell(depth: u16) -> Cell {
+            let mut cell = Cell::empty_cell();
+            for _ in 0..depth {
+                cell = CellBuilder::build_from(cell).unwrap();
+            }
+            cell
+        }
+
+        let code = Boc::decode(tvmasm!("POPROOT"))?;
+
+        // NOTE: The reference VM has no separate strict limits mode for v11.
+        //       Stack depth, continuation nesting and cell creation limits
+        //       are the same for all supported versions, so there are no
+        //       version-specific limits or exception codes to implement.
+        //       This test only pins that behaviour for replays.
+        for version in [VmVersion::Ton(4), VmVersion::Ton(10), VmVersion::LATEST_TON] {
+            for (depth, exit_code) in [
+                (VmState::MAX_DATA_DEPTH, 0),
+                (VmState::MAX_DATA_DEPTH + 1, 8),
+            ] {
+                let mut output = TracingOutput::default();
+                let mut vm_state = VmState::builder()
+                    .with_code(code.clone())
+                    .with_stack([SafeRc::new_dyn_value(make_deep_cell(depth))])
+                    .with_version(version)
+                    .with_debug(&mut output)
+                    .build();
+
+                assert_eq!(!vm_state.run(), exit_code);
+                assert_eq!(vm_state.committed_state.is_some(), exit_code == 0);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn log_opcode_categories() {
+        let subscriber =
+            VmLogSubscriber::new(VmLogMask::MESSAGE, 128).with_categories(OpCategory::ARITH);
+        let rows = subscriber.rows().clone();
+        {
+            let _tracing = tracing::subscriber::set_default(subscriber);
+
+            let code = Boc::decode(tvmasm!("INT 1 INT 2 ADD DUP DROP")).unwrap();
+            let mut vm = VmState::builder()
+                .with_code(code)
+                .with_gas(GasParams::getter())
+                .build();
+            assert_eq!(vm.run(), -1);
+        }
+
+        let rows = rows.lock();
+        let ops = rows
+            .iter()
+            .filter(|row| row.starts_with("execute"))
+            .collect::<Vec<_>>();
+
+        // Only arithmetic ops must be logged.
+        assert_eq!(ops.len(), 3);
+        assert!(ops[2].contains("ADD"));
+    }
+
     #[derive(Default)]
     pub struct TracingOutput {
         buffer: String,
@@ -7,6 +7,12 @@ pub const VM_LOG_TARGET: &str = "tycho_vm";
 
 #[cfg(feature = "tracing")]
 macro_rules! vm_log_op {
+    (@$category:ident $($tt:tt)*) => {
+        $crate::log::__log_op_category(
+            $crate::state::OpCategory::$category,
+            format_args!($($tt)*),
+        )
+    };
     ($($tt:tt)*) => { $crate::log::__log_op(format_args!($($tt)*)) };
 }
 
@@ -15,6 +21,16 @@ pub(crate) fn __log_op(args: std::fmt::Arguments<'_>) {
     tracing::trace!(target: VM_LOG_TARGET, opcode = %args);
 }
 
+#[cfg(feature = "tracing")]
+pub(crate) fn __log_op_category(category: crate::state::OpCategory, args: std::fmt::Arguments<'_>) {
+    // NOTE: Category must be recorded before the opcode.
+    tracing::trace!(
+        target: VM_LOG_TARGET,
+        opcode_category = category.bits(),
+        opcode = %args,
+    );
+}
+
 #[cfg(not(feature = "tracing"))]
 macro_rules! vm_log_op {
     ($($tt:tt)*) => {{}};
@@ -116,7 +132,7 @@ mod subscriber {
     use tracing::{span, Subscriber};
 
     use super::VM_LOG_TARGET;
-    use crate::state::VmLogMask;
+    use crate::state::{OpCategory, VmLogMask};
 
     /// Tracing subscriber which intercepts all VM logs and collets it into [`VmLogRows`].
     ///
@@ -135,22 +151,44 @@ mod subscriber {
     /// ```
     pub struct VmLogSubscriber {
         vm_log_mask: VmLogMask,
+        categories: OpCategory,
         state: VmLogRows,
+        writer: Option<Arc<Mutex<dyn std::fmt::Write + Send>>>,
     }
 
     impl VmLogSubscriber {
         pub fn new(mask: VmLogMask, capacity: usize) -> Self {
             Self {
                 vm_log_mask: mask,
+                categories: OpCategory::ALL,
                 state: VmLogRows {
                     inner: Arc::new(Mutex::new(Inner {
                         capacity,
                         rows: VecDeque::with_capacity(capacity.min(256)),
                     })),
                 },
+                writer: None,
             }
         }
 
+        /// Writes all rows into the specified output (one row per line)
+        /// instead of collecting them into [`VmLogRows`].
+        pub fn with_writer<W>(mut self, writer: Arc<Mutex<W>>) -> Self
+        where
+            W: std::fmt::Write + Send + 'static,
+        {
+            self.writer = Some(writer);
+            self
+        }
+
+        /// Logs only instructions of the specified categories.
+        ///
+        /// Instructions without a category are always logged.
+        pub fn with_categories(mut self, categories: OpCategory) -> Self {
+            self.categories = categories;
+            self
+        }
+
         pub fn rows(&self) -> &VmLogRows {
             &self.state
         }
@@ -174,9 +212,32 @@ mod subscriber {
                 return;
             }
 
+            if let Some(writer) = &self.writer {
+                use std::fmt::Write;
+
+                let mut inner = Inner {
+                    capacity: usize::MAX,
+                    rows: VecDeque::new(),
+                };
+                event.record(&mut LogVisitor {
+                    inner: &mut inner,
+                    mask: self.vm_log_mask,
+                    categories: self.categories,
+                    skip_opcode: false,
+                });
+
+                let mut writer = writer.lock().unwrap();
+                for row in inner.rows {
+                    _ = writeln!(writer, "{row}");
+                }
+                return;
+            }
+
             event.record(&mut LogVisitor {
                 inner: &mut self.state.inner.lock().unwrap(),
                 mask: self.vm_log_mask,
+                categories: self.categories,
+                skip_opcode: false,
             });
         }
 
@@ -188,9 +249,20 @@ mod subscriber {
     struct LogVisitor<'a> {
         inner: &'a mut Inner,
         mask: VmLogMask,
+        categories: OpCategory,
+        skip_opcode: bool,
     }
 
     impl tracing::field::Visit for LogVisitor<'_> {
+        fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+            if field.name() == "opcode_category" {
+                let category = OpCategory::from_bits_retain(value as u16);
+                self.skip_opcode = !self.categories.intersects(category);
+            } else {
+                self.record_debug(field, &value);
+            }
+        }
+
         fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
             use std::fmt::Write;
 
@@ -203,7 +275,7 @@ mod subscriber {
                 "message" if self.mask.contains(VmLogMask::MESSAGE) => {
                     write!(&mut buffer, "{value:?}")
                 }
-                "opcode" if self.mask.contains(VmLogMask::MESSAGE) => {
+                "opcode" if self.mask.contains(VmLogMask::MESSAGE) && !self.skip_opcode => {
                     write!(&mut buffer, "execute {value:?}")
                 }
                 "stack" if self.mask.intersects(STACK_MASK) => {
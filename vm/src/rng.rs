@@ -0,0 +1,51 @@
+use everscale_types::cell::HashBytes;
+use sha2::Digest;
+
+/// Source of randomness for `RANDU256`, `RAND` and `ADDRAND`.
+///
+/// The seed itself is always stored in `c7`, so `RANDSEED` and `SETRAND`
+/// work the same way for all providers. Implementations can ignore
+/// the seed to supply deterministic or recorded randomness.
+pub trait RngProvider {
+    /// Returns a new seed and a random value for the current seed.
+    fn next(&mut self, seed: &HashBytes) -> (HashBytes, HashBytes);
+
+    /// Returns a new seed mixed with the specified value.
+    fn mix(&mut self, seed: &HashBytes, value: &HashBytes) -> HashBytes;
+}
+
+impl<T: RngProvider + ?Sized> RngProvider for &mut T {
+    #[inline]
+    fn next(&mut self, seed: &HashBytes) -> (HashBytes, HashBytes) {
+        T::next(self, seed)
+    }
+
+    #[inline]
+    fn mix(&mut self, seed: &HashBytes, value: &HashBytes) -> HashBytes {
+        T::mix(self, seed, value)
+    }
+}
+
+/// Reference random number generator.
+///
+/// - `next` splits `sha512(seed)` into a new seed and a value;
+/// - `mix` computes `sha256(seed || value)`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultRng;
+
+impl RngProvider for DefaultRng {
+    fn next(&mut self, seed: &HashBytes) -> (HashBytes, HashBytes) {
+        let hash = sha2::Sha512::digest(seed.as_array());
+        (
+            HashBytes::from_slice(&hash[..32]),
+            HashBytes::from_slice(&hash[32..]),
+        )
+    }
+
+    fn mix(&mut self, seed: &HashBytes, value: &HashBytes) -> HashBytes {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(seed.as_array());
+        hasher.update(value.as_array());
+        HashBytes(hasher.finalize().into())
+    }
+}
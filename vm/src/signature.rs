@@ -0,0 +1,121 @@
+use everscale_crypto::ed25519;
+
+/// Signature scheme used by the opcode.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SignatureAlgorithm {
+    /// `CHKSIGNU`, `CHKSIGNS`, `ED25519_CHKSIGNU`, `ED25519_CHKSIGNS`.
+    Ed25519,
+    /// `P256_CHKSIGNU`, `P256_CHKSIGNS`.
+    P256,
+}
+
+/// Signature check request.
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureContext<'a> {
+    /// Signature scheme.
+    pub algorithm: SignatureAlgorithm,
+    /// Signed data (without the signature id).
+    pub data: &'a [u8],
+    /// Raw public key.
+    ///
+    /// 32 bytes for `Ed25519`, 33 bytes (compressed SEC1) for `P256`.
+    pub public_key: &'a [u8],
+    /// Raw signature.
+    pub signature: &'a [u8; 64],
+    /// Signature id which must be prepended to the data.
+    ///
+    /// Only used by `Ed25519` opcodes which support it.
+    pub signature_id: Option<i32>,
+}
+
+/// Signature verifier for `CHKSIGN`-like opcodes.
+///
+/// Gas is always charged by the VM before the check, so implementations
+/// can freely cache results or skip the actual verification.
+pub trait SignatureBackend {
+    /// Returns `true` if the signature is valid.
+    fn verify(&mut self, ctx: &SignatureContext<'_>) -> bool;
+}
+
+impl<T: SignatureBackend + ?Sized> SignatureBackend for &mut T {
+    #[inline]
+    fn verify(&mut self, ctx: &SignatureContext<'_>) -> bool {
+        T::verify(self, ctx)
+    }
+}
+
+/// Reference signature verifier.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultSignatureBackend;
+
+impl SignatureBackend for DefaultSignatureBackend {
+    fn verify(&mut self, ctx: &SignatureContext<'_>) -> bool {
+        match ctx.algorithm {
+            SignatureAlgorithm::Ed25519 => verify_ed25519(ctx),
+            SignatureAlgorithm::P256 => verify_p256(ctx),
+        }
+    }
+}
+
+fn verify_ed25519(ctx: &SignatureContext<'_>) -> bool {
+    let Ok(key) = <[u8; 32]>::try_from(ctx.public_key) else {
+        return false;
+    };
+    let Some(pubkey) = ed25519::PublicKey::from_bytes(key) else {
+        return false;
+    };
+
+    pubkey.verify(
+        ToSign {
+            signature_id: ctx.signature_id,
+            data: ctx.data,
+        },
+        ctx.signature,
+    )
+}
+
+#[cfg(feature = "p256")]
+fn verify_p256(ctx: &SignatureContext<'_>) -> bool {
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::{Signature, VerifyingKey};
+
+    let Ok(pubkey) = VerifyingKey::from_sec1_bytes(ctx.public_key) else {
+        return false;
+    };
+    let Ok(signature) = Signature::from_slice(ctx.signature) else {
+        return false;
+    };
+
+    // NOTE: Data is hashed with SHA-256 before the verification.
+    pubkey.verify(ctx.data, &signature).is_ok()
+}
+
+#[cfg(not(feature = "p256"))]
+fn verify_p256(_: &SignatureContext<'_>) -> bool {
+    false
+}
+
+struct ToSign<'a> {
+    signature_id: Option<i32>,
+    data: &'a [u8],
+}
+
+impl tl_proto::TlWrite for ToSign<'_> {
+    type Repr = tl_proto::Bare;
+
+    #[inline]
+    fn max_size_hint(&self) -> usize {
+        (if self.signature_id.is_some() { 4 } else { 0 }) + self.data.len()
+    }
+
+    #[inline]
+    fn write_to<P>(&self, packet: &mut P)
+    where
+        P: tl_proto::TlPacket,
+    {
+        if let Some(id) = self.signature_id {
+            packet.write_raw_slice(&id.to_be_bytes());
+        }
+        packet.write_raw_slice(self.data);
+    }
+}
@@ -1,9 +1,10 @@
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use everscale_types::error::Error;
 use everscale_types::models::{
-    BlockchainConfigParams, CurrencyCollection, IntAddr, IntMsgInfo, MsgType, StateInit,
+    BlockId, BlockchainConfigParams, CurrencyCollection, IntAddr, IntMsgInfo, MsgType, StateInit,
 };
 use everscale_types::num::Tokens;
 use everscale_types::prelude::*;
@@ -15,6 +16,21 @@ use crate::saferc::{SafeDelete, SafeRc};
 use crate::stack::{RcStackValue, Stack, Tuple};
 use crate::util::OwnedCellSlice;
 
+/// Derives an account rand seed from the block rand seed.
+///
+/// Computes `sha256(block_seed || account)` (or keeps zero seed as is),
+/// which is the same as the on-chain scheme.
+pub fn mix_rand_seed(block_seed: &HashBytes, account: &HashBytes) -> HashBytes {
+    if *block_seed == HashBytes::ZERO {
+        return HashBytes::ZERO;
+    }
+
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(block_seed.as_array());
+    hasher.update(account.as_array());
+    HashBytes(hasher.finalize().into())
+}
+
 /// Version of a VM context.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum VmVersion {
@@ -161,14 +177,7 @@ impl SmcInfoBase {
     }
 
     pub fn with_mixed_rand_seed(mut self, block_seed: &HashBytes, account: &HashBytes) -> Self {
-        if *block_seed == HashBytes::ZERO {
-            self.rand_seed = HashBytes::ZERO;
-        } else {
-            let mut hasher = sha2::Sha256::new();
-            hasher.update(block_seed.as_array());
-            hasher.update(account.as_array());
-            self.rand_seed = HashBytes(hasher.finalize().into());
-        }
+        self.rand_seed = mix_rand_seed(block_seed, account);
         self
     }
 
@@ -258,7 +267,7 @@ pub struct SmcInfoTonV4 {
     pub message_balance: CurrencyCollection,
     /// Storage fees collected on the storage phase.
     pub storage_fees: Tokens,
-    /// Previous blocks info (raw for now).
+    /// Previous blocks info (see [`PrevBlocksInfo`]).
     pub prev_blocks_info: Option<SafeRc<Tuple>>,
 }
 
@@ -295,6 +304,7 @@ impl SmcInfoTonV4 {
             base: self,
             unpacked_config: None,
             due_payment: Tokens::ZERO,
+            precompiled_gas_usage: None,
         }
     }
 
@@ -343,7 +353,8 @@ pub struct SmcInfoTonV6 {
     pub unpacked_config: Option<SafeRc<Tuple>>,
     /// Storage phase debt.
     pub due_payment: Tokens,
-    // TODO: Add `precompiled_gas_usage`.
+    /// Fixed gas usage of the precompiled contract (`ConfigParam45`).
+    pub precompiled_gas_usage: Option<u64>,
 }
 
 impl SmcInfoTonV6 {
@@ -380,6 +391,22 @@ impl SmcInfoTonV6 {
         ]))
     }
 
+    /// Returns an index of the config param in the unpacked config tuple.
+    ///
+    /// NOTE: Storage prices (`ConfigParam18`) are stored as the latest
+    /// entry only, so it is not a complete param.
+    pub fn unpacked_config_idx(param: u32) -> Option<usize> {
+        Some(match param {
+            19 => 1,
+            20 => 2,
+            21 => 3,
+            24 => 4,
+            25 => 5,
+            43 => 6,
+            _ => return None,
+        })
+    }
+
     pub fn unpack_config_partial(
         params: &BlockchainConfigParams,
         now: u32,
@@ -433,6 +460,11 @@ impl SmcInfoTonV6 {
         self
     }
 
+    pub fn with_precompiled_gas_usage(mut self, gas_usage: Option<u64>) -> Self {
+        self.precompiled_gas_usage = gas_usage;
+        self
+    }
+
     pub fn require_ton_v11(self) -> SmcInfoTonV11 {
         SmcInfoTonV11 {
             base: self,
@@ -452,8 +484,11 @@ impl SmcInfoTonV6 {
         items.push(SafeRc::new_dyn_value(BigInt::from(
             self.due_payment.into_inner(),
         )));
-        // precompiled_gas_usage:Integer
-        items.push(Stack::make_null());
+        // precompiled_gas_usage:(Maybe Integer)
+        items.push(match self.precompiled_gas_usage {
+            None => Stack::make_null(),
+            Some(gas_usage) => SafeRc::new_dyn_value(BigInt::from(gas_usage)),
+        });
     }
 }
 
@@ -582,6 +617,9 @@ pub struct UnpackedConfig {
 }
 
 impl UnpackedConfig {
+    /// Max message cells used when the size limits config is not available.
+    pub const DEFAULT_MAX_MSG_CELLS: u32 = 1 << 13;
+
     pub fn into_tuple(self) -> SafeRc<Tuple> {
         SafeRc::new(vec![
             Self::slice_or_null(self.latest_storage_prices),
@@ -627,6 +665,49 @@ impl IntoSliceUnchecked for CellSliceParts {
     }
 }
 
+/// Previous blocks info, ready to be used in [`SmcInfoTonV4::with_prev_blocks_info`].
+///
+/// A `Send + Sync` alternative of C7 [`SafeRc<Tuple>`] (like [`UnpackedConfig`]).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrevBlocksInfo {
+    /// Last masterchain blocks (up to 16, the most recent first).
+    pub last_mc_blocks: Vec<BlockId>,
+    /// The most recent key block.
+    pub prev_key_block: BlockId,
+    /// Every 100th masterchain block (up to 16, the most recent first).
+    ///
+    /// Since TVM v9. The tuple has only two items when `None`.
+    pub last_mc_blocks_100: Option<Vec<BlockId>>,
+}
+
+impl PrevBlocksInfo {
+    pub fn as_tuple(&self) -> SafeRc<Tuple> {
+        let mut items = Vec::with_capacity(3);
+        items.push(Self::block_ids_tuple(&self.last_mc_blocks));
+        items.push(Self::block_id_tuple(&self.prev_key_block));
+        if let Some(blocks) = &self.last_mc_blocks_100 {
+            items.push(Self::block_ids_tuple(blocks));
+        }
+        SafeRc::new(items)
+    }
+
+    fn block_ids_tuple(ids: &[BlockId]) -> RcStackValue {
+        let items = ids.iter().map(Self::block_id_tuple).collect::<Tuple>();
+        SafeRc::new_dyn_value(items)
+    }
+
+    // [ wc:Integer shard:Integer seqno:Integer root_hash:Integer file_hash:Integer ] = BlockId;
+    fn block_id_tuple(id: &BlockId) -> RcStackValue {
+        SafeRc::new_dyn_value(tuple![
+            int id.shard.workchain(),
+            int id.shard.prefix(),
+            int id.seqno,
+            int BigInt::from_bytes_be(Sign::Plus, id.root_hash.as_slice()),
+            int BigInt::from_bytes_be(Sign::Plus, id.file_hash.as_slice()),
+        ])
+    }
+}
+
 /// Internal message parts.
 pub struct UnpackedInMsgSmcInfo {
     pub bounce: bool,
@@ -730,6 +811,223 @@ impl SmcInfo for CustomSmcInfo {
     }
 }
 
+/// Smart Contract Info with the layout selected by the VM version.
+///
+/// Unlike [`SmcInfoBase`] and its extensions, values which are not
+/// present in the layout of the version are rejected, and arbitrary
+/// entries can be replaced or appended.
+#[derive(Debug, Clone)]
+pub struct SmcInfoBuilder {
+    version: VmVersion,
+    info: SmcInfoTonV11,
+    entries: BTreeMap<usize, RcStackValue>,
+}
+
+impl SmcInfoBuilder {
+    /// Max number of `c7` params (same as the max tuple length).
+    pub const MAX_ITEM_COUNT: usize = 255;
+
+    pub fn new(version: VmVersion) -> Self {
+        Self {
+            version,
+            info: Default::default(),
+            entries: Default::default(),
+        }
+    }
+
+    /// Number of `c7` params in the layout of the specified version.
+    pub fn item_count(version: VmVersion) -> usize {
+        if version.is_ton(11..) {
+            SmcInfoTonV11::C7_ITEM_COUNT
+        } else if version.is_ton(6..) {
+            SmcInfoTonV6::C7_ITEM_COUNT
+        } else if version.is_ton(4..) {
+            SmcInfoTonV4::C7_ITEM_COUNT
+        } else {
+            SmcInfoBase::C7_ITEM_COUNT
+        }
+    }
+
+    pub fn version(&self) -> VmVersion {
+        self.version
+    }
+
+    pub fn with_now(mut self, now: u32) -> Self {
+        self.base_mut().now = now;
+        self
+    }
+
+    pub fn with_block_lt(mut self, block_lt: u64) -> Self {
+        self.base_mut().block_lt = block_lt;
+        self
+    }
+
+    pub fn with_tx_lt(mut self, tx_lt: u64) -> Self {
+        self.base_mut().tx_lt = tx_lt;
+        self
+    }
+
+    pub fn with_raw_rand_seed(mut self, raw_rand_seed: HashBytes) -> Self {
+        self.base_mut().rand_seed = raw_rand_seed;
+        self
+    }
+
+    pub fn with_mixed_rand_seed(mut self, block_seed: &HashBytes, account: &HashBytes) -> Self {
+        self.base_mut().rand_seed = mix_rand_seed(block_seed, account);
+        self
+    }
+
+    pub fn with_account_balance(mut self, balance: CurrencyCollection) -> Self {
+        self.base_mut().account_balance = balance;
+        self
+    }
+
+    pub fn with_account_addr(mut self, addr: IntAddr) -> Self {
+        self.base_mut().addr = addr;
+        self
+    }
+
+    pub fn with_config(mut self, params: BlockchainConfigParams) -> Self {
+        self.base_mut().config = Some(params);
+        self
+    }
+
+    pub fn with_code(mut self, code: Cell) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV4::MYCODE_IDX));
+        self.info.base.base.code = Some(code);
+        Ok(self)
+    }
+
+    pub fn with_message_balance(
+        mut self,
+        balance: CurrencyCollection,
+    ) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV4::IN_MSG_VALUE_IDX));
+        self.info.base.base.message_balance = balance;
+        Ok(self)
+    }
+
+    pub fn with_storage_fees(mut self, storage_fees: Tokens) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV4::STORAGE_FEE_IDX));
+        self.info.base.base.storage_fees = storage_fees;
+        Ok(self)
+    }
+
+    pub fn with_prev_blocks_info(
+        mut self,
+        prev_blocks_info: SafeRc<Tuple>,
+    ) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV4::PREV_BLOCKS_IDX));
+        self.info.base.base.prev_blocks_info = Some(prev_blocks_info);
+        Ok(self)
+    }
+
+    pub fn with_unpacked_config(mut self, config: SafeRc<Tuple>) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV6::PARSED_CONFIG_IDX));
+        self.info.base.unpacked_config = Some(config);
+        Ok(self)
+    }
+
+    pub fn with_due_payment(mut self, due_payment: Tokens) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV6::STORAGE_DEBT_IDX));
+        self.info.base.due_payment = due_payment;
+        Ok(self)
+    }
+
+    pub fn with_precompiled_gas_usage(
+        mut self,
+        gas_usage: Option<u64>,
+    ) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV6::PRECOMPILED_GAS_IDX));
+        self.info.base.precompiled_gas_usage = gas_usage;
+        Ok(self)
+    }
+
+    pub fn with_unpacked_in_msg(
+        mut self,
+        in_msg: Option<SafeRc<Tuple>>,
+    ) -> Result<Self, SmcInfoError> {
+        ok!(self.check_index(SmcInfoTonV11::IN_MSG_PARAMS_IDX));
+        self.info.in_msg = in_msg;
+        Ok(self)
+    }
+
+    /// Replaces a param of the layout or appends a new one.
+    ///
+    /// Gaps between the layout and appended params are filled with `null`.
+    /// The magic (param 0) can't be replaced.
+    pub fn with_entry(mut self, index: usize, value: RcStackValue) -> Result<Self, SmcInfoError> {
+        if index == 0 || index >= Self::MAX_ITEM_COUNT {
+            return Err(SmcInfoError::IndexOutOfRange(index));
+        }
+        self.entries.insert(index, value);
+        Ok(self)
+    }
+
+    pub fn build(self) -> CustomSmcInfo {
+        CustomSmcInfo {
+            version: self.version,
+            c7: self.build_c7(),
+        }
+    }
+
+    fn base_mut(&mut self) -> &mut SmcInfoBase {
+        &mut self.info.base.base.base
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), SmcInfoError> {
+        if index < Self::item_count(self.version) {
+            Ok(())
+        } else {
+            Err(SmcInfoError::UnsupportedParam {
+                index,
+                version: self.version,
+            })
+        }
+    }
+}
+
+impl SmcInfo for SmcInfoBuilder {
+    fn version(&self) -> VmVersion {
+        self.version
+    }
+
+    fn build_c7(&self) -> SafeRc<Tuple> {
+        let item_count = Self::item_count(self.version);
+        let len = match self.entries.last_key_value() {
+            Some((index, _)) => std::cmp::max(item_count, index + 1),
+            None => item_count,
+        };
+
+        let mut t1 = Vec::with_capacity(len);
+        match item_count {
+            SmcInfoTonV11::C7_ITEM_COUNT => self.info.write_items(&mut t1),
+            SmcInfoTonV6::C7_ITEM_COUNT => self.info.base.write_items(&mut t1),
+            SmcInfoTonV4::C7_ITEM_COUNT => self.info.base.base.write_items(&mut t1),
+            _ => self.info.base.base.base.write_items(&mut t1),
+        }
+        debug_assert_eq!(t1.len(), item_count);
+
+        for (index, value) in &self.entries {
+            if *index >= t1.len() {
+                t1.resize(*index + 1, Stack::make_null());
+            }
+            t1[*index] = value.clone();
+        }
+
+        SafeRc::new(vec![SafeRc::new_dyn_value(t1)])
+    }
+}
+
+/// Invalid [`SmcInfoBuilder`] param.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SmcInfoError {
+    #[error("c7 param {index} is not supported by {version:?}")]
+    UnsupportedParam { index: usize, version: VmVersion },
+    #[error("c7 param index {0} is out of range")]
+    IndexOutOfRange(usize),
+}
+
 fn balance_as_tuple(balance: &CurrencyCollection) -> SafeRc<Tuple> {
     SafeRc::new(vec![
         SafeRc::new_dyn_value(BigInt::from(balance.tokens.into_inner())),
@@ -739,3 +1037,122 @@ fn balance_as_tuple(balance: &CurrencyCollection) -> SafeRc<Tuple> {
         },
     ])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_layouts() {
+        let t1 = |info: &dyn SmcInfo| {
+            let c7 = info.build_c7();
+            assert_eq!(c7.len(), 1);
+            c7[0].as_tuple().unwrap().to_vec()
+        };
+
+        let addr = IntAddr::Std(everscale_types::models::StdAddr::new(
+            0,
+            HashBytes([0x11; 32]),
+        ));
+        let code = Cell::empty_cell();
+
+        // Same layouts as the typed contexts.
+        for version in [
+            VmVersion::Ton(1),
+            VmVersion::Ton(4),
+            VmVersion::Ton(6),
+            VmVersion::Ton(11),
+        ] {
+            let builder = SmcInfoBuilder::new(version)
+                .with_now(123)
+                .with_raw_rand_seed(HashBytes([0x22; 32]))
+                .with_account_addr(addr.clone());
+            let base = SmcInfoBase::new()
+                .with_now(123)
+                .with_raw_rand_seed(HashBytes([0x22; 32]))
+                .with_account_addr(addr.clone());
+
+            let (builder, expected) = if version.is_ton(11..) {
+                let builder = builder.with_code(code.clone()).unwrap();
+                let expected = base
+                    .require_ton_v4()
+                    .with_code(code.clone())
+                    .require_ton_v6()
+                    .require_ton_v11();
+                (builder, t1(&expected))
+            } else if version.is_ton(6..) {
+                let builder = builder.with_code(code.clone()).unwrap();
+                let expected = base
+                    .require_ton_v4()
+                    .with_code(code.clone())
+                    .require_ton_v6();
+                (builder, t1(&expected))
+            } else if version.is_ton(4..) {
+                let builder = builder.with_code(code.clone()).unwrap();
+                let expected = base.require_ton_v4().with_code(code.clone());
+                (builder, t1(&expected))
+            } else {
+                (builder, t1(&base))
+            };
+
+            let actual = t1(&builder);
+            assert_eq!(actual.len(), SmcInfoBuilder::item_count(version));
+            assert_eq!(format!("{actual:?}"), format!("{expected:?}"));
+            assert_eq!(builder.build().version, version);
+        }
+    }
+
+    #[test]
+    fn builder_validation() {
+        let err = SmcInfoBuilder::new(VmVersion::Ton(3))
+            .with_code(Cell::empty_cell())
+            .unwrap_err();
+        assert_eq!(err, SmcInfoError::UnsupportedParam {
+            index: SmcInfoTonV4::MYCODE_IDX,
+            version: VmVersion::Ton(3),
+        });
+
+        let err = SmcInfoBuilder::new(VmVersion::Ton(6))
+            .with_unpacked_in_msg(None)
+            .unwrap_err();
+        assert_eq!(err, SmcInfoError::UnsupportedParam {
+            index: SmcInfoTonV11::IN_MSG_PARAMS_IDX,
+            version: VmVersion::Ton(6),
+        });
+
+        let builder = SmcInfoBuilder::new(VmVersion::Ton(4));
+        assert_eq!(
+            builder
+                .clone()
+                .with_entry(0, Stack::make_null())
+                .unwrap_err(),
+            SmcInfoError::IndexOutOfRange(0)
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .with_entry(255, Stack::make_null())
+                .unwrap_err(),
+            SmcInfoError::IndexOutOfRange(255)
+        );
+
+        // Custom entries replace or extend the layout.
+        let c7 = builder
+            .with_entry(
+                SmcInfoBase::UNIX_TIME_IDX,
+                SafeRc::new_dyn_value(BigInt::from(10)),
+            )
+            .unwrap()
+            .with_entry(20, SafeRc::new_dyn_value(BigInt::from(20)))
+            .unwrap()
+            .build_c7();
+        let t1 = c7[0].as_tuple().unwrap();
+        assert_eq!(t1.len(), 21);
+        assert_eq!(
+            t1[SmcInfoBase::UNIX_TIME_IDX].as_int(),
+            Some(&BigInt::from(10))
+        );
+        assert!(t1[SmcInfoTonV4::C7_ITEM_COUNT].is_null());
+        assert_eq!(t1[20].as_int(), Some(&BigInt::from(20)));
+    }
+}
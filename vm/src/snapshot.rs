@@ -0,0 +1,330 @@
+use everscale_types::dict::Dict;
+use everscale_types::error::Error;
+use everscale_types::prelude::*;
+
+use crate::cont::ControlRegs;
+use crate::gas::GasSnapshot;
+use crate::saferc::SafeRc;
+use crate::smc_info::VmVersion;
+use crate::stack::{load_slice_as_stack_value, store_slice_as_stack_value, Stack};
+use crate::state::{CommittedState, VmState};
+use crate::util::OwnedCellSlice;
+
+/// Saved state of the paused execution.
+///
+/// Can be stored into a cell to continue the execution later
+/// (see [`VmStateBuilder::with_snapshot`]).
+///
+/// Only the execution state is saved. Libraries, codepages, behaviour
+/// modifiers, debug outputs and other environment must be provided again.
+///
+/// ```text
+/// vm_snapshot#01 version:VmVersion code:VmCellSlice
+///   cp:uint16 steps:uint64 stack:^VmStack
+///   regs:^[ cr:VmSaveList committed:(Maybe ^[ c4:^Cell c5:^Cell ]) ]
+///   gas:^VmGasSnapshot = VmSnapshot;
+///
+/// vm_version_everscale#00 version:uint32 = VmVersion;
+/// vm_version_ton#01 version:uint32 = VmVersion;
+///
+/// vm_gas_snapshot#_ max:uint64 limit:uint64 credit:uint64 base:uint64
+///   consumed_on_accept:(Maybe uint64) remaining:int64 price:uint64
+///   chksign_counter:uint32 free_gas_consumed:uint64
+///   get_extra_balance_counter:uint32
+///   loaded_cells:(HashmapE 256 True) = VmGasSnapshot;
+/// ```
+///
+/// [`VmStateBuilder::with_snapshot`]: crate::VmStateBuilder::with_snapshot
+#[derive(Debug, Clone)]
+pub struct VmSnapshot {
+    pub version: VmVersion,
+    pub code: OwnedCellSlice,
+    pub cp: u16,
+    pub steps: u64,
+    pub stack: SafeRc<Stack>,
+    pub cr: ControlRegs,
+    pub committed_state: Option<CommittedState>,
+    pub gas: GasSnapshot,
+}
+
+impl VmSnapshot {
+    const TAG: u8 = 0x01;
+
+    const VERSION_EVERSCALE: u8 = 0x00;
+    const VERSION_TON: u8 = 0x01;
+
+    /// Saves the state of the VM.
+    ///
+    /// Returns `None` if the state cannot be saved
+    /// (during the nested `RUNVM` execution).
+    pub fn new(st: &VmState<'_>) -> Option<Self> {
+        if st.parent.is_some() {
+            return None;
+        }
+
+        Some(Self {
+            version: st.version,
+            code: st.code.clone(),
+            cp: st.cp.id(),
+            steps: st.steps,
+            stack: st.stack.clone(),
+            cr: st.cr.clone(),
+            committed_state: st.committed_state.clone(),
+            gas: st.gas.snapshot(),
+        })
+    }
+}
+
+impl Store for VmSnapshot {
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        context: &dyn CellContext,
+    ) -> Result<(), Error> {
+        ok!(builder.store_u8(Self::TAG));
+        let (version_tag, version) = match self.version {
+            VmVersion::Everscale(version) => (Self::VERSION_EVERSCALE, version),
+            VmVersion::Ton(version) => (Self::VERSION_TON, version),
+        };
+        ok!(builder.store_u8(version_tag));
+        ok!(builder.store_u32(version));
+        ok!(store_slice_as_stack_value(&self.code, builder));
+        ok!(builder.store_u16(self.cp));
+        ok!(builder.store_u64(self.steps));
+
+        ok!(builder.store_reference(ok!(CellBuilder::build_from_ext(
+            self.stack.as_ref(),
+            context
+        ))));
+
+        let regs = {
+            let mut builder = CellBuilder::new();
+            ok!(self.cr.store_into(&mut builder, context));
+            match &self.committed_state {
+                None => ok!(builder.store_bit_zero()),
+                Some(committed) => {
+                    let mut child = CellBuilder::new();
+                    ok!(child.store_reference(committed.c4.clone()));
+                    ok!(child.store_reference(committed.c5.clone()));
+                    ok!(builder.store_bit_one());
+                    ok!(builder.store_reference(ok!(child.build_ext(context))));
+                }
+            }
+            ok!(builder.build_ext(context))
+        };
+        ok!(builder.store_reference(regs));
+
+        builder.store_reference(ok!(CellBuilder::build_from_ext(&self.gas, context)))
+    }
+}
+
+impl Load<'_> for VmSnapshot {
+    fn load_from(slice: &mut CellSlice<'_>) -> Result<Self, Error> {
+        if ok!(slice.load_u8()) != Self::TAG {
+            return Err(Error::InvalidTag);
+        }
+
+        let version = match ok!(slice.load_u8()) {
+            Self::VERSION_EVERSCALE => VmVersion::Everscale(ok!(slice.load_u32())),
+            Self::VERSION_TON => VmVersion::Ton(ok!(slice.load_u32())),
+            _ => return Err(Error::InvalidTag),
+        };
+        let code = ok!(load_slice_as_stack_value(slice));
+        let cp = ok!(slice.load_u16());
+        let steps = ok!(slice.load_u64());
+
+        let stack = ok!(ok!(slice.load_reference()).parse::<Stack>());
+
+        let regs = &mut ok!(ok!(slice.load_reference()).as_slice());
+        let cr = ok!(ControlRegs::load_from(regs));
+        let committed_state = if ok!(regs.load_bit()) {
+            let committed = &mut ok!(ok!(regs.load_reference()).as_slice());
+            Some(CommittedState {
+                c4: ok!(committed.load_reference_cloned()),
+                c5: ok!(committed.load_reference_cloned()),
+            })
+        } else {
+            None
+        };
+
+        let gas = ok!(ok!(slice.load_reference()).parse::<GasSnapshot>());
+
+        Ok(Self {
+            version,
+            code,
+            cp,
+            steps,
+            stack: SafeRc::new(stack),
+            cr,
+            committed_state,
+            gas,
+        })
+    }
+}
+
+impl Store for GasSnapshot {
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        context: &dyn CellContext,
+    ) -> Result<(), Error> {
+        let mut loaded_cells = Dict::<HashBytes, ()>::new();
+        for hash in &self.loaded_cells {
+            ok!(loaded_cells.set_ext(hash, (), context));
+        }
+
+        ok!(builder.store_u64(self.max));
+        ok!(builder.store_u64(self.limit));
+        ok!(builder.store_u64(self.credit));
+        ok!(builder.store_u64(self.base));
+        ok!(self.consumed_on_accept.store_into(builder, context));
+        ok!(builder.store_u64(self.remaining as u64));
+        ok!(builder.store_u64(self.price));
+        ok!(builder.store_u32(ok!(to_u32(self.chksign_counter))));
+        ok!(builder.store_u64(self.free_gas_consumed));
+        ok!(builder.store_u32(ok!(to_u32(self.get_extra_balance_counter))));
+        loaded_cells.store_into(builder, context)
+    }
+}
+
+impl Load<'_> for GasSnapshot {
+    fn load_from(slice: &mut CellSlice<'_>) -> Result<Self, Error> {
+        let mut result = GasSnapshot {
+            max: ok!(slice.load_u64()),
+            limit: ok!(slice.load_u64()),
+            credit: ok!(slice.load_u64()),
+            base: ok!(slice.load_u64()),
+            consumed_on_accept: ok!(Load::load_from(slice)),
+            remaining: ok!(slice.load_u64()) as i64,
+            price: ok!(slice.load_u64()),
+            chksign_counter: ok!(slice.load_u32()) as usize,
+            free_gas_consumed: ok!(slice.load_u64()),
+            get_extra_balance_counter: ok!(slice.load_u32()) as usize,
+            loaded_cells: Default::default(),
+        };
+
+        let loaded_cells = ok!(Dict::<HashBytes, ()>::load_from(slice));
+        for hash in loaded_cells.keys() {
+            result.loaded_cells.insert(ok!(hash));
+        }
+
+        Ok(result)
+    }
+}
+
+fn to_u32(value: usize) -> Result<u32, Error> {
+    u32::try_from(value).map_err(|_| Error::IntOverflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::prelude::*;
+    use num_bigint::BigInt;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::gas::GasParams;
+
+    #[test]
+    #[traced_test]
+    fn suspend_and_resume() {
+        let code = Boc::decode(tvmasm!(
+            r#"
+            NEWC ENDC DUP CTOS DROP
+            PUSHINT 0 PUSHINT 10
+            PUSHCONT { INC }
+            REPEAT
+            SWAP CTOS DROP
+            "#
+        ))
+        .unwrap();
+
+        let run_full = || {
+            let mut output = crate::tests::TracingOutput::default();
+            let mut state = VmState::builder()
+                .with_code(code.clone())
+                .with_gas(GasParams::getter())
+                .with_debug(&mut output)
+                .build();
+            let exit_code = !state.run();
+            (
+                exit_code,
+                state.stack.clone(),
+                state.gas.consumed(),
+                state.steps,
+            )
+        };
+        let (expected_exit_code, expected_stack, expected_gas, expected_steps) = run_full();
+        assert_eq!(expected_exit_code, 0);
+
+        // Pause in the middle of the loop and save the state.
+        let snapshot = {
+            let mut output = crate::tests::TracingOutput::default();
+            let mut state = VmState::builder()
+                .with_code(code.clone())
+                .with_gas(GasParams::getter())
+                .with_debug(&mut output)
+                .build();
+
+            let mut debugger = state.debugger();
+            for _ in 0..10 {
+                debugger.step();
+            }
+            assert!(!debugger.is_finished());
+
+            let snapshot = state.snapshot().unwrap();
+            Boc::encode(CellBuilder::build_from(&snapshot).unwrap())
+        };
+
+        // Resume from the serialized state.
+        let snapshot = Boc::decode(snapshot)
+            .unwrap()
+            .parse::<VmSnapshot>()
+            .unwrap();
+        assert_eq!(snapshot.steps, 10);
+
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_snapshot(snapshot)
+            .with_debug(&mut output)
+            .build();
+
+        let mut debugger = state.debugger();
+        let exit_code = loop {
+            if let crate::DebuggerStatus::Finished(exit_code) = debugger.step() {
+                break exit_code;
+            }
+        };
+
+        assert_eq!(exit_code, expected_exit_code);
+        assert_eq!(state.gas.consumed(), expected_gas);
+        assert_eq!(state.steps, expected_steps);
+        assert_eq!(state.stack.depth(), expected_stack.depth());
+        assert_eq!(
+            state.stack.items.last().unwrap().as_int().unwrap(),
+            &BigInt::from(10)
+        );
+    }
+
+    #[test]
+    fn gas_snapshot_roundtrip() {
+        let gas = GasSnapshot {
+            max: 1000,
+            limit: 500,
+            credit: 10,
+            base: 510,
+            consumed_on_accept: Some(123),
+            remaining: -5,
+            price: 65536000,
+            loaded_cells: [HashBytes([1; 32]), HashBytes([2; 32])]
+                .into_iter()
+                .collect(),
+            chksign_counter: 3,
+            free_gas_consumed: 40,
+            get_extra_balance_counter: 1,
+        };
+
+        let cell = CellBuilder::build_from(&gas).unwrap();
+        assert_eq!(cell.parse::<GasSnapshot>().unwrap(), gas);
+    }
+}
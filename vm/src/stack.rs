@@ -297,26 +297,11 @@ impl Stack {
     }
 
     pub fn pop_int_or_nan(&mut self) -> VmResult<Option<SafeRc<BigInt>>> {
-        let value = ok!(self.pop());
-        if value.ty() == StackValueType::Int && value.as_int().is_none() {
-            Ok(None)
-        } else {
-            value.into_int().map(Some)
-        }
+        into_int_or_nan(ok!(self.pop()))
     }
 
     pub fn pop_smallint_range(&mut self, min: u32, max: u32) -> VmResult<u32> {
-        let item = self.pop_int()?;
-        if let Some(item) = item.to_u32() {
-            if item >= min && item <= max {
-                return Ok(item);
-            }
-        }
-        vm_bail!(IntegerOutOfRange {
-            min: min as isize,
-            max: max as isize,
-            actual: item.to_string(),
-        })
+        into_smallint_range(ok!(self.pop_int()), min, max)
     }
 
     pub fn pop_long_range(&mut self, min: u64, max: u64) -> VmResult<u64> {
@@ -461,6 +446,138 @@ impl SafeRcMakeMut for Stack {
     }
 }
 
+/// Scoped mutable access to a shared [`Stack`].
+///
+/// Makes the stack unique only once on creation instead of doing
+/// [`SafeRc::make_mut`] for each access. Slots freed by pops through
+/// the guard are reused by the following pushes without checking
+/// the [`Stack::MAX_DEPTH`] limit again.
+///
+/// All other [`Stack`] methods are available through [`DerefMut`].
+///
+/// [`DerefMut`]: std::ops::DerefMut
+pub struct StackGuard<'a> {
+    stack: &'a mut Stack,
+    /// Number of values popped through the guard and not yet replaced.
+    free: usize,
+}
+
+impl<'a> StackGuard<'a> {
+    #[inline]
+    pub fn new(stack: &'a mut SafeRc<Stack>) -> Self {
+        Self {
+            stack: SafeRc::make_mut(stack),
+            free: 0,
+        }
+    }
+
+    #[inline]
+    pub fn push_raw<T: StackValue + ?Sized + 'static>(&mut self, item: SafeRc<T>) -> VmResult<()> {
+        if self.free == 0 {
+            return self.stack.push_raw(item);
+        }
+        self.free -= 1;
+        self.stack.items.push(item.into_dyn_value());
+        Ok(())
+    }
+
+    #[inline]
+    pub fn push<T: StackValue + 'static>(&mut self, item: T) -> VmResult<()> {
+        self.push_raw(SafeRc::new_dyn_value(item))
+    }
+
+    #[inline]
+    pub fn push_nan(&mut self) -> VmResult<()> {
+        self.push_raw(Stack::make_nan())
+    }
+
+    #[inline]
+    pub fn push_bool(&mut self, value: bool) -> VmResult<()> {
+        self.push_raw(Stack::make_bool(value))
+    }
+
+    #[inline]
+    pub fn push_zero(&mut self) -> VmResult<()> {
+        self.push_raw(Stack::make_zero())
+    }
+
+    #[inline]
+    pub fn push_int<T: Into<BigInt>>(&mut self, value: T) -> VmResult<()> {
+        self.push(value.into())
+    }
+
+    pub fn push_raw_int(&mut self, value: SafeRc<BigInt>, quiet: bool) -> VmResult<()> {
+        if value.bitsize(true) <= 257 {
+            self.push_raw(value)
+        } else if quiet {
+            self.push_nan()
+        } else {
+            vm_bail!(IntegerOverflow)
+        }
+    }
+
+    #[inline]
+    pub fn pop(&mut self) -> VmResult<RcStackValue> {
+        let item = ok!(self.stack.pop());
+        self.free += 1;
+        Ok(item)
+    }
+
+    #[inline]
+    pub fn pop_int(&mut self) -> VmResult<SafeRc<BigInt>> {
+        ok!(self.pop()).into_int()
+    }
+
+    #[inline]
+    pub fn pop_int_or_nan(&mut self) -> VmResult<Option<SafeRc<BigInt>>> {
+        into_int_or_nan(ok!(self.pop()))
+    }
+
+    #[inline]
+    pub fn pop_smallint_range(&mut self, min: u32, max: u32) -> VmResult<u32> {
+        into_smallint_range(ok!(self.pop_int()), min, max)
+    }
+}
+
+impl std::ops::Deref for StackGuard<'_> {
+    type Target = Stack;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.stack
+    }
+}
+
+impl std::ops::DerefMut for StackGuard<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // NOTE: Direct modifications can change the depth arbitrarily.
+        self.free = 0;
+        self.stack
+    }
+}
+
+fn into_int_or_nan(value: RcStackValue) -> VmResult<Option<SafeRc<BigInt>>> {
+    if value.ty() == StackValueType::Int && value.as_int().is_none() {
+        Ok(None)
+    } else {
+        value.into_int().map(Some)
+    }
+}
+
+fn into_smallint_range(item: SafeRc<BigInt>, min: u32, max: u32) -> VmResult<u32> {
+    if let Some(item) = item.to_u32() {
+        if item >= min && item <= max {
+            return Ok(item);
+        }
+    }
+    vm_bail!(IntegerOutOfRange {
+        min: min as isize,
+        max: max as isize,
+        actual: item.to_string(),
+    })
+}
+
 impl FromIterator<RcStackValue> for Stack {
     #[inline]
     fn from_iter<T: IntoIterator<Item = RcStackValue>>(iter: T) -> Self {
@@ -1,8 +1,14 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
 use anyhow::Result;
 use bitflags::bitflags;
 use everscale_types::cell::*;
 use everscale_types::error::Error;
-use num_bigint::BigInt;
+use everscale_types::models::MessageLayout;
+use everscale_types::num::Tokens;
+use num_bigint::{BigInt, Sign};
 #[cfg(feature = "tracing")]
 use tracing::instrument;
 
@@ -10,13 +16,18 @@ use crate::cont::{
     AgainCont, ArgContExt, ControlData, ControlRegs, ExcQuitCont, OrdCont, QuitCont, RcCont,
     RepeatCont, UntilCont, WhileCont,
 };
+use crate::debugger::{CodeLocation, Debugger};
 use crate::dispatch::DispatchTable;
 use crate::error::{VmException, VmResult};
 use crate::gas::{GasConsumer, GasParams, LibraryProvider, NoLibraries, ParentGasConsumer};
-use crate::instr::{codepage, codepage0};
+use crate::instr::{codepage, codepage0, Codepages};
+use crate::rng::RngProvider;
 use crate::saferc::SafeRc;
-use crate::smc_info::{SmcInfo, VmVersion};
+use crate::signature::SignatureBackend;
+use crate::smc_info::{SmcInfo, SmcInfoBase, VmVersion};
+use crate::snapshot::VmSnapshot;
 use crate::stack::{RcStackValue, Stack};
+use crate::trace::{StepStart, TraceCollector};
 use crate::util::OwnedCellSlice;
 
 /// Execution state builder.
@@ -31,7 +42,13 @@ pub struct VmStateBuilder<'a> {
     pub init_selector: InitSelectorParams,
     pub version: Option<VmVersion>,
     pub modifiers: BehaviourModifiers,
-    pub debug: Option<&'a mut dyn std::fmt::Write>,
+    pub limits: ExecutionLimits,
+    pub debug: DebugSinks<'a>,
+    pub codepages: Option<&'a Codepages>,
+    pub rng: Option<&'a mut dyn RngProvider>,
+    pub signature_backend: Option<&'a mut dyn SignatureBackend>,
+    pub trace: Option<&'a mut dyn TraceCollector>,
+    pub snapshot: Option<VmSnapshot>,
 }
 
 impl<'a> VmStateBuilder<'a> {
@@ -44,7 +61,10 @@ impl<'a> VmStateBuilder<'a> {
 
         let quit0 = QUIT0.with(SafeRc::clone);
         let quit1 = QUIT1.with(SafeRc::clone);
-        let cp = codepage0();
+        let cp = match self.codepages {
+            Some(codepages) => codepages.get(0).unwrap_or_else(codepage0),
+            None => codepage0(),
+        };
 
         let (code, throw_on_code_access) = match self.code {
             Some(code) => (code, false),
@@ -64,7 +84,9 @@ impl<'a> VmStateBuilder<'a> {
             }
         };
 
-        VmState {
+        let libraries = self.libraries.unwrap_or(&NO_LIBRARIES);
+
+        let mut state = VmState {
             cr: ControlRegs {
                 c: [
                     Some(quit0.clone().into_dyn_cont()),
@@ -85,13 +107,43 @@ impl<'a> VmStateBuilder<'a> {
             steps: 0,
             quit0,
             quit1,
-            gas: GasConsumer::with_libraries(self.gas, self.libraries.unwrap_or(&NO_LIBRARIES)),
+            gas: GasConsumer::with_libraries(self.gas, libraries),
             cp,
             debug: self.debug,
+            codepages: self.codepages,
+            rng: self.rng,
+            signature_backend: self.signature_backend,
+            trace: self.trace,
             modifiers: self.modifiers,
+            limits: self.limits,
             version: self.version.unwrap_or(VmState::DEFAULT_VERSION),
             parent: None,
+            accept_info: None,
+            send_msg_estimates: Vec::new(),
+            interrupted: None,
+        };
+
+        if let Some(snapshot) = self.snapshot {
+            state.version = snapshot.version;
+            state.code = snapshot.code;
+            state.throw_on_code_access = false;
+            match state.resolve_cp(snapshot.cp) {
+                Some(cp) => state.cp = cp,
+                // NOTE: Execution fails immediately with an unknown codepage.
+                None => state.throw_on_code_access = true,
+            }
+            state.steps = snapshot.steps;
+            state.stack = snapshot.stack;
+            state.cr = snapshot.cr;
+            state.committed_state = snapshot.committed_state;
+            state.gas = GasConsumer::from_snapshot(snapshot.gas, libraries);
         }
+
+        state
+            .gas
+            .set_missing_libraries_as_empty(state.modifiers.missing_libraries_as_empty);
+
+        state
     }
 
     pub fn with_libraries<T: LibraryProvider>(mut self, libraries: &'a T) -> Self {
@@ -104,8 +156,59 @@ impl<'a> VmStateBuilder<'a> {
         self
     }
 
+    /// Adds a new debug output target.
+    ///
+    /// Can be called multiple times to write the same output into several sinks.
     pub fn with_debug<T: std::fmt::Write>(mut self, stderr: &'a mut T) -> Self {
-        self.debug = Some(stderr);
+        self.debug.push(stderr);
+        self
+    }
+
+    /// Adds a new typed debug events receiver.
+    ///
+    /// Can be combined with text outputs from [`with_debug`].
+    ///
+    /// [`with_debug`]: Self::with_debug
+    pub fn with_debug_events<T: DebugSink>(mut self, sink: &'a mut T) -> Self {
+        self.debug.push_event_sink(sink);
+        self
+    }
+
+    pub fn with_debug_sinks(mut self, sinks: DebugSinks<'a>) -> Self {
+        self.debug = sinks;
+        self
+    }
+
+    /// Overrides the source of randomness for `RANDU256`, `RAND` and `ADDRAND`.
+    pub fn with_rng<T: RngProvider>(mut self, rng: &'a mut T) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Overrides the signature verifier for `CHKSIGN`-like opcodes.
+    pub fn with_signature_backend<T: SignatureBackend>(mut self, backend: &'a mut T) -> Self {
+        self.signature_backend = Some(backend);
+        self
+    }
+
+    /// Adds codepages which can be selected by `SETCP`-like instructions.
+    pub fn with_codepages(mut self, codepages: &'a Codepages) -> Self {
+        self.codepages = Some(codepages);
+        self
+    }
+
+    /// Continues the execution from the saved state.
+    ///
+    /// Code, stack, control registers, gas and version are taken
+    /// from the snapshot, all other params are used as is.
+    pub fn with_snapshot(mut self, snapshot: VmSnapshot) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Adds a receiver of typed per-step events.
+    pub fn with_trace_collector<T: TraceCollector>(mut self, trace: &'a mut T) -> Self {
+        self.trace = Some(trace);
         self
     }
 
@@ -151,6 +254,26 @@ impl<'a> VmStateBuilder<'a> {
         self.version = Some(version);
         self
     }
+
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.limits.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationFlag) -> Self {
+        self.limits.cancellation = Some(cancellation);
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u64) -> Self {
+        self.limits.max_steps = Some(max_steps);
+        self
+    }
 }
 
 /// Anything that can be used as a VM code source.
@@ -220,10 +343,19 @@ pub struct VmState<'a> {
     pub quit1: SafeRc<QuitCont>,
     pub gas: GasConsumer<'a>,
     pub cp: &'static DispatchTable,
-    pub debug: Option<&'a mut dyn std::fmt::Write>,
+    pub debug: DebugSinks<'a>,
+    pub codepages: Option<&'a Codepages>,
+    pub rng: Option<&'a mut dyn RngProvider>,
+    pub signature_backend: Option<&'a mut dyn SignatureBackend>,
+    pub trace: Option<&'a mut dyn TraceCollector>,
     pub modifiers: BehaviourModifiers,
+    pub limits: ExecutionLimits,
     pub version: VmVersion,
     pub parent: Option<Box<ParentVmState<'a>>>,
+    pub accept_info: Option<AcceptInfo>,
+    pub send_msg_estimates: Vec<SendMsgEstimate>,
+    /// Reason of the aborted execution (if it was interrupted).
+    pub interrupted: Option<VmInterrupt>,
 }
 
 /// Parent execution state.
@@ -267,6 +399,9 @@ impl<'a> VmState<'a> {
 
     pub const MAX_DATA_DEPTH: u16 = 512;
 
+    /// Number of steps between execution limits checks.
+    pub const LIMITS_CHECK_INTERVAL: u32 = 256;
+
     thread_local! {
         static EMPTY_STACK: SafeRc<Stack> = SafeRc::new(Default::default());
     }
@@ -309,7 +444,7 @@ impl<'a> VmState<'a> {
 
             self.cp.dispatch(self)
         } else if !self.code.range().is_refs_empty() {
-            vm_log_op!("implicit JMPREF");
+            vm_log_op!(@CONTROL "implicit JMPREF");
 
             let next_cell = self.code.apply().get_reference_cloned(0)?;
 
@@ -324,7 +459,7 @@ impl<'a> VmState<'a> {
             let cont = SafeRc::from(OrdCont::simple(code, self.cp.id()));
             self.jump(cont)
         } else {
-            vm_log_op!("implicit RET");
+            vm_log_op!(@CONTROL "implicit RET");
 
             self.gas.try_consume_implicit_ret_gas()?;
             self.ret()
@@ -337,16 +472,45 @@ impl<'a> VmState<'a> {
             return VmException::Fatal as u8 as i32;
         }
 
+        self.run_until(false, false, |_| false)
+            .expect("execution is never paused without a pause condition")
+    }
+
+    /// Runs the VM until the execution is finished or `pause` returns `true`.
+    ///
+    /// The condition is checked before each step. Returns `None` if the execution
+    /// was paused. Paused execution must be continued with `resume` set to `true`
+    /// (and `skip_pause` set to `true` to not stop at the same location again).
+    pub(crate) fn run_until<F>(
+        &mut self,
+        mut resume: bool,
+        mut skip_pause: bool,
+        mut pause: F,
+    ) -> Option<i32>
+    where
+        F: FnMut(&Self) -> bool,
+    {
         let mut res = 0;
         loop {
-            res = match self.restore_parent(!res) {
-                Ok(()) => self.run_inner(),
+            let restored = if std::mem::take(&mut resume) {
+                Ok(())
+            } else {
+                self.restore_parent(!res)
+            };
+
+            res = match restored {
+                Ok(()) => self.run_inner(std::mem::take(&mut skip_pause), &mut pause)?,
                 Err(OutOfGas) => {
                     self.steps += 1;
                     self.throw_out_of_gas()
                 }
             };
 
+            if self.interrupted.is_some() {
+                // Interrupted execution is not resumed in the parent.
+                break Some(res);
+            }
+
             if self.parent.is_none() {
                 #[cfg(feature = "tracing")]
                 if self.modifiers.log_mask.contains(VmLogMask::DUMP_C5) {
@@ -354,15 +518,51 @@ impl<'a> VmState<'a> {
                         vm_log_c5!(committed.c5.as_ref());
                     }
                 }
-                break res;
+                break Some(res);
             }
         }
     }
 
-    fn run_inner(&mut self) -> i32 {
+    fn run_inner<F>(&mut self, mut skip_pause: bool, pause: &mut F) -> Option<i32>
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        let check_limits = self.limits.is_set();
+        let mut steps_until_check = 0;
+
         let mut res = 0;
         while res == 0 {
+            if !std::mem::take(&mut skip_pause) && pause(self) {
+                return None;
+            }
+
+            if check_limits {
+                if let Some(max_steps) = self.limits.max_steps {
+                    if self.total_steps() >= max_steps {
+                        let reason = VmInterrupt::StepLimitExceeded;
+                        vm_log_trace!("execution interrupted: {reason}");
+                        self.interrupted = Some(reason);
+                        return Some(VmException::Fatal as u8 as i32);
+                    }
+                }
+
+                if steps_until_check == 0 {
+                    if let Some(reason) = self.limits.check() {
+                        vm_log_trace!("execution interrupted: {reason}");
+                        self.interrupted = Some(reason);
+                        // No negation for unhandled exceptions (to make their faking impossible).
+                        return Some(VmException::Fatal as u8 as i32);
+                    }
+                    steps_until_check = Self::LIMITS_CHECK_INTERVAL;
+                }
+                steps_until_check -= 1;
+            }
+
+            let step_start = self.trace.is_some().then(|| StepStart::capture(self));
             let step_res = self.step();
+            if let Some(step_start) = step_start {
+                step_start.finish(self, &step_res);
+            }
 
             #[cfg(feature = "tracing")]
             if self.modifiers.log_mask.contains(VmLogMask::GAS_REMAINING) {
@@ -393,7 +593,7 @@ impl<'a> VmState<'a> {
                         }
                         Err(e) => {
                             vm_log_trace!("double exception {exception:?}: {e:?}");
-                            return exception.as_exit_code();
+                            return Some(exception.as_exit_code());
                         }
                     }
                 }
@@ -406,10 +606,57 @@ impl<'a> VmState<'a> {
             self.stack = SafeRc::new(Stack {
                 items: vec![Stack::make_zero()],
             });
-            return VmException::CellOverflow.as_exit_code();
+            return Some(VmException::CellOverflow.as_exit_code());
         }
 
-        res
+        Some(res)
+    }
+
+    /// Returns a debugger for this state.
+    pub fn debugger(&mut self) -> Debugger<'_, 'a> {
+        Debugger::new(self)
+    }
+
+    /// Saves the execution state.
+    ///
+    /// Returns `None` during the nested `RUNVM` execution.
+    pub fn snapshot(&self) -> Option<VmSnapshot> {
+        VmSnapshot::new(self)
+    }
+
+    /// Number of steps including the steps of all parent VMs.
+    pub fn total_steps(&self) -> u64 {
+        let mut steps = self.steps;
+        let mut parent = self.parent.as_deref();
+        while let Some(item) = parent {
+            steps += item.steps;
+            parent = item.parent.as_deref();
+        }
+        steps
+    }
+
+    /// Returns the location of the next instruction.
+    pub fn code_location(&self) -> CodeLocation {
+        CodeLocation {
+            cell_hash: *self.code.cell().repr_hash(),
+            offset: self.code.range().offset_bits(),
+        }
+    }
+
+    /// Returns the current rand seed from `c7`.
+    ///
+    /// Can be used after the execution to reproduce random values.
+    pub fn rand_seed(&self) -> Option<HashBytes> {
+        let t1 = self.cr.c7.as_ref()?.first()?.as_tuple_range(0, 255)?;
+        let seed = t1.get(SmcInfoBase::RANDSEED_IDX)?.as_int()?;
+        if seed.sign() == Sign::Minus || seed.bits() > 256 {
+            return None;
+        }
+
+        let bytes = seed.magnitude().to_bytes_be();
+        let mut res = HashBytes::ZERO;
+        res.0[32 - bytes.len()..].copy_from_slice(&bytes);
+        Some(res)
     }
 
     pub fn try_commit(&mut self) -> bool {
@@ -438,6 +685,29 @@ impl<'a> VmState<'a> {
         }
     }
 
+    /// Computes a hash of the current VM state.
+    ///
+    /// State is represented as a cell with two references:
+    /// the remaining code and the serialized stack.
+    pub fn state_hash(&self) -> Result<HashBytes, Error> {
+        let context = Cell::empty_context();
+
+        let mut code = CellBuilder::new();
+        ok!(code.store_slice(self.code.apply()));
+        let code = ok!(code.build_ext(context));
+
+        let mut stack = CellBuilder::new();
+        ok!(self.stack.store_into(&mut stack, context));
+        let stack = ok!(stack.build_ext(context));
+
+        let mut root = CellBuilder::new();
+        ok!(root.store_reference(code));
+        ok!(root.store_reference(stack));
+        let root = ok!(root.build_ext(context));
+
+        Ok(*root.repr_hash())
+    }
+
     pub fn take_stack(&mut self) -> SafeRc<Stack> {
         std::mem::replace(&mut self.stack, Self::EMPTY_STACK.with(SafeRc::clone))
     }
@@ -920,13 +1190,21 @@ impl<'a> VmState<'a> {
     }
 
     pub fn force_cp(&mut self, cp: u16) -> VmResult<()> {
-        let Some(cp) = codepage(cp) else {
+        let Some(cp) = self.resolve_cp(cp) else {
             vm_bail!(InvalidOpcode);
         };
         self.cp = cp;
         Ok(())
     }
 
+    /// Resolves a registered or a builtin codepage.
+    pub fn resolve_cp(&self, cp: u16) -> Option<&'static DispatchTable> {
+        match self.codepages {
+            Some(codepages) => codepages.get(cp),
+            None => codepage(cp),
+        }
+    }
+
     fn take_c0(&mut self) -> VmResult<RcCont> {
         let Some(cont) = self.cr.c[0].replace(self.quit0.clone().into_dyn_cont()) else {
             vm_bail!(InvalidOpcode);
@@ -1034,12 +1312,122 @@ impl<'a> VmState<'a> {
 
 struct OutOfGas;
 
+/// VM state at the first accepting instruction.
+///
+/// Collected only when [`BehaviourModifiers::stop_on_accept`] is enabled.
+#[derive(Clone)]
+pub struct AcceptInfo {
+    /// Code cell with the accepting instruction.
+    pub code: Cell,
+    /// Offset of the accepting instruction in the code cell.
+    pub offset: Size,
+    /// Gas consumed up to and including the accepting instruction.
+    pub gas_consumed: u64,
+    /// Stack right after the accepting instruction.
+    pub stack: SafeRc<Stack>,
+}
+
+/// Outbound message fee and layout computed by `SENDMSG`.
+///
+/// Collected only when [`BehaviourModifiers::record_send_msg_estimates`] is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendMsgEstimate {
+    /// Hash of the message cell passed to `SENDMSG`.
+    pub msg_hash: HashBytes,
+    /// Total fee pushed to the stack.
+    pub total_fee: Tokens,
+    /// Estimated message layout.
+    pub layout: MessageLayout,
+}
+
+/// Execution limits independent of gas.
+///
+/// Unlike gas, these limits are not part of the consensus and must only be used
+/// to bound the execution time (e.g. for RPC services).
+#[derive(Default, Debug, Clone)]
+pub struct ExecutionLimits {
+    /// Execution is aborted after this instant.
+    pub deadline: Option<Instant>,
+    /// Execution is aborted after this flag is set.
+    pub cancellation: Option<CancellationFlag>,
+    /// Execution is aborted after this number of steps
+    /// (including the steps of child VMs).
+    pub max_steps: Option<u64>,
+}
+
+impl ExecutionLimits {
+    /// Returns `true` if any of the limits is set.
+    pub fn is_set(&self) -> bool {
+        self.deadline.is_some() || self.cancellation.is_some() || self.max_steps.is_some()
+    }
+
+    /// Returns the reason to interrupt the execution, if any.
+    ///
+    /// NOTE: Only wall-clock limits are checked here,
+    /// the step limit is checked by the VM before each step.
+    pub fn check(&self) -> Option<VmInterrupt> {
+        if let Some(cancellation) = &self.cancellation {
+            if cancellation.is_cancelled() {
+                return Some(VmInterrupt::Cancelled);
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Some(VmInterrupt::DeadlineExceeded);
+            }
+        }
+        None
+    }
+}
+
+/// Shared flag to cancel the execution from another thread.
+#[derive(Default, Debug, Clone)]
+pub struct CancellationFlag(Arc<AtomicBool>);
+
+impl CancellationFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests all executions with this flag to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if the cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Reason of the interrupted execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum VmInterrupt {
+    #[error("execution deadline exceeded")]
+    DeadlineExceeded,
+    #[error("execution cancelled")]
+    Cancelled,
+    #[error("execution step limit exceeded")]
+    StepLimitExceeded,
+}
+
 /// Falgs to control VM behaviour.
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BehaviourModifiers {
     pub stop_on_accept: bool,
     pub chksig_always_succeed: bool,
     pub signature_with_id: Option<i32>,
+    pub record_send_msg_estimates: bool,
+    /// Resolve missing libraries as empty cells (e.g. for estimation runs).
+    ///
+    /// NOTE: Only applied when the state is built.
+    pub missing_libraries_as_empty: bool,
+    /// Take `CONFIGPARAM` values from the unpacked config (since v6)
+    /// instead of the config dict when possible.
+    ///
+    /// NOTE: Cell loads are not charged in this case, so gas usage
+    /// differs from the reference VM.
+    pub unpacked_config_params: bool,
     #[cfg(feature = "tracing")]
     pub log_mask: VmLogMask,
 }
@@ -1047,7 +1435,7 @@ pub struct BehaviourModifiers {
 #[cfg(feature = "tracing")]
 bitflags! {
     /// VM parts to log.
-    #[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+    #[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Hash)]
     pub struct VmLogMask: u8 {
         const MESSAGE = 1 << 0;
         const DUMP_STACK = 1 << 1;
@@ -1061,7 +1449,226 @@ bitflags! {
     }
 }
 
+#[cfg(feature = "tracing")]
+bitflags! {
+    /// Instruction categories to log.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub struct OpCategory: u16 {
+        const STACK = 1 << 0;
+        const ARITH = 1 << 1;
+        const COMPARE = 1 << 2;
+        const CELL = 1 << 3;
+        const CONTROL = 1 << 4;
+        const DICT = 1 << 5;
+        const TUPLE = 1 << 6;
+        const CRYPTO = 1 << 7;
+        const CONFIG = 1 << 8;
+        const GAS = 1 << 9;
+        const MESSAGE = 1 << 10;
+        const DEBUG = 1 << 11;
+
+        const ALL = 0b1111_1111_1111;
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Default for OpCategory {
+    #[inline]
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// A typed event produced by debug opcodes.
+#[derive(Clone, Copy)]
+pub enum DebugEvent<'a> {
+    /// `DUMPSTK`: all stack items (the top one is the last).
+    DumpStack(&'a [RcStackValue]),
+    /// `DUMP s{idx}`: a stack item or `None` if it is absent.
+    DumpValue(usize, Option<&'a RcStackValue>),
+    /// `STRDUMP`: a string from the slice at `s0`.
+    ///
+    /// `Err` contains the `s0` item if it is not a slice,
+    /// `Err(None)` is used for the empty stack.
+    StrDump(Result<CellSlice<'a>, Option<&'a RcStackValue>>),
+    /// `DEBUGSTR`: an inline string from the code.
+    DumpString(CellSlice<'a>),
+}
+
+impl std::fmt::Display for DebugEvent<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MAX_DUMP_DEPTH: usize = 255;
+
+        match self {
+            Self::DumpStack(items) => {
+                let mut depth = items.len();
+                ok!(write!(f, "#DEBUG#: stack({depth} values) :"));
+                if depth > MAX_DUMP_DEPTH {
+                    ok!(f.write_str(" ..."));
+                    depth = MAX_DUMP_DEPTH;
+                }
+                for value in &items[items.len() - depth..] {
+                    ok!(write!(f, " {}", value.display_list()));
+                }
+                writeln!(f)
+            }
+            Self::DumpValue(idx, Some(value)) => {
+                writeln!(f, "#DEBUG#: s{idx} = {}", value.display_list())
+            }
+            Self::DumpValue(idx, None) => writeln!(f, "#DEBUG#: s{idx} is absent"),
+            Self::StrDump(Ok(slice)) | Self::DumpString(slice) => {
+                writeln!(f, "#DEBUG#: {}", DisplaySliceString(*slice))
+            }
+            Self::StrDump(Err(Some(_))) => writeln!(f, "#DEBUG#: is not a slice"),
+            Self::StrDump(Err(None)) => writeln!(f, "#DEBUG#: s0 is absent"),
+        }
+    }
+}
+
+/// A receiver of typed debug events.
+pub trait DebugSink {
+    fn on_event(&mut self, event: DebugEvent<'_>);
+}
+
+impl<T: FnMut(DebugEvent<'_>)> DebugSink for T {
+    #[inline]
+    fn on_event(&mut self, event: DebugEvent<'_>) {
+        self(event)
+    }
+}
+
+/// A set of debug output targets.
+///
+/// Each write is duplicated into all registered text sinks,
+/// typed events are passed to all registered event sinks.
+#[derive(Default)]
+pub struct DebugSinks<'a> {
+    sinks: Vec<&'a mut dyn std::fmt::Write>,
+    event_sinks: Vec<&'a mut dyn DebugSink>,
+}
+
+impl<'a> DebugSinks<'a> {
+    pub const fn new() -> Self {
+        Self {
+            sinks: Vec::new(),
+            event_sinks: Vec::new(),
+        }
+    }
+
+    /// Registers a new output target.
+    pub fn push(&mut self, sink: &'a mut dyn std::fmt::Write) {
+        self.sinks.push(sink);
+    }
+
+    /// Registers a new typed events receiver.
+    pub fn push_event_sink(&mut self, sink: &'a mut dyn DebugSink) {
+        self.event_sinks.push(sink);
+    }
+
+    /// Returns the number of registered output targets.
+    pub fn len(&self) -> usize {
+        self.sinks.len() + self.event_sinks.len()
+    }
+
+    /// Returns `true` if there are no output targets.
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty() && self.event_sinks.is_empty()
+    }
+
+    /// Removes all output targets.
+    pub fn clear(&mut self) {
+        self.sinks.clear();
+        self.event_sinks.clear();
+    }
+
+    /// Returns itself as a writer if at least one sink is registered.
+    pub fn as_active(&mut self) -> Option<&mut Self> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
+    /// Passes the event to all event sinks and writes
+    /// its text representation into all text sinks.
+    pub fn emit(&mut self, event: DebugEvent<'_>) -> std::fmt::Result {
+        for sink in &mut self.event_sinks {
+            sink.on_event(event);
+        }
+        if self.sinks.is_empty() {
+            return Ok(());
+        }
+        std::fmt::Write::write_fmt(self, format_args!("{event}"))
+    }
+}
+
+impl<'a> From<Option<&'a mut dyn std::fmt::Write>> for DebugSinks<'a> {
+    fn from(sink: Option<&'a mut dyn std::fmt::Write>) -> Self {
+        Self {
+            sinks: sink.into_iter().collect(),
+            event_sinks: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Write for DebugSinks<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for sink in &mut self.sinks {
+            ok!(sink.write_str(s));
+        }
+        Ok(())
+    }
+
+    fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> std::fmt::Result {
+        match self.sinks.as_mut_slice() {
+            [] => Ok(()),
+            [sink] => sink.write_fmt(args),
+            sinks => {
+                // Format only once for multiple sinks.
+                let s = args.to_string();
+                for sink in sinks {
+                    ok!(sink.write_str(&s));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+struct DisplaySliceString<'a>(CellSlice<'a>);
+
+impl std::fmt::Display for DisplaySliceString<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MAX_BIT_LEN: u16 = 127 * 8;
+
+        match (|bytes| {
+            let bit_len = self.0.size_bits();
+            if bit_len > MAX_BIT_LEN || bit_len % 8 != 0 {
+                return None;
+            }
+
+            let mut slice = self.0;
+            let mut bytes = &*slice.load_raw(bytes, bit_len).ok()?;
+            while let [rest @ .., last] = bytes {
+                if *last == 0 || last.is_ascii_whitespace() {
+                    bytes = rest;
+                } else {
+                    break;
+                }
+            }
+
+            std::str::from_utf8(bytes).ok()
+        })(&mut [0; 128])
+        {
+            Some(bytes) => f.write_str(bytes),
+            None => write!(f, "x{:X}", self.0.display_data()),
+        }
+    }
+}
+
 /// Execution effects.
+#[derive(Debug, Clone)]
 pub struct CommittedState {
     /// Contract data.
     pub c4: Cell,
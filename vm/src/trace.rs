@@ -0,0 +1,242 @@
+use crate::debugger::CodeLocation;
+use crate::dispatch::DispatchTable;
+use crate::error::{VmException, VmResult};
+use crate::saferc::SafeRc;
+use crate::stack::Stack;
+use crate::state::VmState;
+
+/// Executed instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceInstr {
+    /// An explicit instruction from the code.
+    Opcode {
+        /// Up to 24 first bits of the code (left-aligned).
+        opcode: u32,
+        /// Number of prefetched bits in `opcode`.
+        ///
+        /// NOTE: This is not the instruction length
+        /// (see [`TraceEvent::instr_bits`]).
+        bits: u16,
+    },
+    /// Jump to the first reference at the end of the code.
+    ImplicitJmpRef,
+    /// Return at the end of the code.
+    ImplicitRet,
+}
+
+/// Info about a single VM step.
+#[derive(Debug, Clone, Copy)]
+pub struct TraceEvent<'a> {
+    /// Step number (starting from 1).
+    pub step: u64,
+    /// Location of the instruction.
+    pub location: CodeLocation,
+    /// Codepage used for the instruction.
+    pub cp: u16,
+    /// Executed instruction.
+    pub instr: TraceInstr,
+    /// Length of the instruction in bits.
+    ///
+    /// `None` if the instruction failed or transferred control
+    /// (so the next instruction is not right after it).
+    pub instr_bits: Option<u16>,
+    /// Gas consumed before the step.
+    pub gas_before: u64,
+    /// Gas consumed after the step.
+    ///
+    /// NOTE: Gas for the exception handling is not included.
+    pub gas_after: u64,
+    /// Stack depth before the step.
+    pub stack_depth_before: usize,
+    /// Stack after the step.
+    ///
+    /// Can be cloned to keep a snapshot (the stack is copied on write).
+    pub stack: &'a SafeRc<Stack>,
+    /// Exception thrown by the instruction.
+    pub exception: Option<VmException>,
+}
+
+/// Receiver of the typed per-step events.
+pub trait TraceCollector {
+    fn on_step(&mut self, event: &TraceEvent<'_>);
+}
+
+impl<T: TraceCollector + ?Sized> TraceCollector for &mut T {
+    #[inline]
+    fn on_step(&mut self, event: &TraceEvent<'_>) {
+        T::on_step(self, event);
+    }
+}
+
+/// State captured before the step.
+pub(crate) struct StepStart {
+    location: CodeLocation,
+    cp: u16,
+    instr: TraceInstr,
+    code_end: (u16, u8),
+    gas_before: u64,
+    stack_depth_before: usize,
+}
+
+impl StepStart {
+    pub fn capture(st: &VmState<'_>) -> Self {
+        let code = st.code.apply();
+        let instr = if !code.is_data_empty() {
+            let (opcode, bits) = DispatchTable::get_opcode_from_slice(&code);
+            TraceInstr::Opcode { opcode, bits }
+        } else if !code.is_refs_empty() {
+            TraceInstr::ImplicitJmpRef
+        } else {
+            TraceInstr::ImplicitRet
+        };
+
+        Self {
+            location: st.code_location(),
+            cp: st.cp.id(),
+            instr,
+            code_end: code_end(st),
+            gas_before: st.gas.consumed(),
+            stack_depth_before: st.stack.depth(),
+        }
+    }
+
+    pub fn finish(self, st: &mut VmState<'_>, res: &VmResult<i32>) {
+        let instr_bits = match res {
+            Ok(_)
+                if st.code.cell().repr_hash() == &self.location.cell_hash
+                    && code_end(st) == self.code_end =>
+            {
+                st.code
+                    .range()
+                    .offset_bits()
+                    .checked_sub(self.location.offset)
+                    .filter(|&bits| bits > 0)
+            }
+            _ => None,
+        };
+
+        let Some(trace) = st.trace.as_deref_mut() else {
+            return;
+        };
+
+        trace.on_step(&TraceEvent {
+            step: st.steps,
+            location: self.location,
+            cp: self.cp,
+            instr: self.instr,
+            instr_bits,
+            gas_before: self.gas_before,
+            gas_after: st.gas.consumed(),
+            stack_depth_before: self.stack_depth_before,
+            stack: &st.stack,
+            exception: res.as_ref().err().map(|e| e.as_exception()),
+        });
+    }
+}
+
+fn code_end(st: &VmState<'_>) -> (u16, u8) {
+    let range = st.code.range();
+    (
+        range.offset_bits() + range.size_bits(),
+        range.offset_refs() + range.size_refs(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_types::prelude::*;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::gas::GasParams;
+
+    type StepInfo = (
+        u64,
+        CodeLocation,
+        TraceInstr,
+        Option<u16>,
+        u64,
+        u64,
+        usize,
+        usize,
+    );
+
+    #[derive(Default)]
+    struct Collector {
+        events: Vec<StepInfo>,
+        exceptions: Vec<Option<VmException>>,
+        last_stack: Option<SafeRc<Stack>>,
+    }
+
+    impl TraceCollector for Collector {
+        fn on_step(&mut self, e: &TraceEvent<'_>) {
+            self.events.push((
+                e.step,
+                e.location,
+                e.instr,
+                e.instr_bits,
+                e.gas_before,
+                e.gas_after,
+                e.stack_depth_before,
+                e.stack.depth(),
+            ));
+            self.exceptions.push(e.exception);
+            self.last_stack = Some(e.stack.clone());
+        }
+    }
+
+    #[test]
+    #[traced_test]
+    fn collect_steps() {
+        let code = Boc::decode(tvmasm!("PUSHINT 1 PUSHINT 2 ADD")).unwrap();
+        let hash = *code.repr_hash();
+        let location = |offset| CodeLocation {
+            cell_hash: hash,
+            offset,
+        };
+
+        let mut collector = Collector::default();
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_gas(GasParams::getter())
+            .with_trace_collector(&mut collector)
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), 0);
+        drop(state);
+
+        let op = |opcode, bits| TraceInstr::Opcode { opcode, bits };
+        assert_eq!(collector.events, vec![
+            (1, location(0), op(0x7172a0, 24), Some(8), 0, 18, 0, 1),
+            (2, location(8), op(0x72a000, 16), Some(8), 18, 36, 1, 2),
+            (3, location(16), op(0xa00000, 8), Some(8), 36, 54, 2, 1),
+            (4, location(24), TraceInstr::ImplicitRet, None, 54, 59, 1, 1),
+        ]);
+        assert!(collector.exceptions.iter().all(Option::is_none));
+        assert_eq!(collector.last_stack.unwrap().depth(), 1);
+    }
+
+    #[test]
+    #[traced_test]
+    fn collect_exceptions() {
+        let code = Boc::decode(tvmasm!("ADD")).unwrap();
+
+        let mut collector = Collector::default();
+        let mut output = crate::tests::TracingOutput::default();
+        let mut state = VmState::builder()
+            .with_code(code)
+            .with_gas(GasParams::getter())
+            .with_trace_collector(&mut collector)
+            .with_debug(&mut output)
+            .build();
+
+        assert_eq!(!state.run(), VmException::StackUnderflow as i32);
+        drop(state);
+
+        assert_eq!(collector.exceptions, vec![Some(
+            VmException::StackUnderflow
+        )]);
+    }
+}